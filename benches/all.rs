@@ -1,4 +1,4 @@
-#![allow(clippy::uninlined_format_args)]
+#![allow(clippy::uninlined_format_args, clippy::useless_vec)]
 use std::{fs, path::Path};
 
 use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
@@ -0,0 +1,75 @@
+#![no_main]
+
+//! Cross-checks the three lines modules against each other on text that
+//! only has LF, CR, and CRLF line breaks.
+//!
+//! On such text `lines` and `lines_crlf` recognize exactly the same breaks,
+//! so they must agree everywhere.  `lines_lf` recognizes a subset of those
+//! breaks (it ignores lone CRs), so its line indices can only be lower and
+//! its line starts only later, and it must agree with the other two when
+//! there are no lone CRs.
+
+use libfuzzer_sys::fuzz_target;
+use str_indices::{lines, lines_crlf, lines_lf};
+
+fuzz_target!(|data: (Vec<u8>, usize)| {
+    // Build text out of line breaks and chars of a few utf8 lengths.
+    let mut text = String::new();
+    for byte in &data.0 {
+        text.push(match byte % 6 {
+            0 | 1 => '\n',
+            2 | 3 => '\r',
+            4 => 'a',
+            _ => 'あ',
+        });
+    }
+    let text = &text[..];
+    let has_lone_cr = text
+        .char_indices()
+        .any(|(i, c)| c == '\r' && !text[i..].starts_with("\r\n"));
+
+    let breaks = lines::count_breaks(text);
+    let breaks_lf = lines_lf::count_breaks(text);
+    assert_eq!(breaks, lines_crlf::count_breaks(text));
+    assert!(breaks_lf <= breaks);
+    assert_eq!(has_lone_cr, breaks_lf < breaks);
+
+    for final_empty_line in [true, false] {
+        assert_eq!(
+            lines::count_lines(text, final_empty_line),
+            lines_crlf::count_lines(text, final_empty_line),
+        );
+    }
+
+    for byte_idx in (0..=(text.len() + 1)).chain([data.1]) {
+        let line = lines::from_byte_idx(text, byte_idx);
+        let line_lf = lines_lf::from_byte_idx(text, byte_idx);
+        assert_eq!(line, lines_crlf::from_byte_idx(text, byte_idx));
+        assert_eq!(
+            lines::from_byte_idx_snapped(text, byte_idx),
+            lines_crlf::from_byte_idx_snapped(text, byte_idx),
+        );
+        assert_eq!(
+            lines::is_break_start(text, byte_idx),
+            lines_crlf::is_break_start(text, byte_idx),
+        );
+        assert!(line_lf <= line);
+        if !has_lone_cr {
+            assert_eq!(line, line_lf);
+            assert_eq!(
+                lines::is_break_start(text, byte_idx),
+                lines_lf::is_break_start(text, byte_idx),
+            );
+        }
+    }
+
+    for line_idx in (0..=(breaks + 1)).chain([data.1]) {
+        let start = lines::to_byte_idx(text, line_idx);
+        let start_lf = lines_lf::to_byte_idx(text, line_idx);
+        assert_eq!(start, lines_crlf::to_byte_idx(text, line_idx));
+        assert!(start_lf >= start);
+        if !has_lone_cr {
+            assert_eq!(start, start_lf);
+        }
+    }
+});
@@ -0,0 +1,107 @@
+#![no_main]
+
+//! Checks that the `segmented` functions give the same results on a text
+//! split into segments as the top-level functions give on the whole text.
+//!
+//! The text is split at fuzzer-chosen points, which often fall between the
+//! CR and LF of a CRLF.  Segments are `&str`s, so a split point in the
+//! middle of a char is snapped back to the start of that char.
+
+use libfuzzer_sys::fuzz_target;
+use str_indices::segmented::{self, TextSource};
+use str_indices::{chars, lines, lines_crlf, lines_lf, utf16};
+
+fuzz_target!(|data: (Vec<u8>, Vec<u16>, usize)| {
+    // Build text out of line breaks and chars of every utf8 length.
+    let mut text = String::new();
+    for byte in &data.0 {
+        text.push(match byte % 10 {
+            0 | 1 => '\n',
+            2 | 3 => '\r',
+            4 => '\u{0085}',
+            5 => '\u{2028}',
+            6 | 7 => 'a',
+            8 => 'あ',
+            _ => '🐸',
+        });
+    }
+    let text = &text[..];
+
+    // Split it into segments.
+    let mut splits: Vec<usize> = data
+        .1
+        .iter()
+        .map(|&i| chars::floor_byte_boundary(text, i as usize % (text.len() + 1)))
+        .collect();
+    splits.sort_unstable();
+    let mut segments = Vec::new();
+    let mut start = 0;
+    for &end in splits.iter().chain([&text.len()]) {
+        segments.push(&text[start..end]);
+        start = end;
+    }
+    let segments = &segments[..];
+    assert_eq!(text, segments.segments().collect::<String>());
+
+    assert_eq!(chars::count(text), segmented::chars::count(segments));
+    assert_eq!(utf16::count(text), segmented::utf16::count(segments));
+    assert_eq!(
+        utf16::count_surrogates(text),
+        segmented::utf16::count_surrogates(segments)
+    );
+    assert_eq!(
+        lines::count_breaks(text),
+        segmented::lines::count_breaks(segments)
+    );
+    assert_eq!(
+        lines_crlf::count_breaks(text),
+        segmented::lines_crlf::count_breaks(segments)
+    );
+    assert_eq!(
+        lines_lf::count_breaks(text),
+        segmented::lines_lf::count_breaks(segments)
+    );
+
+    for i in (0..=(text.len() + 1)).chain([data.2]) {
+        assert_eq!(
+            chars::from_byte_idx(text, i),
+            segmented::chars::from_byte_idx(segments, i)
+        );
+        assert_eq!(
+            chars::to_byte_idx(text, i),
+            segmented::chars::to_byte_idx(segments, i)
+        );
+        assert_eq!(
+            utf16::from_byte_idx(text, i),
+            segmented::utf16::from_byte_idx(segments, i)
+        );
+        assert_eq!(
+            utf16::to_byte_idx(text, i),
+            segmented::utf16::to_byte_idx(segments, i)
+        );
+        assert_eq!(
+            lines::from_byte_idx(text, i),
+            segmented::lines::from_byte_idx(segments, i)
+        );
+        assert_eq!(
+            lines::to_byte_idx(text, i),
+            segmented::lines::to_byte_idx(segments, i)
+        );
+        assert_eq!(
+            lines_crlf::from_byte_idx(text, i),
+            segmented::lines_crlf::from_byte_idx(segments, i)
+        );
+        assert_eq!(
+            lines_crlf::to_byte_idx(text, i),
+            segmented::lines_crlf::to_byte_idx(segments, i)
+        );
+        assert_eq!(
+            lines_lf::from_byte_idx(text, i),
+            segmented::lines_lf::from_byte_idx(segments, i)
+        );
+        assert_eq!(
+            lines_lf::to_byte_idx(text, i),
+            segmented::lines_lf::to_byte_idx(segments, i)
+        );
+    }
+});
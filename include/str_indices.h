@@ -0,0 +1,182 @@
+#ifndef STR_INDICES_H
+#define STR_INDICES_H
+
+/* Generated by cbindgen from src/ffi.rs.  Do not edit by hand. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Counts the chars in the text.
+//
+// See `chars::count()`.
+//
+// # Safety
+//
+// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+// zero.
+size_t str_indices_chars_count(const char *text, size_t len);
+
+// Converts from byte-index to char-index in the text.
+//
+// See `chars::from_byte_idx()`.
+//
+// # Safety
+//
+// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+// zero.
+size_t str_indices_chars_from_byte_idx(const char *text, size_t len, size_t byte_idx);
+
+// Converts from char-index to byte-index in the text.
+//
+// See `chars::to_byte_idx()`.
+//
+// # Safety
+//
+// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+// zero.
+size_t str_indices_chars_to_byte_idx(const char *text, size_t len, size_t char_idx);
+
+// Counts the utf16 code units in the text.
+//
+// See `utf16::count()`.
+//
+// # Safety
+//
+// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+// zero.
+size_t str_indices_utf16_count(const char *text, size_t len);
+
+// Counts the utf16 surrogate pairs in the text.
+//
+// See `utf16::count_surrogates()`.
+//
+// # Safety
+//
+// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+// zero.
+size_t str_indices_utf16_count_surrogates(const char *text, size_t len);
+
+// Converts from byte-index to utf16-code-unit-index in the text.
+//
+// See `utf16::from_byte_idx()`.
+//
+// # Safety
+//
+// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+// zero.
+size_t str_indices_utf16_from_byte_idx(const char *text, size_t len, size_t byte_idx);
+
+// Converts from utf16-code-unit-index to byte-index in the text.
+//
+// See `utf16::to_byte_idx()`.
+//
+// # Safety
+//
+// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+// zero.
+size_t str_indices_utf16_to_byte_idx(const char *text, size_t len, size_t utf16_idx);
+
+// Counts the line breaks (all Unicode line breaks) in the text.
+//
+// See `lines::count_breaks()`.
+//
+// # Safety
+//
+// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+// zero.
+size_t str_indices_lines_count_breaks(const char *text, size_t len);
+
+// Converts from byte-index to line-index (all Unicode line breaks) in the
+// text.
+//
+// See `lines::from_byte_idx()`.
+//
+// # Safety
+//
+// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+// zero.
+size_t str_indices_lines_from_byte_idx(const char *text, size_t len, size_t byte_idx);
+
+// Converts from line-index (all Unicode line breaks) to byte-index in the
+// text.
+//
+// See `lines::to_byte_idx()`.
+//
+// # Safety
+//
+// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+// zero.
+size_t str_indices_lines_to_byte_idx(const char *text, size_t len, size_t line_idx);
+
+// Counts the line breaks (LF and CR) in the text.
+//
+// See `lines_crlf::count_breaks()`.
+//
+// # Safety
+//
+// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+// zero.
+size_t str_indices_lines_crlf_count_breaks(const char *text, size_t len);
+
+// Converts from byte-index to line-index (LF and CR) in the text.
+//
+// See `lines_crlf::from_byte_idx()`.
+//
+// # Safety
+//
+// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+// zero.
+size_t str_indices_lines_crlf_from_byte_idx(const char *text, size_t len, size_t byte_idx);
+
+// Converts from line-index (LF and CR) to byte-index in the text.
+//
+// See `lines_crlf::to_byte_idx()`.
+//
+// # Safety
+//
+// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+// zero.
+size_t str_indices_lines_crlf_to_byte_idx(const char *text, size_t len, size_t line_idx);
+
+// Counts the line breaks (LF only) in the text.
+//
+// See `lines_lf::count_breaks()`.
+//
+// # Safety
+//
+// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+// zero.
+size_t str_indices_lines_lf_count_breaks(const char *text, size_t len);
+
+// Converts from byte-index to line-index (LF only) in the text.
+//
+// See `lines_lf::from_byte_idx()`.
+//
+// # Safety
+//
+// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+// zero.
+size_t str_indices_lines_lf_from_byte_idx(const char *text, size_t len, size_t byte_idx);
+
+// Converts from line-index (LF only) to byte-index in the text.
+//
+// See `lines_lf::to_byte_idx()`.
+//
+// # Safety
+//
+// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+// zero.
+size_t str_indices_lines_lf_to_byte_idx(const char *text, size_t len, size_t line_idx);
+
+#ifdef __cplusplus
+} // extern "C"
+#endif // __cplusplus
+
+#endif /* STR_INDICES_H */
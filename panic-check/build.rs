@@ -0,0 +1,5 @@
+fn main() {
+    // Fail the link if any symbol is left undefined, which is how a
+    // reachable panic shows up (see the panic handler in `src/lib.rs`).
+    println!("cargo:rustc-cdylib-link-arg=-Wl,--no-undefined");
+}
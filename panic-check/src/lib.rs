@@ -0,0 +1,279 @@
+//! Checks that the public entry points of `str_indices` can't panic.
+//!
+//! This covers the indexing modules (`chars`, `utf16`, the lines modules,
+//! `cursor`, `subslice`, `wrap`, and `avx2`), but not the optional
+//! integrations.
+//!
+//! This crate exports a wrapper for each public function, and its panic
+//! handler calls a function that doesn't exist.  With optimizations on, the
+//! compiler removes every panic that it can prove unreachable, so the
+//! build only links if no panic is reachable from any entry point.  If the
+//! link fails with an undefined `str_indices_can_panic` symbol, some entry
+//! point can panic.
+//!
+//! Run with:
+//!
+//! ```text
+//! cd panic-check && cargo build --release
+//! ```
+//!
+//! This is x86-64 Linux only, because it relies on `--no-undefined` in the
+//! linker and checks the `avx2` module.
+
+#![no_std]
+
+use core::ops::Range;
+use core::panic::PanicInfo;
+
+use str_indices::cursor::Cursor;
+use str_indices::lines::{BreakIndices, LineBreak};
+use str_indices::lines_custom::BreakSet;
+use str_indices::measured::MeasuredStr;
+use str_indices::subslice::SubsliceIndexer;
+use str_indices::{
+    avx2, cesu8, chars, line_break_opportunities, lines, lines_crlf, lines_custom, lines_lf, utf16,
+    wrap, wtf8,
+};
+use str_indices::{
+    BlankLines, ColumnUnit, LineLenStats, LineStarts, Lines, LongLines, TrailingWhitespace, Utf16Lens,
+};
+
+#[panic_handler]
+fn panic(_: &PanicInfo) -> ! {
+    extern "C" {
+        fn str_indices_can_panic() -> !;
+    }
+    unsafe { str_indices_can_panic() }
+}
+
+// The `unsafe` block is for the `avx2` functions.  Only whether they can
+// panic is checked here, not whether they can run on the host.
+macro_rules! check {
+    ($($name:ident($($arg:ident: $t:ty),*) -> $ret:ty = $f:path;)*) => {$(
+        #[no_mangle]
+        #[allow(unused_unsafe)]
+        pub fn $name($($arg: $t),*) -> $ret {
+            unsafe { $f($($arg),*) }
+        }
+    )*};
+}
+
+check! {
+    chars_count(text: &str) -> usize = chars::count;
+    chars_count_and_last_boundary(text: &str) -> (usize, usize) = chars::count_and_last_boundary;
+    chars_count_with_lf(text: &str) -> (usize, usize) = chars::count_with_lf;
+    chars_len_histogram(text: &str) -> [usize; 4] = chars::len_histogram;
+    chars_count_in_range(text: &str, byte_range: Range<usize>) -> usize = chars::count_in_range;
+    chars_from_byte_idx(text: &str, byte_idx: usize) -> usize = chars::from_byte_idx;
+    chars_from_byte_idxs(text: &str, byte_idxs: &[usize], out: &mut [usize]) -> () = chars::from_byte_idxs;
+    chars_from_byte_idx_with_count(text: &str, byte_idx: usize, char_count: usize) -> usize = chars::from_byte_idx_with_count;
+    chars_from_byte_idx_snapped(text: &str, byte_idx: usize) -> (usize, usize) = chars::from_byte_idx_snapped;
+    chars_to_byte_idx(text: &str, char_idx: usize) -> usize = chars::to_byte_idx;
+    chars_to_byte_idx_with_remainder(text: &str, char_idx: usize) -> (usize, usize) = chars::to_byte_idx_with_remainder;
+    chars_offset_by(text: &str, byte_idx: usize, delta: isize) -> usize = chars::offset_by;
+    chars_to_byte_idx_from(text: &str, char_idx: usize, anchor_byte: usize, anchor_char: usize) -> usize = chars::to_byte_idx_from;
+    chars_count_up_to(text: &str, max_chars: usize) -> (usize, usize) = chars::count_up_to;
+    chars_split_at(text: &str, char_idx: usize) -> (&str, &str) = chars::split_at;
+    chars_slice(text: &str, char_range: Range<usize>) -> &str = chars::slice;
+    chars_prefix(text: &str, max_chars: usize) -> &str = chars::prefix;
+    chars_suffix(text: &str, max_chars: usize) -> &str = chars::suffix;
+    chars_nth(text: &str, char_idx: usize) -> Option<char> = chars::nth;
+    chars_floor_byte_boundary(text: &str, byte_idx: usize) -> usize = chars::floor_byte_boundary;
+    chars_ceil_byte_boundary(text: &str, byte_idx: usize) -> usize = chars::ceil_byte_boundary;
+    chars_shrink_range(text: &str, byte_range: Range<usize>) -> Range<usize> = chars::shrink_range;
+    chars_expand_range(text: &str, byte_range: Range<usize>) -> Range<usize> = chars::expand_range;
+    chars_common_prefix(a: &str, b: &str) -> usize = chars::common_prefix;
+    chars_common_suffix(a: &str, b: &str) -> usize = chars::common_suffix;
+    chars_common_prefix_chars(a: &str, b: &str) -> (usize, usize) = chars::common_prefix_chars;
+    chars_common_suffix_chars(a: &str, b: &str) -> (usize, usize) = chars::common_suffix_chars;
+    utf16_count(text: &str) -> usize = utf16::count;
+    utf16_count_surrogates(text: &str) -> usize = utf16::count_surrogates;
+    utf16_from_byte_idx(text: &str, byte_idx: usize) -> usize = utf16::from_byte_idx;
+    utf16_from_byte_idx_with_count(text: &str, byte_idx: usize, utf16_count: usize) -> usize = utf16::from_byte_idx_with_count;
+    utf16_from_byte_idxs(text: &str, byte_idxs: &[usize], out: &mut [usize]) -> () = utf16::from_byte_idxs;
+    utf16_from_byte_idx_snapped(text: &str, byte_idx: usize) -> (usize, usize) = utf16::from_byte_idx_snapped;
+    utf16_to_byte_idx(text: &str, utf16_idx: usize) -> usize = utf16::to_byte_idx;
+    utf16_offset_by(text: &str, byte_idx: usize, delta: isize) -> usize = utf16::offset_by;
+    utf16_count_up_to(text: &str, max_units: usize) -> (usize, usize) = utf16::count_up_to;
+    utf16_to_byte_idxs(text: &str, utf16_idxs: &[usize], out: &mut [usize]) -> () = utf16::to_byte_idxs;
+    utf16_to_byte_idx_with_policy(text: &str, utf16_idx: usize, policy: utf16::SurrogatePolicy) -> Option<usize> = utf16::to_byte_idx_with_policy;
+    utf16_from_char_idx(text: &str, char_idx: usize) -> usize = utf16::from_char_idx;
+    utf16_to_char_idx(text: &str, utf16_idx: usize) -> usize = utf16::to_char_idx;
+    utf16_prefix(text: &str, max_units: usize) -> &str = utf16::prefix;
+    utf16_split_at(text: &str, utf16_idx: usize) -> (&str, &str) = utf16::split_at;
+    line_break_opportunities_count(text: &str) -> usize = line_break_opportunities::count;
+    line_break_opportunities_is_opportunity(text: &str, byte_idx: usize) -> bool = line_break_opportunities::is_opportunity;
+    line_break_opportunities_prev(text: &str, byte_idx: usize) -> Option<usize> = line_break_opportunities::prev;
+    line_break_opportunities_next(text: &str, byte_idx: usize) -> Option<usize> = line_break_opportunities::next;
+    lines_count_breaks(text: &str) -> usize = lines::count_breaks;
+    lines_nth_line(text: &str, line_idx: usize) -> Option<&str> = lines::nth_line;
+    lines_count_lines(text: &str, final_empty_line: bool) -> usize = lines::count_lines;
+    lines_count_breaks_up_to(text: &str, max_bytes: usize, max_breaks: usize) -> (usize, usize) = lines::count_breaks_up_to;
+    lines_from_byte_idx(text: &str, byte_idx: usize) -> usize = lines::from_byte_idx;
+    lines_from_byte_idx_snapped(text: &str, byte_idx: usize) -> (usize, usize) = lines::from_byte_idx_snapped;
+    lines_byte_to_line_col(text: &str, byte_idx: usize, unit: ColumnUnit) -> (usize, usize) = lines::byte_to_line_col;
+    lines_line_col_to_byte(text: &str, line_idx: usize, col: usize, unit: ColumnUnit) -> usize = lines::line_col_to_byte;
+    lines_to_byte_idx(text: &str, line_idx: usize) -> usize = lines::to_byte_idx;
+    lines_split_at(text: &str, line_idx: usize) -> (&str, &str) = lines::split_at;
+    lines_is_break_start(text: &str, byte_idx: usize) -> bool = lines::is_break_start;
+    lines_has_break(text: &str) -> bool = lines::has_break;
+    lines_first_break(text: &str) -> Option<usize> = lines::first_break;
+    lines_trailing_break(text: &str) -> Option<(LineBreak, usize)> = lines::trailing_break;
+    lines_normalized_len(text: &str, target: LineBreak) -> usize = lines::normalized_len;
+    lines_last_line_start(text: &str) -> usize = lines::last_line_start;
+    lines_next_break(text: &str, byte_idx: usize) -> Option<(Range<usize>, LineBreak)> = lines::next_break;
+    lines_prev_break(text: &str, byte_idx: usize) -> Option<(Range<usize>, LineBreak)> = lines::prev_break;
+    lines_from_lines_crlf_idx(text: &str, line_idx: usize) -> usize = lines::from_lines_crlf_idx;
+    lines_from_lines_lf_idx(text: &str, line_idx: usize) -> usize = lines::from_lines_lf_idx;
+    lines_start_offsets(text: &str) -> LineStarts<'_> = lines::start_offsets;
+    lines_iter(text: &str, include_break: bool) -> Lines<'_> = lines::iter;
+    lines_line_span(text: &str, line_idx: usize) -> Range<usize> = lines::line_span;
+    lines_line_content_range(text: &str, line_idx: usize, trim: bool) -> Range<usize> = lines::line_content_range;
+    lines_line_content(text: &str, line_idx: usize, trim: bool) -> &str = lines::line_content;
+    lines_line_len(text: &str, line_idx: usize, unit: ColumnUnit) -> usize = lines::line_len;
+    lines_line_at_byte(text: &str, byte_idx: usize, include_break: bool) -> &str = lines::line_at_byte;
+    lines_common_prefix(a: &str, b: &str) -> usize = lines::common_prefix;
+    lines_common_suffix(a: &str, b: &str) -> usize = lines::common_suffix;
+    lines_len_stats(text: &str, final_empty_line: bool) -> LineLenStats = lines::len_stats;
+    lines_max_line_len(text: &str) -> (usize, usize) = lines::max_line_len;
+    lines_count_blank_lines(text: &str, final_empty_line: bool) -> usize = lines::count_blank_lines;
+    lines_count_nonblank(text: &str) -> usize = lines::count_nonblank;
+    lines_crlf_count_breaks(text: &str) -> usize = lines_crlf::count_breaks;
+    lines_crlf_count_lines(text: &str, final_empty_line: bool) -> usize = lines_crlf::count_lines;
+    lines_crlf_from_byte_idx(text: &str, byte_idx: usize) -> usize = lines_crlf::from_byte_idx;
+    lines_crlf_from_byte_idx_snapped(text: &str, byte_idx: usize) -> (usize, usize) = lines_crlf::from_byte_idx_snapped;
+    lines_crlf_byte_to_line_col(text: &str, byte_idx: usize, unit: ColumnUnit) -> (usize, usize) = lines_crlf::byte_to_line_col;
+    lines_crlf_line_col_to_byte(text: &str, line_idx: usize, col: usize, unit: ColumnUnit) -> usize = lines_crlf::line_col_to_byte;
+    lines_crlf_to_byte_idx(text: &str, line_idx: usize) -> usize = lines_crlf::to_byte_idx;
+    lines_crlf_split_at(text: &str, line_idx: usize) -> (&str, &str) = lines_crlf::split_at;
+    lines_crlf_is_break_start(text: &str, byte_idx: usize) -> bool = lines_crlf::is_break_start;
+    lines_crlf_has_break(text: &str) -> bool = lines_crlf::has_break;
+    lines_crlf_first_break(text: &str) -> Option<usize> = lines_crlf::first_break;
+    lines_crlf_from_lines_idx(text: &str, line_idx: usize) -> usize = lines_crlf::from_lines_idx;
+    lines_crlf_from_lines_lf_idx(text: &str, line_idx: usize) -> usize = lines_crlf::from_lines_lf_idx;
+    lines_crlf_start_offsets(text: &str) -> LineStarts<'_> = lines_crlf::start_offsets;
+    lines_crlf_iter(text: &str, include_break: bool) -> Lines<'_> = lines_crlf::iter;
+    lines_crlf_line_span(text: &str, line_idx: usize) -> Range<usize> = lines_crlf::line_span;
+    lines_crlf_line_content_range(text: &str, line_idx: usize, trim: bool) -> Range<usize> = lines_crlf::line_content_range;
+    lines_crlf_line_content(text: &str, line_idx: usize, trim: bool) -> &str = lines_crlf::line_content;
+    lines_crlf_line_len(text: &str, line_idx: usize, unit: ColumnUnit) -> usize = lines_crlf::line_len;
+    lines_crlf_line_at_byte(text: &str, byte_idx: usize, include_break: bool) -> &str = lines_crlf::line_at_byte;
+    lines_crlf_common_prefix(a: &str, b: &str) -> usize = lines_crlf::common_prefix;
+    lines_crlf_common_suffix(a: &str, b: &str) -> usize = lines_crlf::common_suffix;
+    lines_crlf_len_stats(text: &str, final_empty_line: bool) -> LineLenStats = lines_crlf::len_stats;
+    lines_crlf_max_line_len(text: &str) -> (usize, usize) = lines_crlf::max_line_len;
+    lines_crlf_count_blank_lines(text: &str, final_empty_line: bool) -> usize = lines_crlf::count_blank_lines;
+    lines_crlf_count_nonblank(text: &str) -> usize = lines_crlf::count_nonblank;
+    lines_crlf_count_crlf(text: &str) -> usize = lines_crlf::count_crlf;
+    lines_crlf_lf_normalized_byte_idx(text: &str, byte_idx: usize) -> usize = lines_crlf::lf_normalized_byte_idx;
+    lines_custom_count_breaks(text: &str, breaks: BreakSet) -> usize = lines_custom::count_breaks;
+    lines_custom_count_lines(text: &str, final_empty_line: bool, breaks: BreakSet) -> usize = lines_custom::count_lines;
+    lines_custom_from_byte_idx(text: &str, byte_idx: usize, breaks: BreakSet) -> usize = lines_custom::from_byte_idx;
+    lines_custom_to_byte_idx(text: &str, line_idx: usize, breaks: BreakSet) -> usize = lines_custom::to_byte_idx;
+    lines_custom_is_break_start(text: &str, byte_idx: usize, breaks: BreakSet) -> bool = lines_custom::is_break_start;
+    lines_custom_first_break(text: &str, breaks: BreakSet) -> Option<usize> = lines_custom::first_break;
+    lines_lf_count_breaks(text: &str) -> usize = lines_lf::count_breaks;
+    lines_lf_count_lines(text: &str, final_empty_line: bool) -> usize = lines_lf::count_lines;
+    lines_lf_from_byte_idx(text: &str, byte_idx: usize) -> usize = lines_lf::from_byte_idx;
+    lines_lf_from_byte_idx_snapped(text: &str, byte_idx: usize) -> (usize, usize) = lines_lf::from_byte_idx_snapped;
+    lines_lf_byte_to_line_col(text: &str, byte_idx: usize, unit: ColumnUnit) -> (usize, usize) = lines_lf::byte_to_line_col;
+    lines_lf_line_col_to_byte(text: &str, line_idx: usize, col: usize, unit: ColumnUnit) -> usize = lines_lf::line_col_to_byte;
+    lines_lf_to_byte_idx(text: &str, line_idx: usize) -> usize = lines_lf::to_byte_idx;
+    lines_lf_split_at(text: &str, line_idx: usize) -> (&str, &str) = lines_lf::split_at;
+    lines_lf_is_break_start(text: &str, byte_idx: usize) -> bool = lines_lf::is_break_start;
+    lines_lf_has_break(text: &str) -> bool = lines_lf::has_break;
+    lines_lf_first_break(text: &str) -> Option<usize> = lines_lf::first_break;
+    lines_lf_from_lines_idx(text: &str, line_idx: usize) -> usize = lines_lf::from_lines_idx;
+    lines_lf_from_lines_crlf_idx(text: &str, line_idx: usize) -> usize = lines_lf::from_lines_crlf_idx;
+    lines_lf_start_offsets(text: &str) -> LineStarts<'_> = lines_lf::start_offsets;
+    lines_lf_iter(text: &str, include_break: bool) -> Lines<'_> = lines_lf::iter;
+    lines_lf_line_span(text: &str, line_idx: usize) -> Range<usize> = lines_lf::line_span;
+    lines_lf_line_content_range(text: &str, line_idx: usize, trim: bool) -> Range<usize> = lines_lf::line_content_range;
+    lines_lf_line_content(text: &str, line_idx: usize, trim: bool) -> &str = lines_lf::line_content;
+    lines_lf_line_len(text: &str, line_idx: usize, unit: ColumnUnit) -> usize = lines_lf::line_len;
+    lines_lf_line_at_byte(text: &str, byte_idx: usize, include_break: bool) -> &str = lines_lf::line_at_byte;
+    lines_lf_common_prefix(a: &str, b: &str) -> usize = lines_lf::common_prefix;
+    lines_lf_common_suffix(a: &str, b: &str) -> usize = lines_lf::common_suffix;
+    lines_lf_len_stats(text: &str, final_empty_line: bool) -> LineLenStats = lines_lf::len_stats;
+    lines_lf_max_line_len(text: &str) -> (usize, usize) = lines_lf::max_line_len;
+    lines_lf_count_blank_lines(text: &str, final_empty_line: bool) -> usize = lines_lf::count_blank_lines;
+    lines_lf_count_nonblank(text: &str) -> usize = lines_lf::count_nonblank;
+    wrap_wrap_point(text: &str, row_start: usize, max_chars: usize) -> usize = wrap::wrap_point;
+    wrap_wrap_point_with(text: &str, row_start: usize, max_width: usize, char_width: fn(char) -> usize) -> usize = wrap::wrap_point_with;
+
+    // Iterators.
+    chars_ascii_runs_next(iter: &mut chars::AsciiRuns) -> Option<(Range<usize>, bool)> = Iterator::next;
+    chars_boundary_mask(text: &str, out: &mut [u64]) -> () = chars::boundary_mask;
+    chars_boundaries_next(iter: &mut chars::Boundaries) -> Option<usize> = Iterator::next;
+    chars_boundaries_count(iter: chars::Boundaries) -> usize = Iterator::count;
+    chars_boundaries_next_back(iter: &mut chars::Boundaries) -> Option<usize> = DoubleEndedIterator::next_back;
+    chars_boundaries_nth_back(iter: &mut chars::Boundaries, n: usize) -> Option<usize> = DoubleEndedIterator::nth_back;
+    chars_indices_next(iter: &mut chars::Indices) -> Option<(usize, usize)> = Iterator::next;
+    utf16_surrogate_indices_next(iter: &mut utf16::SurrogateIndices) -> Option<(usize, usize)> = Iterator::next;
+    utf16_lens_next(iter: &mut Utf16Lens) -> Option<usize> = Iterator::next;
+    blank_lines_next(iter: &mut BlankLines) -> Option<usize> = Iterator::next;
+    long_lines_next(iter: &mut LongLines) -> Option<usize> = Iterator::next;
+    lines_break_indices_next(iter: &mut BreakIndices) -> Option<(usize, LineBreak)> = Iterator::next;
+    lines_break_indices_next_back(iter: &mut BreakIndices) -> Option<(usize, LineBreak)> = DoubleEndedIterator::next_back;
+    lines_next(iter: &mut Lines<'static>) -> Option<&'static str> = Iterator::next;
+    line_starts_next(iter: &mut LineStarts) -> Option<usize> = Iterator::next;
+    trailing_whitespace_next(iter: &mut TrailingWhitespace) -> Option<Option<usize>> = Iterator::next;
+
+    // Cursor.
+    cursor_move_to_byte(cursor: &mut Cursor, byte_idx: usize) -> () = Cursor::move_to_byte;
+    cursor_move_to_char(cursor: &mut Cursor, char_idx: usize) -> () = Cursor::move_to_char;
+    cursor_move_to_line(cursor: &mut Cursor, line_idx: usize) -> () = Cursor::move_to_line;
+    cursor_move_by_chars(cursor: &mut Cursor, n: isize) -> () = Cursor::move_by_chars;
+    cursor_move_by_lines(cursor: &mut Cursor, n: isize) -> () = Cursor::move_by_lines;
+
+    // Subslice indexer.
+    subslice_new(text: &str, byte_range: Range<usize>) -> SubsliceIndexer<'_> = SubsliceIndexer::new;
+    subslice_char_from_byte_idx(indexer: &SubsliceIndexer, byte_idx: usize) -> usize = SubsliceIndexer::char_from_byte_idx;
+    subslice_char_to_byte_idx(indexer: &SubsliceIndexer, char_idx: usize) -> usize = SubsliceIndexer::char_to_byte_idx;
+    subslice_utf16_from_byte_idx(indexer: &SubsliceIndexer, byte_idx: usize) -> usize = SubsliceIndexer::utf16_from_byte_idx;
+    subslice_utf16_to_byte_idx(indexer: &SubsliceIndexer, utf16_idx: usize) -> usize = SubsliceIndexer::utf16_to_byte_idx;
+    subslice_lines_from_byte_idx(indexer: &SubsliceIndexer, byte_idx: usize) -> usize = SubsliceIndexer::lines_from_byte_idx;
+    subslice_lines_to_byte_idx(indexer: &SubsliceIndexer, line_idx: usize) -> usize = SubsliceIndexer::lines_to_byte_idx;
+    subslice_lines_crlf_from_byte_idx(indexer: &SubsliceIndexer, byte_idx: usize) -> usize = SubsliceIndexer::lines_crlf_from_byte_idx;
+    subslice_lines_crlf_to_byte_idx(indexer: &SubsliceIndexer, line_idx: usize) -> usize = SubsliceIndexer::lines_crlf_to_byte_idx;
+    subslice_lines_lf_from_byte_idx(indexer: &SubsliceIndexer, byte_idx: usize) -> usize = SubsliceIndexer::lines_lf_from_byte_idx;
+    subslice_lines_lf_to_byte_idx(indexer: &SubsliceIndexer, line_idx: usize) -> usize = SubsliceIndexer::lines_lf_to_byte_idx;
+
+    // Measured string slice.
+    measured_new(text: &str) -> MeasuredStr<'_> = MeasuredStr::new;
+    measured_char_from_byte_idx(text: &MeasuredStr, byte_idx: usize) -> usize = MeasuredStr::char_from_byte_idx;
+    measured_char_to_byte_idx(text: &MeasuredStr, char_idx: usize) -> usize = MeasuredStr::char_to_byte_idx;
+    measured_utf16_from_byte_idx(text: &MeasuredStr, byte_idx: usize) -> usize = MeasuredStr::utf16_from_byte_idx;
+    measured_utf16_to_byte_idx(text: &MeasuredStr, utf16_idx: usize) -> usize = MeasuredStr::utf16_to_byte_idx;
+    measured_lines_from_byte_idx(text: &MeasuredStr, byte_idx: usize) -> usize = MeasuredStr::lines_from_byte_idx;
+    measured_lines_to_byte_idx(text: &MeasuredStr, line_idx: usize) -> usize = MeasuredStr::lines_to_byte_idx;
+
+    // AVX2 entry points.
+    cesu8_count(text: &[u8]) -> usize = cesu8::count;
+    cesu8_count_surrogates(text: &[u8]) -> usize = cesu8::count_surrogates;
+    cesu8_from_byte_idx(text: &[u8], byte_idx: usize) -> usize = cesu8::from_byte_idx;
+    cesu8_to_byte_idx(text: &[u8], utf16_idx: usize) -> usize = cesu8::to_byte_idx;
+    wtf8_chars_count(text: &[u8]) -> usize = wtf8::chars::count;
+    wtf8_chars_from_byte_idx(text: &[u8], byte_idx: usize) -> usize = wtf8::chars::from_byte_idx;
+    wtf8_chars_to_byte_idx(text: &[u8], char_idx: usize) -> usize = wtf8::chars::to_byte_idx;
+    wtf8_utf16_count(text: &[u8]) -> usize = wtf8::utf16::count;
+    wtf8_utf16_count_surrogates(text: &[u8]) -> usize = wtf8::utf16::count_surrogates;
+    wtf8_utf16_from_byte_idx(text: &[u8], byte_idx: usize) -> usize = wtf8::utf16::from_byte_idx;
+    wtf8_utf16_to_byte_idx(text: &[u8], utf16_idx: usize) -> usize = wtf8::utf16::to_byte_idx;
+    avx2_chars_count(text: &str) -> usize = avx2::chars::count;
+    avx2_chars_from_byte_idx(text: &str, byte_idx: usize) -> usize = avx2::chars::from_byte_idx;
+    avx2_chars_to_byte_idx(text: &str, char_idx: usize) -> usize = avx2::chars::to_byte_idx;
+    avx2_utf16_count(text: &str) -> usize = avx2::utf16::count;
+    avx2_utf16_count_surrogates(text: &str) -> usize = avx2::utf16::count_surrogates;
+    avx2_utf16_from_byte_idx(text: &str, byte_idx: usize) -> usize = avx2::utf16::from_byte_idx;
+    avx2_utf16_to_byte_idx(text: &str, utf16_idx: usize) -> usize = avx2::utf16::to_byte_idx;
+    avx2_lines_count_breaks(text: &str) -> usize = avx2::lines::count_breaks;
+    avx2_lines_from_byte_idx(text: &str, byte_idx: usize) -> usize = avx2::lines::from_byte_idx;
+    avx2_lines_to_byte_idx(text: &str, line_idx: usize) -> usize = avx2::lines::to_byte_idx;
+    avx2_lines_crlf_count_breaks(text: &str) -> usize = avx2::lines_crlf::count_breaks;
+    avx2_lines_crlf_from_byte_idx(text: &str, byte_idx: usize) -> usize = avx2::lines_crlf::from_byte_idx;
+    avx2_lines_crlf_to_byte_idx(text: &str, line_idx: usize) -> usize = avx2::lines_crlf::to_byte_idx;
+    avx2_lines_lf_count_breaks(text: &str) -> usize = avx2::lines_lf::count_breaks;
+    avx2_lines_lf_from_byte_idx(text: &str, byte_idx: usize) -> usize = avx2::lines_lf::from_byte_idx;
+    avx2_lines_lf_to_byte_idx(text: &str, line_idx: usize) -> usize = avx2::lines_lf::to_byte_idx;
+}
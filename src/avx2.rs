@@ -0,0 +1,273 @@
+//! AVX2 versions of the counting and conversion functions, for callers
+//! that do their own CPU feature detection.
+//!
+//! The rest of this crate only uses instructions that are guaranteed to be
+//! available on the target (SSE2 on x86-64), so that it never needs to
+//! check at run time.  The functions here instead process 32 bytes at a
+//! time with AVX2, and are `unsafe` because calling them on a CPU without
+//! AVX2 is undefined behavior.  Check for it first, e.g. with
+//! `std::is_x86_feature_detected!("avx2")`.
+//!
+//! Each function gives the same results as its counterpart elsewhere in the
+//! crate.
+//!
+//! Only available on x86-64.
+
+use core::arch::x86_64::__m256i;
+
+/// AVX2 versions of the [`chars`](crate::chars) functions.
+pub mod chars {
+    use super::__m256i;
+
+    /// Counts the utf8 chars in a string slice.
+    ///
+    /// See [`crate::chars::count()`].
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn count(text: &str) -> usize {
+        crate::chars::count_impl::<__m256i>(text.as_bytes())
+    }
+
+    /// Converts from byte-index to char-index in a string slice.
+    ///
+    /// See [`crate::chars::from_byte_idx()`].
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn from_byte_idx(text: &str, byte_idx: usize) -> usize {
+        crate::chars::from_byte_idx_impl::<__m256i>(text, byte_idx)
+    }
+
+    /// Converts from char-index to byte-index in a string slice.
+    ///
+    /// See [`crate::chars::to_byte_idx()`].
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn to_byte_idx(text: &str, char_idx: usize) -> usize {
+        crate::chars::to_byte_idx_impl::<__m256i>(text.as_bytes(), char_idx)
+    }
+}
+
+/// AVX2 versions of the [`utf16`](crate::utf16) functions.
+pub mod utf16 {
+    use super::__m256i;
+
+    /// Counts the utf16 code units that would be in a string slice if it
+    /// were encoded as utf16.
+    ///
+    /// See [`crate::utf16::count()`].
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn count(text: &str) -> usize {
+        crate::utf16::count_impl::<__m256i>(text.as_bytes())
+    }
+
+    /// Counts the utf16 surrogate pairs that would be in a string slice if
+    /// it were encoded as utf16.
+    ///
+    /// See [`crate::utf16::count_surrogates()`].
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn count_surrogates(text: &str) -> usize {
+        crate::utf16::count_surrogates_impl::<__m256i>(text.as_bytes())
+    }
+
+    /// Converts from byte-index to utf16-code-unit-index in a string slice.
+    ///
+    /// See [`crate::utf16::from_byte_idx()`].
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn from_byte_idx(text: &str, byte_idx: usize) -> usize {
+        crate::utf16::from_byte_idx_impl::<__m256i>(text, byte_idx)
+    }
+
+    /// Converts from utf16-code-unit-index to byte-index in a string slice.
+    ///
+    /// See [`crate::utf16::to_byte_idx()`].
+    ///
+    /// # Safety
+    ///
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub unsafe fn to_byte_idx(text: &str, utf16_idx: usize) -> usize {
+        crate::utf16::to_byte_idx_impl::<__m256i>(text, utf16_idx)
+    }
+}
+
+/// `$text_arg` converts the text to what the module's `to_byte_idx_impl()`
+/// takes.
+macro_rules! lines_module {
+    ($name:ident, $text_arg:path, $doc:literal) => {
+        #[doc = $doc]
+        pub mod $name {
+            use super::__m256i;
+
+            /// Counts the line breaks in a string slice.
+            ///
+            #[doc = concat!("See [`crate::", stringify!($name), "::count_breaks()`].")]
+            ///
+            /// # Safety
+            ///
+            /// The CPU must support AVX2.
+            #[target_feature(enable = "avx2")]
+            pub unsafe fn count_breaks(text: &str) -> usize {
+                crate::$name::count_breaks_impl::<__m256i>(text.as_bytes())
+            }
+
+            /// Converts from byte-index to line-index in a string slice.
+            ///
+            #[doc = concat!("See [`crate::", stringify!($name), "::from_byte_idx()`].")]
+            ///
+            /// # Safety
+            ///
+            /// The CPU must support AVX2.
+            #[target_feature(enable = "avx2")]
+            pub unsafe fn from_byte_idx(text: &str, byte_idx: usize) -> usize {
+                crate::$name::from_byte_idx_impl::<__m256i>(text, byte_idx)
+            }
+
+            /// Converts from line-index to byte-index in a string slice.
+            ///
+            #[doc = concat!("See [`crate::", stringify!($name), "::to_byte_idx()`].")]
+            ///
+            /// # Safety
+            ///
+            /// The CPU must support AVX2.
+            #[target_feature(enable = "avx2")]
+            pub unsafe fn to_byte_idx(text: &str, line_idx: usize) -> usize {
+                crate::$name::to_byte_idx_impl::<__m256i>($text_arg(text), line_idx)
+            }
+        }
+    };
+}
+
+lines_module!(
+    lines,
+    core::convert::identity,
+    "AVX2 versions of the [`lines`](crate::lines) functions."
+);
+lines_module!(
+    lines_crlf,
+    str::as_bytes,
+    "AVX2 versions of the [`lines_crlf`](crate::lines_crlf) functions."
+);
+lines_module!(
+    lines_lf,
+    str::as_bytes,
+    "AVX2 versions of the [`lines_lf`](crate::lines_lf) functions."
+);
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    const TEXTS: &[&str] = &[
+        "",
+        "Hello\r\nworld!\n",
+        "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r\n",
+    ];
+
+    /// Longer texts with chunk and CRLF boundaries at every offset.
+    fn texts() -> impl Iterator<Item = std::string::String> {
+        TEXTS.iter().flat_map(|text| {
+            (0..40).map(move |pad| {
+                let mut s = "a".repeat(pad);
+                for _ in 0..5 {
+                    s.push_str(text);
+                }
+                s
+            })
+        })
+    }
+
+    #[test]
+    fn matches_default_backend() {
+        if !std::is_x86_feature_detected!("avx2") {
+            return;
+        }
+        for text in texts() {
+            let text = text.as_str();
+            unsafe {
+                assert_eq!(crate::chars::count(text), super::chars::count(text));
+                assert_eq!(crate::utf16::count(text), super::utf16::count(text));
+                assert_eq!(
+                    crate::utf16::count_surrogates(text),
+                    super::utf16::count_surrogates(text)
+                );
+                assert_eq!(
+                    crate::lines::count_breaks(text),
+                    super::lines::count_breaks(text)
+                );
+                assert_eq!(
+                    crate::lines_crlf::count_breaks(text),
+                    super::lines_crlf::count_breaks(text)
+                );
+                assert_eq!(
+                    crate::lines_lf::count_breaks(text),
+                    super::lines_lf::count_breaks(text)
+                );
+                for i in 0..(text.len() + 2) {
+                    assert_eq!(
+                        crate::chars::from_byte_idx(text, i),
+                        super::chars::from_byte_idx(text, i)
+                    );
+                    assert_eq!(
+                        crate::chars::to_byte_idx(text, i),
+                        super::chars::to_byte_idx(text, i)
+                    );
+                    assert_eq!(
+                        crate::utf16::from_byte_idx(text, i),
+                        super::utf16::from_byte_idx(text, i)
+                    );
+                    assert_eq!(
+                        crate::utf16::to_byte_idx(text, i),
+                        super::utf16::to_byte_idx(text, i)
+                    );
+                    assert_eq!(
+                        crate::lines::from_byte_idx(text, i),
+                        super::lines::from_byte_idx(text, i)
+                    );
+                    assert_eq!(
+                        crate::lines::to_byte_idx(text, i),
+                        super::lines::to_byte_idx(text, i)
+                    );
+                    assert_eq!(
+                        crate::lines_crlf::from_byte_idx(text, i),
+                        super::lines_crlf::from_byte_idx(text, i)
+                    );
+                    assert_eq!(
+                        crate::lines_crlf::to_byte_idx(text, i),
+                        super::lines_crlf::to_byte_idx(text, i)
+                    );
+                    assert_eq!(
+                        crate::lines_lf::from_byte_idx(text, i),
+                        super::lines_lf::from_byte_idx(text, i)
+                    );
+                    assert_eq!(
+                        crate::lines_lf::to_byte_idx(text, i),
+                        super::lines_lf::to_byte_idx(text, i)
+                    );
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,149 @@
+//! Information about the implementation this crate was built with.
+//!
+//! The counting and conversion functions process text a chunk of bytes at
+//! a time, using SIMD where available.  Which implementation is used is
+//! decided at build time, from the target architecture and the `simd`
+//! feature.  Data structures built on this crate (e.g. ropes) can use this
+//! module to size and align their text segments to suit it.
+//!
+//! For debugging, the scalar implementation can also be forced at run
+//! time with [`set_force_scalar()`], to rule the SIMD code in or out as the
+//! cause of a problem without recompiling.
+
+use core::sync::atomic::{AtomicBool, Ordering};
+
+use crate::byte_chunk::{ByteChunk, Chunk};
+
+static FORCE_SCALAR: AtomicBool = AtomicBool::new(false);
+
+/// An implementation of the chunked text processing.
+#[non_exhaustive]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Backend {
+    /// Portable scalar code, processing a `usize` at a time.
+    Scalar,
+    /// x86-64 SSE2.
+    Sse2,
+    /// AArch64 NEON.
+    Neon,
+}
+
+impl Backend {
+    /// Returns the name of the backend, for e.g. logging.
+    pub const fn name(self) -> &'static str {
+        match self {
+            Backend::Scalar => "scalar",
+            Backend::Sse2 => "sse2",
+            Backend::Neon => "neon",
+        }
+    }
+}
+
+/// Returns the backend this crate was built with.
+///
+/// This doesn't take [`set_force_scalar()`] into account.
+#[inline]
+pub const fn backend() -> Backend {
+    #[cfg(all(feature = "simd", target_arch = "x86_64"))]
+    return Backend::Sse2;
+
+    #[cfg(all(feature = "simd", target_arch = "aarch64"))]
+    return Backend::Neon;
+
+    #[cfg(any(
+        not(feature = "simd"),
+        not(any(target_arch = "x86_64", target_arch = "aarch64"))
+    ))]
+    return Backend::Scalar;
+}
+
+/// Returns the number of bytes the backend processes at a time.
+#[inline]
+pub const fn chunk_size() -> usize {
+    Chunk::SIZE
+}
+
+/// Returns the alignment, in bytes, that the backend reads chunks at.
+///
+/// Bytes before the first aligned chunk and after the last one are
+/// processed more slowly, so text segments whose start is aligned to this
+/// are processed (slightly) faster.
+#[inline]
+pub const fn chunk_align() -> usize {
+    core::mem::align_of::<Chunk>()
+}
+
+/// Returns a recommended minimum length, in bytes, for text segments.
+///
+/// This is the length at which the backend's fastest code paths take over
+/// regardless of alignment.  Texts can be of any length, but segmenting
+/// text into pieces shorter than this leaves performance on the table.
+#[inline]
+pub const fn min_segment_len() -> usize {
+    // Enough for a full unrolled iteration, plus the unaligned bytes that
+    // may precede it.
+    Chunk::SIZE * (Chunk::UNROLL + 1)
+}
+
+/// Forces (or stops forcing) the use of the scalar implementation,
+/// process-wide.
+///
+/// This is meant for debugging: if results change when the scalar
+/// implementation is forced, the SIMD code is at fault.  It affects all
+/// functions of the crate except those of the [`avx2`](crate::avx2)
+/// module, which are always explicitly AVX2.  Note that with the `memchr`
+/// feature, `memchr` is still used by the scalar implementation.
+///
+/// Functions that are running while this is called may use either
+/// implementation.
+pub fn set_force_scalar(force: bool) {
+    FORCE_SCALAR.store(force, Ordering::Relaxed);
+}
+
+/// Returns whether the scalar implementation is being forced with
+/// [`set_force_scalar()`].
+#[inline]
+pub fn is_scalar_forced() -> bool {
+    FORCE_SCALAR.load(Ordering::Relaxed)
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backend_01() {
+        assert!(chunk_size().is_power_of_two());
+        assert!(chunk_align().is_power_of_two());
+        assert!(chunk_align() <= chunk_size());
+        assert!(min_segment_len() > chunk_size());
+        match backend() {
+            Backend::Scalar => assert_eq!(core::mem::size_of::<usize>(), chunk_size()),
+            Backend::Sse2 | Backend::Neon => assert_eq!(16, chunk_size()),
+        }
+    }
+
+    #[test]
+    fn force_scalar_01() {
+        // Other tests may run while the scalar implementation is forced,
+        // but they should give the same results either way.
+        let text = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r\n".repeat(20);
+        let results = |text: &str| {
+            (
+                crate::chars::count(text),
+                crate::utf16::to_byte_idx(text, 200),
+                crate::lines::count_breaks(text),
+                crate::lines_crlf::from_byte_idx(text, 300),
+                crate::lines_lf::to_byte_idx(text, 13),
+            )
+        };
+        let expected = results(&text);
+        set_force_scalar(true);
+        assert!(is_scalar_forced());
+        assert_eq!(expected, results(&text));
+        set_force_scalar(false);
+        assert!(!is_scalar_forced());
+    }
+}
@@ -15,16 +15,64 @@ pub(crate) type Chunk = aarch64::uint8x16_t;
 ))]
 pub(crate) type Chunk = usize;
 
+/// Calls a function that is generic over `ByteChunk` with the chunk type
+/// to use: normally `Chunk`, but `usize` if the scalar fallback has been
+/// forced at run time with `backend::set_force_scalar()`.
+///
+/// E.g. `with_chunk!(count_impl(text))`.
+macro_rules! with_chunk {
+    ($($f:ident)::+($($arg:expr),*)) => {
+        if crate::backend::is_scalar_forced() {
+            $($f)::+::<usize>($($arg),*)
+        } else {
+            $($f)::+::<crate::byte_chunk::Chunk>($($arg),*)
+        }
+    };
+}
+
+// Compile-time check that the unroll factors are valid.
+const _: () = assert!(Chunk::UNROLL >= 1 && Chunk::UNROLL <= Chunk::MAX_ACC);
+const _: () = assert!(usize::UNROLL >= 1 && usize::UNROLL <= usize::MAX_ACC);
+
 /// Interface for working with chunks of bytes at a time, providing the
 /// operations needed for the functionality in str_utils.
 pub(crate) trait ByteChunk: Copy + Clone {
     /// Size of the chunk in bytes.
     const SIZE: usize;
 
+    /// Whether this is an explicit SIMD type, as opposed to the scalar
+    /// fallback.
+    #[cfg_attr(not(feature = "memchr"), allow(dead_code))]
+    const IS_SIMD: bool;
+
     /// Maximum number of iterations the chunk can accumulate
     /// before sum_bytes() becomes inaccurate.
     const MAX_ACC: usize;
 
+    /// Number of chunks the hot counting loops process per iteration.
+    ///
+    /// Tuned per backend with the benchmark suite.  Must be at least 1
+    /// and at most `MAX_ACC`.
+    const UNROLL: usize;
+
+    // Length thresholds, in bytes, below which the respective functions
+    // bypass the chunk-based code in favor of a simple scalar loop.  Where
+    // the scalar loop stops being competitive depends on both the function
+    // and the backend's setup costs, so these can be tuned per backend with
+    // the benchmark suite.
+
+    /// Threshold for `chars::count()` and `chars::from_byte_idx()`.
+    const CHARS_COUNT_THRESHOLD: usize;
+
+    /// Threshold for `chars::to_byte_idx()`.
+    const CHARS_TO_BYTE_IDX_THRESHOLD: usize;
+
+    /// Threshold for `utf16::count_surrogates()`.
+    const UTF16_SURROGATES_THRESHOLD: usize;
+
+    /// Threshold for `lines_lf::count_breaks()`.
+    const LF_COUNT_THRESHOLD: usize;
+
     /// Creates a new chunk with all bytes set to zero.
     fn zero() -> Self;
 
@@ -35,6 +83,8 @@ pub(crate) trait ByteChunk: Copy + Clone {
     fn is_zero(&self) -> bool;
 
     /// Shifts bytes back lexographically by n bytes.
+    ///
+    /// Shifting by `SIZE` bytes or more results in all zeros.
     fn shift_back_lex(&self, n: usize) -> Self;
 
     /// Shifts the bottom byte of self into the top byte of n.
@@ -58,6 +108,12 @@ pub(crate) trait ByteChunk: Copy + Clone {
     /// Performs a bitwise and on two chunks.
     fn bitand(&self, other: Self) -> Self;
 
+    /// Performs a bitwise or on two chunks.
+    fn bitor(&self, other: Self) -> Self;
+
+    /// Performs a bitwise xor on two chunks.
+    fn bitxor(&self, other: Self) -> Self;
+
     /// Adds the bytes of two chunks together.
     fn add(&self, other: Self) -> Self;
 
@@ -72,151 +128,367 @@ pub(crate) trait ByteChunk: Copy + Clone {
 
     /// Returns the sum of all bytes in the chunk.
     fn sum_bytes(&self) -> usize;
+
+    /// Packs a chunk of flag bytes (each 0 or 1) into the low `SIZE` bits
+    /// of an integer, with the nth lexographic byte in the nth bit.
+    fn pack_flags(&self) -> u64;
+}
+
+/// Implements `ByteChunk` for a primitive unsigned integer type, treating
+/// it as a SWAR ("SIMD within a register") vector of bytes.
+///
+/// Besides `usize`, this is used in tests to exercise the chunk-based code
+/// with word sizes other than the host's (e.g. the 16-bit words of AVR and
+/// MSP430 targets).
+macro_rules! impl_byte_chunk_for_uint {
+    ($t:ty) => {
+        impl ByteChunk for $t {
+            const SIZE: usize = core::mem::size_of::<$t>();
+            const IS_SIMD: bool = false;
+            const MAX_ACC: usize = (256 / core::mem::size_of::<$t>()) - 1;
+            // The scalar ops are cheap relative to loop overhead, so a wide
+            // unroll pays off here.
+            const UNROLL: usize = 16;
+            // The chunk path is already the scalar path, so only bypass it
+            // where it can't do at least one full chunk.
+            const CHARS_COUNT_THRESHOLD: usize = Self::SIZE;
+            const CHARS_TO_BYTE_IDX_THRESHOLD: usize = Self::SIZE + 1;
+            const UTF16_SURROGATES_THRESHOLD: usize = Self::SIZE + 3;
+            const LF_COUNT_THRESHOLD: usize = Self::SIZE;
+
+            #[inline(always)]
+            fn zero() -> Self {
+                0
+            }
+
+            #[inline(always)]
+            fn splat(n: u8) -> Self {
+                const ONES: $t = <$t>::MAX / 0xFF;
+                ONES * n as $t
+            }
+
+            #[inline(always)]
+            fn is_zero(&self) -> bool {
+                *self == 0
+            }
+
+            #[inline(always)]
+            fn shift_back_lex(&self, n: usize) -> Self {
+                // Shifting by the full width or more must yield zero, which
+                // matters for the 3-byte line breaks on 16-bit words.
+                let shift = (n * 8) as u32;
+                if cfg!(target_endian = "little") {
+                    self.checked_shr(shift).unwrap_or(0)
+                } else {
+                    self.checked_shl(shift).unwrap_or(0)
+                }
+            }
+
+            #[inline(always)]
+            fn shift_across(&self, n: Self) -> Self {
+                let shift_distance = (Self::SIZE - 1) * 8;
+                if cfg!(target_endian = "little") {
+                    (*self >> shift_distance) | (n << 8)
+                } else {
+                    (*self << shift_distance) | (n >> 8)
+                }
+            }
+
+            #[inline(always)]
+            fn shr(&self, n: usize) -> Self {
+                *self >> n
+            }
+
+            #[inline(always)]
+            fn cmp_eq_byte(&self, byte: u8) -> Self {
+                const ONES: $t = <$t>::MAX / 0xFF;
+                const ONES_HIGH: $t = ONES << 7;
+                let word = *self ^ (byte as $t * ONES);
+                (!(((word & !ONES_HIGH) + !ONES_HIGH) | word) & ONES_HIGH) >> 7
+            }
+
+            #[inline(always)]
+            fn bytes_between_127(&self, a: u8, b: u8) -> Self {
+                const ONES: $t = <$t>::MAX / 0xFF;
+                const ONES_HIGH: $t = ONES << 7;
+                let tmp = *self & (ONES * 127);
+                (((ONES * (127 + b as $t) - tmp) & !*self & (tmp + (ONES * (127 - a as $t))))
+                    & ONES_HIGH)
+                    >> 7
+            }
+
+            #[inline(always)]
+            fn bitand(&self, other: Self) -> Self {
+                *self & other
+            }
+
+            #[inline(always)]
+            fn bitor(&self, other: Self) -> Self {
+                *self | other
+            }
+
+            #[inline(always)]
+            fn bitxor(&self, other: Self) -> Self {
+                *self ^ other
+            }
+
+            #[inline(always)]
+            fn add(&self, other: Self) -> Self {
+                *self + other
+            }
+
+            #[inline(always)]
+            fn sub(&self, other: Self) -> Self {
+                *self - other
+            }
+
+            #[inline(always)]
+            fn inc_nth_from_end_lex_byte(&self, n: usize) -> Self {
+                if cfg!(target_endian = "little") {
+                    *self + (1 << ((Self::SIZE - 1 - n) * 8))
+                } else {
+                    *self + (1 << (n * 8))
+                }
+            }
+
+            #[inline(always)]
+            fn dec_last_lex_byte(&self) -> Self {
+                if cfg!(target_endian = "little") {
+                    *self - (1 << ((Self::SIZE - 1) * 8))
+                } else {
+                    *self - 1
+                }
+            }
+
+            #[inline(always)]
+            fn sum_bytes(&self) -> usize {
+                const ONES: $t = <$t>::MAX / 0xFF;
+                (self.wrapping_mul(ONES) >> ((Self::SIZE - 1) * 8)) as usize
+            }
+
+            #[inline(always)]
+            fn pack_flags(&self) -> u64 {
+                let mut mask = 0;
+                for (i, byte) in self.to_ne_bytes().iter().enumerate() {
+                    mask |= (*byte as u64) << i;
+                }
+                mask
+            }
+        }
+    };
 }
 
-impl ByteChunk for usize {
-    const SIZE: usize = core::mem::size_of::<usize>();
-    const MAX_ACC: usize = (256 / core::mem::size_of::<usize>()) - 1;
+impl_byte_chunk_for_uint!(usize);
+#[cfg(test)]
+impl_byte_chunk_for_uint!(u16);
+#[cfg(test)]
+impl_byte_chunk_for_uint!(u32);
+#[cfg(test)]
+impl_byte_chunk_for_uint!(u64);
+
+/// Calls `f` with each char-boundary suffix of a shared test text that
+/// starts within its first 16 bytes, so the narrow test chunk types get
+/// exercised at every alignment.
+#[cfg(test)]
+pub(crate) fn for_each_narrow_chunk_text(mut f: impl FnMut(&str)) {
+    let text = concat!(
+        "Hello\u{000B}there\u{000C}!\r\nHow're\u{0085}you\rdoing\u{2028}",
+        "today\u{2029}?\nこんにちは、みんなさん！\r\n🐸🐸\n\r\r\n\n",
+        "It's a fine day, isn't it?\nAren't you glad we're alive?\r\n",
+    );
+    for start in (0..16).filter(|&i| text.is_char_boundary(i)) {
+        f(&text[start..]);
+    }
+}
+
+// Note: use only SSE2 and older instructions, since these are
+// guaranteed on all x86_64 platforms.
+#[cfg(target_arch = "x86_64")]
+impl ByteChunk for x86_64::__m128i {
+    const SIZE: usize = core::mem::size_of::<x86_64::__m128i>();
+    const IS_SIMD: bool = true;
+    const MAX_ACC: usize = 255;
+    // 8 is measurably faster than 4 on texts of ~1KB and up, at the cost of
+    // a couple of nanoseconds on texts shorter than 128 bytes.
+    const UNROLL: usize = 8;
+    // SSE2 setup is cheap, so the chunk path wins from a single chunk on,
+    // except for `to_byte_idx`, whose early-out scalar loop stays
+    // competitive up to about 32 bytes.
+    const CHARS_COUNT_THRESHOLD: usize = Self::SIZE;
+    const CHARS_TO_BYTE_IDX_THRESHOLD: usize = 32;
+    const UTF16_SURROGATES_THRESHOLD: usize = Self::SIZE + 3;
+    const LF_COUNT_THRESHOLD: usize = Self::SIZE;
 
     #[inline(always)]
     fn zero() -> Self {
-        0
+        unsafe { x86_64::_mm_setzero_si128() }
     }
 
     #[inline(always)]
     fn splat(n: u8) -> Self {
-        const ONES: usize = core::usize::MAX / 0xFF;
-        ONES * n as usize
+        unsafe { x86_64::_mm_set1_epi8(n as i8) }
     }
 
     #[inline(always)]
     fn is_zero(&self) -> bool {
-        *self == 0
+        let tmp = unsafe { core::mem::transmute::<Self, (u64, u64)>(*self) };
+        tmp.0 == 0 && tmp.1 == 0
     }
 
     #[inline(always)]
     fn shift_back_lex(&self, n: usize) -> Self {
-        if cfg!(target_endian = "little") {
-            *self >> (n * 8)
-        } else {
-            *self << (n * 8)
+        match n {
+            0 => *self,
+            1 => unsafe { x86_64::_mm_srli_si128(*self, 1) },
+            2 => unsafe { x86_64::_mm_srli_si128(*self, 2) },
+            3 => unsafe { x86_64::_mm_srli_si128(*self, 3) },
+            4 => unsafe { x86_64::_mm_srli_si128(*self, 4) },
+            _ => unreachable!(),
         }
     }
 
     #[inline(always)]
     fn shift_across(&self, n: Self) -> Self {
-        let shift_distance = (Self::SIZE - 1) * 8;
-        if cfg!(target_endian = "little") {
-            (*self >> shift_distance) | (n << 8)
-        } else {
-            (*self << shift_distance) | (n >> 8)
+        unsafe {
+            let bottom_byte = x86_64::_mm_srli_si128(*self, 15);
+            let rest_shifted = x86_64::_mm_slli_si128(n, 1);
+            x86_64::_mm_or_si128(bottom_byte, rest_shifted)
         }
     }
 
     #[inline(always)]
     fn shr(&self, n: usize) -> Self {
-        *self >> n
+        match n {
+            0 => *self,
+            1 => unsafe { x86_64::_mm_srli_epi64(*self, 1) },
+            2 => unsafe { x86_64::_mm_srli_epi64(*self, 2) },
+            3 => unsafe { x86_64::_mm_srli_epi64(*self, 3) },
+            4 => unsafe { x86_64::_mm_srli_epi64(*self, 4) },
+            _ => unreachable!(),
+        }
     }
 
     #[inline(always)]
     fn cmp_eq_byte(&self, byte: u8) -> Self {
-        const ONES: usize = core::usize::MAX / 0xFF;
-        const ONES_HIGH: usize = ONES << 7;
-        let word = *self ^ (byte as usize * ONES);
-        (!(((word & !ONES_HIGH) + !ONES_HIGH) | word) & ONES_HIGH) >> 7
+        let tmp = unsafe { x86_64::_mm_cmpeq_epi8(*self, Self::splat(byte)) };
+        unsafe { x86_64::_mm_and_si128(tmp, Self::splat(1)) }
     }
 
     #[inline(always)]
     fn bytes_between_127(&self, a: u8, b: u8) -> Self {
-        const ONES: usize = core::usize::MAX / 0xFF;
-        const ONES_HIGH: usize = ONES << 7;
-        let tmp = *self & (ONES * 127);
-        (((ONES * (127 + b as usize) - tmp) & !*self & (tmp + (ONES * (127 - a as usize))))
-            & ONES_HIGH)
-            >> 7
+        let tmp1 = unsafe { x86_64::_mm_cmpgt_epi8(*self, Self::splat(a)) };
+        let tmp2 = unsafe { x86_64::_mm_cmplt_epi8(*self, Self::splat(b)) };
+        let tmp3 = unsafe { x86_64::_mm_and_si128(tmp1, tmp2) };
+        unsafe { x86_64::_mm_and_si128(tmp3, Self::splat(1)) }
     }
 
     #[inline(always)]
     fn bitand(&self, other: Self) -> Self {
-        *self & other
+        unsafe { x86_64::_mm_and_si128(*self, other) }
+    }
+
+    #[inline(always)]
+    fn bitor(&self, other: Self) -> Self {
+        unsafe { x86_64::_mm_or_si128(*self, other) }
+    }
+
+    #[inline(always)]
+    fn bitxor(&self, other: Self) -> Self {
+        unsafe { x86_64::_mm_xor_si128(*self, other) }
     }
 
     #[inline(always)]
     fn add(&self, other: Self) -> Self {
-        *self + other
+        unsafe { x86_64::_mm_add_epi8(*self, other) }
     }
 
     #[inline(always)]
     fn sub(&self, other: Self) -> Self {
-        *self - other
+        unsafe { x86_64::_mm_sub_epi8(*self, other) }
     }
 
     #[inline(always)]
     fn inc_nth_from_end_lex_byte(&self, n: usize) -> Self {
-        if cfg!(target_endian = "little") {
-            *self + (1 << ((Self::SIZE - 1 - n) * 8))
-        } else {
-            *self + (1 << (n * 8))
-        }
+        let mut tmp = unsafe { core::mem::transmute::<Self, [u8; 16]>(*self) };
+        tmp[15 - n] += 1;
+        unsafe { core::mem::transmute::<[u8; 16], Self>(tmp) }
     }
 
     #[inline(always)]
     fn dec_last_lex_byte(&self) -> Self {
-        if cfg!(target_endian = "little") {
-            *self - (1 << ((Self::SIZE - 1) * 8))
-        } else {
-            *self - 1
-        }
+        let mut tmp = unsafe { core::mem::transmute::<Self, [u8; 16]>(*self) };
+        tmp[15] -= 1;
+        unsafe { core::mem::transmute::<[u8; 16], Self>(tmp) }
     }
 
     #[inline(always)]
     fn sum_bytes(&self) -> usize {
-        const ONES: usize = core::usize::MAX / 0xFF;
-        self.wrapping_mul(ONES) >> ((Self::SIZE - 1) * 8)
+        let half_sum = unsafe { x86_64::_mm_sad_epu8(*self, x86_64::_mm_setzero_si128()) };
+        let (low, high) = unsafe { core::mem::transmute::<Self, (u64, u64)>(half_sum) };
+        (low + high) as usize
+    }
+
+    #[inline(always)]
+    fn pack_flags(&self) -> u64 {
+        // Move each flag to the top bit of its byte, where movemask reads it.
+        unsafe { x86_64::_mm_movemask_epi8(x86_64::_mm_slli_epi64(*self, 7)) as u32 as u64 }
     }
 }
 
-// Note: use only SSE2 and older instructions, since these are
-// guaranteed on all x86_64 platforms.
+// Only used by the `avx2` module's entry points, which are compiled with
+// AVX2 enabled.  The intrinsics below are only inlined (and only safe to
+// call) in that context, so this must not be used anywhere else.
 #[cfg(target_arch = "x86_64")]
-impl ByteChunk for x86_64::__m128i {
-    const SIZE: usize = core::mem::size_of::<x86_64::__m128i>();
+impl ByteChunk for x86_64::__m256i {
+    const SIZE: usize = core::mem::size_of::<x86_64::__m256i>();
+    const IS_SIMD: bool = true;
     const MAX_ACC: usize = 255;
+    // Not yet tuned: half of the SSE2 unroll, for the same bytes per
+    // iteration.
+    const UNROLL: usize = 4;
+    const CHARS_COUNT_THRESHOLD: usize = Self::SIZE;
+    const CHARS_TO_BYTE_IDX_THRESHOLD: usize = Self::SIZE;
+    const UTF16_SURROGATES_THRESHOLD: usize = Self::SIZE + 3;
+    const LF_COUNT_THRESHOLD: usize = Self::SIZE;
 
     #[inline(always)]
     fn zero() -> Self {
-        unsafe { x86_64::_mm_setzero_si128() }
+        unsafe { x86_64::_mm256_setzero_si256() }
     }
 
     #[inline(always)]
     fn splat(n: u8) -> Self {
-        unsafe { x86_64::_mm_set1_epi8(n as i8) }
+        unsafe { x86_64::_mm256_set1_epi8(n as i8) }
     }
 
     #[inline(always)]
     fn is_zero(&self) -> bool {
-        let tmp = unsafe { core::mem::transmute::<Self, (u64, u64)>(*self) };
-        tmp.0 == 0 && tmp.1 == 0
+        unsafe { x86_64::_mm256_testz_si256(*self, *self) == 1 }
     }
 
     #[inline(always)]
     fn shift_back_lex(&self, n: usize) -> Self {
-        match n {
-            0 => *self,
-            1 => unsafe { x86_64::_mm_srli_si128(*self, 1) },
-            2 => unsafe { x86_64::_mm_srli_si128(*self, 2) },
-            3 => unsafe { x86_64::_mm_srli_si128(*self, 3) },
-            4 => unsafe { x86_64::_mm_srli_si128(*self, 4) },
-            _ => unreachable!(),
+        // `_mm256_alignr_epi8()` shifts within 128-bit lanes, so shift in
+        // the bytes of the upper lane (and zeros above that).
+        unsafe {
+            let upper = x86_64::_mm256_permute2x128_si256(*self, *self, 0x81);
+            match n {
+                0 => *self,
+                1 => x86_64::_mm256_alignr_epi8(upper, *self, 1),
+                2 => x86_64::_mm256_alignr_epi8(upper, *self, 2),
+                3 => x86_64::_mm256_alignr_epi8(upper, *self, 3),
+                4 => x86_64::_mm256_alignr_epi8(upper, *self, 4),
+                _ => unreachable!(),
+            }
         }
     }
 
     #[inline(always)]
     fn shift_across(&self, n: Self) -> Self {
         unsafe {
-            let bottom_byte = x86_64::_mm_srli_si128(*self, 15);
-            let rest_shifted = x86_64::_mm_slli_si128(n, 1);
-            x86_64::_mm_or_si128(bottom_byte, rest_shifted)
+            // The upper lane of self and the lower lane of n.
+            let middle = x86_64::_mm256_permute2x128_si256(*self, n, 0x21);
+            x86_64::_mm256_alignr_epi8(n, middle, 15)
         }
     }
 
@@ -224,69 +496,94 @@ impl ByteChunk for x86_64::__m128i {
     fn shr(&self, n: usize) -> Self {
         match n {
             0 => *self,
-            1 => unsafe { x86_64::_mm_srli_epi64(*self, 1) },
-            2 => unsafe { x86_64::_mm_srli_epi64(*self, 2) },
-            3 => unsafe { x86_64::_mm_srli_epi64(*self, 3) },
-            4 => unsafe { x86_64::_mm_srli_epi64(*self, 4) },
+            1 => unsafe { x86_64::_mm256_srli_epi64(*self, 1) },
+            2 => unsafe { x86_64::_mm256_srli_epi64(*self, 2) },
+            3 => unsafe { x86_64::_mm256_srli_epi64(*self, 3) },
+            4 => unsafe { x86_64::_mm256_srli_epi64(*self, 4) },
             _ => unreachable!(),
         }
     }
 
     #[inline(always)]
     fn cmp_eq_byte(&self, byte: u8) -> Self {
-        let tmp = unsafe { x86_64::_mm_cmpeq_epi8(*self, Self::splat(byte)) };
-        unsafe { x86_64::_mm_and_si128(tmp, Self::splat(1)) }
+        let tmp = unsafe { x86_64::_mm256_cmpeq_epi8(*self, Self::splat(byte)) };
+        unsafe { x86_64::_mm256_and_si256(tmp, Self::splat(1)) }
     }
 
     #[inline(always)]
     fn bytes_between_127(&self, a: u8, b: u8) -> Self {
-        let tmp1 = unsafe { x86_64::_mm_cmpgt_epi8(*self, Self::splat(a)) };
-        let tmp2 = unsafe { x86_64::_mm_cmplt_epi8(*self, Self::splat(b)) };
-        let tmp3 = unsafe { x86_64::_mm_and_si128(tmp1, tmp2) };
-        unsafe { x86_64::_mm_and_si128(tmp3, Self::splat(1)) }
+        let tmp1 = unsafe { x86_64::_mm256_cmpgt_epi8(*self, Self::splat(a)) };
+        let tmp2 = unsafe { x86_64::_mm256_cmpgt_epi8(Self::splat(b), *self) };
+        let tmp3 = unsafe { x86_64::_mm256_and_si256(tmp1, tmp2) };
+        unsafe { x86_64::_mm256_and_si256(tmp3, Self::splat(1)) }
     }
 
     #[inline(always)]
     fn bitand(&self, other: Self) -> Self {
-        unsafe { x86_64::_mm_and_si128(*self, other) }
+        unsafe { x86_64::_mm256_and_si256(*self, other) }
+    }
+
+    #[inline(always)]
+    fn bitor(&self, other: Self) -> Self {
+        unsafe { x86_64::_mm256_or_si256(*self, other) }
+    }
+
+    #[inline(always)]
+    fn bitxor(&self, other: Self) -> Self {
+        unsafe { x86_64::_mm256_xor_si256(*self, other) }
     }
 
     #[inline(always)]
     fn add(&self, other: Self) -> Self {
-        unsafe { x86_64::_mm_add_epi8(*self, other) }
+        unsafe { x86_64::_mm256_add_epi8(*self, other) }
     }
 
     #[inline(always)]
     fn sub(&self, other: Self) -> Self {
-        unsafe { x86_64::_mm_sub_epi8(*self, other) }
+        unsafe { x86_64::_mm256_sub_epi8(*self, other) }
     }
 
     #[inline(always)]
     fn inc_nth_from_end_lex_byte(&self, n: usize) -> Self {
-        let mut tmp = unsafe { core::mem::transmute::<Self, [u8; 16]>(*self) };
-        tmp[15 - n] += 1;
-        unsafe { core::mem::transmute::<[u8; 16], Self>(tmp) }
+        let mut tmp = unsafe { core::mem::transmute::<Self, [u8; 32]>(*self) };
+        tmp[31 - n] += 1;
+        unsafe { core::mem::transmute::<[u8; 32], Self>(tmp) }
     }
 
     #[inline(always)]
     fn dec_last_lex_byte(&self) -> Self {
-        let mut tmp = unsafe { core::mem::transmute::<Self, [u8; 16]>(*self) };
-        tmp[15] -= 1;
-        unsafe { core::mem::transmute::<[u8; 16], Self>(tmp) }
+        let mut tmp = unsafe { core::mem::transmute::<Self, [u8; 32]>(*self) };
+        tmp[31] -= 1;
+        unsafe { core::mem::transmute::<[u8; 32], Self>(tmp) }
     }
 
     #[inline(always)]
     fn sum_bytes(&self) -> usize {
-        let half_sum = unsafe { x86_64::_mm_sad_epu8(*self, x86_64::_mm_setzero_si128()) };
-        let (low, high) = unsafe { core::mem::transmute::<Self, (u64, u64)>(half_sum) };
-        (low + high) as usize
+        let quarter_sums =
+            unsafe { x86_64::_mm256_sad_epu8(*self, x86_64::_mm256_setzero_si256()) };
+        let sums = unsafe { core::mem::transmute::<Self, [u64; 4]>(quarter_sums) };
+        (sums[0] + sums[1] + sums[2] + sums[3]) as usize
+    }
+
+    #[inline(always)]
+    fn pack_flags(&self) -> u64 {
+        unsafe { x86_64::_mm256_movemask_epi8(x86_64::_mm256_slli_epi64(*self, 7)) as u32 as u64 }
     }
 }
 
 #[cfg(target_arch = "aarch64")]
 impl ByteChunk for aarch64::uint8x16_t {
     const SIZE: usize = core::mem::size_of::<Self>();
+    const IS_SIMD: bool = true;
     const MAX_ACC: usize = 255;
+    // Not yet tuned: same as the previous hard-coded unroll.
+    const UNROLL: usize = 4;
+    // Not yet tuned: same as the previous hard-coded cutoffs, and as the
+    // other backends for utf16, which had none.
+    const CHARS_COUNT_THRESHOLD: usize = Self::SIZE;
+    const CHARS_TO_BYTE_IDX_THRESHOLD: usize = Self::SIZE + 1;
+    const UTF16_SURROGATES_THRESHOLD: usize = Self::SIZE + 3;
+    const LF_COUNT_THRESHOLD: usize = Self::SIZE;
 
     #[inline(always)]
     fn zero() -> Self {
@@ -355,6 +652,16 @@ impl ByteChunk for aarch64::uint8x16_t {
         unsafe { aarch64::vandq_u8(*self, other) }
     }
 
+    #[inline(always)]
+    fn bitor(&self, other: Self) -> Self {
+        unsafe { aarch64::vorrq_u8(*self, other) }
+    }
+
+    #[inline(always)]
+    fn bitxor(&self, other: Self) -> Self {
+        unsafe { aarch64::veorq_u8(*self, other) }
+    }
+
     #[inline(always)]
     fn add(&self, other: Self) -> Self {
         unsafe { aarch64::vaddq_u8(*self, other) }
@@ -394,6 +701,19 @@ impl ByteChunk for aarch64::uint8x16_t {
     fn sum_bytes(&self) -> usize {
         unsafe { aarch64::vaddlvq_u8(*self).into() }
     }
+
+    #[inline(always)]
+    fn pack_flags(&self) -> u64 {
+        // NEON has no movemask, so weight each flag by its bit and sum the
+        // bytes of each half.
+        const WEIGHTS: [u8; 16] = [1, 2, 4, 8, 16, 32, 64, 128, 1, 2, 4, 8, 16, 32, 64, 128];
+        unsafe {
+            let weighted = aarch64::vmulq_u8(*self, aarch64::vld1q_u8(WEIGHTS.as_ptr()));
+            let low = aarch64::vaddv_u8(aarch64::vget_low_u8(weighted));
+            let high = aarch64::vaddv_u8(aarch64::vget_high_u8(weighted));
+            low as u64 | ((high as u64) << 8)
+        }
+    }
 }
 
 //=============================================================
@@ -403,8 +723,8 @@ mod tests {
     use super::*;
 
     #[test]
-    fn usize_flag_bytes_01() {
-        let v: usize = 0xE2_09_08_A6_E2_A6_E2_09;
+    fn swar_flag_bytes_01() {
+        let v: u64 = 0xE2_09_08_A6_E2_A6_E2_09;
         assert_eq!(0x00_00_00_00_00_00_00_00, v.cmp_eq_byte(0x07));
         assert_eq!(0x00_00_01_00_00_00_00_00, v.cmp_eq_byte(0x08));
         assert_eq!(0x00_01_00_00_00_00_00_01, v.cmp_eq_byte(0x09));
@@ -413,14 +733,58 @@ mod tests {
     }
 
     #[test]
-    fn usize_bytes_between_127_01() {
-        let v: usize = 0x7E_09_00_A6_FF_7F_08_07;
+    fn swar_bytes_between_127_01() {
+        let v: u64 = 0x7E_09_00_A6_FF_7F_08_07;
         assert_eq!(0x01_01_00_00_00_00_01_01, v.bytes_between_127(0x00, 0x7F));
         assert_eq!(0x00_01_00_00_00_00_01_00, v.bytes_between_127(0x07, 0x7E));
         assert_eq!(0x00_01_00_00_00_00_00_00, v.bytes_between_127(0x08, 0x7E));
     }
 
+    #[test]
+    fn swar_flag_bytes_02() {
+        let v: u16 = 0xE2_09;
+        assert_eq!(0x00_00, v.cmp_eq_byte(0x07));
+        assert_eq!(0x00_01, v.cmp_eq_byte(0x09));
+        assert_eq!(0x01_00, v.cmp_eq_byte(0xE2));
+        assert_eq!(0x00_01, v.bytes_between_127(0x00, 0x7F));
+    }
+
+    #[test]
+    fn sum_bytes_swar() {
+        fn check<T: ByteChunk>() {
+            let ones = T::splat(1);
+            let mut acc = T::zero();
+            for _ in 0..T::MAX_ACC {
+                acc = acc.add(ones);
+            }
+            assert_eq!(acc.sum_bytes(), T::SIZE * T::MAX_ACC);
+        }
+        check::<u16>();
+        check::<u32>();
+        check::<u64>();
+        check::<usize>();
+    }
+
     #[cfg(all(feature = "simd", any(target_arch = "x86_64", target_arch = "aarch64")))]
+    #[test]
+    fn pack_flags_01() {
+        fn check<T: ByteChunk>() {
+            let bytes: [u8; 64] = core::array::from_fn(|i| (i % 3 == 0 || i % 7 == 0) as u8);
+            let flags = unsafe { (bytes.as_ptr() as *const T).read_unaligned() };
+            let expected = (0..T::SIZE)
+                .filter(|&i| bytes[i] == 1)
+                .fold(0, |mask, i| mask | (1 << i));
+            assert_eq!(expected, flags.pack_flags());
+            assert_eq!(0, T::zero().pack_flags());
+            assert_eq!(u64::MAX >> (64 - T::SIZE), T::splat(1).pack_flags());
+        }
+        check::<u16>();
+        check::<u32>();
+        check::<u64>();
+        check::<usize>();
+        check::<Chunk>();
+    }
+
     #[test]
     fn sum_bytes_simd() {
         let ones = Chunk::splat(1);
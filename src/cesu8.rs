@@ -0,0 +1,148 @@
+//! Index by utf16 code units in CESU-8 and Java's Modified UTF-8.
+//!
+//! [CESU-8](https://www.unicode.org/reports/tr26/) encodes chars outside
+//! the Basic Multilingual Plane as a utf16 surrogate pair, each half as a
+//! 3-byte sequence, rather than as a single 4-byte sequence.  Java's
+//! Modified UTF-8, as returned by JNI's `GetStringUTFChars()`, is the same
+//! except that NUL is encoded as the two bytes `C0 80`.
+//!
+//! Either way, every encoded sequence is exactly one utf16 code unit, which
+//! makes utf16 indices (e.g. Java string indices) simple to map to and from
+//! byte indices.  The functions here do that, and treat both encodings the
+//! same.  Lone surrogates, which Java strings may contain, are handled
+//! like any other code unit.
+//!
+//! The text is taken as `&[u8]`, since it isn't valid utf8.  For bytes that
+//! aren't valid CESU-8 or Modified UTF-8 the results are unspecified, but
+//! nothing panics.
+
+/// Counts the utf16 code units in CESU-8 text.
+///
+/// This is the length of the equivalent Java string.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn count(text: &[u8]) -> usize {
+    with_chunk!(crate::chars::count_impl(text))
+}
+
+/// Counts the utf16 surrogate pairs in CESU-8 text.
+///
+/// A pair is a high surrogate immediately followed by a low surrogate, and
+/// stands for one char.  So the number of chars the text encodes is
+/// `count(text) - count_surrogates(text)`, if it has no lone surrogates.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn count_surrogates(text: &[u8]) -> usize {
+    let mut count = 0;
+    let mut i = 0;
+    while let Some(pair) = text.get(i..(i + 6)) {
+        if is_high_surrogate(pair) && is_low_surrogate(pair.get(3..).unwrap_or(&[])) {
+            count += 1;
+            i += 6;
+        } else {
+            i += 1;
+        }
+    }
+    count
+}
+
+/// Converts from byte-index to utf16-code-unit-index in CESU-8 text.
+///
+/// If the byte is in the middle of an encoded sequence, returns the index
+/// of the code unit that the byte belongs to.  In particular, a byte in
+/// the second half of a surrogate pair gives the index of the low
+/// surrogate.
+///
+/// Any past-the-end index will return the one-past-the-end utf16 index.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn from_byte_idx(text: &[u8], byte_idx: usize) -> usize {
+    let mut i = byte_idx.min(text.len());
+    while i > 0 && matches!(text.get(i), Some(byte) if (byte & 0xC0) == 0x80) {
+        i -= 1;
+    }
+    count(text.get(..i).unwrap_or(&[]))
+}
+
+/// Converts from utf16-code-unit-index to byte-index in CESU-8 text.
+///
+/// Since each half of a surrogate pair is encoded separately, the index of
+/// a low surrogate gives the byte index of its encoding, in the middle of
+/// the pair.
+///
+/// Any past-the-end index will return the one-past-the-end byte index.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn to_byte_idx(text: &[u8], utf16_idx: usize) -> usize {
+    with_chunk!(crate::chars::to_byte_idx_impl(text, utf16_idx))
+}
+
+//-------------------------------------------------------------
+
+/// Whether `bytes` starts with an encoded high surrogate (U+D800-U+DBFF).
+#[inline(always)]
+fn is_high_surrogate(bytes: &[u8]) -> bool {
+    matches!(bytes, [0xED, 0xA0..=0xAF, ..])
+}
+
+/// Whether `bytes` starts with an encoded low surrogate (U+DC00-U+DFFF).
+#[inline(always)]
+fn is_low_surrogate(bytes: &[u8]) -> bool {
+    matches!(bytes, [0xED, 0xB0..=0xBF, ..])
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "a", NUL as `C0 80`, "é", U+1F438 (frog) as a surrogate pair, a lone
+    // low surrogate, "b".
+    const TEXT: &[u8] = b"a\xC0\x80\xC3\xA9\xED\xA0\xBD\xED\xB0\xB8\xED\xB0\x80b";
+
+    #[test]
+    fn count_01() {
+        assert_eq!(7, count(TEXT));
+        assert_eq!(0, count(b""));
+        assert_eq!(
+            "a\0é🐸b".encode_utf16().count() + 1,
+            count(TEXT),
+            "lone surrogate counts as one code unit"
+        );
+    }
+
+    #[test]
+    fn count_surrogates_01() {
+        assert_eq!(1, count_surrogates(TEXT));
+        assert_eq!(0, count_surrogates(b""));
+        assert_eq!(0, count_surrogates(b"\xED\xB0\x80\xED\xA0\xBD"));
+        assert_eq!(2, count_surrogates(&[TEXT, TEXT].concat()));
+    }
+
+    #[test]
+    fn byte_idx_01() {
+        let boundaries = [
+            (0, 0),
+            (1, 1),
+            (3, 2),
+            (5, 3),
+            (8, 4),
+            (11, 5),
+            (14, 6),
+            (15, 7),
+        ];
+        for &(byte_idx, utf16_idx) in boundaries.iter() {
+            assert_eq!(utf16_idx, from_byte_idx(TEXT, byte_idx));
+            assert_eq!(byte_idx, to_byte_idx(TEXT, utf16_idx));
+        }
+        assert_eq!(1, from_byte_idx(TEXT, 2));
+        assert_eq!(4, from_byte_idx(TEXT, 10));
+        assert_eq!(7, from_byte_idx(TEXT, 100));
+        assert_eq!(15, to_byte_idx(TEXT, 100));
+    }
+}
@@ -1,13 +1,77 @@
 //! Index by chars.
 
-use crate::byte_chunk::{ByteChunk, Chunk};
+use core::ops::Range;
+
+use crate::byte_chunk::ByteChunk;
 
 /// Counts the chars in a string slice.
 ///
 /// Runs in O(N) time.
-#[inline]
+#[inline(never)]
 pub fn count(text: &str) -> usize {
-    count_impl::<Chunk>(text.as_bytes())
+    with_chunk!(count_impl(text.as_bytes()))
+}
+
+/// Counts the utf8 chars in a string slice, and finds the start of the
+/// last char.
+///
+/// Returns `(char_count, byte_idx)`, where `byte_idx` is the byte index
+/// of the start of the last char, or zero for an empty text.  The start of
+/// the last char is found by scanning back from the end of the text, so
+/// this costs no more than [`count()`].
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn count_and_last_boundary(text: &str) -> (usize, usize) {
+    (
+        count(text),
+        floor_byte_boundary(text, text.len().saturating_sub(1)),
+    )
+}
+
+/// Counts the chars and the LF line breaks in a string slice, in a single
+/// pass.
+///
+/// Returns `(char_count, break_count)`, the same as [`count()`] and
+/// [`lines_lf::count_breaks()`](crate::lines_lf::count_breaks) would, but
+/// without scanning the text twice.  Useful when both are needed for the
+/// same text, e.g. for the leaves of a rope.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn count_with_lf(text: &str) -> (usize, usize) {
+    with_chunk!(count_with_lf_impl(text.as_bytes()))
+}
+
+/// Counts the chars of each utf8 encoded length in a string slice.
+///
+/// Returns the number of 1-, 2-, 3-, and 4-byte chars, in that order.
+/// This is handy for sizing transcoding buffers: e.g. the utf16 length of
+/// the text is `h[0] + h[1] + h[2] + h[3] * 2`, and the char count is the
+/// sum of all four.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn len_histogram(text: &str) -> [usize; 4] {
+    with_chunk!(len_histogram_impl(text.as_bytes()))
+}
+
+/// Counts the utf8 chars in a byte range of a string slice.
+///
+/// Both ends of the range are snapped back to char boundaries as by
+/// [`floor_byte_boundary()`]: a char that the range starts in the middle
+/// of is counted, and one that it ends in the middle of isn't.  This is
+/// the same as `from_byte_idx(text, end) - from_byte_idx(text, start)`,
+/// and makes ranges from byte-oriented tools (e.g. regex matches on
+/// bytes) safe to use directly.  Past-the-end indices are clamped to the
+/// end of the text, and a reversed range counts as empty.
+///
+/// Runs in O(N) time, where N is the length of the range.
+#[inline]
+pub fn count_in_range(text: &str, byte_range: Range<usize>) -> usize {
+    let start = floor_byte_boundary(text, byte_range.start);
+    let end = floor_byte_boundary(text, byte_range.end);
+    count(text.get(start..end).unwrap_or(""))
 }
 
 /// Converts from byte-index to char-index in a string slice.
@@ -18,45 +82,1053 @@ pub fn count(text: &str) -> usize {
 /// Any past-the-end index will return the one-past-the-end char index.
 ///
 /// Runs in O(N) time.
-#[inline]
+#[inline(never)]
 pub fn from_byte_idx(text: &str, byte_idx: usize) -> usize {
+    with_chunk!(from_byte_idx_impl(text, byte_idx))
+}
+
+/// Converts many byte indices to char indices in a string slice.
+///
+/// Writes the char index of each of `byte_idxs` to the corresponding
+/// element of `out`, as [`from_byte_idx()`] would return it.  If `out` is
+/// shorter than `byte_idxs`, only the indices that fit are converted.
+///
+/// When `byte_idxs` is sorted in ascending order, the conversions share a
+/// single pass over the text instead of each scanning from its start.
+/// Unsorted indices are still converted correctly, but each decrease
+/// restarts the scan from the start of the text.
+///
+/// Runs in O(N + M) time for sorted indices, where M is the number of
+/// indices.
+#[inline(never)]
+pub fn from_byte_idxs(text: &str, byte_idxs: &[usize], out: &mut [usize]) {
     let bytes = text.as_bytes();
+    let (mut byte_pos, mut char_pos) = (0, 0);
+    for (&byte_idx, out) in byte_idxs.iter().zip(out.iter_mut()) {
+        let byte_idx = floor_byte_boundary(text, byte_idx);
+        if byte_idx < byte_pos {
+            (byte_pos, char_pos) = (0, 0);
+        }
+        char_pos += with_chunk!(count_impl(bytes.get(byte_pos..byte_idx).unwrap_or(&[])));
+        byte_pos = byte_idx;
+        *out = char_pos;
+    }
+}
 
-    // Ensure the index is either a char boundary or is off the end of
-    // the text.
-    let mut i = byte_idx;
-    while Some(true) == bytes.get(i).map(is_trailing_byte) {
-        i -= 1;
+/// Converts from byte-index to char-index in a string slice, given the
+/// total char count of the slice.
+///
+/// Returns the same as [`from_byte_idx()`], but if `byte_idx` is in the
+/// second half of the text, counts the chars from it to the end and
+/// subtracts them from `char_count` instead of counting from the start.
+/// This makes conversions near the end of large texts cheap when their
+/// char count is already known, e.g. cached in a rope node.
+///
+/// `char_count` must be the number of chars in `text`, as returned by
+/// [`count()`].  If it isn't, the result is unspecified (but it won't
+/// panic).
+///
+/// Runs in O(N) time, where N is the distance from `byte_idx` to the
+/// nearest end of the text.
+#[inline(never)]
+pub fn from_byte_idx_with_count(text: &str, byte_idx: usize, char_count: usize) -> usize {
+    if byte_idx < text.len() / 2 {
+        return with_chunk!(from_byte_idx_impl(text, byte_idx));
     }
+    let tail = crate::split_str_at(text, floor_byte_boundary(text, byte_idx)).1;
+    char_count.saturating_sub(with_chunk!(count_impl(tail.as_bytes())))
+}
 
-    count_impl::<Chunk>(&bytes[0..i.min(bytes.len())])
+/// Converts from byte-index to char-index in a string slice, also
+/// reporting whether the byte index was on a char boundary.
+///
+/// Returns the same char index as [`from_byte_idx()`], along with how
+/// many bytes `byte_idx` was snapped back by to reach a char boundary.
+/// The latter is zero exactly when `byte_idx` was already a char boundary
+/// (including the one-past-the-end index).
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn from_byte_idx_snapped(text: &str, byte_idx: usize) -> (usize, usize) {
+    let mut i = byte_idx.min(text.len());
+    while !text.is_char_boundary(i) {
+        i -= 1;
+    }
+    let slice = text.as_bytes().get(..i).unwrap_or(&[]);
+    (with_chunk!(count_impl(slice)), byte_idx - i)
 }
 
 /// Converts from char-index to byte-index in a string slice.
 ///
 /// Any past-the-end index will return the one-past-the-end byte index.
 ///
+/// This is also the end of the longest prefix of `text` that contains at
+/// most `char_idx` chars, which makes it suitable for truncating text to a
+/// char budget.
+///
 /// Runs in O(N) time.
-#[inline]
+#[inline(never)]
 pub fn to_byte_idx(text: &str, char_idx: usize) -> usize {
-    to_byte_idx_impl::<Chunk>(text.as_bytes(), char_idx)
+    with_chunk!(to_byte_idx_impl(text.as_bytes(), char_idx))
+}
+
+/// Converts from char-index to byte-index in a string slice, also
+/// reporting how far past the end the char index was.
+///
+/// Returns `(byte_idx, chars_remaining)`, where `byte_idx` is the same as
+/// [`to_byte_idx()`] returns, and `chars_remaining` is how many chars
+/// short of `char_idx` the text was: zero unless `char_idx` is past the
+/// end.  When walking a char index across the chunks of a rope, this is
+/// the char index to continue with in the next chunk.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn to_byte_idx_with_remainder(text: &str, char_idx: usize) -> (usize, usize) {
+    let (char_count, byte_idx) = with_chunk!(count_up_to_impl(text.as_bytes(), char_idx));
+    (byte_idx, char_idx - char_count)
+}
+
+/// Moves a byte index forward or backward by a number of chars.
+///
+/// Returns the byte index `delta` chars after `byte_idx`, or before it if
+/// `delta` is negative, stopping at the start or end of the text.  This is
+/// the same as converting `byte_idx` to a char index, adding `delta`, and
+/// converting back, but only scans the text between the two positions.
+///
+/// If `byte_idx` is in the middle of a char, it's snapped back to the start
+/// of that char first.  Any past-the-end index is treated as the
+/// one-past-the-end index.
+///
+/// Runs in O(N) time, where N is the distance moved.
+#[inline]
+pub fn offset_by(text: &str, byte_idx: usize, delta: isize) -> usize {
+    let byte_idx = floor_byte_boundary(text, byte_idx);
+    let (before, after) = crate::split_str_at(text, byte_idx);
+    match delta.unsigned_abs().checked_sub(1) {
+        None => byte_idx,
+        Some(_) if delta > 0 => byte_idx + to_byte_idx(after, delta as usize),
+        Some(n) => boundaries(before).nth_back(n).unwrap_or(0),
+    }
+}
+
+/// Converts from char-index to byte-index in a string slice, scanning from
+/// a known position.
+///
+/// Returns the same as [`to_byte_idx()`], but scans from the anchor
+/// position (`anchor_byte`, `anchor_char`) instead of from the start of
+/// the text, or from the start if that's closer.  This makes repeated
+/// conversions of nearby indices cheap, e.g. when walking through a text
+/// in order.
+///
+/// `anchor_char` must be the char index of `anchor_byte`, as returned by
+/// [`from_byte_idx()`].  If the anchor isn't on a char boundary, it's
+/// snapped back to the start of its char.  If `anchor_char` is wrong, the
+/// result is unspecified (but it won't panic).
+///
+/// Runs in O(N) time, where N is the distance from the nearer of the
+/// anchor and the start of the text.
+#[inline(never)]
+pub fn to_byte_idx_from(
+    text: &str,
+    char_idx: usize,
+    anchor_byte: usize,
+    anchor_char: usize,
+) -> usize {
+    let anchor_byte = floor_byte_boundary(text, anchor_byte);
+    let (before, after) = crate::split_str_at(text, anchor_byte);
+    if char_idx >= anchor_char {
+        anchor_byte + with_chunk!(to_byte_idx_impl(after.as_bytes(), char_idx - anchor_char))
+    } else if char_idx <= anchor_char - char_idx {
+        with_chunk!(to_byte_idx_impl(before.as_bytes(), char_idx))
+    } else {
+        with_chunk!(to_byte_idx_back_impl(
+            before.as_bytes(),
+            anchor_char - char_idx
+        ))
+    }
+}
+
+/// Counts the chars in a string slice, stopping after `max_chars`.
+///
+/// Returns `(char_count, byte_idx)`, where `char_count` is the number of
+/// chars counted (at most `max_chars`) and `byte_idx` is the byte index
+/// where counting stopped.  `byte_idx` is the same as returned by
+/// [`to_byte_idx()`] for `max_chars`, and `char_count` is the number of
+/// chars before it, so this does the work of both that and [`count()`] on
+/// the prefix in a single scan.
+///
+/// Runs in O(N) time, where N is the length of the prefix.
+#[inline(never)]
+pub fn count_up_to(text: &str, max_chars: usize) -> (usize, usize) {
+    with_chunk!(count_up_to_impl(text.as_bytes(), max_chars))
+}
+
+/// Splits a string slice at a char index.
+///
+/// Equivalent to `text.split_at(to_byte_idx(text, char_idx))`, so any
+/// past-the-end index splits at the end of the text.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn split_at(text: &str, char_idx: usize) -> (&str, &str) {
+    crate::split_str_at(text, to_byte_idx(text, char_idx))
+}
+
+/// Slices a string slice by a range of char indices.
+///
+/// Equivalent to slicing `text` with the byte indices from
+/// [`to_byte_idx()`] of both ends of the range, but the end is found by
+/// continuing the scan from the start rather than from the start of the
+/// text again.  Past-the-end indices are clamped to the end of the text,
+/// and a reversed range is treated as empty at its start.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn slice(text: &str, char_range: Range<usize>) -> &str {
+    let (_, rest) = crate::split_str_at(text, to_byte_idx(text, char_range.start));
+    let len = to_byte_idx(rest, char_range.end.saturating_sub(char_range.start));
+    crate::split_str_at(rest, len).0
+}
+
+/// Returns the longest prefix of a string slice with at most `max_chars`
+/// chars.
+///
+/// Equivalent to `&text[..to_byte_idx(text, max_chars)]`.
+///
+/// Runs in O(N) time, where N is the length of the prefix.
+#[inline]
+pub fn prefix(text: &str, max_chars: usize) -> &str {
+    crate::split_str_at(text, to_byte_idx(text, max_chars)).0
+}
+
+/// Returns the longest suffix of a string slice with at most `max_chars`
+/// chars.
+///
+/// Scans backward from the end of the text.
+///
+/// Runs in O(N) time, where N is the length of the suffix.
+#[inline]
+pub fn suffix(text: &str, max_chars: usize) -> &str {
+    let start = match max_chars.checked_sub(1) {
+        None => text.len(),
+        Some(n) => text.char_indices().rev().nth(n).map_or(0, |(i, _)| i),
+    };
+    crate::split_str_at(text, start).1
+}
+
+/// Returns the char at a char index in a string slice.
+///
+/// Returns `None` for any past-the-end index.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn nth(text: &str, char_idx: usize) -> Option<char> {
+    crate::split_str_at(text, to_byte_idx(text, char_idx))
+        .1
+        .chars()
+        .next()
+}
+
+/// Snaps a byte index down to the nearest char boundary.
+///
+/// Returns `byte_idx` itself if it's already a char boundary, and
+/// otherwise the index of the start of the char that the byte belongs to.
+/// This is also the end of the longest prefix of `text` that is at most
+/// `byte_idx` bytes long, which makes it suitable for truncating text to a
+/// byte budget.
+///
+/// Any past-the-end index will return the one-past-the-end byte index.
+///
+/// Runs in O(1) time.
+#[inline]
+pub fn floor_byte_boundary(text: &str, byte_idx: usize) -> usize {
+    let bytes = text.as_bytes();
+    if byte_idx >= bytes.len() {
+        return bytes.len();
+    }
+
+    // Valid utf8 guarantees at most three trailing bytes in a row, preceded
+    // by a leading byte.
+    let mut i = byte_idx;
+    while i > 0 && is_trailing_byte(&bytes[i]) {
+        i -= 1;
+    }
+    i
+}
+
+/// Snaps a byte index up to the nearest char boundary.
+///
+/// Returns `byte_idx` itself if it's already a char boundary, and
+/// otherwise the index of the start of the next char.  This is also the
+/// start of the longest suffix of `text` that starts at or after
+/// `byte_idx`.
+///
+/// Any past-the-end index will return the one-past-the-end byte index.
+///
+/// Runs in O(1) time.
+#[inline]
+pub fn ceil_byte_boundary(text: &str, byte_idx: usize) -> usize {
+    let bytes = text.as_bytes();
+    let mut i = byte_idx.min(bytes.len());
+    while i < bytes.len() && is_trailing_byte(&bytes[i]) {
+        i += 1;
+    }
+    i
+}
+
+/// Shrinks a byte range to the largest range within it whose ends are on
+/// char boundaries.
+///
+/// The result is always safe to slice `text` with.  The ends are first
+/// clamped to the length of `text`, and a reversed range is treated as
+/// empty at its start.  If no char fits entirely within the range, the
+/// result is an empty range.
+///
+/// Runs in O(1) time.
+#[inline]
+pub fn shrink_range(text: &str, byte_range: Range<usize>) -> Range<usize> {
+    let start = ceil_byte_boundary(text, byte_range.start);
+    let end = floor_byte_boundary(text, byte_range.end).max(start);
+    start..end
+}
+
+/// Expands a byte range to the smallest range containing it whose ends are
+/// on char boundaries.
+///
+/// The result is always safe to slice `text` with.  The ends are first
+/// clamped to the length of `text`, and a reversed range is treated as
+/// empty at its start.
+///
+/// Runs in O(1) time.
+#[inline]
+pub fn expand_range(text: &str, byte_range: Range<usize>) -> Range<usize> {
+    let start = floor_byte_boundary(text, byte_range.start);
+    let end = ceil_byte_boundary(text, byte_range.end.max(byte_range.start));
+    start..end
+}
+
+/// Returns the length in bytes of the longest common prefix of two string
+/// slices.
+///
+/// The result is snapped back to a char boundary, so it's always safe to
+/// slice both `a` and `b` with.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn common_prefix(a: &str, b: &str) -> usize {
+    let len = with_chunk!(common_prefix_impl(a.as_bytes(), b.as_bytes(), |_| ()));
+    floor_byte_boundary(a, len)
+}
+
+/// Returns the length in bytes of the longest common suffix of two string
+/// slices.
+///
+/// The result is snapped forward to a char boundary, so that
+/// `a.len() - common_suffix(a, b)` is always safe to slice `a` with, and
+/// likewise for `b`.
+///
+/// Note that the common prefix and suffix can overlap (e.g. in "aba" and
+/// "aba-aba").  When computing a minimal edit, limit the suffix to what
+/// remains after the prefix.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn common_suffix(a: &str, b: &str) -> usize {
+    let len = with_chunk!(common_suffix_impl(a.as_bytes(), b.as_bytes(), |_| ()));
+    a.len() - ceil_byte_boundary(a, a.len() - len)
+}
+
+/// Returns the length of the longest common prefix of two string slices,
+/// in both bytes and chars.
+///
+/// Returns `(byte_len, char_len)`, where `byte_len` is the same as
+/// [`common_prefix()`].  The chars are counted while comparing, so this
+/// only makes a single pass over the prefix.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn common_prefix_chars(a: &str, b: &str) -> (usize, usize) {
+    with_chunk!(common_prefix_chars_impl(a, b))
+}
+
+/// Returns the length of the longest common suffix of two string slices,
+/// in both bytes and chars.
+///
+/// Returns `(byte_len, char_len)`, where `byte_len` is the same as
+/// [`common_suffix()`].  The chars are counted while comparing, so this
+/// only makes a single pass over the suffix.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn common_suffix_chars(a: &str, b: &str) -> (usize, usize) {
+    with_chunk!(common_suffix_chars_impl(a, b))
+}
+
+/// Fills a bitmap marking the char boundaries of a string slice.
+///
+/// Bit `i % 64` of `out[i / 64]` is set if byte `i` of `text` starts a
+/// char (i.e. isn't a utf8 continuation byte), and cleared otherwise.  All
+/// bits past the end of the text are cleared.  This is a building block
+/// for custom index structures: e.g. the number of chars in
+/// `text[..(64 * n)]` is the sum of `count_ones()` over `out[..n]`.
+///
+/// `text.len().div_ceil(64)` words are enough to hold the whole bitmap.
+/// If `out` is shorter, only the bytes that fit are marked.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn boundary_mask(text: &str, out: &mut [u64]) {
+    with_chunk!(boundary_mask_impl(text.as_bytes(), out))
+}
+
+/// Returns an iterator over the maximal runs of ascii and non-ascii chars
+/// in a string slice.
+///
+/// Yields the byte range of each run along with whether it's an ascii run.
+/// Runs alternate between ascii and non-ascii, and together cover the
+/// whole text.  This is useful for e.g. taking fast paths for ascii text
+/// when rendering or shaping.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn ascii_runs(text: &str) -> AsciiRuns<'_> {
+    AsciiRuns { text, byte_idx: 0 }
+}
+
+/// Iterator over runs of ascii and non-ascii chars, created by
+/// [`ascii_runs()`].
+#[derive(Debug, Clone)]
+pub struct AsciiRuns<'a> {
+    text: &'a str,
+    byte_idx: usize,
+}
+
+impl Iterator for AsciiRuns<'_> {
+    type Item = (Range<usize>, bool);
+
+    #[inline]
+    fn next(&mut self) -> Option<(Range<usize>, bool)> {
+        let bytes = self.text.as_bytes();
+        let start = self.byte_idx;
+        if start >= bytes.len() {
+            return None;
+        }
+        let is_ascii = bytes[start].is_ascii();
+        let end = start + with_chunk!(run_len(&bytes[start..], is_ascii));
+        self.byte_idx = end;
+        Some((start..end, is_ascii))
+    }
+}
+
+impl core::iter::FusedIterator for AsciiRuns<'_> {}
+
+/// Returns an iterator over the byte indices of the chars in a string
+/// slice.
+///
+/// Yields the same indices as `text.char_indices().map(|(i, _)| i)`, but
+/// faster: the chars aren't decoded, and runs of ascii are found a chunk
+/// at a time.  Like `char_indices()`, doesn't yield the one-past-the-end
+/// index.
+///
+/// The iterator is double-ended, so e.g. `boundaries(&text[..i]).rev()`
+/// walks the char boundaries backward from `i`.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn boundaries(text: &str) -> Boundaries<'_> {
+    Boundaries {
+        text,
+        start: 0,
+        end: text.len(),
+        front_run_end: 0,
+        front_base: 0,
+        front_mask: 0,
+        back_run_start: text.len(),
+        back_base: text.len(),
+        back_mask: 0,
+    }
+}
+
+/// Returns an iterator over the `(char_idx, byte_idx)` of each char in a
+/// string slice.
+///
+/// Like [`boundaries()`], but also yields the char index of each
+/// boundary.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn indices(text: &str) -> Indices<'_> {
+    Indices {
+        boundaries: boundaries(text),
+        char_idx: 0,
+    }
+}
+
+/// Iterator over the byte indices of the chars in a string slice, created
+/// by [`boundaries()`].
+#[derive(Debug, Clone)]
+pub struct Boundaries<'a> {
+    text: &'a str,
+    /// The range of the text that hasn't been iterated over yet.
+    start: usize,
+    end: usize,
+    /// End of the run of single-byte chars that `start` is known to be
+    /// in, if any.
+    front_run_end: usize,
+    /// Char boundaries after that run, in the 64 bytes from `front_base`,
+    /// with bit `i` for byte `front_base + i`.  Refilled from `start` when
+    /// both run out.
+    front_base: usize,
+    front_mask: u64,
+    /// Start of the run of single-byte chars that `end` is known to be at
+    /// the end of, if any.
+    back_run_start: usize,
+    /// Char boundaries before that run, in the 64 bytes before
+    /// `back_base`, with bit `i` for byte `back_base - 1 - i`.  Refilled
+    /// from `end` when both run out.
+    back_base: usize,
+    back_mask: u64,
+}
+
+impl Iterator for Boundaries<'_> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        let i = self.start;
+        if i >= self.end {
+            return None;
+        }
+        if i < self.front_run_end {
+            self.start += 1;
+            return Some(i);
+        }
+        self.next_from_mask()
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end.saturating_sub(self.start);
+        ((len + 3) / 4, Some(len))
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        count(self.text.get(self.start..self.end).unwrap_or(""))
+    }
+}
+
+impl DoubleEndedIterator for Boundaries<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<usize> {
+        if self.end <= self.start {
+            return None;
+        }
+        if self.back_run_start < self.end {
+            self.end -= 1;
+            return Some(self.end);
+        }
+        self.next_back_from_mask()
+    }
+
+    #[inline]
+    fn nth_back(&mut self, mut n: usize) -> Option<usize> {
+        loop {
+            // Skip over the known run, and then all the boundaries in the
+            // mask, in one go.
+            let skip = n.min(self.end.saturating_sub(self.back_run_start.max(self.start)));
+            self.end -= skip;
+            n -= skip;
+            if n == 0 {
+                return self.next_back();
+            }
+            if self.back_mask == 0 {
+                self.refill_back_mask();
+                if self.back_run_start < self.end {
+                    continue;
+                }
+            }
+            let len = self.back_base.saturating_sub(self.start);
+            if len < 64 {
+                self.back_mask &= (1 << len) - 1;
+            }
+            let count = self.back_mask.count_ones() as usize;
+            if count == 0 {
+                self.end = self.start;
+                return None;
+            } else if n < count {
+                for _ in 0..n {
+                    self.back_mask &= self.back_mask - 1;
+                }
+                return self.next_back();
+            }
+            n -= count;
+            self.end = self.back_base + self.back_mask.leading_zeros() as usize - 64;
+            self.back_mask = 0;
+        }
+    }
+}
+
+impl Boundaries<'_> {
+    /// `next()` once the known run is used up: takes the next boundary
+    /// from `front_mask`, refilling it first if it's empty.
+    #[inline]
+    fn next_from_mask(&mut self) -> Option<usize> {
+        if self.front_mask == 0 {
+            self.refill_front_mask();
+            if self.start < self.front_run_end {
+                self.start += 1;
+                return Some(self.start - 1);
+            }
+        }
+        let mask = self.front_mask;
+        let i = self.front_base + mask.trailing_zeros() as usize;
+        if mask != 0 && i < self.end {
+            self.front_mask = mask & (mask - 1);
+            // Keep `start` on a char boundary, for `count()` and the back
+            // of the iterator.
+            self.start = i + match self.text.as_bytes().get(i) {
+                Some(0..=0x7F) => 1,
+                Some(0x80..=0xDF) => 2,
+                Some(0xE0..=0xEF) => 3,
+                _ => 4,
+            };
+            Some(i)
+        } else {
+            self.front_mask = 0;
+            self.start = self.end;
+            None
+        }
+    }
+
+    /// Refills `front_run_end` and `front_mask` from the (up to) 64 bytes
+    /// from `start`.
+    #[inline(always)]
+    fn refill_front_mask(&mut self) {
+        let bytes = self.text.as_bytes();
+        let block_end = self.end.min(self.start + 64);
+        let block = bytes.get(self.start..block_end).unwrap_or(&[]);
+        let mut mask = 0;
+        with_chunk!(boundary_mask_impl(block, core::slice::from_mut(&mut mask)));
+
+        // Consecutive boundaries from `start` are single-byte chars, except
+        // for the last one if it's followed by a continuation byte, so they
+        // can be stepped over without the mask.  A whole block of them is
+        // likely part of a long ascii run, so that's found in one go.
+        let mut run = mask.trailing_ones();
+        self.front_base = self.start;
+        if run == 64 {
+            let rest = bytes.get(self.start..self.end).unwrap_or(&[]);
+            self.front_run_end = self.start + with_chunk!(run_len(rest, true));
+            self.front_mask = 0;
+        } else {
+            if run as usize != block.len() {
+                run = run.saturating_sub(1);
+            }
+            self.front_run_end = self.start + run as usize;
+            self.front_mask = mask & (u64::MAX << run);
+        }
+    }
+
+    /// `next_back()` once the known run is used up: takes the next
+    /// boundary from `back_mask`, refilling it first if it's empty.
+    #[inline]
+    fn next_back_from_mask(&mut self) -> Option<usize> {
+        if self.back_mask == 0 {
+            self.refill_back_mask();
+            if self.back_run_start < self.end {
+                self.end -= 1;
+                return Some(self.end);
+            }
+        }
+        let mask = self.back_mask;
+        let i = self
+            .back_base
+            .wrapping_sub(1 + mask.trailing_zeros() as usize);
+        if mask != 0 && i >= self.start {
+            self.back_mask = mask & (mask - 1);
+            self.end = i;
+            Some(i)
+        } else {
+            self.back_mask = 0;
+            self.end = self.start;
+            None
+        }
+    }
+
+    /// Refills `back_run_start` and `back_mask` from the (up to) 64 bytes
+    /// before `end`.
+    #[inline(always)]
+    fn refill_back_mask(&mut self) {
+        let bytes = self.text.as_bytes();
+        let block_start = self.end.saturating_sub(64).max(self.start);
+        let block = bytes.get(block_start..self.end).unwrap_or(&[]);
+        let mut mask = 0;
+        with_chunk!(boundary_mask_impl(block, core::slice::from_mut(&mut mask)));
+        let mask = mask.reverse_bits() >> (64 - block.len()).min(63);
+
+        // Consecutive boundaries just before `end` are single-byte chars,
+        // so they can be stepped over without the mask.  A whole block of
+        // them is likely part of a long ascii run, so that's found in one
+        // go.
+        let run = mask.trailing_ones();
+        self.back_base = self.end;
+        if run == 64 {
+            let rest = bytes.get(self.start..self.end).unwrap_or(&[]);
+            self.back_run_start = self.end - with_chunk!(run_len_back(rest, true));
+            self.back_mask = 0;
+        } else {
+            self.back_run_start = self.end - run as usize;
+            self.back_mask = mask & (u64::MAX << run);
+        }
+    }
+}
+
+impl core::iter::FusedIterator for Boundaries<'_> {}
+
+/// Iterator over the char and byte indices of the chars in a string slice,
+/// created by [`indices()`].
+#[derive(Debug, Clone)]
+pub struct Indices<'a> {
+    boundaries: Boundaries<'a>,
+    char_idx: usize,
+}
+
+impl Iterator for Indices<'_> {
+    type Item = (usize, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, usize)> {
+        let byte_idx = self.boundaries.next()?;
+        self.char_idx += 1;
+        Some((self.char_idx - 1, byte_idx))
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.boundaries.size_hint()
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        self.boundaries.count()
+    }
+}
+
+impl core::iter::FusedIterator for Indices<'_> {}
+
+//-------------------------------------------------------------
+
+/// Returns the length of the run of ascii (or non-ascii) bytes at the
+/// start of `bytes`.
+#[inline(always)]
+fn run_len<T: ByteChunk>(bytes: &[u8], is_ascii: bool) -> usize {
+    let in_run = |byte: &u8| byte.is_ascii() == is_ascii;
+    let (start, middle, _) = unsafe { bytes.align_to::<T>() };
+
+    // Take care of any unaligned bytes at the beginning.
+    if let Some(i) = start.iter().position(|byte| !in_run(byte)) {
+        return i;
+    }
+
+    // Skip whole chunks in the run.  The high bits of a chunk are all
+    // clear for ascii, and all set for non-ascii.
+    let high_bits = T::splat(0x80);
+    let run_bits = if is_ascii { T::zero() } else { high_bits };
+    let mut byte_idx = start.len();
+    for chunk in middle.iter() {
+        if !chunk.bitand(high_bits).bitxor(run_bits).is_zero() {
+            break;
+        }
+        byte_idx += T::SIZE;
+    }
+
+    // Find the end of the run within the chunk, or in the remaining bytes.
+    bytes
+        .get(byte_idx..)
+        .unwrap_or(&[])
+        .iter()
+        .position(|byte| !in_run(byte))
+        .map_or(bytes.len(), |i| byte_idx + i)
+}
+
+/// Returns the length of the run of ascii (or non-ascii) bytes at the
+/// end of `bytes`.
+#[inline(always)]
+fn run_len_back<T: ByteChunk>(bytes: &[u8], is_ascii: bool) -> usize {
+    let in_run = |byte: &u8| byte.is_ascii() == is_ascii;
+    let (_, middle, end) = unsafe { bytes.align_to::<T>() };
+
+    // Take care of any unaligned bytes at the end.
+    if let Some(i) = end.iter().rev().position(|byte| !in_run(byte)) {
+        return i;
+    }
+
+    // Skip whole chunks in the run, as in `run_len()`.
+    let high_bits = T::splat(0x80);
+    let run_bits = if is_ascii { T::zero() } else { high_bits };
+    let mut byte_idx = bytes.len() - end.len();
+    for chunk in middle.iter().rev() {
+        if !chunk.bitand(high_bits).bitxor(run_bits).is_zero() {
+            break;
+        }
+        byte_idx -= T::SIZE;
+    }
+
+    // Find the start of the run within the chunk, or in the remaining
+    // bytes.
+    bytes.len()
+        - bytes
+            .get(..byte_idx)
+            .unwrap_or(&[])
+            .iter()
+            .rposition(|byte| !in_run(byte))
+            .map_or(0, |i| i + 1)
+}
+
+#[inline(always)]
+fn boundary_mask_impl<T: ByteChunk>(text: &[u8], out: &mut [u64]) {
+    let mut blocks = text.chunks(64);
+    for word in out.iter_mut() {
+        let block = blocks.next().unwrap_or(&[]);
+
+        // 64 is a multiple of every chunk size, so the chunks can be read
+        // unaligned straight out of the block.
+        let mut mask = 0;
+        let mut i = 0;
+        while i + T::SIZE <= block.len() {
+            let chunk = unsafe { (block.as_ptr().add(i) as *const T).read_unaligned() };
+            let leading = T::splat(1).sub(count_trailing_chunk(chunk));
+            mask |= leading.pack_flags() << i;
+            i += T::SIZE;
+        }
+        for (j, byte) in block.iter().enumerate().skip(i) {
+            mask |= (is_leading_byte(byte) as u64) << j;
+        }
+        *word = mask;
+    }
+}
+
+#[inline(always)]
+fn common_prefix_impl<T: ByteChunk>(
+    a: &[u8],
+    b: &[u8],
+    mut on_equal_chunk: impl FnMut(T),
+) -> usize {
+    let len = a.len().min(b.len());
+
+    // Compare a chunk at a time until there's a mismatch.  The two slices
+    // generally aren't aligned the same way, so use unaligned loads.
+    let mut i = 0;
+    while i + T::SIZE <= len {
+        let (chunk_a, chunk_b) = unsafe {
+            (
+                (a.as_ptr().add(i) as *const T).read_unaligned(),
+                (b.as_ptr().add(i) as *const T).read_unaligned(),
+            )
+        };
+        if !chunk_a.bitxor(chunk_b).is_zero() {
+            break;
+        }
+        on_equal_chunk(chunk_a);
+        i += T::SIZE;
+    }
+
+    // Find the mismatch within the chunk, or in the remaining bytes.
+    i + a
+        .get(i..len)
+        .unwrap_or(&[])
+        .iter()
+        .zip(b.get(i..len).unwrap_or(&[]).iter())
+        .take_while(|(x, y)| x == y)
+        .count()
+}
+
+#[inline(always)]
+fn common_suffix_impl<T: ByteChunk>(
+    a: &[u8],
+    b: &[u8],
+    mut on_equal_chunk: impl FnMut(T),
+) -> usize {
+    let len = a.len().min(b.len());
+    let (a, b) = (&a[(a.len() - len)..], &b[(b.len() - len)..]);
+
+    // Compare a chunk at a time from the end until there's a mismatch.
+    let mut i = len;
+    while i >= T::SIZE {
+        let (chunk_a, chunk_b) = unsafe {
+            (
+                (a.as_ptr().add(i - T::SIZE) as *const T).read_unaligned(),
+                (b.as_ptr().add(i - T::SIZE) as *const T).read_unaligned(),
+            )
+        };
+        if !chunk_a.bitxor(chunk_b).is_zero() {
+            break;
+        }
+        on_equal_chunk(chunk_a);
+        i -= T::SIZE;
+    }
+
+    // Find the mismatch within the chunk, or in the remaining bytes.
+    (len - i)
+        + a.get(..i)
+            .unwrap_or(&[])
+            .iter()
+            .rev()
+            .zip(b.get(..i).unwrap_or(&[]).iter().rev())
+            .take_while(|(x, y)| x == y)
+            .count()
+}
+
+#[inline(always)]
+fn common_prefix_chars_impl<T: ByteChunk>(a: &str, b: &str) -> (usize, usize) {
+    let mut counter = ChunkCharCounter::<T>::new();
+    let len = common_prefix_impl::<T>(a.as_bytes(), b.as_bytes(), |chunk| counter.add(chunk));
+    let (chunked_len, chunked_chars) = counter.finish();
+
+    // The chunks cover the start of the prefix, which may end before or
+    // after the prefix does once it's snapped to a char boundary.
+    let bytes = a.as_bytes();
+    let byte_len = floor_byte_boundary(a, len);
+    let char_len = if byte_len >= chunked_len {
+        chunked_chars + count_leading_bytes(bytes.get(chunked_len..byte_len))
+    } else {
+        chunked_chars - count_leading_bytes(bytes.get(byte_len..chunked_len))
+    };
+    (byte_len, char_len)
+}
+
+#[inline(always)]
+fn common_suffix_chars_impl<T: ByteChunk>(a: &str, b: &str) -> (usize, usize) {
+    let mut counter = ChunkCharCounter::<T>::new();
+    let len = common_suffix_impl::<T>(a.as_bytes(), b.as_bytes(), |chunk| counter.add(chunk));
+    let (chunked_len, chunked_chars) = counter.finish();
+
+    // Likewise, but the chunks cover the end of the suffix.
+    let bytes = a.as_bytes();
+    let start = ceil_byte_boundary(a, a.len() - len);
+    let chunked_start = a.len() - chunked_len;
+    let char_len = if start <= chunked_start {
+        chunked_chars + count_leading_bytes(bytes.get(start..chunked_start))
+    } else {
+        chunked_chars - count_leading_bytes(bytes.get(chunked_start..start))
+    };
+    (a.len() - start, char_len)
+}
+
+/// Counts the chars in a sequence of chunks, a chunk at a time.
+struct ChunkCharCounter<T: ByteChunk> {
+    acc: T,
+    acc_chunks: usize,
+    chunks: usize,
+    trailing: usize,
+}
+
+impl<T: ByteChunk> ChunkCharCounter<T> {
+    #[inline(always)]
+    fn new() -> Self {
+        ChunkCharCounter {
+            acc: T::zero(),
+            acc_chunks: 0,
+            chunks: 0,
+            trailing: 0,
+        }
+    }
+
+    #[inline(always)]
+    fn add(&mut self, chunk: T) {
+        self.acc = self.acc.add(count_trailing_chunk(chunk));
+        self.acc_chunks += 1;
+        self.chunks += 1;
+        if self.acc_chunks == T::MAX_ACC {
+            self.trailing += self.acc.sum_bytes();
+            self.acc = T::zero();
+            self.acc_chunks = 0;
+        }
+    }
+
+    /// Returns the number of bytes and chars in the chunks.
+    #[inline(always)]
+    fn finish(self) -> (usize, usize) {
+        let bytes = self.chunks * T::SIZE;
+        (bytes, bytes - self.trailing - self.acc.sum_bytes())
+    }
+}
+
+/// Counts the leading bytes, i.e. chars, in a byte slice, or zero if
+/// `None`.
+#[inline(always)]
+fn count_leading_bytes(bytes: Option<&[u8]>) -> usize {
+    bytes
+        .unwrap_or(&[])
+        .iter()
+        .filter(|byte| is_leading_byte(byte))
+        .count()
+}
+
+#[inline(always)]
+pub(crate) fn from_byte_idx_impl<T: ByteChunk>(text: &str, byte_idx: usize) -> usize {
+    let bytes = text.as_bytes();
+
+    // Ensure the index is either a char boundary or is off the end of
+    // the text.
+    let mut i = byte_idx;
+    while Some(true) == bytes.get(i).map(is_trailing_byte) {
+        i -= 1;
+    }
+
+    count_impl::<T>(&bytes[0..i.min(bytes.len())])
 }
 
-//-------------------------------------------------------------
+#[inline(always)]
+pub(crate) fn to_byte_idx_impl<T: ByteChunk>(text: &[u8], char_idx: usize) -> usize {
+    count_up_to_impl::<T>(text, char_idx).1
+}
+
+/// Returns the byte index of the char `chars_back` chars before the end of
+/// `text`, or zero if there are fewer chars than that.
+///
+/// Scans backward from the end a block at a time, counting the chars of
+/// each block with the chunked counting.
+#[inline(always)]
+fn to_byte_idx_back_impl<T: ByteChunk>(text: &[u8], chars_back: usize) -> usize {
+    // Big enough to amortize the setup costs of the chunked counting.
+    const BLOCK_LEN: usize = 1 << 12;
+
+    let mut end = text.len();
+    let mut counted = 0;
+    while end > 0 {
+        // Blocks never split a char.
+        let mut start = end.saturating_sub(BLOCK_LEN);
+        while start > 0 && matches!(text.get(start), Some(b) if is_trailing_byte(b)) {
+            start -= 1;
+        }
+        let block = text.get(start..end).unwrap_or(&[]);
+        let block_count = count_impl::<T>(block);
+        if counted + block_count >= chars_back {
+            return start + to_byte_idx_impl::<T>(block, counted + block_count - chars_back);
+        }
+        counted += block_count;
+        end = start;
+    }
+    0
+}
 
+/// Returns `(char_count, byte_count)`, where `byte_count` is as returned
+/// by `to_byte_idx()` and `char_count` is the number of chars before it.
 #[inline(always)]
-fn to_byte_idx_impl<T: ByteChunk>(text: &[u8], char_idx: usize) -> usize {
-    if text.len() <= T::SIZE {
+pub(crate) fn count_up_to_impl<T: ByteChunk>(text: &[u8], char_idx: usize) -> (usize, usize) {
+    if text.len() < T::CHARS_TO_BYTE_IDX_THRESHOLD {
         // Bypass the more complex routine for short strings, where the
         // complexity hurts performance.
         let mut char_count = 0;
         for (i, byte) in text.iter().enumerate() {
             char_count += is_leading_byte(byte) as usize;
             if char_count > char_idx {
-                return i;
+                return (char_idx, i);
             }
         }
-        return text.len();
+        return (char_count, text.len());
     }
     // Get `middle` so we can do more efficient chunk-based counting.
     // We can't use this to get `end`, however, because the start index of
@@ -71,7 +1143,7 @@ fn to_byte_idx_impl<T: ByteChunk>(text: &[u8], char_idx: usize) -> usize {
     for byte in start.iter() {
         char_count += is_leading_byte(byte) as usize;
         if char_count > char_idx {
-            return byte_count;
+            return (char_idx, byte_count);
         }
         byte_count += 1;
     }
@@ -79,18 +1151,13 @@ fn to_byte_idx_impl<T: ByteChunk>(text: &[u8], char_idx: usize) -> usize {
     // Process chunks in the fast path. Ensure that we don't go past the number
     // of chars we are counting towards
     let fast_path_chunks = middle.len().min((char_idx - char_count) / T::SIZE);
-    let bytes = T::SIZE * 4;
-    for chunks in middle[..fast_path_chunks].chunks_exact(4) {
-        let val1 = count_trailing_chunk(chunks[0]);
-        let val2 = count_trailing_chunk(chunks[1]);
-        let val3 = count_trailing_chunk(chunks[2]);
-        let val4 = count_trailing_chunk(chunks[3]);
-        char_count += bytes - val1.add(val2).add(val3.add(val4)).sum_bytes();
-        byte_count += bytes;
-    }
+    let groups_len = fast_path_chunks - fast_path_chunks % T::UNROLL;
+    let bytes = T::SIZE * groups_len;
+    char_count += bytes - count_trailing_groups(middle.get(..groups_len).unwrap_or(&[]));
+    byte_count += bytes;
 
     // Process the rest of chunks in the slow path.
-    for chunk in middle[(fast_path_chunks - fast_path_chunks % 4)..].iter() {
+    for chunk in middle.get(groups_len..).unwrap_or(&[]).iter() {
         let new_char_count = char_count + T::SIZE - count_trailing_chunk(*chunk).sum_bytes();
         if new_char_count >= char_idx {
             break;
@@ -100,7 +1167,7 @@ fn to_byte_idx_impl<T: ByteChunk>(text: &[u8], char_idx: usize) -> usize {
     }
 
     // Take care of any unaligned bytes at the end.
-    let end = &text[byte_count..];
+    let end = text.get(byte_count..).unwrap_or(&[]);
     for byte in end.iter() {
         char_count += is_leading_byte(byte) as usize;
         if char_count > char_idx {
@@ -109,15 +1176,18 @@ fn to_byte_idx_impl<T: ByteChunk>(text: &[u8], char_idx: usize) -> usize {
         byte_count += 1;
     }
 
-    byte_count
+    (char_count.min(char_idx), byte_count)
 }
 
 #[inline(always)]
 pub(crate) fn count_impl<T: ByteChunk>(text: &[u8]) -> usize {
-    if text.len() < T::SIZE {
+    if text.len() < usize::SIZE {
+        return text.iter().map(|x| is_leading_byte(x) as usize).sum();
+    }
+    if text.len() < T::CHARS_COUNT_THRESHOLD {
         // Bypass the more complex routine for short strings, where the
         // complexity hurts performance.
-        return text.iter().map(|x| is_leading_byte(x) as usize).sum();
+        return text.len() - count_trailing_swar(text, 0, text.len());
     }
     // Get `middle` for more efficient chunk-based counting.
     let (start, middle, end) = unsafe { text.align_to::<T>() };
@@ -125,28 +1195,144 @@ pub(crate) fn count_impl<T: ByteChunk>(text: &[u8]) -> usize {
     let mut inv_count = 0;
 
     // Take care of unaligned bytes at the beginning.
-    inv_count += start.iter().filter(|x| is_trailing_byte(x)).count();
+    inv_count += count_trailing_swar(text, 0, start.len());
 
     // Take care of the middle bytes in big chunks. Loop unrolled.
-    for chunks in middle.chunks_exact(4) {
-        let val1 = count_trailing_chunk(chunks[0]);
-        let val2 = count_trailing_chunk(chunks[1]);
-        let val3 = count_trailing_chunk(chunks[2]);
-        let val4 = count_trailing_chunk(chunks[3]);
-        inv_count += val1.add(val2).add(val3.add(val4)).sum_bytes();
-    }
+    let groups_len = middle.len() - middle.len() % T::UNROLL;
+    inv_count += count_trailing_groups(middle.get(..groups_len).unwrap_or(&[]));
     let mut acc = T::zero();
-    for chunk in middle.chunks_exact(4).remainder() {
+    for chunk in middle.get(groups_len..).unwrap_or(&[]).iter() {
         acc = acc.add(count_trailing_chunk(*chunk));
     }
     inv_count += acc.sum_bytes();
 
     // Take care of unaligned bytes at the end.
-    inv_count += end.iter().filter(|x| is_trailing_byte(x)).count();
+    inv_count += count_trailing_swar(text, text.len() - end.len(), text.len());
 
     text.len() - inv_count
 }
 
+/// Returns `(char_count, lf_count)`.
+#[inline(always)]
+pub(crate) fn count_with_lf_impl<T: ByteChunk>(text: &[u8]) -> (usize, usize) {
+    let count_bytes = |bytes: &[u8]| {
+        bytes.iter().fold((0, 0), |(chars, lfs), byte| {
+            (
+                chars + is_leading_byte(byte) as usize,
+                lfs + (*byte == 0x0A) as usize,
+            )
+        })
+    };
+    if text.len() < T::SIZE {
+        return count_bytes(text);
+    }
+    // Get `middle` for more efficient chunk-based counting.
+    let (start, middle, end) = unsafe { text.align_to::<T>() };
+
+    // Take care of unaligned bytes at the beginning and end.
+    let (start_chars, start_lfs) = count_bytes(start);
+    let (end_chars, end_lfs) = count_bytes(end);
+    let mut char_count = start_chars + end_chars;
+    let mut lf_count = start_lfs + end_lfs;
+
+    // Take care of the middle bytes in big chunks. Loop unrolled.
+    for chunks in middle.chunks(T::UNROLL) {
+        let mut trailing_acc = T::zero();
+        let mut lf_acc = T::zero();
+        for chunk in chunks.iter() {
+            trailing_acc = trailing_acc.add(count_trailing_chunk(*chunk));
+            lf_acc = lf_acc.add(chunk.cmp_eq_byte(0x0A));
+        }
+        char_count += chunks.len() * T::SIZE - trailing_acc.sum_bytes();
+        lf_count += lf_acc.sum_bytes();
+    }
+
+    (char_count, lf_count)
+}
+
+/// Counts the bytes that are at least 0xC0, 0xE0, and 0xF0, i.e. the
+/// leading bytes of chars at least 2, 3, and 4 bytes long.  In valid utf8
+/// that's enough to recover the whole histogram, since the chars' lengths
+/// add up to the text's.
+#[inline(always)]
+fn len_histogram_impl<T: ByteChunk>(text: &[u8]) -> [usize; 4] {
+    let count_bytes = |bytes: &[u8]| {
+        bytes.iter().fold([0; 3], |[c0, e0, f0], byte| {
+            [
+                c0 + (*byte >= 0xC0) as usize,
+                e0 + (*byte >= 0xE0) as usize,
+                f0 + (*byte >= 0xF0) as usize,
+            ]
+        })
+    };
+    let [mut c0, mut e0, mut f0] = [0; 3];
+    let mut add_counts = |[c, e, f]: [usize; 3]| {
+        c0 += c;
+        e0 += e;
+        f0 += f;
+    };
+
+    if text.len() < T::SIZE {
+        add_counts(count_bytes(text));
+    } else {
+        // Get `middle` for more efficient chunk-based counting.
+        let (start, middle, end) = unsafe { text.align_to::<T>() };
+
+        // Take care of unaligned bytes at the beginning and end.
+        add_counts(count_bytes(start));
+        add_counts(count_bytes(end));
+
+        // Take care of the middle bytes in big chunks. Loop unrolled.
+        for chunks in middle.chunks(T::UNROLL) {
+            let mut accs = [T::zero(); 3];
+            for chunk in chunks.iter() {
+                for (acc, lead) in accs.iter_mut().zip([0xC0, 0xE0, 0xF0]) {
+                    *acc = acc.add(chunk.bitand(T::splat(lead)).cmp_eq_byte(lead));
+                }
+            }
+            add_counts(accs.map(|acc| acc.sum_bytes()));
+        }
+    }
+
+    let (n4, n3, n2) = (f0, e0 - f0, c0 - e0);
+    [text.len() - n2 * 2 - n3 * 3 - n4 * 4, n2, n3, n4]
+}
+
+/// Counts the trailing bytes in `text[start..end]` a `usize` at a time.
+///
+/// Each step loads a full `usize` worth of bytes that contains the bytes
+/// being counted, and masks off the bytes outside of the range.  `text`
+/// MUST be at least `size_of::<usize>()` bytes long, so that such a load is
+/// always possible.
+#[inline(always)]
+fn count_trailing_swar(text: &[u8], start: usize, end: usize) -> usize {
+    const W: usize = core::mem::size_of::<usize>();
+    debug_assert!(text.len() >= W);
+
+    let mut count = 0;
+    let mut i = start;
+    while i < end {
+        let n = (end - i).min(W);
+        let load_i = i.min(text.len() - W);
+        let mut word = [0u8; W];
+        word.copy_from_slice(&text[load_i..(load_i + W)]);
+        let flags = count_trailing_chunk(usize::from_ne_bytes(word));
+
+        // Mask off the flags of bytes outside of `i..(i + n)`.
+        let low_mask = if n == W { !0 } else { (1usize << (n * 8)) - 1 };
+        let mask = if cfg!(target_endian = "little") {
+            low_mask << ((i - load_i) * 8)
+        } else {
+            low_mask << ((W - (i - load_i) - n) * 8)
+        };
+        count += (flags & mask).sum_bytes();
+
+        i += n;
+    }
+
+    count
+}
+
 #[inline(always)]
 fn is_leading_byte(byte: &u8) -> bool {
     (byte & 0xC0) != 0x80
@@ -157,6 +1343,85 @@ fn is_trailing_byte(byte: &u8) -> bool {
     (byte & 0xC0) == 0x80
 }
 
+/// Counts the trailing bytes in chunks processed in groups of `T::UNROLL`.
+///
+/// `chunks.len()` must be a multiple of `T::UNROLL`.
+#[inline(always)]
+fn count_trailing_groups<T: ByteChunk>(chunks: &[T]) -> usize {
+    sum_non_ascii_groups(chunks, |group| {
+        let mut acc = T::zero();
+        for chunk in group.iter() {
+            acc = acc.add(count_trailing_chunk(*chunk));
+        }
+        acc.sum_bytes()
+    })
+}
+
+/// Sums `count_group()` over chunks processed in groups of `T::UNROLL`,
+/// skipping all-ascii groups.  `count_group()` must return zero for those,
+/// and should return the number of trailing bytes (or something close to
+/// it) for the others.
+///
+/// `chunks.len()` must be a multiple of `T::UNROLL`.  `count_group()` is
+/// passed a multiple of `T::UNROLL` chunks at a time, but never more than
+/// `BLOCK_GROUPS` groups' worth or `T::MAX_ACC` chunks.
+///
+/// Checking whether a group is all ascii is cheaper than counting it.  But
+/// the check is a branch, which slows down non-ascii text, so once a group
+/// isn't ascii this counts whole blocks of groups unconditionally until a
+/// block has no trailing bytes again.
+#[inline(always)]
+pub(crate) fn sum_non_ascii_groups<T: ByteChunk>(
+    chunks: &[T],
+    mut count_group: impl FnMut(&[T]) -> usize,
+) -> usize {
+    let is_ascii = |group: &[T]| {
+        group
+            .iter()
+            .fold(T::zero(), |acc, chunk| acc.bitor(*chunk))
+            .bitand(T::splat(0x80))
+            .is_zero()
+    };
+
+    let mut chunks = chunks;
+    let mut count = 0;
+    'outer: loop {
+        // Skip ascii groups.
+        loop {
+            if chunks.len() < T::UNROLL {
+                break 'outer;
+            }
+            let (group, rest) = chunks.split_at(T::UNROLL);
+            chunks = rest;
+            if !is_ascii(group) {
+                count += count_group(group);
+                break;
+            }
+        }
+
+        // Count blocks of groups until one is all ascii.
+        loop {
+            let max_block_len = (T::UNROLL * BLOCK_GROUPS).min(T::MAX_ACC - T::MAX_ACC % T::UNROLL);
+            let block_len = max_block_len.min(chunks.len());
+            if block_len == 0 {
+                break 'outer;
+            }
+            let (block, rest) = chunks.split_at(block_len);
+            chunks = rest;
+            let block_count = count_group(block);
+            count += block_count;
+            if block_count == 0 {
+                break;
+            }
+        }
+    }
+    count
+}
+
+/// The number of groups of chunks that `sum_non_ascii_groups()` counts at a
+/// time once it finds non-ascii text.
+const BLOCK_GROUPS: usize = 4;
+
 #[inline(always)]
 fn count_trailing_chunk<T: ByteChunk>(val: T) -> T {
     val.bitand(T::splat(0xc0)).cmp_eq_byte(0x80)
@@ -185,6 +1450,48 @@ mod tests {
         assert_eq!(100, count(TEXT_LINES));
     }
 
+    #[test]
+    fn count_03() {
+        // Every sub-slice, to exercise all head/tail lengths and alignments.
+        let text = "Hello せかい! こんにちは🐸 world! Hello せかい! こんにちは🐸 world!";
+        for (start, _) in text.char_indices() {
+            for (end, _) in text[start..].char_indices() {
+                let slice = &text[start..(start + end)];
+                assert_eq!(slice.chars().count(), count(slice));
+            }
+        }
+    }
+
+    #[test]
+    fn count_and_last_boundary_01() {
+        assert_eq!((0, 0), count_and_last_boundary(""));
+        assert_eq!((1, 0), count_and_last_boundary("a"));
+        assert_eq!((1, 0), count_and_last_boundary("🐸"));
+        assert_eq!((3, 2), count_and_last_boundary("ab🐸"));
+        assert_eq!((4, 6), count_and_last_boundary("ab🐸c"));
+    }
+
+    #[test]
+    fn count_in_range_01() {
+        // 🐸 is bytes 2..6.
+        let text = "ab🐸éc\n";
+        assert_eq!(2, count_in_range(text, 1..7));
+        assert_eq!(1, count_in_range(text, 1..3));
+        assert_eq!(1, count_in_range(text, 3..7));
+        assert_eq!(0, count_in_range(text, 3..5));
+        assert_eq!(6, count_in_range(text, 0..100));
+        assert_eq!(0, count_in_range(text, 50..100));
+        assert_eq!(0, count_in_range(text, Range { start: 5, end: 1 }));
+        for start in 0..12 {
+            for end in start..12 {
+                assert_eq!(
+                    from_byte_idx(text, end) - from_byte_idx(text, start),
+                    count_in_range(text, start..end)
+                );
+            }
+        }
+    }
+
     #[test]
     fn from_byte_idx_01() {
         let text = "Hello せかい!";
@@ -260,6 +1567,60 @@ mod tests {
         }
     }
 
+    #[test]
+    fn from_byte_idx_with_count_01() {
+        let text = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r\n".repeat(4);
+        for end in (0..=text.len()).filter(|&i| text.is_char_boundary(i)) {
+            let text = &text[..end];
+            let char_count = count(text);
+            for i in 0..(text.len() + 2) {
+                assert_eq!(
+                    from_byte_idx(text, i),
+                    from_byte_idx_with_count(text, i, char_count)
+                );
+            }
+        }
+
+        // Wrong counts don't panic.
+        assert_eq!(0, from_byte_idx_with_count("abc", 2, 0));
+    }
+
+    #[test]
+    fn from_byte_idxs_01() {
+        let text = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r\n".repeat(4);
+        let text = text.as_str();
+        let byte_idxs: [usize; 12] = [0, 0, 3, 4, 5, 30, 31, 60, 100, 200, 200, 999];
+        let mut out = [usize::MAX; 12];
+        from_byte_idxs(text, &byte_idxs, &mut out);
+        for (&i, &char_idx) in byte_idxs.iter().zip(out.iter()) {
+            assert_eq!(from_byte_idx(text, i), char_idx);
+        }
+
+        // Unsorted.
+        let byte_idxs = [100, 3, 999, 31, 0, 60];
+        from_byte_idxs(text, &byte_idxs, &mut out);
+        for (&i, &char_idx) in byte_idxs.iter().zip(out.iter()) {
+            assert_eq!(from_byte_idx(text, i), char_idx);
+        }
+
+        // Short output.
+        let mut out = [usize::MAX; 2];
+        from_byte_idxs(text, &byte_idxs, &mut out);
+        assert_eq!([from_byte_idx(text, 100), from_byte_idx(text, 3)], out);
+    }
+
+    #[test]
+    fn from_byte_idx_snapped_01() {
+        let text = "Hello せ!";
+        assert_eq!((0, 0), from_byte_idx_snapped(text, 0));
+        assert_eq!((6, 0), from_byte_idx_snapped(text, 6));
+        assert_eq!((6, 1), from_byte_idx_snapped(text, 7));
+        assert_eq!((6, 2), from_byte_idx_snapped(text, 8));
+        assert_eq!((7, 0), from_byte_idx_snapped(text, 9));
+        assert_eq!((8, 0), from_byte_idx_snapped(text, 10));
+        assert_eq!((8, 2), from_byte_idx_snapped(text, 12));
+    }
+
     #[test]
     fn to_byte_idx_01() {
         let text = "Hello せかい!";
@@ -327,4 +1688,622 @@ mod tests {
             assert_eq!(124, to_byte_idx(TEXT_LINES, i));
         }
     }
+
+    #[test]
+    fn to_byte_idx_with_remainder_01() {
+        let text = "ab🐸éc\n";
+        assert_eq!((0, 0), to_byte_idx_with_remainder(text, 0));
+        assert_eq!((6, 0), to_byte_idx_with_remainder(text, 3));
+        assert_eq!((10, 0), to_byte_idx_with_remainder(text, 6));
+        assert_eq!((10, 1), to_byte_idx_with_remainder(text, 7));
+        assert_eq!((10, 94), to_byte_idx_with_remainder(text, 100));
+        assert_eq!((0, 5), to_byte_idx_with_remainder("", 5));
+
+        // Chained across chunks.
+        let chunks = ["ab", "🐸é", "", "c\n"];
+        let mut char_idx = 5;
+        let mut byte_idx = 0;
+        for chunk in chunks {
+            let (i, remaining) = to_byte_idx_with_remainder(chunk, char_idx);
+            if remaining == 0 && i < chunk.len() {
+                byte_idx += i;
+                break;
+            }
+            byte_idx += chunk.len();
+            char_idx = remaining;
+        }
+        assert_eq!(to_byte_idx(text, 5), byte_idx);
+    }
+
+    #[test]
+    fn to_byte_idx_from_01() {
+        let text = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r\n".repeat(4);
+        let text = text.as_str();
+        for anchor_byte in 0..(text.len() + 2) {
+            let anchor_char = from_byte_idx(text, anchor_byte);
+            for i in 0..(count(text) + 2) {
+                assert_eq!(
+                    to_byte_idx(text, i),
+                    to_byte_idx_from(text, i, anchor_byte, anchor_char)
+                );
+            }
+        }
+
+        // Wrong anchors don't panic.
+        to_byte_idx_from("abc", 1, 2, 100);
+        to_byte_idx_from("abc", 1, 100, 2);
+    }
+
+    #[test]
+    fn to_byte_idx_from_02() {
+        // Long enough for the backward scan to cross several blocks.
+        let text = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r\n".repeat(400);
+        let text = text.as_str();
+        let anchor_byte = text.len() - 4;
+        let anchor_char = from_byte_idx(text, anchor_byte);
+        for i in (0..(count(text) + 2)).step_by(7) {
+            assert_eq!(
+                to_byte_idx(text, i),
+                to_byte_idx_from(text, i, anchor_byte, anchor_char)
+            );
+            if i < anchor_char {
+                assert_eq!(
+                    to_byte_idx(text, i),
+                    to_byte_idx_back_impl::<u16>(&text.as_bytes()[..anchor_byte], anchor_char - i)
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn ascii_group_skipping_01() {
+        // Ascii and non-ascii runs longer than `T::UNROLL * BLOCK_GROUPS`
+        // chunks of every chunk type, at every alignment, so that whole
+        // groups are skipped and whole blocks of groups are counted.
+        use crate::byte_chunk::Chunk;
+        let text = [
+            "a".repeat(700),
+            "é".repeat(350),
+            "b".repeat(1100),
+            "こ🐸".repeat(100),
+            "c\n".repeat(300),
+        ]
+        .concat();
+        for start in 0..64 {
+            let text = &text[start..];
+            let bytes = text.as_bytes();
+            let char_count = text.chars().count();
+            assert_eq!(char_count, count_impl::<u16>(bytes));
+            assert_eq!(char_count, count_impl::<u32>(bytes));
+            assert_eq!(char_count, count_impl::<Chunk>(bytes));
+            for (char_idx, (byte_idx, _)) in text.char_indices().enumerate().step_by(29) {
+                assert_eq!(byte_idx, to_byte_idx_impl::<u16>(bytes, char_idx));
+                assert_eq!(byte_idx, to_byte_idx_impl::<u32>(bytes, char_idx));
+                assert_eq!(byte_idx, to_byte_idx_impl::<Chunk>(bytes, char_idx));
+                assert_eq!(char_idx, from_byte_idx_impl::<u16>(text, byte_idx));
+                assert_eq!(char_idx, from_byte_idx_impl::<u32>(text, byte_idx));
+                assert_eq!(char_idx, from_byte_idx_impl::<Chunk>(text, byte_idx));
+            }
+        }
+    }
+
+    #[test]
+    fn count_up_to_01() {
+        let text = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r\n".repeat(4);
+        for end in (0..=text.len()).filter(|&i| text.is_char_boundary(i)) {
+            let text = &text[..end];
+            for i in 0..(count(text) + 2) {
+                let byte_idx = to_byte_idx(text, i);
+                let expected = (count(&text[..byte_idx]), byte_idx);
+                assert_eq!(expected, count_up_to(text, i));
+                assert_eq!(expected, count_up_to_impl::<u16>(text.as_bytes(), i));
+                assert_eq!(expected, count_up_to_impl::<u32>(text.as_bytes(), i));
+            }
+        }
+        assert_eq!((0, 0), count_up_to("", 5));
+        assert_eq!((2, 5), count_up_to("a🐸bc", 2));
+        assert_eq!((4, 7), count_up_to("a🐸bc", 9));
+    }
+
+    #[test]
+    fn split_at_01() {
+        let text = "Hello せ!";
+        assert_eq!(("", "Hello せ!"), split_at(text, 0));
+        assert_eq!(("Hello ", "せ!"), split_at(text, 6));
+        assert_eq!(("Hello せ", "!"), split_at(text, 7));
+        assert_eq!(("Hello せ!", ""), split_at(text, 8));
+        assert_eq!(("Hello せ!", ""), split_at(text, 9));
+    }
+
+    #[test]
+    fn slice_01() {
+        let text = "ab🐸éc\n";
+        assert_eq!("b🐸é", slice(text, 1..4));
+        assert_eq!("", slice(text, 2..2));
+        assert_eq!("", slice(text, Range { start: 4, end: 2 }));
+        assert_eq!("c\n", slice(text, 4..100));
+        assert_eq!("", slice(text, 100..200));
+        assert_eq!(text, slice(text, 0..6));
+        for start in 0..8 {
+            for end in start..8 {
+                let expected = &text[to_byte_idx(text, start)..to_byte_idx(text, end)];
+                assert_eq!(expected, slice(text, start..end));
+            }
+        }
+    }
+
+    #[test]
+    fn prefix_01() {
+        let text = "ab🐸éc\n";
+        assert_eq!("", prefix(text, 0));
+        assert_eq!("ab🐸", prefix(text, 3));
+        assert_eq!(text, prefix(text, 6));
+        assert_eq!(text, prefix(text, 100));
+        assert_eq!("", prefix("", 3));
+
+        assert_eq!("", suffix(text, 0));
+        assert_eq!("🐸éc\n", suffix(text, 4));
+        assert_eq!(text, suffix(text, 6));
+        assert_eq!(text, suffix(text, 100));
+        assert_eq!("", suffix("", 3));
+    }
+
+    #[test]
+    fn nth_01() {
+        let text = "ab🐸éc\n";
+        assert!((0..8)
+            .map(|i| nth(text, i))
+            .eq(text.chars().map(Some).chain([None, None])));
+        assert_eq!(None, nth("", 0));
+        assert_eq!(None, nth(text, usize::MAX));
+    }
+
+    #[test]
+    fn floor_byte_boundary_01() {
+        let text = "Hello せ!";
+        assert_eq!(0, floor_byte_boundary(text, 0));
+        assert_eq!(6, floor_byte_boundary(text, 6));
+        assert_eq!(6, floor_byte_boundary(text, 7));
+        assert_eq!(6, floor_byte_boundary(text, 8));
+        assert_eq!(9, floor_byte_boundary(text, 9));
+        assert_eq!(10, floor_byte_boundary(text, 10));
+        assert_eq!(10, floor_byte_boundary(text, 11));
+        assert_eq!(0, floor_byte_boundary("", 3));
+    }
+
+    #[test]
+    fn floor_byte_boundary_02() {
+        for (i, c) in "a🐸".char_indices() {
+            for offset in 0..c.len_utf8() {
+                assert_eq!(i, floor_byte_boundary("a🐸", i + offset));
+            }
+        }
+    }
+
+    #[test]
+    fn ceil_byte_boundary_01() {
+        let text = "Hello せ!";
+        assert_eq!(0, ceil_byte_boundary(text, 0));
+        assert_eq!(6, ceil_byte_boundary(text, 6));
+        assert_eq!(9, ceil_byte_boundary(text, 7));
+        assert_eq!(9, ceil_byte_boundary(text, 8));
+        assert_eq!(9, ceil_byte_boundary(text, 9));
+        assert_eq!(10, ceil_byte_boundary(text, 10));
+        assert_eq!(10, ceil_byte_boundary(text, 11));
+        assert_eq!(0, ceil_byte_boundary("", 3));
+    }
+
+    #[test]
+    fn ceil_byte_boundary_02() {
+        for (i, c) in "a🐸b".char_indices() {
+            for offset in 1..c.len_utf8() {
+                assert_eq!(i + c.len_utf8(), ceil_byte_boundary("a🐸b", i + offset));
+            }
+        }
+    }
+
+    #[test]
+    fn shrink_range_01() {
+        // せ is bytes 6..9.
+        let text = "Hello せ!";
+        assert_eq!(0..10, shrink_range(text, 0..10));
+        assert_eq!(6..9, shrink_range(text, 6..9));
+        assert_eq!(9..9, shrink_range(text, 7..9));
+        assert_eq!(6..6, shrink_range(text, 6..8));
+        assert_eq!(9..9, shrink_range(text, 7..8));
+        assert_eq!(9..10, shrink_range(text, 8..20));
+        assert_eq!(10..10, shrink_range(text, 15..20));
+        assert_eq!(9..9, shrink_range(text, Range { start: 8, end: 2 }));
+    }
+
+    #[test]
+    fn expand_range_01() {
+        // せ is bytes 6..9.
+        let text = "Hello せ!";
+        assert_eq!(0..10, expand_range(text, 0..10));
+        assert_eq!(6..9, expand_range(text, 6..9));
+        assert_eq!(6..9, expand_range(text, 7..9));
+        assert_eq!(6..9, expand_range(text, 6..8));
+        assert_eq!(6..9, expand_range(text, 7..8));
+        assert_eq!(6..10, expand_range(text, 8..20));
+        assert_eq!(10..10, expand_range(text, 15..20));
+        assert_eq!(6..9, expand_range(text, Range { start: 8, end: 2 }));
+    }
+
+    #[test]
+    fn ranges_are_sliceable() {
+        let text = "a🐸せ";
+        for start in 0..(text.len() + 2) {
+            for end in 0..(text.len() + 2) {
+                let inner = shrink_range(text, start..end);
+                let outer = expand_range(text, start..end);
+                assert!(text.get(inner.clone()).is_some());
+                assert!(text.get(outer.clone()).is_some());
+                assert!(outer.start <= inner.start && inner.end <= outer.end);
+            }
+        }
+    }
+
+    // Exercises the chunk-based code with 16- and 32-bit words, as used on
+    // 16-bit (AVR, MSP430) and 32-bit targets, regardless of the host.
+    #[test]
+    fn common_prefix_01() {
+        assert_eq!(0, common_prefix("", ""));
+        assert_eq!(0, common_prefix("abc", ""));
+        assert_eq!(3, common_prefix("abc", "abc"));
+        assert_eq!(2, common_prefix("abc", "abd"));
+        assert_eq!(2, common_prefix("ab", "abc"));
+
+        // Differ in the last byte of a multi-byte char.
+        assert_eq!(1, common_prefix("aこ", "aご"));
+        assert_eq!(1, common_suffix("こa", "ごa"));
+
+        assert_eq!(0, common_suffix("", ""));
+        assert_eq!(3, common_suffix("abc", "abc"));
+        assert_eq!(2, common_suffix("abc", "xbc"));
+        assert_eq!(2, common_suffix("bc", "abc"));
+        assert_eq!(3, common_suffix("aba", "aba-aba"));
+    }
+
+    #[test]
+    fn common_prefix_02() {
+        // Long enough to go through the chunk loops, with a difference at
+        // every position and with different relative alignments.
+        let text = "Hello there!  How're you doing?  It's a fine day, isn't it?  こんにちは";
+        for offset in 0..4 {
+            let mut buf = [b'x'; 128];
+            buf[offset..(offset + text.len())].copy_from_slice(text.as_bytes());
+            let a = core::str::from_utf8(&buf[offset..(offset + text.len())]).unwrap();
+            assert_eq!(text.len(), common_prefix(text, a));
+            assert_eq!(text.len(), common_suffix(text, a));
+            for (i, c) in text.char_indices() {
+                let mut b = [0u8; 128];
+                let len = i + "🐸".len() + (text.len() - i - c.len_utf8());
+                b[..i].copy_from_slice(&text.as_bytes()[..i]);
+                b[i..(i + 4)].copy_from_slice("🐸".as_bytes());
+                b[(i + 4)..len].copy_from_slice(&text.as_bytes()[(i + c.len_utf8())..]);
+                let b = core::str::from_utf8(&b[..len]).unwrap();
+                assert_eq!(i, common_prefix(a, b));
+                assert_eq!(i, common_prefix(b, a));
+                assert_eq!(text.len() - i - c.len_utf8(), common_suffix(a, b));
+                assert_eq!(text.len() - i - c.len_utf8(), common_suffix(b, a));
+            }
+        }
+    }
+
+    #[test]
+    fn common_prefix_chars_01() {
+        assert_eq!((0, 0), common_prefix_chars("", ""));
+        assert_eq!((6, 3), common_prefix_chars("a🐸bc", "a🐸bd"));
+        assert_eq!((1, 1), common_prefix_chars("aこ", "aご"));
+        assert_eq!((0, 0), common_suffix_chars("", ""));
+        assert_eq!((5, 2), common_suffix_chars("xb🐸", "yb🐸"));
+        assert_eq!((1, 1), common_suffix_chars("こa", "ごa"));
+    }
+
+    #[test]
+    fn common_prefix_chars_02() {
+        // Long enough to go through the chunk loops, with a difference at
+        // every position.
+        let text =
+            "Hello there!  How're you doing?  It's a fine day, isn't it?  こんにちは🐸".repeat(3);
+        let text = text.as_str();
+        for (i, c) in text.char_indices() {
+            let b = [&text[..i], "é", &text[(i + c.len_utf8())..]].concat();
+            let b = b.as_str();
+            for (x, y) in [(text, b), (b, text)] {
+                let prefix = common_prefix(x, y);
+                assert_eq!((prefix, count(&x[..prefix])), common_prefix_chars(x, y));
+                let suffix = common_suffix(x, y);
+                let expected = (suffix, count(&x[(x.len() - suffix)..]));
+                assert_eq!(expected, common_suffix_chars(x, y));
+                for f in [
+                    common_prefix_chars_impl::<u16>,
+                    common_prefix_chars_impl::<u32>,
+                ] {
+                    assert_eq!(common_prefix_chars(x, y), f(x, y));
+                }
+                for f in [
+                    common_suffix_chars_impl::<u16>,
+                    common_suffix_chars_impl::<u32>,
+                ] {
+                    assert_eq!(common_suffix_chars(x, y), f(x, y));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn ascii_runs_01() {
+        assert_eq!(None, ascii_runs("").next());
+        assert!(ascii_runs("abc").eq([(0..3, true)]));
+        assert!(ascii_runs("こんにちは").eq([(0..15, false)]));
+        assert!(ascii_runs("ab🐸éc\n").eq([(0..2, true), (2..8, false), (8..10, true)]));
+        assert!(ascii_runs("éaé").eq([(0..2, false), (2..3, true), (3..5, false)]));
+    }
+
+    #[test]
+    fn ascii_runs_02() {
+        // Long runs that go through the chunk loop, at various alignments.
+        let text = concat!(
+            "Hello there!  How're you doing?  It's a fine day, isn't it?",
+            "こんにちは、みんなさん！こんにちは、みんなさん！",
+            "Hello there!  How're you doing?  It's a fine day, isn't it?",
+            "🐸",
+        );
+        for start in (0..16).filter(|&i| text.is_char_boundary(i)) {
+            let text = &text[start..];
+            let mut end = 0;
+            for (range, is_ascii) in ascii_runs(text) {
+                assert_eq!(end, range.start);
+                assert!(range.start < range.end);
+                assert!(text[range.clone()]
+                    .chars()
+                    .all(|c| c.is_ascii() == is_ascii));
+                end = range.end;
+            }
+            assert_eq!(text.len(), end);
+            assert_eq!(4, ascii_runs(text).count());
+        }
+    }
+
+    #[test]
+    fn boundaries_01() {
+        assert_eq!(None, boundaries("").next());
+        assert!(boundaries("ab🐸éc\n").eq([0, 1, 2, 6, 8, 9]));
+        assert!(indices("ab🐸éc\n").eq([(0, 0), (1, 1), (2, 2), (3, 6), (4, 8), (5, 9)]));
+    }
+
+    #[test]
+    fn boundaries_02() {
+        // Long ascii runs, to exercise the chunked path, at every
+        // alignment.
+        let text = "Hello world, this is ascii. こんにちは🐸 and more ascii!\n".repeat(4);
+        for start in (0..text.len()).filter(|&i| text.is_char_boundary(i)) {
+            let text = &text[start..];
+            assert!(boundaries(text).eq(text.char_indices().map(|(i, _)| i)));
+            assert!(indices(text).eq(text.char_indices().map(|(i, _)| i).enumerate()));
+            assert_eq!(count(text), boundaries(text).count());
+
+            let mut iter = boundaries(text);
+            for _ in 0..5 {
+                iter.next();
+            }
+            let (min, max) = iter.size_hint();
+            let remaining = iter.count();
+            assert!(min <= remaining && Some(remaining) <= max);
+        }
+    }
+
+    #[test]
+    fn boundaries_03() {
+        // Ascii runs longer than the 64-byte blocks that the boundaries
+        // are taken from, at every alignment.
+        let text = ["x".repeat(70), "é".into(), "y".repeat(130), "こ🐸".into()]
+            .concat()
+            .repeat(2);
+        for start in (0..text.len()).filter(|&i| text.is_char_boundary(i)) {
+            let text = &text[start..];
+            assert!(boundaries(text).eq(text.char_indices().map(|(i, _)| i)));
+            assert!(boundaries(text)
+                .rev()
+                .eq(text.char_indices().rev().map(|(i, _)| i)));
+            for n in [0, 1, 63, 64, 65, 100, 200, 1000] {
+                let expected = text.char_indices().rev().nth(n).map(|(i, _)| i);
+                assert_eq!(expected, boundaries(text).nth_back(n));
+            }
+        }
+    }
+
+    #[test]
+    fn boundaries_rev_01() {
+        assert_eq!(None, boundaries("").next_back());
+        assert!(boundaries("ab🐸éc\n").rev().eq([9, 8, 6, 2, 1, 0]));
+
+        // Meeting in the middle.
+        let mut iter = boundaries("ab🐸éc\n");
+        assert_eq!(Some(0), iter.next());
+        assert_eq!(Some(9), iter.next_back());
+        assert_eq!(Some(8), iter.next_back());
+        assert_eq!(Some(1), iter.next());
+        assert_eq!(Some(6), iter.next_back());
+        assert_eq!(Some(2), iter.next());
+        assert_eq!(None, iter.next());
+        assert_eq!(None, iter.next_back());
+    }
+
+    #[test]
+    fn boundaries_rev_02() {
+        let text = "Hello world, this is ascii. こんにちは🐸 and more ascii!\n".repeat(4);
+        for start in (0..text.len()).filter(|&i| text.is_char_boundary(i)) {
+            let text = &text[start..];
+            let mut buf = [0; 256];
+            let mut len = 0;
+            for (i, _) in text.char_indices() {
+                buf[len] = i;
+                len += 1;
+            }
+            let expected = &buf[..len];
+            assert!(boundaries(text).rev().eq(expected.iter().rev().copied()));
+
+            // Alternating ends.
+            let mut iter = boundaries(text);
+            let (mut front, mut back) = (0, expected.len());
+            for n in 0.. {
+                let next = if n % 3 == 0 {
+                    iter.next_back()
+                } else {
+                    iter.next()
+                };
+                match next {
+                    None => break,
+                    Some(i) if n % 3 == 0 => {
+                        back -= 1;
+                        assert_eq!(expected[back], i);
+                    }
+                    Some(i) => {
+                        assert_eq!(expected[front], i);
+                        front += 1;
+                    }
+                }
+            }
+            assert_eq!(front, back);
+
+            for n in 0..(expected.len() + 1) {
+                let mut iter = boundaries(text);
+                assert_eq!(expected.iter().rev().nth(n).copied(), iter.nth_back(n));
+                assert!(iter.rev().eq(expected.iter().rev().skip(n + 1).copied()));
+            }
+
+            for (range, is_ascii) in ascii_runs(text) {
+                let bytes = &text.as_bytes()[..range.end];
+                assert_eq!(range.len(), run_len_back::<u16>(bytes, is_ascii));
+                assert_eq!(range.len(), run_len_back::<u32>(bytes, is_ascii));
+                assert_eq!(range.len(), run_len_back::<usize>(bytes, is_ascii));
+            }
+        }
+    }
+
+    #[test]
+    fn count_with_lf_01() {
+        assert_eq!((0, 0), count_with_lf(""));
+        assert_eq!((1, 1), count_with_lf("\n"));
+        assert_eq!((6, 2), count_with_lf("こ\r\nん\n🐸"));
+        assert_eq!((100, 3), count_with_lf(TEXT_LINES));
+
+        let text = TEXT_LINES.repeat(20);
+        assert_eq!((2000, 60), count_with_lf(&text));
+    }
+
+    #[test]
+    fn len_histogram_01() {
+        assert_eq!([0, 0, 0, 0], len_histogram(""));
+        assert_eq!([3, 1, 2, 1], len_histogram("aéこ🐸\nん!"));
+        assert_eq!([88, 0, 12, 0], len_histogram(TEXT_LINES));
+
+        let text = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r\n".repeat(20);
+        let h = len_histogram(&text);
+        assert_eq!(count(&text), h.iter().sum::<usize>());
+        assert_eq!(crate::utf16::count(&text), h[0] + h[1] + h[2] + h[3] * 2);
+    }
+
+    #[test]
+    fn offset_by_01() {
+        let text = "ab🐸éc\n";
+        assert_eq!(6, offset_by(text, 2, 1));
+        assert_eq!(9, offset_by(text, 2, 3));
+        assert_eq!(10, offset_by(text, 2, 100));
+        assert_eq!(1, offset_by(text, 2, -1));
+        assert_eq!(0, offset_by(text, 2, -100));
+        assert_eq!(2, offset_by(text, 4, 0));
+        assert_eq!(6, offset_by(text, 4, 1));
+        assert_eq!(8, offset_by(text, 100, -2));
+        assert_eq!(0, offset_by("", 0, isize::MIN));
+        assert_eq!(10, offset_by(text, 0, isize::MAX));
+    }
+
+    #[test]
+    fn offset_by_02() {
+        let text = "Hello world, this is ascii. こんにちは🐸 and more ascii!\n".repeat(4);
+        let char_len = count(&text) as isize;
+        for i in 0..(text.len() + 2) {
+            let char_idx = from_byte_idx(&text, i) as isize;
+            for delta in -(char_len + 2)..(char_len + 2) {
+                let expected = to_byte_idx(&text, (char_idx + delta).max(0) as usize);
+                assert_eq!(expected, offset_by(&text, i, delta));
+            }
+        }
+    }
+
+    #[test]
+    fn boundary_mask_01() {
+        let mut out = [u64::MAX; 2];
+        boundary_mask("ab🐸éc\n", &mut out);
+        assert_eq!([0b11_0100_0111, 0], out);
+
+        // Too short an output is fine.
+        boundary_mask("abc", &mut []);
+    }
+
+    #[test]
+    fn boundary_mask_02() {
+        let text = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r\n".repeat(8);
+        for start in (0..text.len()).filter(|&i| text.is_char_boundary(i)) {
+            let text = &text[start..];
+            let bytes = text.as_bytes();
+            let mut expected = [0u64; 8];
+            for (i, _) in text.char_indices() {
+                expected[i / 64] |= 1 << (i % 64);
+            }
+            let mut out = [u64::MAX; 8];
+            boundary_mask(text, &mut out);
+            assert_eq!(expected, out);
+            boundary_mask_impl::<u16>(bytes, &mut out);
+            assert_eq!(expected, out);
+            boundary_mask_impl::<u32>(bytes, &mut out);
+            assert_eq!(expected, out);
+
+            let mut out = [u64::MAX; 1];
+            boundary_mask(text, &mut out);
+            assert_eq!(expected[..1], out);
+        }
+    }
+
+    #[test]
+    fn narrow_chunks() {
+        crate::byte_chunk::for_each_narrow_chunk_text(|text| {
+            let bytes = text.as_bytes();
+            assert_eq!(count(text), count_impl::<u16>(bytes));
+            assert_eq!(count(text), count_impl::<u32>(bytes));
+            let expected = (count(text), crate::lines_lf::count_breaks(text));
+            assert_eq!(expected, count_with_lf_impl::<u16>(bytes));
+            assert_eq!(expected, count_with_lf_impl::<u32>(bytes));
+            assert_eq!(expected, count_with_lf_impl::<usize>(bytes));
+            assert_eq!(expected, count_with_lf(text));
+            let mut expected = [0; 4];
+            for c in text.chars() {
+                expected[c.len_utf8() - 1] += 1;
+            }
+            assert_eq!(expected, len_histogram(text));
+            assert_eq!(expected, len_histogram_impl::<u16>(bytes));
+            assert_eq!(expected, len_histogram_impl::<u32>(bytes));
+            assert_eq!(expected, len_histogram_impl::<usize>(bytes));
+            for i in 0..(count(text) + 2) {
+                assert_eq!(to_byte_idx(text, i), to_byte_idx_impl::<u16>(bytes, i));
+                assert_eq!(to_byte_idx(text, i), to_byte_idx_impl::<u32>(bytes, i));
+            }
+            for (range, is_ascii) in ascii_runs(text) {
+                let bytes = &bytes[range.start..];
+                assert_eq!(range.len(), run_len::<u16>(bytes, is_ascii));
+                assert_eq!(range.len(), run_len::<u32>(bytes, is_ascii));
+            }
+            for end in (0..text.len()).filter(|&i| text.is_char_boundary(i)) {
+                let (a, b) = (&bytes[..end], &bytes[(text.len() - end)..]);
+                assert_eq!(end, common_prefix_impl::<u16>(bytes, a, |_| ()));
+                assert_eq!(end, common_prefix_impl::<u32>(bytes, a, |_| ()));
+                assert_eq!(end, common_suffix_impl::<u16>(bytes, b, |_| ()));
+                assert_eq!(end, common_suffix_impl::<u32>(bytes, b, |_| ()));
+            }
+        });
+    }
 }
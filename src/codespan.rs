@@ -0,0 +1,162 @@
+//! A [`codespan_reporting`] file database backed by this crate.
+//!
+//! Only available with the `codespan-reporting` feature.
+//!
+//! [`SourceFile`] is a drop-in replacement for codespan's `SimpleFile`
+//! that doesn't build a table of line starts up front.  Instead, lines are
+//! looked up on demand with [`lines_lf`], which is cheap for the handful of
+//! lookups that rendering a diagnostic takes, and avoids scanning (and
+//! allocating for) the whole file before the first diagnostic can be
+//! shown.  Line and column numbers are identical to `SimpleFile`'s.
+
+use core::ops::Range;
+
+use codespan_reporting::files::{Error, Files};
+
+use crate::{chars, lines_lf};
+
+/// A single source file, for use with `codespan_reporting`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SourceFile<Name, Source> {
+    name: Name,
+    source: Source,
+}
+
+impl<Name, Source> SourceFile<Name, Source>
+where
+    Name: core::fmt::Display,
+    Source: AsRef<str>,
+{
+    /// Creates a new source file.
+    ///
+    /// Runs in O(1) time.
+    pub fn new(name: Name, source: Source) -> SourceFile<Name, Source> {
+        SourceFile { name, source }
+    }
+
+    /// Returns the name of the file.
+    pub fn name(&self) -> &Name {
+        &self.name
+    }
+
+    /// Returns the source of the file.
+    pub fn source(&self) -> &Source {
+        &self.source
+    }
+
+    /// Returns the starting byte index of the given line, with the same
+    /// errors as `SimpleFile`.
+    fn line_start(&self, line_index: usize) -> Result<usize, Error> {
+        let source = self.source.as_ref();
+        let start = lines_lf::to_byte_idx(source, line_index);
+        if start == source.len() && line_index > 0 {
+            // Either the last line, the one-past-the-end line, or beyond.
+            let max = lines_lf::count_breaks(source);
+            if line_index > max + 1 {
+                return Err(Error::LineTooLarge {
+                    given: line_index,
+                    max,
+                });
+            }
+        }
+        Ok(start)
+    }
+}
+
+impl<'a, Name, Source> Files<'a> for SourceFile<Name, Source>
+where
+    Name: 'a + core::fmt::Display + Clone,
+    Source: 'a + AsRef<str>,
+{
+    type FileId = ();
+    type Name = Name;
+    type Source = &'a str;
+
+    fn name(&self, (): ()) -> Result<Name, Error> {
+        Ok(self.name.clone())
+    }
+
+    fn source(&self, (): ()) -> Result<&str, Error> {
+        Ok(self.source.as_ref())
+    }
+
+    fn line_index(&self, (): (), byte_index: usize) -> Result<usize, Error> {
+        Ok(lines_lf::from_byte_idx(self.source.as_ref(), byte_index))
+    }
+
+    fn column_number(&self, (): (), line_index: usize, byte_index: usize) -> Result<usize, Error> {
+        let line_range = self.line_range((), line_index)?;
+        let line = &self.source.as_ref()[line_range.clone()];
+        let column_index = chars::from_byte_idx(line, byte_index.saturating_sub(line_range.start));
+
+        Ok(column_index + 1)
+    }
+
+    fn line_range(&self, (): (), line_index: usize) -> Result<Range<usize>, Error> {
+        let line_start = self.line_start(line_index)?;
+        let next_line_start = self.line_start(line_index + 1)?;
+
+        Ok(line_start..next_line_start)
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codespan_reporting::files::Location;
+
+    const TEXT: &str = "foo\nbar\r\n\nbaz🐸z";
+
+    #[test]
+    fn line_index_01() {
+        let file = SourceFile::new("test", TEXT);
+        let expected = [0, 0, 0, 0, 1, 1, 1, 1, 1, 2, 3, 3];
+        for (i, e) in expected.iter().enumerate() {
+            assert_eq!(*e, file.line_index((), i).unwrap());
+        }
+        assert_eq!(3, file.line_index((), 100).unwrap());
+    }
+
+    #[test]
+    fn line_range_01() {
+        let file = SourceFile::new("test", TEXT);
+        assert_eq!(0..4, file.line_range((), 0).unwrap());
+        assert_eq!(4..9, file.line_range((), 1).unwrap());
+        assert_eq!(9..10, file.line_range((), 2).unwrap());
+        assert_eq!(10..18, file.line_range((), 3).unwrap());
+        assert!(matches!(
+            file.line_range((), 4),
+            Err(Error::LineTooLarge { given: 5, max: 3 })
+        ));
+        assert!(matches!(
+            file.line_range((), 5),
+            Err(Error::LineTooLarge { given: 5, max: 3 })
+        ));
+    }
+
+    #[test]
+    fn location_01() {
+        let file = SourceFile::new("test", TEXT);
+        let location = |line_number, column_number| Location {
+            line_number,
+            column_number,
+        };
+        assert_eq!(location(1, 1), file.location((), 0).unwrap());
+        assert_eq!(location(2, 3), file.location((), 6).unwrap());
+        assert_eq!(location(4, 4), file.location((), 13).unwrap());
+        // Middle of the frog.
+        assert_eq!(location(4, 4), file.location((), 15).unwrap());
+        assert_eq!(location(4, 5), file.location((), 17).unwrap());
+        assert_eq!(location(4, 6), file.location((), 18).unwrap());
+    }
+
+    #[test]
+    fn trailing_line_break() {
+        let file = SourceFile::new("test", "a\n");
+        assert_eq!(1, file.line_index((), 2).unwrap());
+        assert_eq!(2..2, file.line_range((), 1).unwrap());
+        assert!(file.line_range((), 2).is_err());
+    }
+}
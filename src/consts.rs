@@ -0,0 +1,197 @@
+//! `const fn` versions of the counting functions.
+//!
+//! These are simple byte-at-a-time scalar implementations, usable in const
+//! contexts, e.g. for computing metrics of `include_str!()` assets at
+//! compile time:
+//!
+//! ```
+//! use str_indices::consts;
+//!
+//! const TEXT: &str = "Hello\nworld!\n";
+//! const LINE_BREAKS: usize = consts::lines_lf::count_breaks(TEXT);
+//! const CHARS: usize = consts::chars::count(TEXT);
+//!
+//! assert_eq!(2, LINE_BREAKS);
+//! assert_eq!(13, CHARS);
+//! ```
+//!
+//! At run time they give the same results as their non-const counterparts,
+//! but are much slower on anything but short texts, so prefer those outside
+//! of const contexts.
+
+/// Const versions of the [`chars`](crate::chars) counting functions.
+pub mod chars {
+    /// Counts the chars in a string slice.
+    ///
+    /// Const version of [`crate::chars::count()`].
+    ///
+    /// Runs in O(N) time.
+    pub const fn count(text: &str) -> usize {
+        let bytes = text.as_bytes();
+        let mut count = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            count += ((bytes[i] & 0xC0) != 0x80) as usize;
+            i += 1;
+        }
+        count
+    }
+}
+
+/// Const versions of the [`utf16`](crate::utf16) counting functions.
+pub mod utf16 {
+    /// Counts the utf16 code units that would be in a string slice if it
+    /// were encoded as utf16.
+    ///
+    /// Const version of [`crate::utf16::count()`].
+    ///
+    /// Runs in O(N) time.
+    pub const fn count(text: &str) -> usize {
+        super::chars::count(text) + count_surrogates(text)
+    }
+
+    /// Counts the utf16 surrogate pairs that would be in a string slice if
+    /// it were encoded as utf16.
+    ///
+    /// Const version of [`crate::utf16::count_surrogates()`].
+    ///
+    /// Runs in O(N) time.
+    pub const fn count_surrogates(text: &str) -> usize {
+        let bytes = text.as_bytes();
+        let mut count = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            // Only chars encoded as 4 bytes in utf8 need a surrogate pair.
+            count += (bytes[i] >= 0xF0) as usize;
+            i += 1;
+        }
+        count
+    }
+}
+
+/// Const versions of the [`lines`](crate::lines) counting functions.
+pub mod lines {
+    /// Counts the line breaks in a string slice.
+    ///
+    /// Const version of [`crate::lines::count_breaks()`].
+    ///
+    /// Runs in O(N) time.
+    #[allow(clippy::if_same_then_else)]
+    pub const fn count_breaks(text: &str) -> usize {
+        let bytes = text.as_bytes();
+        let mut count = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            let byte = bytes[i];
+            if byte >= 0x0A && byte <= 0x0D {
+                // A CRLF is counted at its LF.
+                let is_crlf_start = byte == 0x0D && i + 1 < bytes.len() && bytes[i + 1] == 0x0A;
+                count += !is_crlf_start as usize;
+            } else if byte == 0xC2 && i + 1 < bytes.len() && bytes[i + 1] == 0x85 {
+                count += 1;
+            } else if byte == 0xE2
+                && i + 2 < bytes.len()
+                && bytes[i + 1] == 0x80
+                && (bytes[i + 2] >> 1) == 0x54
+            {
+                count += 1;
+            }
+            i += 1;
+        }
+        count
+    }
+}
+
+/// Const versions of the [`lines_crlf`](crate::lines_crlf) counting
+/// functions.
+pub mod lines_crlf {
+    /// Counts the line breaks in a string slice.
+    ///
+    /// Const version of [`crate::lines_crlf::count_breaks()`].
+    ///
+    /// Runs in O(N) time.
+    pub const fn count_breaks(text: &str) -> usize {
+        let bytes = text.as_bytes();
+        let mut count = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            let byte = bytes[i];
+            if byte == 0x0A {
+                count += 1;
+            } else if byte == 0x0D {
+                // A CRLF is counted at its LF.
+                count += !(i + 1 < bytes.len() && bytes[i + 1] == 0x0A) as usize;
+            }
+            i += 1;
+        }
+        count
+    }
+}
+
+/// Const versions of the [`lines_lf`](crate::lines_lf) counting functions.
+pub mod lines_lf {
+    /// Counts the line breaks in a string slice.
+    ///
+    /// Const version of [`crate::lines_lf::count_breaks()`].
+    ///
+    /// Runs in O(N) time.
+    pub const fn count_breaks(text: &str) -> usize {
+        let bytes = text.as_bytes();
+        let mut count = 0;
+        let mut i = 0;
+        while i < bytes.len() {
+            count += (bytes[i] == 0x0A) as usize;
+            i += 1;
+        }
+        count
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 46 bytes, 27 chars, 2 surrogate pairs.
+    const TEXT: &str = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\u{000B}\u{000C}🐸\r";
+
+    #[test]
+    fn const_context() {
+        const CHARS: usize = chars::count(TEXT);
+        const UTF16: usize = utf16::count(TEXT);
+        const LINES: usize = lines::count_breaks(TEXT);
+        const LINES_CRLF: usize = lines_crlf::count_breaks(TEXT);
+        const LINES_LF: usize = lines_lf::count_breaks(TEXT);
+        assert_eq!(27, CHARS);
+        assert_eq!(29, UTF16);
+        assert_eq!(8, LINES);
+        assert_eq!(4, LINES_CRLF);
+        assert_eq!(2, LINES_LF);
+    }
+
+    #[test]
+    fn matches_runtime() {
+        let boundaries = || (0..=TEXT.len()).filter(|&i| TEXT.is_char_boundary(i));
+        for start in boundaries() {
+            for end in boundaries().filter(|&end| end >= start) {
+                let text = &TEXT[start..end];
+                assert_eq!(crate::chars::count(text), chars::count(text));
+                assert_eq!(crate::utf16::count(text), utf16::count(text));
+                assert_eq!(
+                    crate::utf16::count_surrogates(text),
+                    utf16::count_surrogates(text)
+                );
+                assert_eq!(crate::lines::count_breaks(text), lines::count_breaks(text));
+                assert_eq!(
+                    crate::lines_crlf::count_breaks(text),
+                    lines_crlf::count_breaks(text)
+                );
+                assert_eq!(
+                    crate::lines_lf::count_breaks(text),
+                    lines_lf::count_breaks(text)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,312 @@
+//! A cursor that keeps byte, char, utf16, and line positions in sync.
+//!
+//! Converting between indexing schemes from scratch scans the text from
+//! its start every time.  A [`Cursor`] instead remembers its position in
+//! every scheme at once, and each movement only scans the text between the
+//! old and new positions.  This makes it cheap to, for example, follow a
+//! caret around a large document.
+//!
+//! Lines are as defined by the [`lines`](crate::lines) module (all Unicode
+//! line breaks).
+
+use crate::{chars, lines, utf16};
+
+/// A position in a string slice, tracked as a byte, char, utf16, and line
+/// index simultaneously.
+///
+/// The position is always on a char boundary.  Like the rest of this
+/// crate, the line index of a position in the middle of a CRLF is the line
+/// that the CRLF ends.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Cursor<'a> {
+    text: &'a str,
+    byte_idx: usize,
+    char_idx: usize,
+    utf16_idx: usize,
+    line_idx: usize,
+}
+
+impl<'a> Cursor<'a> {
+    /// Creates a new cursor at the start of the text.
+    ///
+    /// Runs in O(1) time.
+    #[inline]
+    pub fn new(text: &'a str) -> Cursor<'a> {
+        Cursor {
+            text,
+            byte_idx: 0,
+            char_idx: 0,
+            utf16_idx: 0,
+            line_idx: 0,
+        }
+    }
+
+    /// Returns the text the cursor is in.
+    #[inline]
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Returns the byte index of the cursor.
+    #[inline]
+    pub fn byte_idx(&self) -> usize {
+        self.byte_idx
+    }
+
+    /// Returns the char index of the cursor.
+    #[inline]
+    pub fn char_idx(&self) -> usize {
+        self.char_idx
+    }
+
+    /// Returns the utf16 code unit index of the cursor.
+    #[inline]
+    pub fn utf16_idx(&self) -> usize {
+        self.utf16_idx
+    }
+
+    /// Returns the line index of the cursor.
+    #[inline]
+    pub fn line_idx(&self) -> usize {
+        self.line_idx
+    }
+
+    /// Moves the cursor to a byte index.
+    ///
+    /// If the byte is in the middle of a multi-byte char, moves to the
+    /// start of that char.  Any past-the-end index moves to the end of the
+    /// text.
+    ///
+    /// Runs in O(N) time, where N is the distance moved.
+    pub fn move_to_byte(&mut self, byte_idx: usize) {
+        let new_idx = chars::floor_byte_boundary(self.text, byte_idx);
+        let crlf_middle = |i| !crate::is_not_crlf_middle(i, self.text.as_bytes());
+
+        // Note that counting line breaks in a slice that ends in the middle
+        // of a CRLF counts the CR, whereas the line index of that position
+        // doesn't.
+        if new_idx > self.byte_idx {
+            let delta = self.text.get(self.byte_idx..new_idx).unwrap_or("");
+            self.char_idx += chars::count(delta);
+            self.utf16_idx += utf16::count(delta);
+            self.line_idx += lines::count_breaks(delta);
+            self.line_idx -= crlf_middle(new_idx) as usize;
+        } else if new_idx < self.byte_idx {
+            let delta = self.text.get(new_idx..self.byte_idx).unwrap_or("");
+            self.char_idx -= chars::count(delta);
+            self.utf16_idx -= utf16::count(delta);
+            self.line_idx += crlf_middle(self.byte_idx) as usize;
+            self.line_idx -= lines::count_breaks(delta);
+        }
+        self.byte_idx = new_idx;
+    }
+
+    /// Moves the cursor to a char index.
+    ///
+    /// Any past-the-end index moves to the end of the text.
+    ///
+    /// Runs in O(N) time, where N is the distance moved.
+    #[inline]
+    pub fn move_to_char(&mut self, char_idx: usize) {
+        if char_idx >= self.char_idx {
+            self.move_by_chars_forward(char_idx - self.char_idx);
+        } else {
+            self.move_by_chars_backward(self.char_idx - char_idx);
+        }
+    }
+
+    /// Moves the cursor to the start of a line.
+    ///
+    /// Any past-the-end index moves to the start of the last line.
+    ///
+    /// Runs in O(N) time, where N is the distance moved.
+    #[inline]
+    pub fn move_to_line(&mut self, line_idx: usize) {
+        if line_idx >= self.line_idx {
+            self.move_by_lines_forward(line_idx - self.line_idx);
+        } else {
+            self.move_by_lines_backward(self.line_idx - line_idx);
+        }
+    }
+
+    /// Moves the cursor forward (positive) or backward (negative) by a
+    /// number of chars.
+    ///
+    /// Stops at the start or end of the text.
+    ///
+    /// Runs in O(N) time, where N is the distance moved.
+    #[inline]
+    pub fn move_by_chars(&mut self, n: isize) {
+        if n >= 0 {
+            self.move_by_chars_forward(n.unsigned_abs());
+        } else {
+            self.move_by_chars_backward(n.unsigned_abs());
+        }
+    }
+
+    /// Moves the cursor to the start of the line `n` lines after
+    /// (positive) or before (negative) the cursor's current line.
+    ///
+    /// With `n == 0`, moves to the start of the current line.  Stops at the
+    /// first or last line.
+    ///
+    /// Runs in O(N) time, where N is the distance moved.
+    #[inline]
+    pub fn move_by_lines(&mut self, n: isize) {
+        if n > 0 {
+            self.move_by_lines_forward(n.unsigned_abs());
+        } else {
+            self.move_by_lines_backward(n.unsigned_abs());
+        }
+    }
+
+    //-----------------------------------------------------
+
+    #[inline(always)]
+    fn move_by_chars_forward(&mut self, n: usize) {
+        let rest = crate::split_str_at(self.text, self.byte_idx).1;
+        self.move_to_byte(self.byte_idx + chars::to_byte_idx(rest, n));
+    }
+
+    #[inline(always)]
+    fn move_by_chars_backward(&mut self, n: usize) {
+        if n == 0 {
+            return;
+        }
+        let new_idx = crate::split_str_at(self.text, self.byte_idx)
+            .0
+            .char_indices()
+            .rev()
+            .nth(n - 1)
+            .map_or(0, |(i, _)| i);
+        self.move_to_byte(new_idx);
+    }
+
+    #[inline(always)]
+    fn move_by_lines_forward(&mut self, n: usize) {
+        // Start from the CR if in the middle of a CRLF, so that the LF
+        // isn't counted as a line break of its own.
+        let mut from = self.byte_idx;
+        if !crate::is_not_crlf_middle(from, self.text.as_bytes()) {
+            from -= 1;
+        }
+
+        // Skip over `n` line breaks, or as many as there are.
+        let mut line_start = None;
+        for _ in 0..n {
+            match lines::first_break(crate::split_str_at(self.text, from).1) {
+                Some(i) => {
+                    let break_start = from + i;
+                    let after = crate::split_str_at(self.text, break_start).1;
+                    from = break_start + lines::to_byte_idx(after, 1);
+                    line_start = Some(from);
+                }
+                None => break,
+            }
+        }
+
+        match line_start {
+            Some(i) => self.move_to_byte(i),
+            None => self.move_by_lines_backward(0),
+        }
+    }
+
+    #[inline(always)]
+    fn move_by_lines_backward(&mut self, n: usize) {
+        // The start of the target line is just after the (n + 1)th line
+        // break before the cursor, or the start of the text.
+        let mut end = self.byte_idx;
+        if !crate::is_not_crlf_middle(end, self.text.as_bytes()) {
+            end -= 1;
+        }
+        let line_start = crate::split_str_at(self.text, end)
+            .0
+            .char_indices()
+            .rev()
+            .filter(|&(i, _)| lines::is_break_start(self.text, i))
+            .nth(n)
+            .map_or(0, |(i, _)| {
+                i + lines::to_byte_idx(crate::split_str_at(self.text, i).1, 1)
+            });
+        self.move_to_byte(line_start);
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r\n";
+
+    fn assert_synced(cursor: &Cursor) {
+        let i = cursor.byte_idx();
+        assert!(TEXT.is_char_boundary(i));
+        assert_eq!(chars::from_byte_idx(TEXT, i), cursor.char_idx());
+        assert_eq!(utf16::from_byte_idx(TEXT, i), cursor.utf16_idx());
+        assert_eq!(lines::from_byte_idx(TEXT, i), cursor.line_idx());
+    }
+
+    #[test]
+    fn move_to_byte_01() {
+        // Every pair of positions, in both directions.
+        for i in 0..(TEXT.len() + 2) {
+            for j in 0..(TEXT.len() + 2) {
+                let mut cursor = Cursor::new(TEXT);
+                cursor.move_to_byte(i);
+                assert_synced(&cursor);
+                cursor.move_to_byte(j);
+                assert_synced(&cursor);
+                assert_eq!(chars::floor_byte_boundary(TEXT, j), cursor.byte_idx());
+            }
+        }
+    }
+
+    #[test]
+    fn move_by_chars_01() {
+        let char_count = chars::count(TEXT) as isize;
+        for start in 0..=char_count {
+            for n in (-char_count - 2)..(char_count + 2) {
+                let mut cursor = Cursor::new(TEXT);
+                cursor.move_to_char(start as usize);
+                assert_synced(&cursor);
+                cursor.move_by_chars(n);
+                assert_synced(&cursor);
+                assert_eq!((start + n).clamp(0, char_count) as usize, cursor.char_idx());
+            }
+        }
+    }
+
+    #[test]
+    fn move_by_lines_01() {
+        let last_line = lines::count_breaks(TEXT) as isize;
+        for i in 0..(TEXT.len() + 1) {
+            for n in (-last_line - 2)..(last_line + 2) {
+                let mut cursor = Cursor::new(TEXT);
+                cursor.move_to_byte(i);
+                let start_line = cursor.line_idx() as isize;
+                cursor.move_by_lines(n);
+                assert_synced(&cursor);
+                let line_idx = (start_line + n).clamp(0, last_line) as usize;
+                assert_eq!(line_idx, cursor.line_idx());
+                assert_eq!(lines::to_byte_idx(TEXT, line_idx), cursor.byte_idx());
+            }
+        }
+    }
+
+    #[test]
+    fn move_to_line_01() {
+        let mut cursor = Cursor::new(TEXT);
+        cursor.move_to_line(3);
+        assert_eq!((3, 26), (cursor.line_idx(), cursor.byte_idx()));
+        cursor.move_to_line(100);
+        assert_eq!((6, TEXT.len()), (cursor.line_idx(), cursor.byte_idx()));
+        cursor.move_to_line(1);
+        assert_eq!((1, 11), (cursor.line_idx(), cursor.byte_idx()));
+
+        let mut cursor = Cursor::new("a\nb");
+        cursor.move_to_line(5);
+        assert_eq!((1, 2), (cursor.line_idx(), cursor.byte_idx()));
+    }
+}
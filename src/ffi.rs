@@ -0,0 +1,398 @@
+//! C-compatible bindings.
+//!
+//! Only available with the `ffi` feature.  Every function here is a thin
+//! `extern "C"` wrapper around the function of the same name in the
+//! corresponding module, e.g. `str_indices_chars_count()` wraps
+//! [`chars::count()`](crate::chars::count).  See those for the details of
+//! each function's behavior.
+//!
+//! Text is passed as a pointer and a length in bytes.  A C header is
+//! generated with [cbindgen](https://github.com/mozilla/cbindgen) and
+//! checked in at `include/str_indices.h`.  To regenerate it, run the
+//! following from the repository root:
+//!
+//! ```text
+//! cbindgen --config cbindgen.toml --output include/str_indices.h
+//! ```
+//!
+//! All functions in this module have the same safety requirements: `text`
+//! must point to `len` bytes of valid utf8, which must not be mutated for
+//! the duration of the call.  `text` may be null if `len` is zero.
+//!
+//! Note that the crate itself remains `no_std`, so it can't be built
+//! directly as a `staticlib` or `cdylib`.  To link it into a C or C++
+//! project, depend on it with the `ffi` feature from a small `staticlib`
+//! crate of your own (which provides `std` or a panic handler).
+
+use core::ffi::c_char;
+
+use crate::{chars, lines, lines_crlf, lines_lf, utf16};
+
+/// Builds a string slice from a C pointer and length.
+///
+/// `text` must point to `len` bytes of valid utf8, or be null with a `len`
+/// of zero.
+#[inline(always)]
+unsafe fn text_from_raw<'a>(text: *const c_char, len: usize) -> &'a str {
+    if text.is_null() {
+        debug_assert!(len == 0);
+        return "";
+    }
+    core::str::from_utf8_unchecked(core::slice::from_raw_parts(text as *const u8, len))
+}
+
+//-------------------------------------------------------------
+
+/// Counts the chars in the text.
+///
+/// See `chars::count()`.
+///
+/// # Safety
+///
+/// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+/// zero.
+#[no_mangle]
+pub unsafe extern "C" fn str_indices_chars_count(text: *const c_char, len: usize) -> usize {
+    chars::count(text_from_raw(text, len))
+}
+
+/// Converts from byte-index to char-index in the text.
+///
+/// See `chars::from_byte_idx()`.
+///
+/// # Safety
+///
+/// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+/// zero.
+#[no_mangle]
+pub unsafe extern "C" fn str_indices_chars_from_byte_idx(
+    text: *const c_char,
+    len: usize,
+    byte_idx: usize,
+) -> usize {
+    chars::from_byte_idx(text_from_raw(text, len), byte_idx)
+}
+
+/// Converts from char-index to byte-index in the text.
+///
+/// See `chars::to_byte_idx()`.
+///
+/// # Safety
+///
+/// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+/// zero.
+#[no_mangle]
+pub unsafe extern "C" fn str_indices_chars_to_byte_idx(
+    text: *const c_char,
+    len: usize,
+    char_idx: usize,
+) -> usize {
+    chars::to_byte_idx(text_from_raw(text, len), char_idx)
+}
+
+//-------------------------------------------------------------
+
+/// Counts the utf16 code units in the text.
+///
+/// See `utf16::count()`.
+///
+/// # Safety
+///
+/// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+/// zero.
+#[no_mangle]
+pub unsafe extern "C" fn str_indices_utf16_count(text: *const c_char, len: usize) -> usize {
+    utf16::count(text_from_raw(text, len))
+}
+
+/// Counts the utf16 surrogate pairs in the text.
+///
+/// See `utf16::count_surrogates()`.
+///
+/// # Safety
+///
+/// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+/// zero.
+#[no_mangle]
+pub unsafe extern "C" fn str_indices_utf16_count_surrogates(
+    text: *const c_char,
+    len: usize,
+) -> usize {
+    utf16::count_surrogates(text_from_raw(text, len))
+}
+
+/// Converts from byte-index to utf16-code-unit-index in the text.
+///
+/// See `utf16::from_byte_idx()`.
+///
+/// # Safety
+///
+/// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+/// zero.
+#[no_mangle]
+pub unsafe extern "C" fn str_indices_utf16_from_byte_idx(
+    text: *const c_char,
+    len: usize,
+    byte_idx: usize,
+) -> usize {
+    utf16::from_byte_idx(text_from_raw(text, len), byte_idx)
+}
+
+/// Converts from utf16-code-unit-index to byte-index in the text.
+///
+/// See `utf16::to_byte_idx()`.
+///
+/// # Safety
+///
+/// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+/// zero.
+#[no_mangle]
+pub unsafe extern "C" fn str_indices_utf16_to_byte_idx(
+    text: *const c_char,
+    len: usize,
+    utf16_idx: usize,
+) -> usize {
+    utf16::to_byte_idx(text_from_raw(text, len), utf16_idx)
+}
+
+//-------------------------------------------------------------
+
+/// Counts the line breaks (all Unicode line breaks) in the text.
+///
+/// See `lines::count_breaks()`.
+///
+/// # Safety
+///
+/// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+/// zero.
+#[no_mangle]
+pub unsafe extern "C" fn str_indices_lines_count_breaks(text: *const c_char, len: usize) -> usize {
+    lines::count_breaks(text_from_raw(text, len))
+}
+
+/// Converts from byte-index to line-index (all Unicode line breaks) in the
+/// text.
+///
+/// See `lines::from_byte_idx()`.
+///
+/// # Safety
+///
+/// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+/// zero.
+#[no_mangle]
+pub unsafe extern "C" fn str_indices_lines_from_byte_idx(
+    text: *const c_char,
+    len: usize,
+    byte_idx: usize,
+) -> usize {
+    lines::from_byte_idx(text_from_raw(text, len), byte_idx)
+}
+
+/// Converts from line-index (all Unicode line breaks) to byte-index in the
+/// text.
+///
+/// See `lines::to_byte_idx()`.
+///
+/// # Safety
+///
+/// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+/// zero.
+#[no_mangle]
+pub unsafe extern "C" fn str_indices_lines_to_byte_idx(
+    text: *const c_char,
+    len: usize,
+    line_idx: usize,
+) -> usize {
+    lines::to_byte_idx(text_from_raw(text, len), line_idx)
+}
+
+//-------------------------------------------------------------
+
+/// Counts the line breaks (LF and CR) in the text.
+///
+/// See `lines_crlf::count_breaks()`.
+///
+/// # Safety
+///
+/// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+/// zero.
+#[no_mangle]
+pub unsafe extern "C" fn str_indices_lines_crlf_count_breaks(
+    text: *const c_char,
+    len: usize,
+) -> usize {
+    lines_crlf::count_breaks(text_from_raw(text, len))
+}
+
+/// Converts from byte-index to line-index (LF and CR) in the text.
+///
+/// See `lines_crlf::from_byte_idx()`.
+///
+/// # Safety
+///
+/// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+/// zero.
+#[no_mangle]
+pub unsafe extern "C" fn str_indices_lines_crlf_from_byte_idx(
+    text: *const c_char,
+    len: usize,
+    byte_idx: usize,
+) -> usize {
+    lines_crlf::from_byte_idx(text_from_raw(text, len), byte_idx)
+}
+
+/// Converts from line-index (LF and CR) to byte-index in the text.
+///
+/// See `lines_crlf::to_byte_idx()`.
+///
+/// # Safety
+///
+/// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+/// zero.
+#[no_mangle]
+pub unsafe extern "C" fn str_indices_lines_crlf_to_byte_idx(
+    text: *const c_char,
+    len: usize,
+    line_idx: usize,
+) -> usize {
+    lines_crlf::to_byte_idx(text_from_raw(text, len), line_idx)
+}
+
+//-------------------------------------------------------------
+
+/// Counts the line breaks (LF only) in the text.
+///
+/// See `lines_lf::count_breaks()`.
+///
+/// # Safety
+///
+/// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+/// zero.
+#[no_mangle]
+pub unsafe extern "C" fn str_indices_lines_lf_count_breaks(
+    text: *const c_char,
+    len: usize,
+) -> usize {
+    lines_lf::count_breaks(text_from_raw(text, len))
+}
+
+/// Converts from byte-index to line-index (LF only) in the text.
+///
+/// See `lines_lf::from_byte_idx()`.
+///
+/// # Safety
+///
+/// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+/// zero.
+#[no_mangle]
+pub unsafe extern "C" fn str_indices_lines_lf_from_byte_idx(
+    text: *const c_char,
+    len: usize,
+    byte_idx: usize,
+) -> usize {
+    lines_lf::from_byte_idx(text_from_raw(text, len), byte_idx)
+}
+
+/// Converts from line-index (LF only) to byte-index in the text.
+///
+/// See `lines_lf::to_byte_idx()`.
+///
+/// # Safety
+///
+/// `text` must point to `len` bytes of valid utf8, or be null if `len` is
+/// zero.
+#[no_mangle]
+pub unsafe extern "C" fn str_indices_lines_lf_to_byte_idx(
+    text: *const c_char,
+    len: usize,
+    line_idx: usize,
+) -> usize {
+    lines_lf::to_byte_idx(text_from_raw(text, len), line_idx)
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 33 bytes, 18 chars, 19 utf16 code units, 4 lines.
+    const TEXT: &str = "Hi🐸!\nこんにちは\r\nworld\u{2028}";
+
+    #[test]
+    fn null_text() {
+        let null = core::ptr::null();
+        unsafe {
+            assert_eq!(0, str_indices_chars_count(null, 0));
+            assert_eq!(0, str_indices_utf16_to_byte_idx(null, 0, 5));
+            assert_eq!(0, str_indices_lines_from_byte_idx(null, 0, 5));
+        }
+    }
+
+    #[test]
+    fn matches_rust_api() {
+        let (ptr, len) = (TEXT.as_ptr() as *const c_char, TEXT.len());
+        unsafe {
+            assert_eq!(chars::count(TEXT), str_indices_chars_count(ptr, len));
+            assert_eq!(utf16::count(TEXT), str_indices_utf16_count(ptr, len));
+            assert_eq!(
+                utf16::count_surrogates(TEXT),
+                str_indices_utf16_count_surrogates(ptr, len)
+            );
+            assert_eq!(
+                lines::count_breaks(TEXT),
+                str_indices_lines_count_breaks(ptr, len)
+            );
+            assert_eq!(
+                lines_crlf::count_breaks(TEXT),
+                str_indices_lines_crlf_count_breaks(ptr, len)
+            );
+            assert_eq!(
+                lines_lf::count_breaks(TEXT),
+                str_indices_lines_lf_count_breaks(ptr, len)
+            );
+            for i in 0..(len + 2) {
+                assert_eq!(
+                    chars::from_byte_idx(TEXT, i),
+                    str_indices_chars_from_byte_idx(ptr, len, i)
+                );
+                assert_eq!(
+                    chars::to_byte_idx(TEXT, i),
+                    str_indices_chars_to_byte_idx(ptr, len, i)
+                );
+                assert_eq!(
+                    utf16::from_byte_idx(TEXT, i),
+                    str_indices_utf16_from_byte_idx(ptr, len, i)
+                );
+                assert_eq!(
+                    utf16::to_byte_idx(TEXT, i),
+                    str_indices_utf16_to_byte_idx(ptr, len, i)
+                );
+                assert_eq!(
+                    lines::from_byte_idx(TEXT, i),
+                    str_indices_lines_from_byte_idx(ptr, len, i)
+                );
+                assert_eq!(
+                    lines::to_byte_idx(TEXT, i),
+                    str_indices_lines_to_byte_idx(ptr, len, i)
+                );
+                assert_eq!(
+                    lines_crlf::from_byte_idx(TEXT, i),
+                    str_indices_lines_crlf_from_byte_idx(ptr, len, i)
+                );
+                assert_eq!(
+                    lines_crlf::to_byte_idx(TEXT, i),
+                    str_indices_lines_crlf_to_byte_idx(ptr, len, i)
+                );
+                assert_eq!(
+                    lines_lf::from_byte_idx(TEXT, i),
+                    str_indices_lines_lf_from_byte_idx(ptr, len, i)
+                );
+                assert_eq!(
+                    lines_lf::to_byte_idx(TEXT, i),
+                    str_indices_lines_lf_to_byte_idx(ptr, len, i)
+                );
+            }
+        }
+    }
+}
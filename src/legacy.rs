@@ -0,0 +1,247 @@
+//! Map indices between legacy-encoded text and its utf8 decoding.
+//!
+//! Only available with the `encoding_rs` feature.
+//!
+//! When a file in a legacy encoding (Shift-JIS, Windows-1252, etc.) is
+//! decoded to utf8 for processing, byte indices into the decoded text
+//! generally don't match byte indices into the original file.  The
+//! functions in this module convert between the two, given the original
+//! bytes and their [`Encoding`].
+//!
+//! Decoding is done the way `Encoding::decode_with_bom_removal()` does
+//! it: malformed sequences are replaced with U+FFFD, and a BOM for the
+//! given encoding is removed.  Nothing is allocated: the original bytes are
+//! re-decoded on the fly into a small stack buffer.
+
+use encoding_rs::{CoderResult, Decoder, Encoding};
+
+/// Size of the stack buffer that decoded text is written into.
+const BUF_LEN: usize = 256;
+
+/// Upper bound on the utf8 written for a single source byte.  This covers
+/// a replacement character for a pending malformed sequence plus the
+/// decoding of the byte itself, with plenty of headroom.
+const MAX_BYTE_OUTPUT: usize = 16;
+
+/// Converts from byte-index in the decoded utf8 to byte-index in the
+/// original legacy-encoded bytes.
+///
+/// If the utf8 byte is in the middle of a multi-byte char, returns the
+/// index of the start of the source bytes that char was decoded from.
+///
+/// Any past-the-end index will return the one-past-the-end source index.
+///
+/// Runs in O(N) time.
+pub fn from_byte_idx(source: &[u8], encoding: &'static Encoding, byte_idx: usize) -> usize {
+    let mut decoder = encoding.new_decoder_with_bom_removal();
+    let mut buf = [0u8; BUF_LEN];
+    let mut read = 0;
+    let mut written = 0;
+
+    // Decode in chunks that can't possibly reach the target index, even in
+    // the worst case.  The margin ensures that any char left partially
+    // decoded at the end of a chunk is finished before the target.
+    loop {
+        let limit = byte_idx
+            .saturating_sub(written + MAX_BYTE_OUTPUT)
+            .min(BUF_LEN);
+        let mut n = (source.len() - read).min(limit / 4);
+        while n > 0 && !matches!(decoder.max_utf8_buffer_length(n), Some(max) if max <= limit) {
+            n /= 2;
+        }
+        if n == 0 {
+            break;
+        }
+        let (_, r, w, _) = decoder.decode_to_utf8(&source[read..(read + n)], &mut buf, false);
+        read += r;
+        written += w;
+    }
+
+    // Decode the rest one byte at a time, tracking where the most recent
+    // char started in the source.  This continues until the output passes
+    // the target, since a char starting exactly at the target may begin
+    // with source bytes that are still pending.
+    let mut char_start = read;
+    while read < source.len() {
+        let last = read + 1 == source.len();
+        let (_, r, w, _) = decoder.decode_to_utf8(&source[read..(read + 1)], &mut buf, last);
+        read += r;
+        if w > 0 {
+            if written + w > byte_idx {
+                // A byte that ends a malformed sequence can emit U+FFFD for
+                // that sequence followed by its own decoding.  Every char
+                // after the first comes from one of the trailing source
+                // bytes, one byte each.
+                let is_start = |b: &u8| (b & 0xC0) != 0x80;
+                let chars = buf[..w].iter().filter(|b| is_start(b)).count();
+                let chars_after = buf[(byte_idx - written + 1)..w]
+                    .iter()
+                    .filter(|b| is_start(b))
+                    .count();
+                let idx = read.saturating_sub(chars_after + 1);
+                return if chars_after + 1 == chars {
+                    char_start.min(idx)
+                } else {
+                    idx
+                };
+            }
+            written += w;
+            char_start = read;
+        }
+    }
+
+    source.len()
+}
+
+/// Converts from byte-index in the original legacy-encoded bytes to
+/// byte-index in the decoded utf8.
+///
+/// If the source byte is in the middle of an encoded char, returns the
+/// utf8 index of the char that the byte belongs to.
+///
+/// Any past-the-end index will return the one-past-the-end utf8 index.
+///
+/// Runs in O(N) time.
+pub fn to_byte_idx(source: &[u8], encoding: &'static Encoding, source_idx: usize) -> usize {
+    let end = source_idx.min(source.len());
+    let mut decoder = encoding.new_decoder_with_bom_removal();
+
+    // Flush trailing malformed sequences only if this is the whole text,
+    // so that the result matches the length of the full decoding.
+    decoded_len(&mut decoder, &source[..end], end == source.len())
+}
+
+/// Returns the length in bytes of the utf8 decoding of `source`.
+#[inline(always)]
+fn decoded_len(decoder: &mut Decoder, source: &[u8], last: bool) -> usize {
+    let mut buf = [0u8; BUF_LEN];
+    let mut read = 0;
+    let mut written = 0;
+    loop {
+        let (result, r, w, _) = decoder.decode_to_utf8(&source[read..], &mut buf, last);
+        read += r;
+        written += w;
+        if let CoderResult::InputEmpty = result {
+            return written;
+        }
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use encoding_rs::{SHIFT_JIS, UTF_16LE, UTF_8, WINDOWS_1252};
+
+    // "日本語 abc" in Shift-JIS: 3 two-byte chars, then ascii.
+    const SJIS: &[u8] = b"\x93\xfa\x96\x7b\x8c\xea abc";
+
+    // "café €!" in Windows-1252.  The é and € are 2 and 3 bytes in utf8.
+    const CP1252: &[u8] = b"caf\xe9 \x80!";
+
+    #[test]
+    fn to_byte_idx_01() {
+        let expected = [0, 0, 3, 3, 6, 6, 9, 10, 11, 12, 13];
+        for (i, e) in expected.iter().enumerate() {
+            assert_eq!(*e, to_byte_idx(SJIS, SHIFT_JIS, i));
+        }
+        assert_eq!(13, to_byte_idx(SJIS, SHIFT_JIS, 100));
+
+        let expected = [0, 1, 2, 3, 5, 6, 9, 10];
+        for (i, e) in expected.iter().enumerate() {
+            assert_eq!(*e, to_byte_idx(CP1252, WINDOWS_1252, i));
+        }
+    }
+
+    #[test]
+    fn from_byte_idx_01() {
+        let expected = [0, 0, 0, 2, 2, 2, 4, 4, 4, 6, 7, 8, 9, 10];
+        for (i, e) in expected.iter().enumerate() {
+            assert_eq!(*e, from_byte_idx(SJIS, SHIFT_JIS, i));
+        }
+        assert_eq!(10, from_byte_idx(SJIS, SHIFT_JIS, 100));
+
+        let expected = [0, 1, 2, 3, 3, 4, 5, 5, 5, 6, 7];
+        for (i, e) in expected.iter().enumerate() {
+            assert_eq!(*e, from_byte_idx(CP1252, WINDOWS_1252, i));
+        }
+    }
+
+    #[test]
+    fn bom_removal() {
+        // BOM, "a", "🐸" (surrogate pair) in utf16le.
+        let source = b"\xff\xfea\x00\x3d\xd8\x38\xdc";
+        assert_eq!(0, to_byte_idx(source, UTF_16LE, 2));
+        assert_eq!(1, to_byte_idx(source, UTF_16LE, 4));
+        assert_eq!(1, to_byte_idx(source, UTF_16LE, 6));
+        assert_eq!(5, to_byte_idx(source, UTF_16LE, 8));
+        assert_eq!(0, from_byte_idx(source, UTF_16LE, 0));
+        assert_eq!(4, from_byte_idx(source, UTF_16LE, 1));
+        assert_eq!(4, from_byte_idx(source, UTF_16LE, 3));
+        assert_eq!(8, from_byte_idx(source, UTF_16LE, 5));
+    }
+
+    #[test]
+    fn trailing_malformed() {
+        // A lone Shift-JIS lead byte decodes to U+FFFD at the end.
+        let source = b"a\x93";
+        assert_eq!(1, to_byte_idx(source, SHIFT_JIS, 1));
+        assert_eq!(4, to_byte_idx(source, SHIFT_JIS, 2));
+        assert_eq!(1, from_byte_idx(source, SHIFT_JIS, 1));
+        assert_eq!(1, from_byte_idx(source, SHIFT_JIS, 3));
+        assert_eq!(2, from_byte_idx(source, SHIFT_JIS, 4));
+    }
+
+    #[test]
+    fn malformed_then_ascii() {
+        // A lone utf8 lead byte only decodes to U+FFFD once the next byte
+        // arrives, and both are emitted together.
+        let source = b"\xC3A";
+        assert_eq!(4, to_byte_idx(source, UTF_8, 2));
+        assert_eq!(0, from_byte_idx(source, UTF_8, 0));
+        assert_eq!(0, from_byte_idx(source, UTF_8, 2));
+        assert_eq!(1, from_byte_idx(source, UTF_8, 3));
+        assert_eq!(2, from_byte_idx(source, UTF_8, 4));
+
+        // Same, with a truncated multi-byte sequence.
+        let source = b"\xE2\x82A";
+        assert_eq!(0, from_byte_idx(source, UTF_8, 2));
+        assert_eq!(2, from_byte_idx(source, UTF_8, 3));
+
+        // Two malformed bytes in a row, then ascii.
+        let source = b"\xC3\xC3A";
+        assert_eq!(0, from_byte_idx(source, UTF_8, 2));
+        assert_eq!(1, from_byte_idx(source, UTF_8, 3));
+        assert_eq!(1, from_byte_idx(source, UTF_8, 5));
+        assert_eq!(2, from_byte_idx(source, UTF_8, 6));
+
+        // A Shift-JIS lead byte followed by an invalid ascii trail byte.
+        let source = b"\x930b";
+        assert_eq!(0, from_byte_idx(source, SHIFT_JIS, 2));
+        assert_eq!(1, from_byte_idx(source, SHIFT_JIS, 3));
+        assert_eq!(2, from_byte_idx(source, SHIFT_JIS, 4));
+    }
+
+    #[test]
+    fn long_text_round_trip() {
+        // Long enough to exercise the chunked fast path.
+        let mut source = [0u8; 2000];
+        for (i, chunk) in source.chunks_mut(SJIS.len()).enumerate() {
+            chunk.copy_from_slice(&SJIS[..chunk.len()]);
+            if i % 7 == 3 {
+                chunk[chunk.len() - 1] = b'\n';
+            }
+        }
+        let mut utf8_idx = 0;
+        for i in 0..source.len() {
+            let idx = to_byte_idx(&source, SHIFT_JIS, i);
+            assert!(idx >= utf8_idx);
+            if idx != utf8_idx || i == 0 {
+                // `i` is the start of a char.
+                assert_eq!(i, from_byte_idx(&source, SHIFT_JIS, idx));
+            }
+            utf8_idx = idx;
+        }
+    }
+}
@@ -9,27 +9,733 @@
 //!
 //! None of the functions in this crate panic: all inputs have a defined
 //! output.
+//!
+//! [`debug_check_consistency()`] can be used to verify that the functions
+//! are consistent with each other and with the standard library on
+//! particular text.
 
+#[cfg(target_arch = "x86_64")]
+pub mod avx2;
+pub mod backend;
+#[macro_use]
 mod byte_chunk;
+pub mod cesu8;
 pub mod chars;
+#[cfg(feature = "codespan-reporting")]
+pub mod codespan;
+pub mod consts;
+pub mod cursor;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+#[cfg(feature = "encoding_rs")]
+pub mod legacy;
+pub mod line_break_opportunities;
 pub mod lines;
 pub mod lines_crlf;
+pub mod lines_custom;
 pub mod lines_lf;
+#[cfg(feature = "lsp-types")]
+pub mod lsp;
+pub mod measured;
+pub mod naive;
+pub mod segmented;
+pub mod subslice;
+#[cfg(feature = "test-support")]
+pub mod test_support;
+#[cfg(feature = "uniffi")]
+pub mod uniffi_api;
 pub mod utf16;
+#[cfg(feature = "alloc")]
+pub mod utf16_table;
+pub mod wrap;
+pub mod wtf8;
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+// UniFFI's generated scaffolding needs `std`.
+#[cfg(feature = "uniffi")]
+extern crate std;
+#[cfg(feature = "uniffi")]
+uniffi::setup_scaffolding!();
+
+/// Statistics about the byte lengths of the lines in a string slice.
+///
+/// Returned by the `len_stats()` function of each lines module, e.g.
+/// [`lines_lf::len_stats()`].  Line lengths include their line break.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct LineLenStats {
+    /// The number of lines.
+    pub count: usize,
+    /// The length of the shortest line, or zero if there are no lines.
+    pub min: usize,
+    /// The length of the longest line, or zero if there are no lines.
+    pub max: usize,
+    /// The total length of all lines.
+    pub total: usize,
+}
+
+impl LineLenStats {
+    /// Returns the mean line length, or zero if there are no lines.
+    pub fn mean(&self) -> f64 {
+        if self.count == 0 {
+            0.0
+        } else {
+            self.total as f64 / self.count as f64
+        }
+    }
+
+    #[inline(always)]
+    fn add(&mut self, len: usize) {
+        self.min = if self.count == 0 {
+            len
+        } else {
+            self.min.min(len)
+        };
+        self.max = self.max.max(len);
+        self.total += len;
+        self.count += 1;
+    }
+}
+
+/// The unit that columns are measured in, for converting between byte
+/// indices and (line, column) positions.
+///
+/// Used by the `byte_to_line_col()`, `line_col_to_byte()`, and
+/// `line_len()` functions of each lines module, e.g.
+/// [`lines_lf::byte_to_line_col()`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColumnUnit {
+    /// Bytes (utf8 code units).
+    Bytes,
+    /// Chars (Unicode scalar values, or utf32 code units).
+    Chars,
+    /// Utf16 code units.
+    Utf16,
+}
+
+impl ColumnUnit {
+    /// Returns the length of `text` in this unit.
+    #[inline(always)]
+    fn count(self, text: &str) -> usize {
+        match self {
+            ColumnUnit::Bytes => text.len(),
+            ColumnUnit::Chars => chars::count(text),
+            ColumnUnit::Utf16 => utf16::count(text),
+        }
+    }
+
+    /// Converts from an index in this unit to byte-index in `text`.
+    #[inline(always)]
+    fn to_byte_idx(self, text: &str, idx: usize) -> usize {
+        match self {
+            ColumnUnit::Bytes => chars::floor_byte_boundary(text, idx),
+            ColumnUnit::Chars => chars::to_byte_idx(text, idx),
+            ColumnUnit::Utf16 => utf16::to_byte_idx(text, idx),
+        }
+    }
+}
+
+/// Iterator over the indices of blank lines in a string slice.
+///
+/// Returned by the `blank_lines()` function of each lines module, e.g.
+/// [`lines_lf::blank_lines()`].
+#[derive(Debug, Clone)]
+pub struct BlankLines<'a> {
+    lines: LineContents<'a>,
+}
+
+impl Iterator for BlankLines<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        self.lines
+            .find(|(_, _, content)| content.bytes().all(|byte| byte.is_ascii_whitespace()))
+            .map(|(line_idx, _, _)| line_idx)
+    }
+}
+
+impl core::iter::FusedIterator for BlankLines<'_> {}
+
+/// Iterator over the indices of lines in a string slice that are longer
+/// than a given length.
+///
+/// Returned by the `long_lines()` and `long_lines_chars()` functions of
+/// each lines module, e.g. [`lines_lf::long_lines()`].
+#[derive(Debug, Clone)]
+pub struct LongLines<'a> {
+    lines: LineContents<'a>,
+    max_len: usize,
+    in_chars: bool,
+}
+
+impl Iterator for LongLines<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        let (max_len, in_chars) = (self.max_len, self.in_chars);
+        self.lines
+            .find(|(_, _, content)| {
+                // A line can't have more chars than bytes, so only count the
+                // chars of lines that might be too long.
+                content.len() > max_len && (!in_chars || chars::count(content) > max_len)
+            })
+            .map(|(line_idx, _, _)| line_idx)
+    }
+}
+
+impl core::iter::FusedIterator for LongLines<'_> {}
+
+/// Iterator over where the trailing whitespace of each line in a string
+/// slice begins.
+///
+/// Returned by the `trailing_whitespace()` function of each lines module,
+/// e.g. [`lines_lf::trailing_whitespace()`].
+#[derive(Debug, Clone)]
+pub struct TrailingWhitespace<'a> {
+    lines: LineContents<'a>,
+}
+
+impl Iterator for TrailingWhitespace<'_> {
+    type Item = Option<usize>;
+
+    fn next(&mut self) -> Option<Option<usize>> {
+        let (_, byte_idx, content) = self.lines.next()?;
+        let bytes = content.as_bytes();
+        let trimmed_len = bytes
+            .iter()
+            .rposition(|byte| !byte.is_ascii_whitespace())
+            .map_or(0, |i| i + 1);
+        if trimmed_len < bytes.len() {
+            Some(Some(byte_idx + trimmed_len))
+        } else {
+            Some(None)
+        }
+    }
+}
+
+impl core::iter::FusedIterator for TrailingWhitespace<'_> {}
+
+/// Iterator over the lines of a string slice.
+///
+/// Returned by the `iter()` function of each lines module, e.g.
+/// [`lines_lf::iter()`].
+#[derive(Debug, Clone)]
+pub struct Lines<'a> {
+    lines: LineContents<'a>,
+    text: &'a str,
+    include_break: bool,
+}
+
+impl<'a> Iterator for Lines<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        let (_, byte_idx, content) = self.lines.next()?;
+        if !self.include_break {
+            return Some(content);
+        }
+        let line_end = if self.lines.done {
+            self.text.len()
+        } else {
+            self.lines.byte_idx
+        };
+        self.text.get(byte_idx..line_end).or(Some(content))
+    }
+}
+
+impl core::iter::FusedIterator for Lines<'_> {}
+
+/// Iterator over the byte indices of the line starts in a string slice.
+///
+/// Returned by the `start_offsets()` function of each lines module, e.g.
+/// [`lines_lf::start_offsets()`].
+#[derive(Debug, Clone)]
+pub struct LineStarts<'a> {
+    lines: LineContents<'a>,
+}
+
+impl Iterator for LineStarts<'_> {
+    type Item = usize;
+
+    fn next(&mut self) -> Option<usize> {
+        self.lines.next().map(|(_, byte_idx, _)| byte_idx)
+    }
+}
+
+impl core::iter::FusedIterator for LineStarts<'_> {}
+
+/// Iterator over the utf16 lengths of the lines in a string slice.
+///
+/// Returned by the `utf16_lens()` function of each lines module, e.g.
+/// [`lines_lf::utf16_lens()`].
+#[derive(Clone)]
+pub struct Utf16Lens<'a> {
+    text: &'a str,
+    done: bool,
+    first_line_utf16_len: fn(&str) -> (usize, usize, bool),
+}
+
+impl core::fmt::Debug for Utf16Lens<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("Utf16Lens")
+            .field("text", &self.text)
+            .field("done", &self.done)
+            .finish()
+    }
+}
+
+impl Iterator for Utf16Lens<'_> {
+    type Item = usize;
+
+    #[inline]
+    fn next(&mut self) -> Option<usize> {
+        if self.done {
+            return None;
+        }
+        let (line_len, utf16_len, has_break) = (self.first_line_utf16_len)(self.text);
+        self.done = !has_break;
+        self.text = split_str_at(self.text, line_len).1;
+        Some(utf16_len)
+    }
+}
+
+impl core::iter::FusedIterator for Utf16Lens<'_> {}
+
+/// Iterator over the lines of a string slice, yielding each line's index,
+/// start byte index, and content (excluding its line break), for a given
+/// lines module.
+#[derive(Clone)]
+struct LineContents<'a> {
+    text: &'a str,
+    line_idx: usize,
+    byte_idx: usize,
+    final_empty_line: bool,
+    done: bool,
+    first_break: fn(&str) -> Option<usize>,
+    to_byte_idx: fn(&str, usize) -> usize,
+}
+
+impl<'a> LineContents<'a> {
+    #[inline(always)]
+    fn new(
+        text: &'a str,
+        final_empty_line: bool,
+        first_break: fn(&str) -> Option<usize>,
+        to_byte_idx: fn(&str, usize) -> usize,
+    ) -> LineContents<'a> {
+        LineContents {
+            text,
+            line_idx: 0,
+            byte_idx: 0,
+            final_empty_line,
+            done: false,
+            first_break,
+            to_byte_idx,
+        }
+    }
+
+    #[inline(always)]
+    fn blank_lines(self) -> BlankLines<'a> {
+        BlankLines { lines: self }
+    }
+
+    #[inline(always)]
+    fn count_nonblank(self) -> usize {
+        self.filter(|(_, _, content)| !content.bytes().all(|byte| byte.is_ascii_whitespace()))
+            .count()
+    }
+
+    /// Returns the byte length and index of the first longest line.
+    #[inline(always)]
+    fn max_len(self) -> (usize, usize) {
+        self.fold((0, 0), |(max_len, max_idx), (line_idx, _, content)| {
+            if content.len() > max_len {
+                (content.len(), line_idx)
+            } else {
+                (max_len, max_idx)
+            }
+        })
+    }
+
+    #[inline(always)]
+    fn long_lines(self, max_len: usize, in_chars: bool) -> LongLines<'a> {
+        LongLines {
+            lines: self,
+            max_len,
+            in_chars,
+        }
+    }
+
+    #[inline(always)]
+    fn lines(self, include_break: bool) -> Lines<'a> {
+        Lines {
+            text: self.text,
+            lines: self,
+            include_break,
+        }
+    }
+
+    #[inline(always)]
+    fn line_starts(self) -> LineStarts<'a> {
+        LineStarts { lines: self }
+    }
+
+    #[inline(always)]
+    fn trailing_whitespace(self) -> TrailingWhitespace<'a> {
+        TrailingWhitespace { lines: self }
+    }
+}
+
+impl core::fmt::Debug for LineContents<'_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+        f.debug_struct("LineContents")
+            .field("text", &self.text)
+            .field("line_idx", &self.line_idx)
+            .field("byte_idx", &self.byte_idx)
+            .field("final_empty_line", &self.final_empty_line)
+            .finish()
+    }
+}
+
+impl<'a> Iterator for LineContents<'a> {
+    type Item = (usize, usize, &'a str);
+
+    fn next(&mut self) -> Option<(usize, usize, &'a str)> {
+        if self.done {
+            return None;
+        }
+        let line_idx = self.line_idx;
+        let byte_idx = self.byte_idx;
+        self.line_idx += 1;
+        let content = match (self.first_break)(self.text) {
+            Some(i) => {
+                let (content, rest) = split_str_at(self.text, i);
+                let line_end = i + (self.to_byte_idx)(rest, 1);
+                self.text = split_str_at(self.text, line_end).1;
+                self.byte_idx += line_end;
+                content
+            }
+            None => {
+                self.done = true;
+                if self.text.is_empty() && !self.final_empty_line {
+                    return None;
+                }
+                self.text
+            }
+        };
+        Some((line_idx, byte_idx, content))
+    }
+}
+
+/// Checks that this crate's functions are consistent with each other and
+/// with the standard library on the given text.
+///
+/// This is meant for use in tests: it lets users verify that the crate's
+/// invariants hold on their own data and platform (e.g. with an unusual
+/// target or SIMD backend).  It checks that:
+///
+/// - The `chars` functions agree with `str::char_indices()`.
+/// - The `utf16` functions agree with `str::encode_utf16()` and
+///   `char::len_utf16()`.
+/// - The lines functions agree with `str::split_inclusive()` on their
+///   respective line breaks.
+///
+/// Every byte index is checked, so this runs in O(N^2) time.  Keep the
+/// texts small-ish.
+///
+/// Returns the name of the first check that failed, if any.
+pub fn debug_check_consistency(text: &str) -> Result<(), &'static str> {
+    // Chars and utf16.
+    let mut char_idx = 0;
+    let mut utf16_idx = 0;
+    let mut surrogates = 0;
+    for (byte_idx, c) in text.char_indices() {
+        for i in byte_idx..(byte_idx + c.len_utf8()) {
+            if chars::from_byte_idx(text, i) != char_idx {
+                return Err("chars::from_byte_idx");
+            }
+            if utf16::from_byte_idx(text, i) != utf16_idx {
+                return Err("utf16::from_byte_idx");
+            }
+        }
+        if chars::to_byte_idx(text, char_idx) != byte_idx {
+            return Err("chars::to_byte_idx");
+        }
+        for i in utf16_idx..(utf16_idx + c.len_utf16()) {
+            if utf16::to_byte_idx(text, i) != byte_idx {
+                return Err("utf16::to_byte_idx");
+            }
+        }
+        char_idx += 1;
+        utf16_idx += c.len_utf16();
+        surrogates += c.len_utf16() - 1;
+    }
+    if chars::count(text) != char_idx
+        || chars::from_byte_idx(text, text.len()) != char_idx
+        || chars::to_byte_idx(text, char_idx) != text.len()
+    {
+        return Err("chars::count");
+    }
+    if utf16::count(text) != text.encode_utf16().count()
+        || utf16::count(text) != utf16_idx
+        || utf16::count_surrogates(text) != surrogates
+        || utf16::to_byte_idx(text, utf16_idx) != text.len()
+    {
+        return Err("utf16::count");
+    }
+
+    // Lines.
+    if !check_lines(
+        text,
+        |c| {
+            matches!(
+                c,
+                '\u{000A}'..='\u{000D}' | '\u{0085}' | '\u{2028}' | '\u{2029}'
+            )
+        },
+        lines::count_breaks,
+        lines::from_byte_idx,
+        lines::to_byte_idx,
+    ) {
+        return Err("lines");
+    }
+    if !check_lines(
+        text,
+        |c| c == '\n' || c == '\r',
+        lines_crlf::count_breaks,
+        lines_crlf::from_byte_idx,
+        lines_crlf::to_byte_idx,
+    ) {
+        return Err("lines_crlf");
+    }
+    if !check_lines(
+        text,
+        |c| c == '\n',
+        lines_lf::count_breaks,
+        lines_lf::from_byte_idx,
+        lines_lf::to_byte_idx,
+    ) {
+        return Err("lines_lf");
+    }
+
+    Ok(())
+}
+
+/// Returns whether one lines module agrees with `str::split_inclusive()`
+/// on its line break chars, treating CRLF as a single line break.
+fn check_lines(
+    text: &str,
+    is_break: fn(char) -> bool,
+    count_breaks: fn(&str) -> usize,
+    from_byte_idx: fn(&str, usize) -> usize,
+    to_byte_idx: fn(&str, usize) -> usize,
+) -> bool {
+    let mut line_idx = 0;
+    let mut line_start = 0;
+    let mut end = 0;
+    for piece in text.split_inclusive(is_break) {
+        end += piece.len();
+        if !is_not_crlf_middle(end, text.as_bytes()) {
+            // The line continues through the LF of the CRLF.
+            continue;
+        }
+        for i in line_start..end {
+            if from_byte_idx(text, i) != line_idx {
+                return false;
+            }
+        }
+        if to_byte_idx(text, line_idx) != line_start {
+            return false;
+        }
+        line_idx += 1;
+        line_start = end;
+    }
+
+    // A text that ends with a line break (or is empty) ends with an empty
+    // line.  Otherwise the loop above already covered the last line.
+    if text.is_empty() || text.ends_with(is_break) {
+        if to_byte_idx(text, line_idx) != text.len() || from_byte_idx(text, text.len()) != line_idx
+        {
+            return false;
+        }
+    } else {
+        line_idx -= 1;
+    }
+    if count_breaks(text) != line_idx {
+        return false;
+    }
+
+    true
+}
+
+/// Implementation of the lines modules' `len_stats()`, given that
+/// module's `first_break()` and `to_byte_idx()`.
+#[inline(always)]
+fn line_len_stats(
+    text: &str,
+    final_empty_line: bool,
+    first_break: fn(&str) -> Option<usize>,
+    to_byte_idx: fn(&str, usize) -> usize,
+) -> LineLenStats {
+    let mut stats = LineLenStats::default();
+    let mut rest = text;
+    while let Some(i) = first_break(rest) {
+        let line_end = i + to_byte_idx(split_str_at(rest, i).1, 1);
+        stats.add(line_end);
+        rest = split_str_at(rest, line_end).1;
+    }
+    if final_empty_line || !rest.is_empty() {
+        stats.add(rest.len());
+    }
+    stats
+}
 
-/// Returns the alignment difference between the start of `bytes` and the
-/// type `T`.
+/// Implementation of the lines modules' `first_line_utf16_len()`, given that
+/// module's `is_break_start()` and a function that flags the bytes of a
+/// chunk that could start one of its line breaks.
 ///
-/// Or put differently: returns how many bytes into `bytes` you need to walk
-/// to reach the alignment of `T` in memory.
+/// Returns the byte and utf16 lengths of the first line of `text`,
+/// including its line break, and whether it has a line break.  The utf16
+/// code units are counted in the same pass over the chunks that looks for
+/// the line break.
+#[inline(always)]
+fn first_line_utf16_len_impl<T: byte_chunk::ByteChunk>(
+    text: &str,
+    break_start_flags: impl Fn(&T) -> T,
+    is_break_start: impl Fn(&str, usize) -> bool,
+) -> (usize, usize, bool) {
+    let bytes = text.as_bytes();
+    let count_bytes = |from: usize, to: usize| {
+        bytes
+            .get(from..to)
+            .unwrap_or(&[])
+            .iter()
+            .map(|byte| ((byte & 0xC0) != 0x80) as usize + ((byte & 0xF0) == 0xF0) as usize)
+            .sum::<usize>()
+    };
+    let find_break = |from: usize, to: usize| (from..to).find(|&i| is_break_start(text, i));
+
+    // Accumulates the trailing bytes and leading bytes of 4-byte chars of
+    // the (unaligned) chunk at a byte index, or returns where the line
+    // break in it starts, if any.  Only the bytes flagged as possible line
+    // break starts need checking.
+    let mut utf16_count = 0;
+    let mut acc_inv_chars = T::zero();
+    let mut acc_surrogates = T::zero();
+    let mut acc_len = 0;
+    let mut scan_chunk = |byte_idx: usize| {
+        let chunk = unsafe { (bytes.as_ptr().add(byte_idx) as *const T).read_unaligned() };
+        let mut mask = break_start_flags(&chunk).pack_flags();
+        while mask != 0 {
+            let i = byte_idx + mask.trailing_zeros() as usize;
+            if is_break_start(text, i) {
+                // Count the code units before the line break from the same
+                // flags, as bits.
+                let before = (1 << (i - byte_idx)) - 1;
+                let inv_chars = chunk.bitand(T::splat(0xc0)).cmp_eq_byte(0x80).pack_flags();
+                let surrogates = chunk.bitand(T::splat(0xf0)).cmp_eq_byte(0xf0).pack_flags();
+                utf16_count += (i - byte_idx) - (inv_chars & before).count_ones() as usize
+                    + (surrogates & before).count_ones() as usize;
+                return Some(i);
+            }
+            mask &= mask - 1;
+        }
+        if acc_len == T::MAX_ACC {
+            utf16_count +=
+                (T::SIZE * acc_len) - acc_inv_chars.sum_bytes() + acc_surrogates.sum_bytes();
+            (acc_inv_chars, acc_surrogates, acc_len) = (T::zero(), T::zero(), 0);
+        }
+        acc_inv_chars = acc_inv_chars.add(chunk.bitand(T::splat(0xc0)).cmp_eq_byte(0x80));
+        acc_surrogates = acc_surrogates.add(chunk.bitand(T::splat(0xf0)).cmp_eq_byte(0xf0));
+        acc_len += 1;
+        None
+    };
+
+    // Most lines are short, so start a chunk at a time, and only go a
+    // group of `T::UNROLL` chunks at a time once the line is longer than a
+    // group.  Like `utf16::count()`, groups of ascii without any possible
+    // line breaks only need their length counted.
+    let mut byte_idx = 0;
+    let mut ascii_len = 0;
+    let group_len = T::SIZE * T::UNROLL;
+    let mut line_break = None;
+    'scan: {
+        while byte_idx < group_len && byte_idx + T::SIZE <= bytes.len() {
+            line_break = scan_chunk(byte_idx);
+            if line_break.is_some() {
+                break 'scan;
+            }
+            byte_idx += T::SIZE;
+        }
+        while byte_idx + group_len <= bytes.len() {
+            let (flags, ored) = (0..T::UNROLL).fold((T::zero(), T::zero()), |acc, j| {
+                let ptr = unsafe { bytes.as_ptr().add(byte_idx + (j * T::SIZE)) };
+                let chunk = unsafe { (ptr as *const T).read_unaligned() };
+                (acc.0.bitor(break_start_flags(&chunk)), acc.1.bitor(chunk))
+            });
+            if flags.is_zero() && ored.bitand(T::splat(0x80)).is_zero() {
+                ascii_len += group_len;
+                byte_idx += group_len;
+                continue;
+            }
+            for _ in 0..T::UNROLL {
+                line_break = scan_chunk(byte_idx);
+                if line_break.is_some() {
+                    break 'scan;
+                }
+                byte_idx += T::SIZE;
+            }
+        }
+        while byte_idx + T::SIZE <= bytes.len() {
+            line_break = scan_chunk(byte_idx);
+            if line_break.is_some() {
+                break 'scan;
+            }
+            byte_idx += T::SIZE;
+        }
+    }
+    if acc_len > 0 {
+        utf16_count += (T::SIZE * acc_len) - acc_inv_chars.sum_bytes() + acc_surrogates.sum_bytes();
+    }
+    utf16_count += ascii_len;
+    if let Some(i) = line_break {
+        byte_idx = i;
+    }
+
+    // Take care of any bytes at the end that don't fill a chunk.
+    if line_break.is_none() {
+        line_break = find_break(byte_idx, bytes.len());
+    }
+
+    // Count the rest of the line a byte at a time.  Every line break
+    // that these modules share is one byte long except for these.
+    let (line_end, has_break) = match line_break {
+        Some(i) => match bytes.get(i..) {
+            Some([0x0D, 0x0A, ..] | [0xC2, ..]) => (i + 2, true),
+            Some([0xE2, ..]) => (i + 3, true),
+            _ => (i + 1, true),
+        },
+        None => (bytes.len(), false),
+    };
+    (
+        line_end,
+        utf16_count + count_bytes(byte_idx, line_end),
+        has_break,
+    )
+}
+
+/// Like `str::split_at()`, but without a panic path, so that it doesn't
+/// get in the way of the panic-freedom check (see `panic-check/`).
 ///
-/// Will return 0 if already aligned at the start, and will return the length
-/// of `bytes` if alignment is beyond the end of `bytes`.
+/// Callers only pass char boundaries, but if `byte_idx` somehow isn't one,
+/// this splits at the end of the text.
 #[inline(always)]
-fn alignment_diff<T>(bytes: &[u8]) -> usize {
-    let alignment = core::mem::align_of::<T>();
-    let ptr = bytes.as_ptr() as usize;
-    (alignment - ((ptr - 1) & (alignment - 1)) - 1).min(bytes.len())
+fn split_str_at(text: &str, byte_idx: usize) -> (&str, &str) {
+    match (text.get(..byte_idx), text.get(byte_idx..)) {
+        (Some(before), Some(after)) => (before, after),
+        _ => (text, ""),
+    }
 }
 
 /// Utility function used in some of the lines modules.
@@ -131,4 +837,50 @@ mod tests {
         assert_eq!(21, line_to_char_idx(text, char_to_line_idx(text, 21)));
         assert_eq!(5, char_to_line_idx(text, line_to_char_idx(text, 5)));
     }
+
+    #[test]
+    fn utf16_lens_long() {
+        // Long enough lines to span many accumulations of chunks, with line
+        // breaks (including CRLFs) at every alignment.
+        let line = ["Hel🐸lo wörld, こんにちは! ", "a"].concat().repeat(400);
+        let text = [
+            line.as_str(),
+            "\r",
+            &line,
+            "\r\n",
+            &line,
+            "\n\u{2028}\n",
+            &line,
+        ]
+        .concat();
+        type Fns = (
+            fn(&str) -> Utf16Lens<'_>,
+            fn(&str, usize) -> usize,
+            fn(&str) -> usize,
+        );
+        let checks: [Fns; 3] = [
+            (lines::utf16_lens, lines::to_byte_idx, lines::count_breaks),
+            (
+                lines_crlf::utf16_lens,
+                lines_crlf::to_byte_idx,
+                lines_crlf::count_breaks,
+            ),
+            (
+                lines_lf::utf16_lens,
+                lines_lf::to_byte_idx,
+                lines_lf::count_breaks,
+            ),
+        ];
+        for offset in 0..64 {
+            let text = split_str_at(&text, chars::to_byte_idx(&text, offset)).1;
+            for (utf16_lens, to_byte_idx, count_breaks) in checks {
+                assert_eq!(count_breaks(text) + 1, utf16_lens(text).count());
+                for (line_idx, len) in utf16_lens(text).enumerate() {
+                    let start = to_byte_idx(text, line_idx);
+                    let end = to_byte_idx(text, line_idx + 1);
+                    assert_eq!(utf16::count(&text[start..end]), len);
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,383 @@
+//! Find line break opportunities.
+//!
+//! A line break opportunity is a position where text may be wrapped onto
+//! the next line.  This module implements a useful subset of the rules in
+//! [Unicode Annex #14](https://www.unicode.org/reports/tr14/), enough for
+//! line filling in terminals and simple GUI layout:
+//!
+//! - After mandatory line breaks (LF, CR, CRLF, VT, FF, NEL, and the line
+//!   and paragraph separators), but never between the CR and LF of a CRLF.
+//! - After spaces, but not before them.  So a run of spaces stays at the
+//!   end of the line before the break.
+//! - After zero width spaces.
+//! - After hyphens and dashes, tabs, and the other "break after" spaces
+//!   (e.g. U+3000 IDEOGRAPHIC SPACE), except between a hyphen and a digit.
+//! - Before and after CJK ideographs, kana, and Hangul syllables, and
+//!   emoji.
+//!
+//! And there are never opportunities:
+//!
+//! - Before or after no-break spaces and word joiners.
+//! - Before closing punctuation (e.g. `)`, `,`, `.`, `」`, `。`) or small
+//!   kana, or after opening punctuation (e.g. `(`, `「`), even with spaces
+//!   in between.
+//! - Before combining marks and zero width joiners, or after zero width
+//!   joiners, so that e.g. emoji sequences stay together.
+//!
+//! Everything else, e.g. letters and digits, is joined together.  Other
+//! rules of the full algorithm, such as those for quotation marks,
+//! numeric expressions, and complex-context scripts like Thai, aren't
+//! implemented.  For simpler whitespace-only wrapping, see the
+//! [`wrap`](crate::wrap) module.
+//!
+//! Opportunities are byte indices strictly between the start and end of
+//! the text.  (The end of the text is always a break, so it isn't
+//! reported.)  Chunks of plain ascii words are skipped with SIMD, so the
+//! functions here are fast on mostly-ascii text.
+
+use crate::byte_chunk::ByteChunk;
+
+/// Counts the line break opportunities in a string slice.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn count(text: &str) -> usize {
+    with_chunk!(count_impl(text))
+}
+
+/// Returns whether there is a line break opportunity at the given byte
+/// index, i.e. whether a line may be broken just before that byte.
+///
+/// Byte indices that aren't char boundaries, or aren't strictly between
+/// the start and end of the text, return false.
+///
+/// Runs in O(1) time, except after runs of spaces, which are scanned back
+/// over.
+#[inline]
+pub fn is_opportunity(text: &str, byte_idx: usize) -> bool {
+    if byte_idx == 0 || byte_idx >= text.len() || !text.is_char_boundary(byte_idx) {
+        return false;
+    }
+    let (before, after) = crate::split_str_at(text, byte_idx);
+    let raw_before = before.chars().next_back().map_or(Class::Al, class);
+    let next = after.chars().next().map_or(Class::Al, class);
+
+    // Mandatory breaks.
+    match raw_before {
+        Class::Cr if next == Class::Lf => return false,
+        Class::Cr | Class::Lf | Class::Bk => return true,
+        _ => {}
+    }
+    if matches!(
+        next,
+        Class::Bk | Class::Cr | Class::Lf | Class::Sp | Class::Zw
+    ) {
+        return false;
+    }
+    if raw_before == Class::Zwj || matches!(next, Class::Cm | Class::Zwj) {
+        return false;
+    }
+
+    // Combining marks take on the class of the char they combine with, and
+    // some rules look past spaces.
+    let mut classes = before
+        .chars()
+        .rev()
+        .map(class)
+        .filter(|&class| !matches!(class, Class::Cm | Class::Zwj));
+    let prev = classes.next().unwrap_or(Class::Al);
+    let before_spaces = match prev {
+        Class::Sp => classes
+            .find(|&class| class != Class::Sp)
+            .unwrap_or(Class::Al),
+        class => class,
+    };
+
+    if before_spaces == Class::Zw {
+        return true;
+    }
+    if prev == Class::Gl || next == Class::Gl || next == Class::Cl || before_spaces == Class::Op {
+        return false;
+    }
+    if prev == Class::Sp {
+        return true;
+    }
+    if matches!(next, Class::Ba | Class::Hy) {
+        return false;
+    }
+    match prev {
+        Class::Ba => true,
+        Class::Hy => next != Class::Nu,
+        _ => prev == Class::Id || next == Class::Id,
+    }
+}
+
+/// Returns the last line break opportunity at or before the given byte
+/// index, if any.
+///
+/// This is where to wrap a row of text that can't extend past `byte_idx`,
+/// e.g. because the char there doesn't fit.
+///
+/// Runs in O(N) time, but stops at the opportunity.
+#[inline(never)]
+pub fn prev(text: &str, byte_idx: usize) -> Option<usize> {
+    let end = byte_idx.saturating_add(1).min(text.len());
+    with_chunk!(find_impl(text, 1.min(end)..end, true))
+}
+
+/// Returns the first line break opportunity at or after the given byte
+/// index, if any.
+///
+/// Runs in O(N) time, but stops at the opportunity.
+#[inline(never)]
+pub fn next(text: &str, byte_idx: usize) -> Option<usize> {
+    let start = byte_idx.clamp(1, text.len().max(1));
+    with_chunk!(find_impl(text, start..text.len(), false))
+}
+
+//-------------------------------------------------------------
+
+/// The line breaking classes of Unicode Annex #14 that this module
+/// distinguishes, some of them merged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Class {
+    /// Mandatory breaks other than CR and LF.
+    Bk,
+    Cr,
+    Lf,
+    /// Space.
+    Sp,
+    /// Zero width space.
+    Zw,
+    /// Zero width joiner.
+    Zwj,
+    /// Combining marks, including variation selectors and emoji modifiers.
+    Cm,
+    /// Glue: no-break spaces and word joiners.
+    Gl,
+    /// Closing punctuation and other chars that can't start a line.
+    Cl,
+    /// Opening punctuation.
+    Op,
+    /// Break after: tabs, dashes, and breaking spaces other than U+0020.
+    Ba,
+    /// Hyphen-minus.
+    Hy,
+    /// Digits.
+    Nu,
+    /// Ideographs, kana, Hangul syllables, and emoji.
+    Id,
+    /// Everything else.
+    Al,
+}
+
+#[inline(always)]
+fn class(c: char) -> Class {
+    match c {
+        '\n' => Class::Lf,
+        '\r' => Class::Cr,
+        '\u{000B}' | '\u{000C}' | '\u{0085}' | '\u{2028}' | '\u{2029}' => Class::Bk,
+        ' ' => Class::Sp,
+        '\u{200B}' => Class::Zw,
+        '\u{200D}' => Class::Zwj,
+        '\u{0300}'..='\u{036F}'
+        | '\u{FE00}'..='\u{FE0F}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{1F3FB}'..='\u{1F3FF}'
+        | '\u{E0020}'..='\u{E007F}' => Class::Cm,
+        '\u{00A0}' | '\u{2007}' | '\u{2011}' | '\u{202F}' | '\u{2060}' | '\u{FEFF}' => Class::Gl,
+        ')' | ']' | '}' | ',' | '.' | ':' | ';' | '!' | '?' | '/' | '%' => Class::Cl,
+        '、' | '。' | '，' | '．' | '：' | '；' | '！' | '？' | '）' | '］' | '｝' | '」'
+        | '』' | '】' | '〉' | '》' | '〕' | '〗' | '〙' | '〛' | 'ー' | '々' | '〻' | 'ゝ'
+        | 'ゞ' | 'ヽ' | 'ヾ' | '・' | '‼' | '⁇' | '⁈' | '⁉' => Class::Cl,
+        'ぁ' | 'ぃ' | 'ぅ' | 'ぇ' | 'ぉ' | 'っ' | 'ゃ' | 'ゅ' | 'ょ' | 'ゎ' | 'ゕ' | 'ゖ'
+        | 'ァ' | 'ィ' | 'ゥ' | 'ェ' | 'ォ' | 'ッ' | 'ャ' | 'ュ' | 'ョ' | 'ヮ' | 'ヵ' | 'ヶ' => {
+            Class::Cl
+        }
+        '(' | '[' | '{' | '（' | '［' | '｛' | '「' | '『' | '【' | '〈' | '《' | '〔' | '〖'
+        | '〘' | '〚' => Class::Op,
+        '\t'
+        | '\u{00AD}'
+        | '\u{1680}'
+        | '\u{2000}'..='\u{2006}'
+        | '\u{2008}'..='\u{200A}'
+        | '\u{2010}'
+        | '\u{2012}'
+        | '\u{2013}'
+        | '\u{205F}'
+        | '\u{3000}' => Class::Ba,
+        '-' => Class::Hy,
+        '0'..='9' => Class::Nu,
+        '\u{2E80}'..='\u{2FFF}'
+        | '\u{3040}'..='\u{30FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{AC00}'..='\u{D7A3}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{1F300}'..='\u{1FAFF}'
+        | '\u{20000}'..='\u{3FFFD}' => Class::Id,
+        _ => Class::Al,
+    }
+}
+
+/// Whether a chunk is plain ascii text without any spaces, control chars,
+/// or hyphens.  Such chunks can't have line break opportunities except
+/// at their start.
+#[inline(always)]
+fn is_plain<T: ByteChunk>(chunk: &T) -> bool {
+    chunk
+        .bytes_between_127(0x20, 0x7F)
+        .sub(chunk.cmp_eq_byte(b'-'))
+        .bitxor(T::splat(1))
+        .is_zero()
+}
+
+#[inline(always)]
+fn count_impl<T: ByteChunk>(text: &str) -> usize {
+    let (start, middle, _) = unsafe { text.as_bytes().align_to::<T>() };
+    let is_opp = |&i: &usize| is_opportunity(text, i);
+
+    // Take care of any unaligned bytes at the beginning.
+    let mut count = (0..start.len()).filter(is_opp).count();
+    let mut byte_idx = start.len();
+
+    // Only check the start of plain chunks, and the rest a byte at a
+    // time.
+    for chunk in middle.iter() {
+        count += if is_plain(chunk) {
+            is_opp(&byte_idx) as usize
+        } else {
+            (byte_idx..(byte_idx + T::SIZE)).filter(is_opp).count()
+        };
+        byte_idx += T::SIZE;
+    }
+
+    // Take care of any unaligned bytes at the end.
+    count + (byte_idx..text.len()).filter(is_opp).count()
+}
+
+/// Finds the first (or last, if `rev` is true) line break opportunity in
+/// `range`.
+#[inline(always)]
+fn find_impl<T: ByteChunk>(text: &str, range: core::ops::Range<usize>, rev: bool) -> Option<usize> {
+    let bytes = text.as_bytes().get(range.clone()).unwrap_or(&[]);
+    let (start, middle, end) = unsafe { bytes.align_to::<T>() };
+    let is_opp = |&i: &usize| is_opportunity(text, i);
+    let find = |from: usize, len: usize| {
+        let mut positions = from..(from + len);
+        if rev {
+            positions.rfind(is_opp)
+        } else {
+            positions.find(is_opp)
+        }
+    };
+    let find_in_chunk = |chunk: &T, byte_idx: usize| {
+        if is_plain(chunk) {
+            Some(byte_idx).filter(is_opp)
+        } else {
+            find(byte_idx, T::SIZE)
+        }
+    };
+
+    let middle_idx = range.start + start.len();
+    let end_idx = middle_idx + (middle.len() * T::SIZE);
+    if rev {
+        find(end_idx, end.len())
+            .or_else(|| {
+                middle
+                    .iter()
+                    .enumerate()
+                    .rev()
+                    .find_map(|(i, chunk)| find_in_chunk(chunk, middle_idx + (i * T::SIZE)))
+            })
+            .or_else(|| find(range.start, start.len()))
+    } else {
+        find(range.start, start.len())
+            .or_else(|| {
+                middle
+                    .iter()
+                    .enumerate()
+                    .find_map(|(i, chunk)| find_in_chunk(chunk, middle_idx + (i * T::SIZE)))
+            })
+            .or_else(|| find(end_idx, end.len()))
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The line break opportunities in `text`, checked one by one.
+    fn opportunities(text: &str) -> impl DoubleEndedIterator<Item = usize> + '_ {
+        (0..text.len()).filter(move |&i| is_opportunity(text, i))
+    }
+
+    #[test]
+    fn is_opportunity_01() {
+        let cases: [(&str, &[usize]); 16] = [
+            ("", &[]),
+            ("foo", &[]),
+            ("foo bar", &[4]),
+            ("foo   bar ", &[6]),
+            ("foo\nbar", &[4]),
+            ("foo\r\nbar", &[5]),
+            ("\n\n", &[1]),
+            ("well-known 42 -5", &[5, 11, 14]),
+            ("a\tb\u{3000}c", &[2, 6]),
+            ("foo\u{00A0}bar x\u{2060}y", &[9]),
+            ("(foo) bar, (baz).", &[6, 11]),
+            ("a ( b ) c", &[2, 8]),
+            ("こんにちは。", &[3, 6, 9, 12]),
+            ("「こん」です", &[6, 12, 15]),
+            ("ab\u{200B}cd", &[5]),
+            ("👍🏽 👨\u{200D}👩x", &[9, 20]),
+        ];
+        for (text, expected) in cases {
+            assert!(
+                opportunities(text).eq(expected.iter().copied()),
+                "{:?}",
+                text
+            );
+            assert_eq!(expected.len(), count(text), "{:?}", text);
+        }
+        assert!(!is_opportunity("foo bar", 100));
+        assert!(!is_opportunity("foo こ", 5));
+    }
+
+    #[test]
+    fn prev_next_01() {
+        let text = "The quick brown fox jumps over the lazy dog.\r\n\
+                    こんにちは、世界！ well-known (example) text\u{00A0}here"
+            .repeat(4);
+        let expected: [usize; 0] = [];
+        assert!(opportunities("").eq(expected));
+        assert_eq!(None, prev("", 0));
+        assert_eq!(None, next("", 0));
+        assert_eq!(None, prev("a b", 1));
+        assert_eq!(Some(2), prev("a b", 2));
+        assert_eq!(Some(2), next("a b", 0));
+        assert_eq!(None, next("a b", 3));
+
+        assert_eq!(opportunities(&text).count(), count(&text));
+        for i in 0..(text.len() + 2) {
+            assert_eq!(opportunities(&text).rfind(|&o| o <= i), prev(&text, i));
+            assert_eq!(opportunities(&text).find(|&o| o >= i), next(&text, i));
+        }
+    }
+
+    #[test]
+    fn narrow_chunks() {
+        let text = "The quick brown fox jumps-over the lazy dog.  こんにちは、世界！".repeat(3);
+        let count_naive = opportunities(&text).count();
+        assert_eq!(count_naive, count_impl::<u16>(&text));
+        assert_eq!(count_naive, count_impl::<u32>(&text));
+        for i in [0, 1, 7, 20, 50, 100, text.len()] {
+            let range = 1.min(i)..i;
+            let last = opportunities(&text).rfind(|&o| o < i);
+            assert_eq!(last, find_impl::<u16>(&text, range.clone(), true));
+            assert_eq!(last, find_impl::<u32>(&text, range, true));
+        }
+    }
+}
@@ -12,17 +12,47 @@
 //! - `U+2029`          &mdash; Paragraph Separator
 //! - `U+000D` `U+000A` &mdash; CRLF (Carriage Return + Line Feed)
 
-use crate::alignment_diff;
-use crate::byte_chunk::{ByteChunk, Chunk};
+use core::ops::Range;
+
+use crate::byte_chunk::ByteChunk;
+use crate::lines_custom::{convert_line_idx_impl, BreakSet};
 
 /// Counts the line breaks in a string slice.
 ///
 /// Runs in O(N) time.
-#[inline]
+#[inline(never)]
 pub fn count_breaks(text: &str) -> usize {
-    count_breaks_impl::<Chunk>(text.as_bytes())
+    with_chunk!(count_breaks_impl(text.as_bytes()))
 }
 
+/// Counts the lines in a string slice.
+///
+/// If `final_empty_line` is true, this follows the convention of the rest
+/// of this module: a string that ends with a line break has a final empty
+/// line, and an empty string is a single empty line.  So the result is
+/// always `count_breaks(text) + 1`.
+///
+/// If `final_empty_line` is false, this instead follows the convention of
+/// `str::lines()`, but with this module's line breaks: a line break at the
+/// end of the string doesn't start a new line, and an empty string has no
+/// lines.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn count_lines(text: &str, final_empty_line: bool) -> usize {
+    let breaks = count_breaks(text);
+    if final_empty_line || !(text.is_empty() || text.ends_with(LINE_BREAKS)) {
+        breaks + 1
+    } else {
+        breaks
+    }
+}
+
+/// The line break chars.  CRLF is covered by LF.
+const LINE_BREAKS: [char; 7] = [
+    '\u{000A}', '\u{000B}', '\u{000C}', '\u{000D}', '\u{0085}', '\u{2028}', '\u{2029}',
+];
+
 /// Converts from byte-index to line-index in a string slice.
 ///
 /// Line break characters are considered to be a part of the line they
@@ -33,18 +63,65 @@ pub fn count_breaks(text: &str) -> usize {
 /// Any past-the-end index will return the last line index.
 ///
 /// Runs in O(N) time.
-#[inline]
+#[inline(never)]
 pub fn from_byte_idx(text: &str, byte_idx: usize) -> usize {
-    let mut i = byte_idx.min(text.len());
-    while !text.is_char_boundary(i) {
-        i -= 1;
-    }
-    let nl_count = count_breaks_impl::<Chunk>(&text.as_bytes()[..i]);
-    if crate::is_not_crlf_middle(i, text.as_bytes()) {
-        nl_count
-    } else {
-        nl_count - 1
-    }
+    with_chunk!(from_byte_idx_impl(text, byte_idx))
+}
+
+/// Converts from byte-index to line-index in a string slice, also
+/// reporting whether the byte index was at the start of a line.
+///
+/// Returns the same line index as [`from_byte_idx()`], along with how
+/// many bytes `byte_idx` is past the start of that line.  The latter is
+/// zero exactly when `byte_idx` was already the start of a line.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn from_byte_idx_snapped(text: &str, byte_idx: usize) -> (usize, usize) {
+    let line_idx = from_byte_idx(text, byte_idx);
+    (line_idx, byte_idx - to_byte_idx(text, line_idx))
+}
+
+/// Converts from byte-index to a (line-index, column) position in a
+/// string slice.
+///
+/// The line index is the same as from [`from_byte_idx()`], and the column
+/// is measured from the start of that line in the given `unit`.  Byte
+/// indices in the middle of a char refer to the start of that char, and
+/// any past-the-end index will return the position of the end of the
+/// text.
+///
+/// The lines before `byte_idx` are skipped with the same accelerated
+/// scanning as [`from_byte_idx()`], and then only the part of its own line
+/// before it is measured.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn byte_to_line_col(text: &str, byte_idx: usize, unit: crate::ColumnUnit) -> (usize, usize) {
+    let byte_idx = crate::chars::floor_byte_boundary(text, byte_idx);
+    let (line_idx, offset) = from_byte_idx_snapped(text, byte_idx);
+    let before = text.get((byte_idx - offset)..byte_idx).unwrap_or("");
+    (line_idx, unit.count(before))
+}
+
+/// Converts from a (line-index, column) position to byte-index in a
+/// string slice.
+///
+/// The inverse of [`byte_to_line_col()`], with the column measured from
+/// the start of the line in the given `unit`.  A column in the middle of a
+/// char refers to the start of that char.
+///
+/// Columns past the end of a line are clamped to the end of its content,
+/// before its line break, so the result is always on the given line.
+/// Lines past the end of the text refer to the end of the text.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn line_col_to_byte(text: &str, line_idx: usize, col: usize, unit: crate::ColumnUnit) -> usize {
+    let start = to_byte_idx(text, line_idx);
+    let line = crate::split_str_at(text, start).1;
+    let content = crate::split_str_at(line, first_break(line).unwrap_or(line.len())).0;
+    start + unit.to_byte_idx(content, col)
 }
 
 /// Converts from line-index to byte-index in a string slice.
@@ -56,50 +133,825 @@ pub fn from_byte_idx(text: &str, byte_idx: usize) -> usize {
 /// Any past-the-end index will return the one-past-the-end byte index.
 ///
 /// Runs in O(N) time.
-#[inline]
+#[inline(never)]
 pub fn to_byte_idx(text: &str, line_idx: usize) -> usize {
-    to_byte_idx_impl::<Chunk>(text, line_idx)
+    with_chunk!(to_byte_idx_impl(text, line_idx))
 }
 
-//-------------------------------------------------------------
+/// Counts the line breaks in a string slice, stopping at a maximum number
+/// of bytes or line breaks, whichever comes first.
+///
+/// Returns the number of line breaks counted and the byte index where
+/// counting stopped: just after the `max_breaks`th line break, or at
+/// `max_bytes` if that comes first, or at the end of the text.  This is
+/// useful for e.g. incremental parsing, where each call continues from
+/// where the last one stopped.
+///
+/// A `max_bytes` in the middle of a char stops at the start of that char,
+/// and one between the CR and LF of a CRLF stops before the CR, so that
+/// line breaks are never split between calls.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn count_breaks_up_to(text: &str, max_bytes: usize, max_breaks: usize) -> (usize, usize) {
+    let mut end = crate::chars::floor_byte_boundary(text, max_bytes);
+    if !crate::is_not_crlf_middle(end, text.as_bytes()) {
+        end -= 1;
+    }
+    with_chunk!(scan_to_impl(crate::split_str_at(text, end).0, max_breaks))
+}
 
-#[inline(always)]
-fn to_byte_idx_impl<T: ByteChunk>(text: &str, line_idx: usize) -> usize {
-    let mut bytes = text.as_bytes();
-    let mut line_break_count = 0;
+/// Splits a string slice at the start of a line.
+///
+/// Equivalent to `text.split_at(to_byte_idx(text, line_idx))`: the first
+/// slice contains the lines before `line_idx` (including the line break
+/// ending them), and the second starts with line `line_idx`.  Any
+/// past-the-end index splits at the end of the text.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn split_at(text: &str, line_idx: usize) -> (&str, &str) {
+    crate::split_str_at(text, to_byte_idx(text, line_idx))
+}
 
-    // Handle unaligned bytes at the start.
-    let aligned_idx = alignment_diff::<T>(bytes);
-    if aligned_idx > 0 {
-        let result = count_breaks_up_to(bytes, aligned_idx, line_idx);
-        line_break_count += result.0;
-        bytes = &bytes[result.1..];
+/// Returns whether a line break starts at the given byte index.
+///
+/// A CRLF is treated as a single line break starting at the CR, so this
+/// returns false for the LF of a CRLF.  Multi-byte line breaks only start
+/// at their first byte.
+///
+/// Any past-the-end index returns false.
+///
+/// Runs in O(1) time.
+#[inline]
+pub fn is_break_start(text: &str, byte_idx: usize) -> bool {
+    let bytes = text.as_bytes();
+    match bytes.get(byte_idx) {
+        Some(0x0A) => byte_idx == 0 || bytes[byte_idx - 1] != 0x0D,
+        Some(0x0B..=0x0D) => true,
+        Some(0xC2) => bytes.get(byte_idx + 1) == Some(&0x85),
+        Some(0xE2) => {
+            bytes.get(byte_idx + 1) == Some(&0x80)
+                && matches!(bytes.get(byte_idx + 2), Some(0xA8 | 0xA9))
+        }
+        _ => false,
     }
+}
 
-    // Count line breaks in big chunks.
-    if alignment_diff::<T>(bytes) == 0 {
-        while bytes.len() >= T::SIZE {
-            // Unsafe because the called function depends on correct alignment.
-            let tmp = unsafe { count_breaks_in_chunk_from_ptr::<T>(bytes) }.sum_bytes();
-            if tmp + line_break_count >= line_idx {
-                break;
+/// Converts from a [`lines_crlf`](crate::lines_crlf) line-index to a
+/// line-index in this module's scheme, for the same text.
+///
+/// Every line start in `lines_crlf` is also a line start in this module, so
+/// this returns the index of the same line start.
+///
+/// Equivalent to `from_byte_idx(text, lines_crlf::to_byte_idx(text, line_idx))`,
+/// but counts the line breaks of both schemes in the same scan, which
+/// stops at the start of the line.  Any past-the-end index will return the
+/// last line index.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn from_lines_crlf_idx(text: &str, line_idx: usize) -> usize {
+    with_chunk!(convert_line_idx_impl(
+        text.as_bytes(),
+        line_idx,
+        BreakSet::LF.union(BreakSet::CR),
+        BreakSet::ALL
+    ))
+}
+
+/// Converts from a [`lines_lf`](crate::lines_lf) line-index to a
+/// line-index in this module's scheme, for the same text.
+///
+/// Every line start in `lines_lf` is also a line start in this module, so
+/// this returns the index of the same line start.
+///
+/// Equivalent to `from_byte_idx(text, lines_lf::to_byte_idx(text, line_idx))`,
+/// but counts the line breaks of both schemes in the same scan, which
+/// stops at the start of the line.  Any past-the-end index will return the
+/// last line index.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn from_lines_lf_idx(text: &str, line_idx: usize) -> usize {
+    with_chunk!(convert_line_idx_impl(
+        text.as_bytes(),
+        line_idx,
+        BreakSet::LF,
+        BreakSet::ALL
+    ))
+}
+
+/// Returns the byte range of a line, including its line break.
+///
+/// The range starts at `to_byte_idx(text, line_idx)` and ends just after
+/// the line's break, or at the end of the text for the last line, so that
+/// consecutive lines' spans are contiguous.
+///
+/// Any past-the-end index will return an empty range at the end of the
+/// text.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn line_span(text: &str, line_idx: usize) -> Range<usize> {
+    let start = to_byte_idx(text, line_idx);
+    let line = crate::split_str_at(text, start).1;
+    start..(start + to_byte_idx(line, 1))
+}
+
+/// Returns the byte range of the content of a line, excluding its line
+/// break.
+///
+/// If `trim` is true, leading and trailing ascii whitespace (as defined by
+/// `u8::is_ascii_whitespace()`) is excluded as well.  As with
+/// `str::trim()`, a line that is entirely whitespace then gives an empty
+/// range at the end of its content.
+///
+/// Any past-the-end index will return an empty range at the end of the
+/// text.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn line_content_range(text: &str, line_idx: usize, trim: bool) -> Range<usize> {
+    let start = to_byte_idx(text, line_idx);
+    let line = crate::split_str_at(text, start).1;
+    let line = crate::split_str_at(line, first_break(line).unwrap_or(line.len())).0;
+    if !trim {
+        return start..(start + line.len());
+    }
+
+    let bytes = line.as_bytes();
+    let leading = bytes
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    let trailing = bytes[leading..]
+        .iter()
+        .rev()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(0);
+    (start + leading)..(start + bytes.len() - trailing)
+}
+
+/// Returns the content of a line, excluding its line break.
+///
+/// Equivalent to `&text[line_content_range(text, line_idx, trim)]`.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn line_content(text: &str, line_idx: usize, trim: bool) -> &str {
+    text.get(line_content_range(text, line_idx, trim))
+        .unwrap_or("")
+}
+
+/// Returns the content of a line, excluding its line break, or `None` if
+/// there is no such line.
+///
+/// Like [`line_content()`] (untrimmed), except that past-the-end indices
+/// aren't clamped.  Text ending in a line break has a final empty line, so
+/// e.g. `"a\n"` has lines `"a"` and `""`.  The text is scanned only once,
+/// up to the end of the line.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn nth_line(text: &str, line_idx: usize) -> Option<&str> {
+    let (count, start) = with_chunk!(scan_to_impl(text, line_idx));
+    if count < line_idx {
+        return None;
+    }
+    let line = crate::split_str_at(text, start).1;
+    Some(crate::split_str_at(line, first_break(line).unwrap_or(line.len())).0)
+}
+
+/// Returns the length of a line, excluding its line break, in the given
+/// unit.
+///
+/// Equivalent to measuring [`line_content()`] (untrimmed) with e.g.
+/// [`chars::count()`](crate::chars::count) or
+/// [`utf16::count()`](crate::utf16::count).
+///
+/// Any past-the-end index will return zero.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn line_len(text: &str, line_idx: usize, unit: crate::ColumnUnit) -> usize {
+    unit.count(line_content(text, line_idx, false))
+}
+
+/// Returns the line containing the given byte.
+///
+/// If `include_break` is true the line's break is included, as in
+/// [`line_span()`], and otherwise it's excluded, as in
+/// [`line_content()`].  A byte of a line break is part of the line that
+/// the break ends.
+///
+/// Any past-the-end index will return the last line.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn line_at_byte(text: &str, byte_idx: usize, include_break: bool) -> &str {
+    let start = byte_idx - from_byte_idx_snapped(text, byte_idx).1;
+    let line = crate::split_str_at(text, start).1;
+    let end = if include_break {
+        to_byte_idx(line, 1)
+    } else {
+        first_break(line).unwrap_or(line.len())
+    };
+    crate::split_str_at(line, end).0
+}
+
+/// Returns the length in bytes of the longest common prefix of two string
+/// slices that ends at a line start in both.
+///
+/// Like [`chars::common_prefix()`](crate::chars::common_prefix), but
+/// snapped back to the start of a line, so that the prefix consists of
+/// whole lines.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn common_prefix(a: &str, b: &str) -> usize {
+    let len = crate::chars::common_prefix(a, b);
+    let line_start = |text| to_byte_idx(text, from_byte_idx(text, len));
+    line_start(a).min(line_start(b))
+}
+
+/// Returns the length in bytes of the longest common suffix of two string
+/// slices that starts at a line start in both.
+///
+/// Like [`chars::common_suffix()`](crate::chars::common_suffix), but
+/// snapped forward to the start of a line, so that the suffix consists of
+/// whole lines.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn common_suffix(a: &str, b: &str) -> usize {
+    let len = crate::chars::common_suffix(a, b);
+    let suffix_len = |text: &str| {
+        let start = text.len() - len;
+        let line_idx = from_byte_idx(text, start);
+        let mut line_start = to_byte_idx(text, line_idx);
+        if line_start < start {
+            line_start = to_byte_idx(text, line_idx + 1);
+        }
+        text.len() - line_start
+    };
+    suffix_len(a).min(suffix_len(b))
+}
+
+/// Returns whether there are any line breaks in a string slice.
+///
+/// Equivalent to `count_breaks(text) > 0`, but stops at the first line
+/// break.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn has_break(text: &str) -> bool {
+    first_break(text).is_some()
+}
+
+/// Returns the byte index of the start of the first line break in a
+/// string slice, if any.
+///
+/// A CRLF starts at its CR.  Stops scanning at the first line break.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn first_break(text: &str) -> Option<usize> {
+    with_chunk!(first_break_impl(text))
+}
+
+/// Returns the kind and byte length of the line break at the end of a
+/// string slice, if it ends with one.
+///
+/// A CRLF at the end is reported as a single [`LineBreak::CrLf`], not as
+/// an LF.
+///
+/// Runs in O(1) time.
+#[inline]
+pub fn trailing_break(text: &str) -> Option<(LineBreak, usize)> {
+    let bytes = text.as_bytes();
+    let line_break = match *bytes {
+        [.., 0x0D, 0x0A] => LineBreak::CrLf,
+        [.., 0x0A] => LineBreak::Lf,
+        [.., 0x0B] => LineBreak::Vt,
+        [.., 0x0C] => LineBreak::Ff,
+        [.., 0x0D] => LineBreak::Cr,
+        [.., 0xC2, 0x85] => LineBreak::Nel,
+        [.., 0xE2, 0x80, 0xA8] => LineBreak::Ls,
+        [.., 0xE2, 0x80, 0xA9] => LineBreak::Ps,
+        _ => return None,
+    };
+    Some((line_break, line_break.as_str().len()))
+}
+
+/// Finds the first line break that starts at or after a byte index.
+///
+/// Returns the line break's byte range and kind.  A byte index in the
+/// middle of a line break (e.g. between the CR and LF of a CRLF) only
+/// finds later line breaks.
+///
+/// Runs in O(N) time, but stops at the line break.
+#[inline(never)]
+pub fn next_break(text: &str, byte_idx: usize) -> Option<(Range<usize>, LineBreak)> {
+    let mut start = crate::chars::ceil_byte_boundary(text, byte_idx);
+    if !crate::is_not_crlf_middle(start, text.as_bytes()) {
+        start += 1;
+    }
+    let i = start + first_break(crate::split_str_at(text, start).1)?;
+    Some(break_at(text, i))
+}
+
+/// Finds the last line break that starts before a byte index.
+///
+/// Returns the line break's byte range and kind.  The range may extend
+/// past `byte_idx`, if `byte_idx` is in the middle of the line break.
+/// Together with [`next_break()`] this finds the line breaks on either
+/// side of a byte index, scanning backward and forward respectively.
+///
+/// Runs in O(N) time, but stops at the line break.
+#[inline(never)]
+pub fn prev_break(text: &str, byte_idx: usize) -> Option<(Range<usize>, LineBreak)> {
+    let end = byte_idx.min(text.len());
+    let i = with_chunk!(last_break_impl(text, end))?;
+    Some(break_at(text, i))
+}
+
+/// Returns the byte index of the start of the last line in a string
+/// slice.
+///
+/// Equivalent to `to_byte_idx(text, count_breaks(text))`, but scans
+/// backward from the end of the text, so it only takes time proportional
+/// to the length of the last line.  A string that ends with a line break
+/// has a final empty line, which starts at the end of the text.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn last_line_start(text: &str) -> usize {
+    prev_break(text, text.len()).map_or(0, |(range, _)| range.end)
+}
+
+/// Returns the byte length that a string slice would have if all of its
+/// line breaks were converted to `target`.
+///
+/// This is useful for e.g. sizing a buffer before normalizing line
+/// endings, without allocating.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn normalized_len(text: &str, target: LineBreak) -> usize {
+    // Every line break has at least one byte, and CRLF and NEL have one
+    // more, and LS and PS two more.
+    let break_count = count_breaks(text);
+    let count_custom = |breaks| crate::lines_custom::count_breaks(text, breaks);
+    let break_bytes = break_count
+        + crate::lines_crlf::count_crlf(text)
+        + count_custom(BreakSet::NEL)
+        + (2 * count_custom(BreakSet::LS.union(BreakSet::PS)));
+    text.len() - break_bytes + (break_count * target.as_str().len())
+}
+
+/// Computes statistics about the byte lengths of the lines in a string
+/// slice, in a single pass.
+///
+/// Each line's length includes its line break.  `final_empty_line`
+/// determines whether a final empty line (after a line break at the end of
+/// the string, or an empty string) is counted, as in [`count_lines()`].
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn len_stats(text: &str, final_empty_line: bool) -> crate::LineLenStats {
+    crate::line_len_stats(text, final_empty_line, first_break, to_byte_idx)
+}
+
+/// Finds the longest line in a string slice.
+///
+/// Returns the line's length in bytes, not including its line break, and
+/// its line index.  If several lines are equally long, the first of them
+/// is returned.  An empty string returns `(0, 0)`.
+///
+/// This is useful for e.g. sizing horizontal scrollbars.  Use
+/// [`chars::count()`](crate::chars::count) or
+/// [`utf16::count()`](crate::utf16::count) on the line for its length in
+/// other units.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn max_line_len(text: &str) -> (usize, usize) {
+    crate::LineContents::new(text, true, first_break, to_byte_idx).max_len()
+}
+
+/// Counts the blank lines in a string slice.
+///
+/// A blank line is one that is empty or contains only ascii whitespace (as
+/// defined by `u8::is_ascii_whitespace()`), not counting its line break.
+/// `final_empty_line` determines whether a final empty line (after a line
+/// break at the end of the string, or an empty string) is included, as in
+/// [`count_lines()`].
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn count_blank_lines(text: &str, final_empty_line: bool) -> usize {
+    blank_lines(text, final_empty_line).count()
+}
+
+/// Counts the non-blank lines in a string slice.
+///
+/// These are the lines that contain at least one byte that isn't ascii
+/// whitespace, i.e. the lines that aren't blank as defined in
+/// [`count_blank_lines()`].  So the result is always
+/// `count_lines(text, false) - count_blank_lines(text, false)`, but is
+/// computed in a single pass.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn count_nonblank(text: &str) -> usize {
+    crate::LineContents::new(text, false, first_break, to_byte_idx).count_nonblank()
+}
+
+/// Returns an iterator over the line indices of the blank lines in a
+/// string slice.
+///
+/// See [`count_blank_lines()`] for what counts as a blank line.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn blank_lines(text: &str, final_empty_line: bool) -> crate::BlankLines<'_> {
+    crate::LineContents::new(text, final_empty_line, first_break, to_byte_idx).blank_lines()
+}
+
+/// Returns an iterator over the line indices of the lines in a string
+/// slice that are longer than `max_bytes` bytes.
+///
+/// Line lengths don't include line breaks.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn long_lines(text: &str, max_bytes: usize) -> crate::LongLines<'_> {
+    crate::LineContents::new(text, false, first_break, to_byte_idx).long_lines(max_bytes, false)
+}
+
+/// Returns an iterator over the line indices of the lines in a string
+/// slice that are longer than `max_chars` chars.
+///
+/// Line lengths don't include line breaks.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn long_lines_chars(text: &str, max_chars: usize) -> crate::LongLines<'_> {
+    crate::LineContents::new(text, false, first_break, to_byte_idx).long_lines(max_chars, true)
+}
+
+/// Returns an iterator over where the trailing ascii whitespace of each
+/// line in a string slice begins.
+///
+/// Yields one item per line: the byte index of the start of the line's
+/// trailing whitespace (as defined by `u8::is_ascii_whitespace()`), or
+/// `None` if the line doesn't end in whitespace.  The line break itself
+/// isn't counted as whitespace.  The final empty line is included if
+/// `final_empty_line` is true, as in [`count_lines()`], and always yields
+/// `None`.
+///
+/// This is useful for e.g. trimming trailing whitespace on save: the
+/// ranges to remove are from each yielded index to the end of its line's
+/// content.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn trailing_whitespace(text: &str, final_empty_line: bool) -> crate::TrailingWhitespace<'_> {
+    crate::LineContents::new(text, final_empty_line, first_break, to_byte_idx).trailing_whitespace()
+}
+
+/// Returns an iterator over the lines of a string slice.
+///
+/// Like `str::lines()`, but recognizing all the line breaks listed in the
+/// module docs rather than just LF and CRLF.  As with `str::lines()`, a
+/// line break at the end of the string doesn't start a final empty line,
+/// and an empty string has no lines, so there are
+/// `count_lines(text, false)` items.  If `include_break` is true each item includes its line break,
+/// and the items together make up the whole string.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn iter(text: &str, include_break: bool) -> crate::Lines<'_> {
+    crate::LineContents::new(text, false, first_break, to_byte_idx).lines(include_break)
+}
+
+/// Returns an iterator over the byte indices of the line starts in a
+/// string slice.
+///
+/// Yields `to_byte_idx(text, line_idx)` for each line, in order, but in
+/// O(N) time overall rather than O(N) per line.  As elsewhere in this
+/// module, a string that ends with a line break (or is empty) has a final
+/// empty line, so there are always `count_breaks(text) + 1` items.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn start_offsets(text: &str) -> crate::LineStarts<'_> {
+    crate::LineContents::new(text, true, first_break, to_byte_idx).line_starts()
+}
+
+/// Returns an iterator over the line breaks in a string slice.
+///
+/// Yields the byte index of the start of each line break, along with its
+/// kind.  A CRLF is a single line break starting at its CR.  The iterator
+/// is double-ended, so it can also find the line breaks from the end.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn break_indices(text: &str) -> BreakIndices<'_> {
+    BreakIndices {
+        text,
+        start: 0,
+        end: text.len(),
+    }
+}
+
+/// Returns an iterator over the utf16 lengths of the lines in a string
+/// slice.
+///
+/// Each line's length includes its line break, and a string that ends
+/// with a line break (or is empty) has a final empty line, consistent with
+/// the rest of this module.  So the lengths always sum to
+/// `utf16::count(text)`, and there are always `count_breaks(text) + 1` of
+/// them.
+///
+/// This is useful for building the line tables needed by e.g. LSP servers.
+/// Each line's code units are counted in the same pass that finds its
+/// line break.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn utf16_lens(text: &str) -> crate::Utf16Lens<'_> {
+    crate::Utf16Lens {
+        text,
+        done: false,
+        first_line_utf16_len,
+    }
+}
+
+/// Returns the byte and utf16 lengths of the first line of a string
+/// slice, and whether it has a line break.  Used by [`utf16_lens()`].
+#[inline(never)]
+fn first_line_utf16_len(text: &str) -> (usize, usize, bool) {
+    with_chunk!(crate::first_line_utf16_len_impl(
+        text,
+        break_start_flags,
+        is_break_start
+    ))
+}
+
+/// Iterator over the line breaks in a string slice, created by
+/// [`break_indices()`].
+#[derive(Debug, Clone)]
+pub struct BreakIndices<'a> {
+    text: &'a str,
+    /// Where to continue searching forward from.
+    start: usize,
+    /// Where to continue searching backward from.
+    end: usize,
+}
+
+impl Iterator for BreakIndices<'_> {
+    type Item = (usize, LineBreak);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, LineBreak)> {
+        match next_break(self.text, self.start) {
+            Some((range, line_break)) if range.start < self.end => {
+                self.start = range.end;
+                Some((range.start, line_break))
+            }
+            _ => {
+                self.start = self.end;
+                None
+            }
+        }
+    }
+}
+
+impl DoubleEndedIterator for BreakIndices<'_> {
+    #[inline]
+    fn next_back(&mut self) -> Option<(usize, LineBreak)> {
+        match prev_break(self.text, self.end) {
+            Some((range, line_break)) if range.start >= self.start => {
+                self.end = range.start;
+                Some((range.start, line_break))
             }
-            line_break_count += tmp;
+            _ => {
+                self.end = self.start;
+                None
+            }
+        }
+    }
+}
+
+impl core::iter::FusedIterator for BreakIndices<'_> {}
+
+/// A kind of line break recognized by this module.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum LineBreak {
+    /// `U+000A`, LF (Line Feed).
+    Lf,
+    /// `U+000B`, VT (Vertical Tab).
+    Vt,
+    /// `U+000C`, FF (Form Feed).
+    Ff,
+    /// `U+000D`, CR (Carriage Return).
+    Cr,
+    /// `U+000D` `U+000A`, CRLF (Carriage Return + Line Feed).
+    CrLf,
+    /// `U+0085`, NEL (Next Line).
+    Nel,
+    /// `U+2028`, Line Separator.
+    Ls,
+    /// `U+2029`, Paragraph Separator.
+    Ps,
+}
+
+impl LineBreak {
+    /// Returns the line break as a string slice.
+    pub fn as_str(self) -> &'static str {
+        match self {
+            LineBreak::Lf => "\n",
+            LineBreak::Vt => "\u{000B}",
+            LineBreak::Ff => "\u{000C}",
+            LineBreak::Cr => "\r",
+            LineBreak::CrLf => "\r\n",
+            LineBreak::Nel => "\u{0085}",
+            LineBreak::Ls => "\u{2028}",
+            LineBreak::Ps => "\u{2029}",
+        }
+    }
+}
+
+//-------------------------------------------------------------
+
+#[inline(always)]
+pub(crate) fn from_byte_idx_impl<T: ByteChunk>(text: &str, byte_idx: usize) -> usize {
+    let mut i = byte_idx.min(text.len());
+    while !text.is_char_boundary(i) {
+        i -= 1;
+    }
+    let nl_count = count_breaks_impl::<T>(text.as_bytes().get(..i).unwrap_or(&[]));
+    if crate::is_not_crlf_middle(i, text.as_bytes()) {
+        nl_count
+    } else {
+        nl_count - 1
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_byte_idx_impl<T: ByteChunk>(text: &str, line_idx: usize) -> usize {
+    scan_to_impl::<T>(text, line_idx).1
+}
 
-            bytes = &bytes[T::SIZE..];
+/// Finds the start of line `line_idx`, returning how many line breaks
+/// were counted on the way (at most `line_idx`) and its byte index.
+#[inline(always)]
+fn scan_to_impl<T: ByteChunk>(text: &str, line_idx: usize) -> (usize, usize) {
+    let bytes = text.as_bytes();
+
+    // Get `middle` so we can do more efficient chunk-based counting.
+    // We can't use this to get `end`, however, because the start index of
+    // `end` actually depends on the accumulating line counts during the
+    // counting process.
+    let (start, middle, _) = unsafe { bytes.align_to::<T>() };
+
+    // Take care of any unaligned bytes at the beginning.
+    let (mut line_break_count, mut byte_idx) = count_breaks_bytewise(bytes, start.len(), line_idx);
+
+    // Skip the chunks if the line was already found.
+    let middle = if line_break_count < line_idx {
+        middle
+    } else {
+        &[]
+    };
+
+    // Process the chunks `T::UNROLL` at a time.
+    let mut chunk_count = 0;
+    for chunks in middle.chunks_exact(T::UNROLL) {
+        let chunks_bytes = bytes.get(byte_idx..).unwrap_or(&[]);
+        let new_line_break_count =
+            line_break_count + count_breaks_in_chunks(chunks, chunks_bytes).sum_bytes();
+        if new_line_break_count >= line_idx {
+            break;
         }
+        line_break_count = new_line_break_count;
+        byte_idx += T::SIZE * chunks.len();
+        chunk_count += chunks.len();
     }
 
-    // Handle unaligned bytes at the end.
-    let result = count_breaks_up_to(bytes, bytes.len(), line_idx - line_break_count);
-    bytes = &bytes[result.1..];
+    // Process the rest of the chunks.
+    for _ in middle.get(chunk_count..).unwrap_or(&[]).iter() {
+        let chunk_bytes = bytes.get(byte_idx..).unwrap_or(&[]);
+        // Unsafe because the called function depends on correct alignment.
+        let new_line_break_count = line_break_count
+            + unsafe { count_breaks_in_chunk_from_ptr::<T>(chunk_bytes) }.sum_bytes();
+        if new_line_break_count >= line_idx {
+            break;
+        }
+        line_break_count = new_line_break_count;
+        byte_idx += T::SIZE;
+    }
+
+    // Take care of any unaligned bytes at the end.
+    let rest = bytes.get(byte_idx..).unwrap_or(&[]);
+    let result = count_breaks_bytewise(rest, rest.len(), line_idx - line_break_count);
+    line_break_count += result.0;
+    byte_idx += result.1;
 
     // Finish up
-    let mut byte_idx = text.len() - bytes.len();
     while !text.is_char_boundary(byte_idx) {
         byte_idx += 1;
     }
-    byte_idx
+    (line_break_count, byte_idx)
+}
+
+/// Returns the byte range and kind of the line break starting at `i`,
+/// which must be the start of a line break.
+#[inline(always)]
+fn break_at(text: &str, i: usize) -> (Range<usize>, LineBreak) {
+    let bytes = text.as_bytes();
+    let line_break = match bytes.get(i) {
+        Some(0x0A) => LineBreak::Lf,
+        Some(0x0B) => LineBreak::Vt,
+        Some(0x0C) => LineBreak::Ff,
+        Some(0x0D) if bytes.get(i + 1) == Some(&0x0A) => LineBreak::CrLf,
+        Some(0x0D) => LineBreak::Cr,
+        Some(0xC2) => LineBreak::Nel,
+        _ if bytes.get(i + 2) == Some(&0xA8) => LineBreak::Ls,
+        _ => LineBreak::Ps,
+    };
+    (i..(i + line_break.as_str().len()), line_break)
+}
+
+/// Finds the start of the last line break that starts before `end`.
+#[inline(always)]
+fn last_break_impl<T: ByteChunk>(text: &str, end: usize) -> Option<usize> {
+    let bytes = text.as_bytes().get(..end).unwrap_or(&[]);
+    let (start, middle, tail) = unsafe { bytes.align_to::<T>() };
+    let is_start = |&i: &usize| is_break_start(text, i);
+
+    // Take care of any unaligned bytes at the end.
+    let mut byte_idx = bytes.len() - tail.len();
+    if let Some(i) = (byte_idx..bytes.len()).rev().find(is_start) {
+        return Some(i);
+    }
+
+    // Skip chunks without any bytes that can start a line break, and
+    // check the rest a byte at a time, as in `first_break_impl()`.
+    for chunk in middle.iter().rev() {
+        byte_idx -= T::SIZE;
+        let flags = chunk
+            .bytes_between_127(0x09, 0x0E)
+            .add(chunk.cmp_eq_byte(0xC2))
+            .add(chunk.cmp_eq_byte(0xE2));
+        if !flags.is_zero() {
+            let chunk_range = byte_idx..(byte_idx + T::SIZE);
+            if let Some(i) = chunk_range.rev().find(is_start) {
+                return Some(i);
+            }
+        }
+    }
+
+    // Take care of any unaligned bytes at the beginning.
+    (0..start.len()).rev().find(is_start)
+}
+
+/// Flags the bytes of a chunk that could start a line break.
+#[inline(always)]
+fn break_start_flags<T: ByteChunk>(chunk: &T) -> T {
+    chunk
+        .bytes_between_127(0x09, 0x0E)
+        .add(chunk.cmp_eq_byte(0xC2))
+        .add(chunk.cmp_eq_byte(0xE2))
+}
+
+#[inline(always)]
+fn first_break_impl<T: ByteChunk>(text: &str) -> Option<usize> {
+    let (start, middle, _) = unsafe { text.as_bytes().align_to::<T>() };
+
+    // Take care of any unaligned bytes at the beginning.
+    if let Some(i) = (0..start.len()).find(|&i| is_break_start(text, i)) {
+        return Some(i);
+    }
+
+    // Skip chunks without any bytes that can start a line break, and
+    // check the rest a byte at a time.  A line break can't start in the
+    // middle of a CRLF here, because the CR would have been found first.
+    let mut byte_idx = start.len();
+    for chunk in middle.iter() {
+        if !break_start_flags(chunk).is_zero() {
+            let chunk_range = byte_idx..(byte_idx + T::SIZE);
+            if let Some(i) = chunk_range.into_iter().find(|&i| is_break_start(text, i)) {
+                return Some(i);
+            }
+        }
+        byte_idx += T::SIZE;
+    }
+
+    // Take care of any unaligned bytes at the end.
+    (byte_idx..text.len()).find(|&i| is_break_start(text, i))
 }
 
 /// Counts the line breaks in a utf8 encoded string.
@@ -114,38 +966,52 @@ fn to_byte_idx_impl<T: ByteChunk>(text: &str, line_idx: usize) -> usize {
 /// - u{2028}        (Line Separator)
 /// - u{2029}        (Paragraph Separator)
 #[inline(always)]
-fn count_breaks_impl<T: ByteChunk>(text: &[u8]) -> usize {
-    let mut bytes = text;
-    let mut count = 0;
+pub(crate) fn count_breaks_impl<T: ByteChunk>(text: &[u8]) -> usize {
+    let (start, middle, _) = unsafe { text.align_to::<T>() };
 
-    // Handle unaligned bytes at the start.
-    let aligned_idx = alignment_diff::<T>(bytes);
-    if aligned_idx > 0 {
-        let result = count_breaks_up_to(bytes, aligned_idx, bytes.len());
-        count += result.0;
-        bytes = &bytes[result.1..];
+    // Take care of any unaligned bytes at the beginning.
+    let mut count = count_breaks_bytewise(text, start.len(), text.len()).0;
+    let mut byte_idx = start.len();
+
+    // Process the chunks `T::UNROLL` at a time.
+    for chunks in middle.chunks(T::UNROLL) {
+        let chunks_bytes = text.get(byte_idx..).unwrap_or(&[]);
+        count += count_breaks_in_chunks(chunks, chunks_bytes).sum_bytes();
+        byte_idx += T::SIZE * chunks.len();
     }
 
-    // Count line breaks in big chunks.
-    let mut i = 0;
+    // Take care of any unaligned bytes at the end.
+    let rest = text.get(byte_idx..).unwrap_or(&[]);
+    count + count_breaks_bytewise(rest, rest.len(), rest.len()).0
+}
+
+/// Used internally in the line-break counting functions.
+///
+/// Counts the line breaks in `chunks` (at most `T::MAX_ACC` of them),
+/// whose bytes are the start of `bytes`, per byte.
+#[inline(always)]
+fn count_breaks_in_chunks<T: ByteChunk>(chunks: &[T], bytes: &[u8]) -> T {
+    // Most text only has single-byte line breaks other than CR, which can
+    // be counted directly.  Otherwise, fall back to the full per-chunk
+    // count.
     let mut acc = T::zero();
-    while bytes.len() >= T::SIZE {
-        // Unsafe because the called function depends on correct alignment.
-        acc = acc.add(unsafe { count_breaks_in_chunk_from_ptr::<T>(bytes) });
-        i += 1;
-        if i == T::MAX_ACC {
-            i = 0;
-            count += acc.sum_bytes();
-            acc = T::zero();
+    let mut other_flags = T::zero();
+    for chunk in chunks.iter() {
+        acc = acc.add(chunk.bytes_between_127(0x09, 0x0E));
+        other_flags = other_flags
+            .bitor(chunk.cmp_eq_byte(0x0D))
+            .bitor(chunk.cmp_eq_byte(0xC2))
+            .bitor(chunk.cmp_eq_byte(0xE2));
+    }
+    if !other_flags.is_zero() {
+        acc = T::zero();
+        for i in 0..chunks.len() {
+            let chunk_bytes = bytes.get((i * T::SIZE)..).unwrap_or(&[]);
+            // Unsafe because the called function depends on correct alignment.
+            acc = acc.add(unsafe { count_breaks_in_chunk_from_ptr::<T>(chunk_bytes) });
         }
-        bytes = &bytes[T::SIZE..];
     }
-    count += acc.sum_bytes();
-
-    // Handle unaligned bytes at the end.
-    count += count_breaks_up_to(bytes, bytes.len(), bytes.len()).0;
-
-    count
+    acc
 }
 
 /// Used internally in the line-break counting functions.
@@ -154,9 +1020,10 @@ fn count_breaks_impl<T: ByteChunk>(text: &[u8]) -> usize {
 /// line breaks, and returns the counted lines and how many bytes were processed.
 #[inline(always)]
 #[allow(clippy::if_same_then_else)]
-fn count_breaks_up_to(bytes: &[u8], max_bytes: usize, max_breaks: usize) -> (usize, usize) {
+fn count_breaks_bytewise(bytes: &[u8], max_bytes: usize, max_breaks: usize) -> (usize, usize) {
     let mut ptr = 0;
     let mut count = 0;
+    let max_bytes = max_bytes.min(bytes.len());
     while ptr < max_bytes && count < max_breaks {
         let byte = bytes[ptr];
 
@@ -295,6 +1162,16 @@ mod tests {
         assert_eq!(8, count_breaks(text));
     }
 
+    #[test]
+    fn count_lines_01() {
+        assert_eq!(1, count_lines("", true));
+        assert_eq!(0, count_lines("", false));
+        assert_eq!(1, count_lines("a", false));
+        assert_eq!(3, count_lines("a\u{2028}b\r\n", true));
+        assert_eq!(2, count_lines("a\u{2028}b\r\n", false));
+        assert_eq!(3, count_lines("a\u{2028}b\r\nc", false));
+    }
+
     #[test]
     fn from_byte_idx_01() {
         let text = "Here\nare\nsome\nwords";
@@ -366,6 +1243,18 @@ mod tests {
         }
     }
 
+    #[test]
+    fn from_byte_idx_snapped_01() {
+        let text = "a\r\nb\u{2028}c";
+        assert_eq!((0, 0), from_byte_idx_snapped(text, 0));
+        assert_eq!((0, 2), from_byte_idx_snapped(text, 2));
+        assert_eq!((1, 0), from_byte_idx_snapped(text, 3));
+        assert_eq!((1, 2), from_byte_idx_snapped(text, 5));
+        assert_eq!((2, 0), from_byte_idx_snapped(text, 7));
+        assert_eq!((2, 1), from_byte_idx_snapped(text, 8));
+        assert_eq!((2, 3), from_byte_idx_snapped(text, 10));
+    }
+
     #[test]
     fn to_byte_idx_01() {
         let text = "Here\r\nare\r\nsome\r\nwords";
@@ -399,6 +1288,519 @@ mod tests {
         assert_eq!(124, to_byte_idx(TEXT_LINES, 6));
     }
 
+    #[test]
+    fn split_at_01() {
+        let text = "a\r\nb\u{2028}c";
+        assert_eq!(("", text), split_at(text, 0));
+        assert_eq!(("a\r\n", "b\u{2028}c"), split_at(text, 1));
+        assert_eq!(("a\r\nb\u{2028}", "c"), split_at(text, 2));
+        assert_eq!((text, ""), split_at(text, 3));
+    }
+
+    #[test]
+    fn is_break_start_01() {
+        let text = "a\r\nb\rc\u{000B}\u{0085}\u{2028}\u{2029}\n";
+        let starts = [1, 4, 6, 7, 9, 12, 15];
+        for i in 0..(text.len() + 2) {
+            assert_eq!(starts.contains(&i), is_break_start(text, i), "{}", i);
+        }
+    }
+
+    #[test]
+    fn from_other_scheme_idx_01() {
+        // Line starts: 0, 4, 6, 9, 11 in `lines`, 0, 6, 9, 11 in
+        // `lines_crlf`, and 0, 9, 11 in `lines_lf`.
+        let text = "a\u{2028}b\rc\r\nd\ne";
+        for (i, e) in [0, 2, 3, 4, 4].iter().enumerate() {
+            assert_eq!(*e, from_lines_crlf_idx(text, i));
+        }
+        for (i, e) in [0, 3, 4, 4].iter().enumerate() {
+            assert_eq!(*e, from_lines_lf_idx(text, i));
+        }
+    }
+
+    #[test]
+    fn len_stats_01() {
+        let stats = len_stats("", true);
+        assert_eq!(
+            (1, 0, 0, 0),
+            (stats.count, stats.min, stats.max, stats.total)
+        );
+        assert_eq!(crate::LineLenStats::default(), len_stats("", false));
+        assert_eq!(0.0, len_stats("", false).mean());
+
+        let text = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r";
+        for final_empty_line in [true, false] {
+            let stats = len_stats(text, final_empty_line);
+            assert_eq!(count_lines(text, final_empty_line), stats.count);
+            assert_eq!(text.len(), stats.total);
+            let lens = (0..stats.count).map(|i| to_byte_idx(text, i + 1) - to_byte_idx(text, i));
+            assert_eq!(lens.clone().min().unwrap(), stats.min);
+            assert_eq!(lens.max().unwrap(), stats.max);
+            assert_eq!(text.len() as f64 / stats.count as f64, stats.mean());
+        }
+    }
+
+    #[test]
+    fn max_line_len_01() {
+        assert_eq!((0, 0), max_line_len(""));
+        assert_eq!((0, 0), max_line_len("\n\n"));
+        assert_eq!((3, 0), max_line_len("foo"));
+        assert_eq!(
+            (6, 2),
+            max_line_len("foo\r\nbar\u{2028}foobar\u{0085}baz\u{000B}qux")
+        );
+        assert_eq!((3, 0), max_line_len("foo\u{2029}bar\n"));
+        assert_eq!((15, 1), max_line_len("a\nこんにちは\r\n"));
+    }
+
+    #[test]
+    fn blank_lines_01() {
+        assert_eq!(1, count_blank_lines("", true));
+        assert_eq!(0, count_blank_lines("", false));
+        assert_eq!(0, count_blank_lines("a", true));
+
+        let text = "foo\n\n  \t\r\nbar\n \n\n";
+        assert!(blank_lines(text, true).eq([1, 2, 4, 5, 6]));
+        assert!(blank_lines(text, false).eq([1, 2, 4, 5]));
+        assert_eq!(5, count_blank_lines(text, true));
+        assert_eq!(4, count_blank_lines(text, false));
+
+        let text = "foo\u{2028}\u{2029} \rbar\u{0085}";
+        assert!(blank_lines(text, true).eq([1, 2, 4]));
+    }
+
+    #[test]
+    fn count_nonblank_01() {
+        assert_eq!(0, count_nonblank(""));
+        assert_eq!(0, count_nonblank(" \t\n\n"));
+        assert_eq!(1, count_nonblank("a"));
+        let text = "foo\n\n  \t\r\nbar\u{2028} \u{0085}baz\u{000C}\n";
+        assert_eq!(3, count_nonblank(text));
+        assert_eq!(
+            count_lines(text, false) - count_blank_lines(text, false),
+            count_nonblank(text)
+        );
+    }
+
+    #[test]
+    fn long_lines_01() {
+        assert_eq!(None, long_lines("", 0).next());
+        assert!(long_lines("a", 0).eq([0]));
+
+        let text = "foo\nbarbaz\r\nこんにちは\n\nabcd";
+        assert!(long_lines(text, 3).eq([1, 2, 4]));
+        assert!(long_lines(text, 6).eq([2]));
+        assert_eq!(None, long_lines(text, 15).next());
+        assert!(long_lines_chars(text, 3).eq([1, 2, 4]));
+        assert!(long_lines_chars(text, 4).eq([1, 2]));
+        assert!(long_lines_chars(text, 5).eq([1]));
+        assert_eq!(None, long_lines_chars(text, 6).next());
+    }
+
+    #[test]
+    fn trailing_whitespace_01() {
+        assert!(trailing_whitespace("", true).eq([None]));
+        assert!(trailing_whitespace("", false).eq([]));
+        assert!(trailing_whitespace(" \t", false).eq([Some(0)]));
+
+        let text = "foo \nbar\n  \n\nbaz\t \n";
+        assert!(trailing_whitespace(text, true).eq([Some(3), None, Some(9), None, Some(16), None]));
+        assert!(trailing_whitespace(text, false).eq([Some(3), None, Some(9), None, Some(16)]));
+        for (line_idx, ws_start) in trailing_whitespace(text, false).enumerate() {
+            let range = line_content_range(text, line_idx, false);
+            let trimmed = text[range.clone()].trim_end_matches(|c: char| c.is_ascii_whitespace());
+            let expected = range.start + trimmed.len();
+            assert_eq!((expected < range.end).then_some(expected), ws_start);
+        }
+    }
+
+    #[test]
+    fn utf16_lens_01() {
+        let text = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r";
+        let mut line_count = 0;
+        let mut utf16_idx = 0;
+        for len in utf16_lens(text) {
+            let start = crate::utf16::to_byte_idx(text, utf16_idx);
+            assert_eq!(to_byte_idx(text, line_count), start);
+            utf16_idx += len;
+            line_count += 1;
+        }
+        assert_eq!(count_breaks(text) + 1, line_count);
+        assert_eq!(crate::utf16::count(text), utf16_idx);
+
+        assert!(utf16_lens("").eq([0]));
+        assert!(utf16_lens("a🐸").eq([3]));
+        assert!(utf16_lens("a\rb\r\n").eq([2, 3, 0]));
+    }
+
+    #[test]
+    fn start_offsets_01() {
+        assert!(start_offsets("").eq([0]));
+        assert!(start_offsets("foo").eq([0]));
+        assert!(start_offsets("\n").eq([0, 1]));
+
+        let text = "foo\r\nbar\u{2028}\u{2029}baz\rqux\u{000B}\n";
+        assert!(start_offsets(text).eq([0, 5, 11, 14, 18, 22, 23]));
+        for (line_idx, byte_idx) in start_offsets(text).enumerate() {
+            assert_eq!(to_byte_idx(text, line_idx), byte_idx);
+        }
+        assert_eq!(count_breaks(text) + 1, start_offsets(text).count());
+    }
+
+    #[test]
+    fn iter_01() {
+        assert_eq!(None, iter("", false).next());
+        assert_eq!(None, iter("", true).next());
+        assert!(iter("\n", false).eq([""]));
+
+        let text = "foo\r\nbar\u{2028}\u{2029}baz\rqux\u{000B}\n";
+        assert!(iter(text, false).eq(["foo", "bar", "", "baz", "qux", ""]));
+        assert!(iter(text, true).eq([
+            "foo\r\n",
+            "bar\u{2028}",
+            "\u{2029}",
+            "baz\r",
+            "qux\u{000B}",
+            "\n",
+        ]));
+        assert!(iter("foo\u{0085}bar", true).eq(["foo\u{0085}", "bar"]));
+        assert_eq!(count_lines(text, false), iter(text, false).count());
+        assert_eq!(text.len(), iter(text, true).map(str::len).sum::<usize>());
+    }
+
+    #[test]
+    fn byte_to_line_col_01() {
+        use crate::ColumnUnit::*;
+        let text = "foo\r\nb🐸r\u{2028}こんにちは";
+        assert_eq!((0, 0), byte_to_line_col(text, 0, Bytes));
+        assert_eq!((0, 3), byte_to_line_col(text, 3, Chars));
+        assert_eq!((0, 4), byte_to_line_col(text, 4, Utf16));
+        assert_eq!((1, 0), byte_to_line_col(text, 5, Utf16));
+        assert_eq!((1, 1), byte_to_line_col(text, 8, Utf16));
+        assert_eq!((1, 5), byte_to_line_col(text, 10, Bytes));
+        assert_eq!((1, 2), byte_to_line_col(text, 10, Chars));
+        assert_eq!((1, 3), byte_to_line_col(text, 10, Utf16));
+        assert_eq!((2, 0), byte_to_line_col(text, 14, Chars));
+        assert_eq!((2, 2), byte_to_line_col(text, 21, Chars));
+        assert_eq!((2, 5), byte_to_line_col(text, 100, Utf16));
+        assert_eq!((2, 15), byte_to_line_col(text, 100, Bytes));
+        assert_eq!((0, 0), byte_to_line_col("", 0, Chars));
+    }
+
+    #[test]
+    fn line_col_to_byte_01() {
+        use crate::ColumnUnit::*;
+        let text = "foo\r\nb🐸r\u{2028}こんにちは";
+        assert_eq!(0, line_col_to_byte(text, 0, 0, Bytes));
+        assert_eq!(3, line_col_to_byte(text, 0, 4, Chars));
+        assert_eq!(3, line_col_to_byte(text, 0, 100, Utf16));
+        assert_eq!(6, line_col_to_byte(text, 1, 1, Chars));
+        assert_eq!(6, line_col_to_byte(text, 1, 2, Utf16));
+        assert_eq!(10, line_col_to_byte(text, 1, 3, Utf16));
+        assert_eq!(6, line_col_to_byte(text, 1, 3, Bytes));
+        assert_eq!(11, line_col_to_byte(text, 1, 100, Bytes));
+        assert_eq!(20, line_col_to_byte(text, 2, 2, Chars));
+        assert_eq!(29, line_col_to_byte(text, 9, 0, Chars));
+        assert_eq!(0, line_col_to_byte("", 0, 1, Chars));
+        for byte_idx in [0, 3, 5, 6, 10, 11, 14, 20, 29] {
+            for unit in [Bytes, Chars, Utf16] {
+                let (line_idx, col) = byte_to_line_col(text, byte_idx, unit);
+                assert_eq!(byte_idx, line_col_to_byte(text, line_idx, col, unit));
+            }
+        }
+    }
+
+    #[test]
+    fn trailing_break_01() {
+        assert_eq!(None, trailing_break(""));
+        assert_eq!(None, trailing_break("foo"));
+        assert_eq!(None, trailing_break("foo\nbar"));
+        assert_eq!(Some((LineBreak::Lf, 1)), trailing_break("\n"));
+        assert_eq!(Some((LineBreak::CrLf, 2)), trailing_break("foo\r\n"));
+        assert_eq!(Some((LineBreak::Cr, 1)), trailing_break("foo\n\r"));
+        assert_eq!(Some((LineBreak::Vt, 1)), trailing_break("foo\u{000B}"));
+        assert_eq!(Some((LineBreak::Ff, 1)), trailing_break("foo\u{000C}"));
+        assert_eq!(Some((LineBreak::Nel, 2)), trailing_break("foo\u{0085}"));
+        assert_eq!(Some((LineBreak::Ls, 3)), trailing_break("foo\u{2028}"));
+        assert_eq!(Some((LineBreak::Ps, 3)), trailing_break("こ\u{2029}"));
+        assert_eq!(None, trailing_break("\u{2027}"));
+
+        // Consistent with the rest of the module.
+        for text in ["a\n", "a\r\n", "\u{0085}", "a\u{2029}", "ab", "\n\r"] {
+            let ends_with_break =
+                from_byte_idx_snapped(text, text.len()).1 == 0 && !text.is_empty();
+            assert_eq!(ends_with_break, trailing_break(text).is_some());
+            if let Some((line_break, len)) = trailing_break(text) {
+                assert!(text.ends_with(line_break.as_str()));
+                assert!(is_break_start(text, text.len() - len));
+            }
+        }
+    }
+
+    #[test]
+    fn next_prev_break_01() {
+        assert_eq!(None, next_break("", 0));
+        assert_eq!(None, prev_break("", 0));
+        assert_eq!(None, prev_break("\n", 0));
+        assert_eq!(Some((0..1, LineBreak::Lf)), prev_break("\n", 1));
+
+        let text = "foo\r\nbar\u{2028}baz\u{0085}\u{2029}\rqux";
+        assert_eq!(Some((3..5, LineBreak::CrLf)), next_break(text, 0));
+        assert_eq!(Some((3..5, LineBreak::CrLf)), next_break(text, 3));
+        assert_eq!(Some((8..11, LineBreak::Ls)), next_break(text, 4));
+        assert_eq!(Some((14..16, LineBreak::Nel)), next_break(text, 12));
+        assert_eq!(Some((16..19, LineBreak::Ps)), next_break(text, 15));
+        assert_eq!(Some((19..20, LineBreak::Cr)), next_break(text, 17));
+        assert_eq!(None, next_break(text, 20));
+        assert_eq!(None, next_break(text, 100));
+        assert_eq!(None, prev_break(text, 3));
+        assert_eq!(Some((3..5, LineBreak::CrLf)), prev_break(text, 4));
+        assert_eq!(Some((3..5, LineBreak::CrLf)), prev_break(text, 8));
+        assert_eq!(Some((8..11, LineBreak::Ls)), prev_break(text, 9));
+        assert_eq!(Some((19..20, LineBreak::Cr)), prev_break(text, 100));
+
+        // Long enough for the chunked scans, checked against the slow way.
+        let text = "Hello\u{000B}world!\r\nこんにちは\u{000C}\r\u{0085}🐸".repeat(8);
+        let starts = || (0..text.len()).filter(|&i| is_break_start(&text, i));
+        for i in 0..(text.len() + 2) {
+            let next = starts().find(|&s| s >= i).map(|s| break_at(&text, s));
+            let prev = starts().rev().find(|&s| s < i).map(|s| break_at(&text, s));
+            assert_eq!(next, next_break(&text, i));
+            assert_eq!(prev, prev_break(&text, i));
+        }
+    }
+
+    #[test]
+    fn last_line_start_01() {
+        assert_eq!(0, last_line_start(""));
+        assert_eq!(0, last_line_start("foo"));
+        assert_eq!(1, last_line_start("\n"));
+        assert_eq!(5, last_line_start("foo\r\n"));
+        assert_eq!(4, last_line_start("foo\rbar"));
+        assert_eq!(6, last_line_start("foo\u{2028}bar"));
+
+        let text = "Hello\u{000B}world!\r\nこんにちは\u{000C}\r\u{0085}🐸".repeat(8);
+        for end in (0..=text.len()).filter(|&i| text.is_char_boundary(i)) {
+            let text = &text[..end];
+            assert_eq!(to_byte_idx(text, count_breaks(text)), last_line_start(text));
+        }
+    }
+
+    #[test]
+    fn normalized_len_01() {
+        assert_eq!(0, normalized_len("", LineBreak::CrLf));
+        assert_eq!(3, normalized_len("foo", LineBreak::CrLf));
+
+        let text = "foo\r\nbar\nbaz\u{2028}\u{0085}\r";
+        assert_eq!(14, normalized_len(text, LineBreak::Lf));
+        assert_eq!(19, normalized_len(text, LineBreak::CrLf));
+        assert_eq!(24, normalized_len(text, LineBreak::Ps));
+        let lf_normalized = "foo\nbar\nbaz\n\n\n";
+        assert_eq!(lf_normalized.len(), normalized_len(text, LineBreak::Lf));
+        assert_eq!(
+            lf_normalized.len(),
+            normalized_len(lf_normalized, LineBreak::Lf)
+        );
+
+        // Every kind of line break, long enough for the chunked counting.
+        let text = "a\nb\u{000B}c\u{000C}d\re\r\nf\u{0085}g\u{2028}h\u{2029}こ\n\r".repeat(20);
+        let mut break_bytes = 0;
+        let mut byte_idx = 0;
+        while let Some((range, _)) = next_break(&text, byte_idx) {
+            break_bytes += range.len();
+            byte_idx = range.end;
+        }
+        let break_count = count_breaks(&text);
+        assert_eq!(
+            text.len() - break_bytes + (2 * break_count),
+            normalized_len(&text, LineBreak::CrLf)
+        );
+    }
+
+    #[test]
+    fn break_indices_01() {
+        assert_eq!(None, break_indices("").next());
+        assert_eq!(None, break_indices("foo").next_back());
+
+        let text = "foo\r\nbar\u{2028}\u{000B}baz\r";
+        let expected = [
+            (3, LineBreak::CrLf),
+            (8, LineBreak::Ls),
+            (11, LineBreak::Vt),
+            (15, LineBreak::Cr),
+        ];
+        assert!(break_indices(text).eq(expected));
+        assert!(break_indices(text).rev().eq(expected.into_iter().rev()));
+        let mut iter = break_indices(text);
+        assert_eq!(Some(expected[0]), iter.next());
+        assert_eq!(Some(expected[3]), iter.next_back());
+        assert_eq!(Some(expected[2]), iter.next_back());
+        assert_eq!(Some(expected[1]), iter.next());
+        assert_eq!(None, iter.next());
+        assert_eq!(None, iter.next_back());
+
+        let text = text.repeat(10);
+        assert_eq!(count_breaks(&text), break_indices(&text).count());
+        for (line_idx, (byte_idx, line_break)) in break_indices(&text).enumerate() {
+            assert!(text[byte_idx..].starts_with(line_break.as_str()));
+            let line_end = byte_idx + line_break.as_str().len();
+            assert_eq!(to_byte_idx(&text, line_idx + 1), line_end);
+        }
+    }
+
+    #[test]
+    fn count_breaks_up_to_01() {
+        assert_eq!((0, 0), count_breaks_up_to("", 10, 10));
+        assert_eq!((0, 0), count_breaks_up_to("foo\n", 10, 0));
+        assert_eq!((1, 4), count_breaks_up_to("foo\nbar", 10, 1));
+        assert_eq!((1, 7), count_breaks_up_to("foo\nbar", 7, 10));
+        assert_eq!((0, 3), count_breaks_up_to("foo\r\nbar", 4, 10));
+        assert_eq!((1, 5), count_breaks_up_to("foo\r\nbar", 5, 10));
+        assert_eq!((1, 5), count_breaks_up_to("foo\r\nbar", 100, 1));
+        assert_eq!((0, 3), count_breaks_up_to("foo\u{2028}", 5, 10));
+        assert_eq!((1, 6), count_breaks_up_to("foo\u{2028}", 6, 10));
+
+        // Incremental counting, in pieces.
+        let text = "Hello\u{000B}world!\r\nこんにちは\u{000C}\r\u{0085}🐸\n".repeat(8);
+        for (max_bytes, max_breaks) in [(4, 100), (7, 100), (100, 1), (100, 3), (33, 2)] {
+            let (mut count, mut byte_idx) = (0, 0);
+            while byte_idx < text.len() {
+                let rest = &text[byte_idx..];
+                let (n, len) = count_breaks_up_to(rest, max_bytes, max_breaks);
+                assert!(n <= max_breaks && len > 0 && len <= max_bytes);
+                assert_eq!(n, count_breaks(&rest[..len]));
+                count += n;
+                byte_idx += len;
+                assert_eq!(from_byte_idx(&text, byte_idx), count);
+            }
+            assert_eq!(count_breaks(&text), count);
+        }
+    }
+
+    #[test]
+    fn line_span_01() {
+        let text = "  foo\r\nbar \r \u{2028}\tbaz\n";
+        assert_eq!(0..7, line_span(text, 0));
+        assert_eq!(7..12, line_span(text, 1));
+        assert_eq!(12..16, line_span(text, 2));
+        assert_eq!(16..21, line_span(text, 3));
+        assert_eq!(21..21, line_span(text, 4));
+        assert_eq!(21..21, line_span(text, 9));
+        assert_eq!(0..0, line_span("", 0));
+        assert_eq!(0..3, line_span("foo", 0));
+    }
+
+    #[test]
+    fn line_at_byte_01() {
+        let text = "foo\r\nbar\u{2028}baz";
+        assert_eq!("foo", line_at_byte(text, 0, false));
+        assert_eq!("foo", line_at_byte(text, 4, false));
+        assert_eq!("foo\r\n", line_at_byte(text, 3, true));
+        assert_eq!("bar", line_at_byte(text, 9, false));
+        assert_eq!("bar\u{2028}", line_at_byte(text, 6, true));
+        assert_eq!("baz", line_at_byte(text, 11, false));
+        assert_eq!("baz", line_at_byte(text, 100, true));
+        assert_eq!("", line_at_byte("foo\n", 4, true));
+        assert_eq!("", line_at_byte("", 0, false));
+    }
+
+    #[test]
+    fn line_len_01() {
+        use crate::ColumnUnit::*;
+        let text = "foo\r\nb🐸r\u{2028}こんにちは";
+        assert_eq!(3, line_len(text, 0, Bytes));
+        assert_eq!(3, line_len(text, 0, Chars));
+        assert_eq!(6, line_len(text, 1, Bytes));
+        assert_eq!(3, line_len(text, 1, Chars));
+        assert_eq!(4, line_len(text, 1, Utf16));
+        assert_eq!(15, line_len(text, 2, Bytes));
+        assert_eq!(5, line_len(text, 2, Utf16));
+        assert_eq!(0, line_len(text, 3, Bytes));
+        assert_eq!(0, line_len("", 0, Chars));
+    }
+
+    #[test]
+    fn nth_line_01() {
+        let text = "foo\r\nb🐸r\u{2028}\rこんにちは\n";
+        assert_eq!(Some("foo"), nth_line(text, 0));
+        assert_eq!(Some("b🐸r"), nth_line(text, 1));
+        assert_eq!(Some(""), nth_line(text, 2));
+        assert_eq!(Some("こんにちは"), nth_line(text, 3));
+        assert_eq!(Some(""), nth_line(text, 4));
+        assert_eq!(None, nth_line(text, 5));
+        assert_eq!(Some(""), nth_line("", 0));
+        assert_eq!(None, nth_line("", 1));
+        for i in 0..=count_breaks(TEXT_LINES) {
+            assert_eq!(
+                Some(line_content(TEXT_LINES, i, false)),
+                nth_line(TEXT_LINES, i)
+            );
+        }
+    }
+
+    #[test]
+    fn line_content_range_01() {
+        let text = "  foo\r\nbar \r \u{2028}\tbaz\n";
+        assert_eq!(0..5, line_content_range(text, 0, false));
+        assert_eq!(2..5, line_content_range(text, 0, true));
+        assert_eq!(7..11, line_content_range(text, 1, false));
+        assert_eq!(7..10, line_content_range(text, 1, true));
+        assert_eq!(12..13, line_content_range(text, 2, false));
+        assert_eq!(13..13, line_content_range(text, 2, true));
+        assert_eq!("\tbaz", line_content(text, 3, false));
+        assert_eq!("baz", line_content(text, 3, true));
+        assert_eq!(21..21, line_content_range(text, 4, true));
+        assert_eq!(21..21, line_content_range(text, 9, false));
+        assert_eq!(0..0, line_content_range("", 0, false));
+        assert_eq!(2..2, line_content_range(" \t", 0, true));
+    }
+
+    #[test]
+    fn common_prefix_01() {
+        assert_eq!(0, common_prefix("", ""));
+        assert_eq!(4, common_prefix("foo\nbar", "foo\nbaz"));
+        assert_eq!(0, common_prefix("foo\nbar", "foo"));
+        assert_eq!(6, common_prefix("foo\u{2028}bar", "foo\u{2028}baz"));
+        // A CR followed by an LF in only one of them.
+        assert_eq!(0, common_prefix("foo\r\nbar", "foo\rbar"));
+        assert_eq!(3, common_suffix("foo\nbar", "fo\nbar"));
+        assert_eq!(0, common_suffix("foo\nbar", "foo\nbaz"));
+        assert_eq!(3, common_suffix("a\r\nbar", "a\nbar"));
+        assert_eq!(3, common_suffix("x\r\nbar", "y\u{0085}bar"));
+    }
+
+    #[test]
+    fn first_break_01() {
+        assert_eq!(None, first_break(""));
+        assert!(!has_break(""));
+        assert_eq!(None, first_break("abc\u{00C2}"));
+        assert!(!has_break("abc\u{00C2}"));
+        assert_eq!(Some(3), first_break("abc\u{2029}def"));
+        assert_eq!(Some(3), first_break("abc\u{0085}\ndef"));
+        assert_eq!(Some(1), first_break("a\r\nb"));
+        assert_eq!(Some(3), first_break("aé\u{2028}\n"));
+    }
+
+    #[test]
+    fn first_break_02() {
+        // Long enough to go through the chunk loop, with the line break
+        // at every position.
+        let text =
+            "xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx";
+        assert_eq!(None, first_break(text));
+        for i in 0..text.len() {
+            for br in ["\n", "\r\n", "\u{000B}", "\u{0085}", "\u{2028}"] {
+                let mut buf = [0u8; 96];
+                buf[..text.len()].copy_from_slice(text.as_bytes());
+                buf[i..(i + br.len())].copy_from_slice(br.as_bytes());
+                let text = core::str::from_utf8(&buf[..text.len().max(i + br.len())]).unwrap();
+                assert_eq!(Some(i), first_break(text));
+                assert!(has_break(text));
+            }
+        }
+    }
+
     #[test]
     fn line_byte_round_trip() {
         let text = "\nHere\nare\nsome\nwords\n";
@@ -411,4 +1813,22 @@ mod tests {
         assert_eq!(21, to_byte_idx(text, from_byte_idx(text, 21)));
         assert_eq!(5, from_byte_idx(text, to_byte_idx(text, 5)));
     }
+
+    // Exercises the chunk-based code with 16- and 32-bit words, as used on
+    // 16-bit (AVR, MSP430) and 32-bit targets, regardless of the host.
+    #[test]
+    fn narrow_chunks() {
+        crate::byte_chunk::for_each_narrow_chunk_text(|text| {
+            let bytes = text.as_bytes();
+            let breaks = count_breaks(text);
+            assert_eq!(breaks, count_breaks_impl::<u16>(bytes));
+            assert_eq!(breaks, count_breaks_impl::<u32>(bytes));
+            assert_eq!(first_break(text), first_break_impl::<u16>(text));
+            assert_eq!(first_break(text), first_break_impl::<u32>(text));
+            for i in 0..(breaks + 2) {
+                assert_eq!(to_byte_idx(text, i), to_byte_idx_impl::<u16>(text, i));
+                assert_eq!(to_byte_idx(text, i), to_byte_idx_impl::<u32>(text, i));
+            }
+        });
+    }
 }
@@ -9,14 +9,40 @@
 //! (Note: if you only want to recognize LF and CRLF, without
 //! recognizing CR individually, see the [`lines_lf`](crate::lines_lf) module.)
 
-use crate::byte_chunk::{ByteChunk, Chunk};
+use core::ops::Range;
+
+use crate::byte_chunk::ByteChunk;
+use crate::lines_custom::{convert_line_idx_impl, BreakSet};
 
 /// Counts the line breaks in a string slice.
 ///
 /// Runs in O(N) time.
-#[inline]
+#[inline(never)]
 pub fn count_breaks(text: &str) -> usize {
-    count_breaks_impl::<Chunk>(text.as_bytes())
+    with_chunk!(count_breaks_impl(text.as_bytes()))
+}
+
+/// Counts the lines in a string slice.
+///
+/// If `final_empty_line` is true, this follows the convention of the rest
+/// of this module: a string that ends with a line break has a final empty
+/// line, and an empty string is a single empty line.  So the result is
+/// always `count_breaks(text) + 1`.
+///
+/// If `final_empty_line` is false, this instead follows the convention of
+/// `str::lines()`, but with this module's line breaks: a line break at the
+/// end of the string doesn't start a new line, and an empty string has no
+/// lines.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn count_lines(text: &str, final_empty_line: bool) -> usize {
+    let breaks = count_breaks(text);
+    if final_empty_line || !(text.is_empty() || text.ends_with(['\n', '\r'])) {
+        breaks + 1
+    } else {
+        breaks
+    }
 }
 
 /// Converts from byte-index to line-index in a string slice.
@@ -29,15 +55,65 @@ pub fn count_breaks(text: &str) -> usize {
 /// Any past-the-end index will return the last line index.
 ///
 /// Runs in O(N) time.
-#[inline]
+#[inline(never)]
 pub fn from_byte_idx(text: &str, byte_idx: usize) -> usize {
-    let i = byte_idx.min(text.len());
-    let nl_count = count_breaks_impl::<Chunk>(&text.as_bytes()[..i]);
-    if crate::is_not_crlf_middle(i, text.as_bytes()) {
-        nl_count
-    } else {
-        nl_count - 1
-    }
+    with_chunk!(from_byte_idx_impl(text, byte_idx))
+}
+
+/// Converts from byte-index to line-index in a string slice, also
+/// reporting whether the byte index was at the start of a line.
+///
+/// Returns the same line index as [`from_byte_idx()`], along with how
+/// many bytes `byte_idx` is past the start of that line.  The latter is
+/// zero exactly when `byte_idx` was already the start of a line.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn from_byte_idx_snapped(text: &str, byte_idx: usize) -> (usize, usize) {
+    let line_idx = from_byte_idx(text, byte_idx);
+    (line_idx, byte_idx - to_byte_idx(text, line_idx))
+}
+
+/// Converts from byte-index to a (line-index, column) position in a
+/// string slice.
+///
+/// The line index is the same as from [`from_byte_idx()`], and the column
+/// is measured from the start of that line in the given `unit`.  Byte
+/// indices in the middle of a char refer to the start of that char, and
+/// any past-the-end index will return the position of the end of the
+/// text.
+///
+/// The lines before `byte_idx` are skipped with the same accelerated
+/// scanning as [`from_byte_idx()`], and then only the part of its own line
+/// before it is measured.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn byte_to_line_col(text: &str, byte_idx: usize, unit: crate::ColumnUnit) -> (usize, usize) {
+    let byte_idx = crate::chars::floor_byte_boundary(text, byte_idx);
+    let (line_idx, offset) = from_byte_idx_snapped(text, byte_idx);
+    let before = text.get((byte_idx - offset)..byte_idx).unwrap_or("");
+    (line_idx, unit.count(before))
+}
+
+/// Converts from a (line-index, column) position to byte-index in a
+/// string slice.
+///
+/// The inverse of [`byte_to_line_col()`], with the column measured from
+/// the start of the line in the given `unit`.  A column in the middle of a
+/// char refers to the start of that char.
+///
+/// Columns past the end of a line are clamped to the end of its content,
+/// before its line break, so the result is always on the given line.
+/// Lines past the end of the text refer to the end of the text.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn line_col_to_byte(text: &str, line_idx: usize, col: usize, unit: crate::ColumnUnit) -> usize {
+    let start = to_byte_idx(text, line_idx);
+    let line = crate::split_str_at(text, start).1;
+    let content = crate::split_str_at(line, first_break(line).unwrap_or(line.len())).0;
+    start + unit.to_byte_idx(content, col)
 }
 
 /// Converts from line-index to byte-index in a string slice.
@@ -49,9 +125,447 @@ pub fn from_byte_idx(text: &str, byte_idx: usize) -> usize {
 /// Any past-the-end index will return the one-past-the-end byte index.
 ///
 /// Runs in O(N) time.
-#[inline]
+#[inline(never)]
 pub fn to_byte_idx(text: &str, line_idx: usize) -> usize {
-    to_byte_idx_impl::<Chunk>(text.as_bytes(), line_idx)
+    with_chunk!(to_byte_idx_impl(text.as_bytes(), line_idx))
+}
+
+/// Splits a string slice at the start of a line.
+///
+/// Equivalent to `text.split_at(to_byte_idx(text, line_idx))`: the first
+/// slice contains the lines before `line_idx` (including the line break
+/// ending them), and the second starts with line `line_idx`.  Any
+/// past-the-end index splits at the end of the text.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn split_at(text: &str, line_idx: usize) -> (&str, &str) {
+    crate::split_str_at(text, to_byte_idx(text, line_idx))
+}
+
+/// Returns whether a line break starts at the given byte index.
+///
+/// A CRLF is treated as a single line break starting at the CR, so this
+/// returns false for the LF of a CRLF.
+///
+/// Any past-the-end index returns false.
+///
+/// Runs in O(1) time.
+#[inline]
+pub fn is_break_start(text: &str, byte_idx: usize) -> bool {
+    let bytes = text.as_bytes();
+    match bytes.get(byte_idx) {
+        Some(&LF) => byte_idx == 0 || bytes[byte_idx - 1] != CR,
+        Some(&CR) => true,
+        _ => false,
+    }
+}
+
+/// Converts from a [`lines`](crate::lines) line-index to a
+/// line-index in this module's scheme, for the same text.
+///
+/// A line in `lines` is never split across lines of this module, so this
+/// returns the index of the line that contains it.
+///
+/// Equivalent to `from_byte_idx(text, lines::to_byte_idx(text, line_idx))`,
+/// but counts the line breaks of both schemes in the same scan, which
+/// stops at the start of the line.  Any past-the-end index will return the
+/// last line index.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn from_lines_idx(text: &str, line_idx: usize) -> usize {
+    with_chunk!(convert_line_idx_impl(
+        text.as_bytes(),
+        line_idx,
+        BreakSet::ALL,
+        BreakSet::LF.union(BreakSet::CR)
+    ))
+}
+
+/// Converts from a [`lines_lf`](crate::lines_lf) line-index to a
+/// line-index in this module's scheme, for the same text.
+///
+/// Every line start in `lines_lf` is also a line start in this module, so
+/// this returns the index of the same line start.
+///
+/// Equivalent to `from_byte_idx(text, lines_lf::to_byte_idx(text, line_idx))`,
+/// but counts the line breaks of both schemes in the same scan, which
+/// stops at the start of the line.  Any past-the-end index will return the
+/// last line index.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn from_lines_lf_idx(text: &str, line_idx: usize) -> usize {
+    with_chunk!(convert_line_idx_impl(
+        text.as_bytes(),
+        line_idx,
+        BreakSet::LF,
+        BreakSet::LF.union(BreakSet::CR)
+    ))
+}
+
+/// Returns the byte range of a line, including its line break.
+///
+/// The range starts at `to_byte_idx(text, line_idx)` and ends just after
+/// the line's break, or at the end of the text for the last line, so that
+/// consecutive lines' spans are contiguous.
+///
+/// Any past-the-end index will return an empty range at the end of the
+/// text.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn line_span(text: &str, line_idx: usize) -> Range<usize> {
+    let start = to_byte_idx(text, line_idx);
+    let line = crate::split_str_at(text, start).1;
+    start..(start + to_byte_idx(line, 1))
+}
+
+/// Returns the byte range of the content of a line, excluding its line
+/// break.
+///
+/// If `trim` is true, leading and trailing ascii whitespace (as defined by
+/// `u8::is_ascii_whitespace()`) is excluded as well.  As with
+/// `str::trim()`, a line that is entirely whitespace then gives an empty
+/// range at the end of its content.
+///
+/// Any past-the-end index will return an empty range at the end of the
+/// text.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn line_content_range(text: &str, line_idx: usize, trim: bool) -> Range<usize> {
+    let start = to_byte_idx(text, line_idx);
+    let line = crate::split_str_at(text, start).1;
+    let line = crate::split_str_at(line, first_break(line).unwrap_or(line.len())).0;
+    if !trim {
+        return start..(start + line.len());
+    }
+
+    let bytes = line.as_bytes();
+    let leading = bytes
+        .iter()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(bytes.len());
+    let trailing = bytes[leading..]
+        .iter()
+        .rev()
+        .position(|byte| !byte.is_ascii_whitespace())
+        .unwrap_or(0);
+    (start + leading)..(start + bytes.len() - trailing)
+}
+
+/// Returns the content of a line, excluding its line break.
+///
+/// Equivalent to `&text[line_content_range(text, line_idx, trim)]`.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn line_content(text: &str, line_idx: usize, trim: bool) -> &str {
+    text.get(line_content_range(text, line_idx, trim))
+        .unwrap_or("")
+}
+
+/// Returns the length of a line, excluding its line break, in the given
+/// unit.
+///
+/// Equivalent to measuring [`line_content()`] (untrimmed) with e.g.
+/// [`chars::count()`](crate::chars::count) or
+/// [`utf16::count()`](crate::utf16::count).
+///
+/// Any past-the-end index will return zero.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn line_len(text: &str, line_idx: usize, unit: crate::ColumnUnit) -> usize {
+    unit.count(line_content(text, line_idx, false))
+}
+
+/// Returns the line containing the given byte.
+///
+/// If `include_break` is true the line's break is included, as in
+/// [`line_span()`], and otherwise it's excluded, as in
+/// [`line_content()`].  A byte of a line break is part of the line that
+/// the break ends.
+///
+/// Any past-the-end index will return the last line.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn line_at_byte(text: &str, byte_idx: usize, include_break: bool) -> &str {
+    let start = byte_idx - from_byte_idx_snapped(text, byte_idx).1;
+    let line = crate::split_str_at(text, start).1;
+    let end = if include_break {
+        to_byte_idx(line, 1)
+    } else {
+        first_break(line).unwrap_or(line.len())
+    };
+    crate::split_str_at(line, end).0
+}
+
+/// Returns the length in bytes of the longest common prefix of two string
+/// slices that ends at a line start in both.
+///
+/// Like [`chars::common_prefix()`](crate::chars::common_prefix), but
+/// snapped back to the start of a line, so that the prefix consists of
+/// whole lines.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn common_prefix(a: &str, b: &str) -> usize {
+    let len = crate::chars::common_prefix(a, b);
+    let line_start = |text| to_byte_idx(text, from_byte_idx(text, len));
+    line_start(a).min(line_start(b))
+}
+
+/// Returns the length in bytes of the longest common suffix of two string
+/// slices that starts at a line start in both.
+///
+/// Like [`chars::common_suffix()`](crate::chars::common_suffix), but
+/// snapped forward to the start of a line, so that the suffix consists of
+/// whole lines.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn common_suffix(a: &str, b: &str) -> usize {
+    let len = crate::chars::common_suffix(a, b);
+    let suffix_len = |text: &str| {
+        let start = text.len() - len;
+        let line_idx = from_byte_idx(text, start);
+        let mut line_start = to_byte_idx(text, line_idx);
+        if line_start < start {
+            line_start = to_byte_idx(text, line_idx + 1);
+        }
+        text.len() - line_start
+    };
+    suffix_len(a).min(suffix_len(b))
+}
+
+/// Counts the CRLF pairs in a string slice.
+///
+/// Normalizing the line breaks of the text from CRLF to LF (without
+/// touching lone CRs) removes one byte per CRLF, so the normalized text is
+/// `text.len() - count_crlf(text)` bytes long.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn count_crlf(text: &str) -> usize {
+    with_chunk!(count_crlf_impl(text.as_bytes()))
+}
+
+/// Converts a byte index in a string slice to the corresponding byte index
+/// after normalizing its CRLF line breaks to LF.
+///
+/// This lets stored positions be adjusted for the normalization without
+/// having to produce the normalized text first.  A byte index in the
+/// middle of a CRLF (i.e. at its LF) maps to the position of the LF in the
+/// normalized text, the same as the start of the CRLF.
+///
+/// Any past-the-end index will return the one-past-the-end index of the
+/// normalized text.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn lf_normalized_byte_idx(text: &str, byte_idx: usize) -> usize {
+    let i = byte_idx.min(text.len());
+    let crlf_middle = !crate::is_not_crlf_middle(i, text.as_bytes());
+    i - with_chunk!(count_crlf_impl(&text.as_bytes()[..i])) - crlf_middle as usize
+}
+
+/// Returns whether there are any line breaks in a string slice.
+///
+/// Equivalent to `count_breaks(text) > 0`, but stops at the first line
+/// break.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn has_break(text: &str) -> bool {
+    first_break(text).is_some()
+}
+
+/// Returns the byte index of the start of the first line break in a
+/// string slice, if any.
+///
+/// A CRLF starts at its CR.  Stops scanning at the first line break.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn first_break(text: &str) -> Option<usize> {
+    with_chunk!(first_break_impl(text))
+}
+
+/// Computes statistics about the byte lengths of the lines in a string
+/// slice, in a single pass.
+///
+/// Each line's length includes its line break.  `final_empty_line`
+/// determines whether a final empty line (after a line break at the end of
+/// the string, or an empty string) is counted, as in [`count_lines()`].
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn len_stats(text: &str, final_empty_line: bool) -> crate::LineLenStats {
+    crate::line_len_stats(text, final_empty_line, first_break, to_byte_idx)
+}
+
+/// Finds the longest line in a string slice.
+///
+/// Returns the line's length in bytes, not including its line break, and
+/// its line index.  If several lines are equally long, the first of them
+/// is returned.  An empty string returns `(0, 0)`.
+///
+/// This is useful for e.g. sizing horizontal scrollbars.  Use
+/// [`chars::count()`](crate::chars::count) or
+/// [`utf16::count()`](crate::utf16::count) on the line for its length in
+/// other units.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn max_line_len(text: &str) -> (usize, usize) {
+    crate::LineContents::new(text, true, first_break, to_byte_idx).max_len()
+}
+
+/// Counts the blank lines in a string slice.
+///
+/// A blank line is one that is empty or contains only ascii whitespace (as
+/// defined by `u8::is_ascii_whitespace()`), not counting its line break.
+/// `final_empty_line` determines whether a final empty line (after a line
+/// break at the end of the string, or an empty string) is included, as in
+/// [`count_lines()`].
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn count_blank_lines(text: &str, final_empty_line: bool) -> usize {
+    blank_lines(text, final_empty_line).count()
+}
+
+/// Counts the non-blank lines in a string slice.
+///
+/// These are the lines that contain at least one byte that isn't ascii
+/// whitespace, i.e. the lines that aren't blank as defined in
+/// [`count_blank_lines()`].  So the result is always
+/// `count_lines(text, false) - count_blank_lines(text, false)`, but is
+/// computed in a single pass.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn count_nonblank(text: &str) -> usize {
+    crate::LineContents::new(text, false, first_break, to_byte_idx).count_nonblank()
+}
+
+/// Returns an iterator over the line indices of the blank lines in a
+/// string slice.
+///
+/// See [`count_blank_lines()`] for what counts as a blank line.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn blank_lines(text: &str, final_empty_line: bool) -> crate::BlankLines<'_> {
+    crate::LineContents::new(text, final_empty_line, first_break, to_byte_idx).blank_lines()
+}
+
+/// Returns an iterator over the line indices of the lines in a string
+/// slice that are longer than `max_bytes` bytes.
+///
+/// Line lengths don't include line breaks.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn long_lines(text: &str, max_bytes: usize) -> crate::LongLines<'_> {
+    crate::LineContents::new(text, false, first_break, to_byte_idx).long_lines(max_bytes, false)
+}
+
+/// Returns an iterator over the line indices of the lines in a string
+/// slice that are longer than `max_chars` chars.
+///
+/// Line lengths don't include line breaks.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn long_lines_chars(text: &str, max_chars: usize) -> crate::LongLines<'_> {
+    crate::LineContents::new(text, false, first_break, to_byte_idx).long_lines(max_chars, true)
+}
+
+/// Returns an iterator over where the trailing ascii whitespace of each
+/// line in a string slice begins.
+///
+/// Yields one item per line: the byte index of the start of the line's
+/// trailing whitespace (as defined by `u8::is_ascii_whitespace()`), or
+/// `None` if the line doesn't end in whitespace.  The line break itself
+/// isn't counted as whitespace.  The final empty line is included if
+/// `final_empty_line` is true, as in [`count_lines()`], and always yields
+/// `None`.
+///
+/// This is useful for e.g. trimming trailing whitespace on save: the
+/// ranges to remove are from each yielded index to the end of its line's
+/// content.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn trailing_whitespace(text: &str, final_empty_line: bool) -> crate::TrailingWhitespace<'_> {
+    crate::LineContents::new(text, final_empty_line, first_break, to_byte_idx).trailing_whitespace()
+}
+
+/// Returns an iterator over the lines of a string slice.
+///
+/// Like `str::lines()`, a line break at the end of the string doesn't
+/// start a final empty line, and an empty string has no lines, so there
+/// are `count_lines(text, false)` items.  If `include_break` is true each
+/// item includes its line break, and the items together make up the whole
+/// string.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn iter(text: &str, include_break: bool) -> crate::Lines<'_> {
+    crate::LineContents::new(text, false, first_break, to_byte_idx).lines(include_break)
+}
+
+/// Returns an iterator over the byte indices of the line starts in a
+/// string slice.
+///
+/// Yields `to_byte_idx(text, line_idx)` for each line, in order, but in
+/// O(N) time overall rather than O(N) per line.  As elsewhere in this
+/// module, a string that ends with a line break (or is empty) has a final
+/// empty line, so there are always `count_breaks(text) + 1` items.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn start_offsets(text: &str) -> crate::LineStarts<'_> {
+    crate::LineContents::new(text, true, first_break, to_byte_idx).line_starts()
+}
+
+/// Returns an iterator over the utf16 lengths of the lines in a string
+/// slice.
+///
+/// Each line's length includes its line break, and a string that ends
+/// with a line break (or is empty) has a final empty line, consistent with
+/// the rest of this module.  So the lengths always sum to
+/// `utf16::count(text)`, and there are always `count_breaks(text) + 1` of
+/// them.
+///
+/// This is useful for building the line tables needed by e.g. LSP servers.
+/// Each line's code units are counted in the same pass that finds its
+/// line break.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn utf16_lens(text: &str) -> crate::Utf16Lens<'_> {
+    crate::Utf16Lens {
+        text,
+        done: false,
+        first_line_utf16_len,
+    }
+}
+
+/// Returns the byte and utf16 lengths of the first line of a string
+/// slice, and whether it has a line break.  Used by [`utf16_lens()`].
+#[inline(never)]
+fn first_line_utf16_len(text: &str) -> (usize, usize, bool) {
+    with_chunk!(crate::first_line_utf16_len_impl(
+        text,
+        break_start_flags,
+        is_break_start
+    ))
 }
 
 //-------------------------------------------------------------
@@ -59,7 +573,18 @@ const LF: u8 = b'\n';
 const CR: u8 = b'\r';
 
 #[inline(always)]
-fn to_byte_idx_impl<T: ByteChunk>(text: &[u8], line_idx: usize) -> usize {
+pub(crate) fn from_byte_idx_impl<T: ByteChunk>(text: &str, byte_idx: usize) -> usize {
+    let i = byte_idx.min(text.len());
+    let nl_count = count_breaks_impl::<T>(&text.as_bytes()[..i]);
+    if crate::is_not_crlf_middle(i, text.as_bytes()) {
+        nl_count
+    } else {
+        nl_count - 1
+    }
+}
+
+#[inline(always)]
+pub(crate) fn to_byte_idx_impl<T: ByteChunk>(text: &[u8], line_idx: usize) -> usize {
     // Get `middle` so we can do more efficient chunk-based counting.
     // We can't use this to get `end`, however, because the start index of
     // `end` actually depends on the accumulating line counts during the
@@ -116,7 +641,7 @@ fn to_byte_idx_impl<T: ByteChunk>(text: &[u8], line_idx: usize) -> usize {
     }
 
     // Process the rest of the chunks.
-    for chunk in middle[chunk_count..].iter() {
+    for chunk in middle.get(chunk_count..).unwrap_or(&[]).iter() {
         let lf_flags = chunk.cmp_eq_byte(LF);
         let cr_flags = chunk.cmp_eq_byte(CR);
         let crlf_flags = prev.shift_across(cr_flags).bitand(lf_flags);
@@ -131,7 +656,7 @@ fn to_byte_idx_impl<T: ByteChunk>(text: &[u8], line_idx: usize) -> usize {
 
     // Take care of any unaligned bytes at the end.
     last_was_cr = text.get(byte_count.saturating_sub(1)) == Some(&CR);
-    for byte in text[byte_count..].iter().copied() {
+    for byte in text.get(byte_count..).unwrap_or(&[]).iter().copied() {
         let is_lf = byte == LF;
         let is_cr = byte == CR;
         if break_count == line_idx {
@@ -151,13 +676,49 @@ fn to_byte_idx_impl<T: ByteChunk>(text: &[u8], line_idx: usize) -> usize {
     byte_count
 }
 
+/// Flags the bytes of a chunk that could start a line break.
+#[inline(always)]
+fn break_start_flags<T: ByteChunk>(chunk: &T) -> T {
+    chunk.cmp_eq_byte(LF).add(chunk.cmp_eq_byte(CR))
+}
+
+#[inline(always)]
+fn first_break_impl<T: ByteChunk>(text: &str) -> Option<usize> {
+    // Any CR or LF found scanning forward starts a line break, since the
+    // CR of a CRLF is found before its LF.
+    let is_break = |byte: &u8| *byte == LF || *byte == CR;
+    let (start, middle, _) = unsafe { text.as_bytes().align_to::<T>() };
+
+    // Take care of any unaligned bytes at the beginning.
+    if let Some(i) = start.iter().position(is_break) {
+        return Some(i);
+    }
+
+    // Skip chunks without line breaks, and find the first one in the rest
+    // a byte at a time.
+    let mut byte_idx = start.len();
+    for chunk in middle.iter() {
+        if !break_start_flags(chunk).is_zero() {
+            break;
+        }
+        byte_idx += T::SIZE;
+    }
+
+    text.as_bytes()
+        .get(byte_idx..)
+        .unwrap_or(&[])
+        .iter()
+        .position(is_break)
+        .map(|i| byte_idx + i)
+}
+
 /// Counts the line breaks in a utf8 encoded string.
 ///
 /// The following unicode sequences are considered newlines by this function:
 /// - u{000A}        (Line Feed)
 /// - u{000D}        (Carriage Return)
 #[inline(always)]
-fn count_breaks_impl<T: ByteChunk>(text: &[u8]) -> usize {
+pub(crate) fn count_breaks_impl<T: ByteChunk>(text: &[u8]) -> usize {
     // Get `middle` so we can do more efficient chunk-based counting.
     let (start, middle, end) = unsafe { text.align_to::<T>() };
 
@@ -211,6 +772,51 @@ fn count_breaks_impl<T: ByteChunk>(text: &[u8]) -> usize {
     count
 }
 
+#[inline(always)]
+fn count_crlf_impl<T: ByteChunk>(text: &[u8]) -> usize {
+    // Get `middle` so we can do more efficient chunk-based counting.
+    let (start, middle, end) = unsafe { text.align_to::<T>() };
+
+    let mut count = 0;
+
+    // Take care of unaligned bytes at the beginning.
+    let mut last_was_cr = false;
+    for byte in start.iter().copied() {
+        count += (last_was_cr & (byte == LF)) as usize;
+        last_was_cr = byte == CR;
+    }
+
+    // Take care of the middle bytes in big chunks.
+    let mut prev = T::splat(last_was_cr as u8);
+    for chunks in middle.chunks_exact(2) {
+        let cr_flags0 = chunks[0].cmp_eq_byte(CR);
+        let crlf_flags0 = prev
+            .shift_across(cr_flags0)
+            .bitand(chunks[0].cmp_eq_byte(LF));
+        let cr_flags1 = chunks[1].cmp_eq_byte(CR);
+        let crlf_flags1 = cr_flags0
+            .shift_across(cr_flags1)
+            .bitand(chunks[1].cmp_eq_byte(LF));
+        count += crlf_flags0.add(crlf_flags1).sum_bytes();
+        prev = cr_flags1;
+    }
+
+    if let Some(chunk) = middle.chunks_exact(2).remainder().iter().next() {
+        let cr_flags = chunk.cmp_eq_byte(CR);
+        let crlf_flags = prev.shift_across(cr_flags).bitand(chunk.cmp_eq_byte(LF));
+        count += crlf_flags.sum_bytes();
+    }
+
+    // Take care of unaligned bytes at the end.
+    last_was_cr = text.get((text.len() - end.len()).saturating_sub(1)) == Some(&CR);
+    for byte in end.iter().copied() {
+        count += (last_was_cr & (byte == LF)) as usize;
+        last_was_cr = byte == CR;
+    }
+
+    count
+}
+
 //=============================================================
 
 #[cfg(test)]
@@ -230,6 +836,16 @@ mod tests {
         assert_eq!(3, count_breaks(text));
     }
 
+    #[test]
+    fn count_lines_01() {
+        assert_eq!(1, count_lines("", true));
+        assert_eq!(0, count_lines("", false));
+        assert_eq!(1, count_lines("a", false));
+        assert_eq!(3, count_lines("a\rb\r\n", true));
+        assert_eq!(2, count_lines("a\rb\r\n", false));
+        assert_eq!(3, count_lines("a\rb\r\nc", false));
+    }
+
     #[test]
     fn from_byte_idx_01() {
         let text = "Here\nare\nsome\nwords";
@@ -301,6 +917,17 @@ mod tests {
         }
     }
 
+    #[test]
+    fn from_byte_idx_snapped_01() {
+        let text = "a\r\nb\rc";
+        assert_eq!((0, 0), from_byte_idx_snapped(text, 0));
+        assert_eq!((0, 2), from_byte_idx_snapped(text, 2));
+        assert_eq!((1, 0), from_byte_idx_snapped(text, 3));
+        assert_eq!((1, 1), from_byte_idx_snapped(text, 4));
+        assert_eq!((2, 0), from_byte_idx_snapped(text, 5));
+        assert_eq!((2, 4), from_byte_idx_snapped(text, 9));
+    }
+
     #[test]
     fn to_byte_idx_01() {
         let text = "Here\r\nare\r\nsome\r\nwords";
@@ -334,6 +961,344 @@ mod tests {
         assert_eq!(124, to_byte_idx(TEXT_LINES, 6));
     }
 
+    #[test]
+    fn split_at_01() {
+        let text = "a\r\nb\rc";
+        assert_eq!(("", text), split_at(text, 0));
+        assert_eq!(("a\r\n", "b\rc"), split_at(text, 1));
+        assert_eq!(("a\r\nb\r", "c"), split_at(text, 2));
+        assert_eq!((text, ""), split_at(text, 3));
+    }
+
+    #[test]
+    fn is_break_start_01() {
+        let text = "\na\r\nb\rc\u{0085}\n\r";
+        let starts = [0, 2, 5, 9, 10];
+        for i in 0..(text.len() + 2) {
+            assert_eq!(starts.contains(&i), is_break_start(text, i), "{}", i);
+        }
+    }
+
+    #[test]
+    fn from_other_scheme_idx_01() {
+        // Line starts: 0, 4, 6, 9, 11 in `lines`, 0, 6, 9, 11 in
+        // `lines_crlf`, and 0, 9, 11 in `lines_lf`.
+        let text = "a\u{2028}b\rc\r\nd\ne";
+        for (i, e) in [0, 0, 1, 2, 3, 3].iter().enumerate() {
+            assert_eq!(*e, from_lines_idx(text, i));
+        }
+        for (i, e) in [0, 2, 3, 3].iter().enumerate() {
+            assert_eq!(*e, from_lines_lf_idx(text, i));
+        }
+    }
+
+    #[test]
+    fn len_stats_01() {
+        let stats = len_stats("", true);
+        assert_eq!(
+            (1, 0, 0, 0),
+            (stats.count, stats.min, stats.max, stats.total)
+        );
+        assert_eq!(crate::LineLenStats::default(), len_stats("", false));
+        assert_eq!(0.0, len_stats("", false).mean());
+
+        let text = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r";
+        for final_empty_line in [true, false] {
+            let stats = len_stats(text, final_empty_line);
+            assert_eq!(count_lines(text, final_empty_line), stats.count);
+            assert_eq!(text.len(), stats.total);
+            let lens = (0..stats.count).map(|i| to_byte_idx(text, i + 1) - to_byte_idx(text, i));
+            assert_eq!(lens.clone().min().unwrap(), stats.min);
+            assert_eq!(lens.max().unwrap(), stats.max);
+            assert_eq!(text.len() as f64 / stats.count as f64, stats.mean());
+        }
+    }
+
+    #[test]
+    fn max_line_len_01() {
+        assert_eq!((0, 0), max_line_len(""));
+        assert_eq!((0, 0), max_line_len("\r\r"));
+        assert_eq!((3, 0), max_line_len("foo"));
+        assert_eq!((6, 3), max_line_len("foo\r\nbar\rbaz\nfoobar\n"));
+        assert_eq!((3, 0), max_line_len("foo\rbar\r\n"));
+        assert_eq!((15, 1), max_line_len("a\nこんにちは\r\n"));
+    }
+
+    #[test]
+    fn blank_lines_01() {
+        assert_eq!(1, count_blank_lines("", true));
+        assert_eq!(0, count_blank_lines("", false));
+        assert_eq!(0, count_blank_lines("a", true));
+
+        let text = "foo\n\n  \t\r\nbar\n \n\n";
+        assert!(blank_lines(text, true).eq([1, 2, 4, 5, 6]));
+        assert!(blank_lines(text, false).eq([1, 2, 4, 5]));
+        assert_eq!(5, count_blank_lines(text, true));
+        assert_eq!(4, count_blank_lines(text, false));
+
+        let text = "foo\r\r \nbar\u{2028}\r";
+        assert!(blank_lines(text, true).eq([1, 2, 4]));
+    }
+
+    #[test]
+    fn count_nonblank_01() {
+        assert_eq!(0, count_nonblank(""));
+        assert_eq!(0, count_nonblank(" \t\n\r\n"));
+        assert_eq!(1, count_nonblank("a"));
+        let text = "foo\n\n  \t\r\nbar\r \rbaz\r\n";
+        assert_eq!(3, count_nonblank(text));
+        assert_eq!(
+            count_lines(text, false) - count_blank_lines(text, false),
+            count_nonblank(text)
+        );
+    }
+
+    #[test]
+    fn long_lines_01() {
+        assert_eq!(None, long_lines("", 0).next());
+        assert!(long_lines("a", 0).eq([0]));
+
+        let text = "foo\nbarbaz\r\nこんにちは\n\nabcd";
+        assert!(long_lines(text, 3).eq([1, 2, 4]));
+        assert!(long_lines(text, 6).eq([2]));
+        assert_eq!(None, long_lines(text, 15).next());
+        assert!(long_lines_chars(text, 3).eq([1, 2, 4]));
+        assert!(long_lines_chars(text, 4).eq([1, 2]));
+        assert!(long_lines_chars(text, 5).eq([1]));
+        assert_eq!(None, long_lines_chars(text, 6).next());
+    }
+
+    #[test]
+    fn trailing_whitespace_01() {
+        assert!(trailing_whitespace("", true).eq([None]));
+        assert!(trailing_whitespace("", false).eq([]));
+        assert!(trailing_whitespace(" \t", false).eq([Some(0)]));
+
+        let text = "foo \nbar\n  \n\nbaz\t \n";
+        assert!(trailing_whitespace(text, true).eq([Some(3), None, Some(9), None, Some(16), None]));
+        assert!(trailing_whitespace(text, false).eq([Some(3), None, Some(9), None, Some(16)]));
+        for (line_idx, ws_start) in trailing_whitespace(text, false).enumerate() {
+            let range = line_content_range(text, line_idx, false);
+            let trimmed = text[range.clone()].trim_end_matches(|c: char| c.is_ascii_whitespace());
+            let expected = range.start + trimmed.len();
+            assert_eq!((expected < range.end).then_some(expected), ws_start);
+        }
+    }
+
+    #[test]
+    fn utf16_lens_01() {
+        let text = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r";
+        let mut line_count = 0;
+        let mut utf16_idx = 0;
+        for len in utf16_lens(text) {
+            let start = crate::utf16::to_byte_idx(text, utf16_idx);
+            assert_eq!(to_byte_idx(text, line_count), start);
+            utf16_idx += len;
+            line_count += 1;
+        }
+        assert_eq!(count_breaks(text) + 1, line_count);
+        assert_eq!(crate::utf16::count(text), utf16_idx);
+
+        assert!(utf16_lens("").eq([0]));
+        assert!(utf16_lens("a🐸").eq([3]));
+        assert!(utf16_lens("a\rb\r\n").eq([2, 3, 0]));
+    }
+
+    #[test]
+    fn start_offsets_01() {
+        assert!(start_offsets("").eq([0]));
+        assert!(start_offsets("\r").eq([0, 1]));
+
+        let text = "foo\r\nbar\r\rbaz\nqux\n";
+        assert!(start_offsets(text).eq([0, 5, 9, 10, 14, 18]));
+        for (line_idx, byte_idx) in start_offsets(text).enumerate() {
+            assert_eq!(to_byte_idx(text, line_idx), byte_idx);
+        }
+        assert_eq!(count_breaks(text) + 1, start_offsets(text).count());
+    }
+
+    #[test]
+    fn iter_01() {
+        assert_eq!(None, iter("", false).next());
+        assert!(iter("\r", true).eq(["\r"]));
+
+        let text = "foo\r\nbar\r\rbaz\nqux";
+        assert!(iter(text, false).eq(["foo", "bar", "", "baz", "qux"]));
+        assert!(iter(text, true).eq(["foo\r\n", "bar\r", "\r", "baz\n", "qux"]));
+        assert_eq!(count_lines(text, false), iter(text, false).count());
+        assert_eq!(text.len(), iter(text, true).map(str::len).sum::<usize>());
+    }
+
+    #[test]
+    fn byte_to_line_col_01() {
+        use crate::ColumnUnit::*;
+        let text = "foo\r\nb🐸r\rこんにちは";
+        assert_eq!((0, 0), byte_to_line_col(text, 0, Bytes));
+        assert_eq!((0, 4), byte_to_line_col(text, 4, Utf16));
+        assert_eq!((1, 0), byte_to_line_col(text, 5, Utf16));
+        assert_eq!((1, 1), byte_to_line_col(text, 8, Utf16));
+        assert_eq!((1, 2), byte_to_line_col(text, 10, Chars));
+        assert_eq!((1, 3), byte_to_line_col(text, 10, Utf16));
+        assert_eq!((2, 0), byte_to_line_col(text, 12, Chars));
+        assert_eq!((2, 1), byte_to_line_col(text, 15, Utf16));
+        assert_eq!((2, 5), byte_to_line_col(text, 100, Chars));
+        assert_eq!((0, 0), byte_to_line_col("", 0, Chars));
+    }
+
+    #[test]
+    fn line_col_to_byte_01() {
+        use crate::ColumnUnit::*;
+        let text = "foo\r\nb🐸r\rこんにちは";
+        assert_eq!(3, line_col_to_byte(text, 0, 4, Chars));
+        assert_eq!(6, line_col_to_byte(text, 1, 2, Utf16));
+        assert_eq!(11, line_col_to_byte(text, 1, 100, Bytes));
+        assert_eq!(15, line_col_to_byte(text, 2, 1, Chars));
+        assert_eq!(27, line_col_to_byte(text, 2, 100, Utf16));
+        assert_eq!(27, line_col_to_byte(text, 9, 0, Chars));
+        for byte_idx in [0, 3, 5, 6, 10, 11, 12, 15, 27] {
+            for unit in [Bytes, Chars, Utf16] {
+                let (line_idx, col) = byte_to_line_col(text, byte_idx, unit);
+                assert_eq!(byte_idx, line_col_to_byte(text, line_idx, col, unit));
+            }
+        }
+    }
+
+    #[test]
+    fn line_span_01() {
+        let text = "  foo\r\nbar \r \n\tbaz\n";
+        assert_eq!(0..7, line_span(text, 0));
+        assert_eq!(7..12, line_span(text, 1));
+        assert_eq!(12..14, line_span(text, 2));
+        assert_eq!(14..19, line_span(text, 3));
+        assert_eq!(19..19, line_span(text, 4));
+        assert_eq!(19..19, line_span(text, 9));
+        assert_eq!(0..0, line_span("", 0));
+        assert_eq!(0..3, line_span("foo", 0));
+    }
+
+    #[test]
+    fn line_at_byte_01() {
+        let text = "foo\r\nbar\rbaz";
+        assert_eq!("foo", line_at_byte(text, 0, false));
+        assert_eq!("foo", line_at_byte(text, 4, false));
+        assert_eq!("foo\r\n", line_at_byte(text, 3, true));
+        assert_eq!("bar", line_at_byte(text, 8, false));
+        assert_eq!("bar\r", line_at_byte(text, 5, true));
+        assert_eq!("baz", line_at_byte(text, 9, false));
+        assert_eq!("baz", line_at_byte(text, 100, true));
+        assert_eq!("", line_at_byte("", 0, false));
+    }
+
+    #[test]
+    fn line_len_01() {
+        use crate::ColumnUnit::*;
+        let text = "foo\r\nb🐸r\rこんにちは";
+        assert_eq!(3, line_len(text, 0, Bytes));
+        assert_eq!(6, line_len(text, 1, Bytes));
+        assert_eq!(3, line_len(text, 1, Chars));
+        assert_eq!(4, line_len(text, 1, Utf16));
+        assert_eq!(5, line_len(text, 2, Chars));
+        assert_eq!(0, line_len(text, 3, Bytes));
+    }
+
+    #[test]
+    fn line_content_range_01() {
+        let text = "  foo\r\nbar \r \n\tbaz\n";
+        assert_eq!(0..5, line_content_range(text, 0, false));
+        assert_eq!(2..5, line_content_range(text, 0, true));
+        assert_eq!(7..11, line_content_range(text, 1, false));
+        assert_eq!(7..10, line_content_range(text, 1, true));
+        assert_eq!(12..13, line_content_range(text, 2, false));
+        assert_eq!(13..13, line_content_range(text, 2, true));
+        assert_eq!("\tbaz", line_content(text, 3, false));
+        assert_eq!("baz", line_content(text, 3, true));
+        assert_eq!(19..19, line_content_range(text, 4, true));
+        assert_eq!(19..19, line_content_range(text, 9, false));
+        assert_eq!(0..0, line_content_range("", 0, false));
+        assert_eq!(2..2, line_content_range(" \t", 0, true));
+    }
+
+    #[test]
+    fn common_prefix_01() {
+        assert_eq!(0, common_prefix("", ""));
+        assert_eq!(4, common_prefix("foo\nbar", "foo\nbaz"));
+        assert_eq!(0, common_prefix("foo\nbar", "foo"));
+        assert_eq!(0, common_prefix("foo\u{2028}bar", "foo\u{2028}baz"));
+        // A CR followed by an LF in only one of them.
+        assert_eq!(0, common_prefix("foo\r\nbar", "foo\rbar"));
+        assert_eq!(3, common_suffix("foo\nbar", "fo\nbar"));
+        assert_eq!(0, common_suffix("foo\nbar", "foo\nbaz"));
+        assert_eq!(3, common_suffix("a\rbar", "b\nbar"));
+    }
+
+    #[test]
+    fn count_crlf_01() {
+        assert_eq!(0, count_crlf(""));
+        assert_eq!(0, count_crlf("\r"));
+        assert_eq!(1, count_crlf("\r\r\n\n\r"));
+        assert_eq!(3, count_crlf("a\r\nb\r\n\r\n\n\r"));
+
+        // Long enough to go through the chunk loop, with CRLFs at every
+        // alignment.
+        let mut buf = [b'x'; 100];
+        for i in (0..99).step_by(3) {
+            buf[i] = b'\r';
+            buf[i + 1] = b'\n';
+        }
+        let text = core::str::from_utf8(&buf).unwrap();
+        for start in 0..16 {
+            let text = &text[start..];
+            let expected = text.as_bytes().windows(2).filter(|w| w == b"\r\n").count();
+            assert_eq!(expected, count_crlf(text));
+            assert_eq!(expected, count_crlf_impl::<u16>(text.as_bytes()));
+            assert_eq!(expected, count_crlf_impl::<u32>(text.as_bytes()));
+        }
+    }
+
+    #[test]
+    fn lf_normalized_byte_idx_01() {
+        let text = "a\r\nb\rc\r\n\r\n";
+        let expected = [0, 1, 1, 2, 3, 4, 5, 5, 6, 6, 7];
+        for (i, e) in expected.iter().enumerate() {
+            assert_eq!(*e, lf_normalized_byte_idx(text, i));
+        }
+        assert_eq!(7, lf_normalized_byte_idx(text, 100));
+        assert_eq!(
+            text.len() - count_crlf(text),
+            lf_normalized_byte_idx(text, text.len())
+        );
+    }
+
+    #[test]
+    fn first_break_01() {
+        assert_eq!(None, first_break(""));
+        assert!(!has_break(""));
+        assert_eq!(None, first_break("abc\u{0085}\u{2028}"));
+        assert!(!has_break("abc\u{0085}\u{2028}"));
+        assert_eq!(Some(3), first_break("abc\rdef"));
+        assert_eq!(Some(1), first_break("a\r\nb"));
+        assert_eq!(Some(2), first_break("ab\n\r"));
+    }
+
+    #[test]
+    fn first_break_02() {
+        // Long enough to go through the chunk loop, with the line break
+        // at every position.
+        let text =
+            "xxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxxx";
+        assert_eq!(None, first_break(text));
+        for i in 0..text.len() {
+            for br in ["\n", "\r", "\r\n"] {
+                let mut buf = [0u8; 96];
+                buf[..text.len()].copy_from_slice(text.as_bytes());
+                buf[i..(i + br.len())].copy_from_slice(br.as_bytes());
+                let text = core::str::from_utf8(&buf[..text.len().max(i + br.len())]).unwrap();
+                assert_eq!(Some(i), first_break(text));
+                assert!(has_break(text));
+            }
+        }
+    }
+
     #[test]
     fn line_byte_round_trip() {
         let text = "\nHere\nare\nsome\nwords\n";
@@ -346,4 +1311,22 @@ mod tests {
         assert_eq!(21, to_byte_idx(text, from_byte_idx(text, 21)));
         assert_eq!(5, from_byte_idx(text, to_byte_idx(text, 5)));
     }
+
+    // Exercises the chunk-based code with 16- and 32-bit words, as used on
+    // 16-bit (AVR, MSP430) and 32-bit targets, regardless of the host.
+    #[test]
+    fn narrow_chunks() {
+        crate::byte_chunk::for_each_narrow_chunk_text(|text| {
+            let bytes = text.as_bytes();
+            let breaks = count_breaks(text);
+            assert_eq!(breaks, count_breaks_impl::<u16>(bytes));
+            assert_eq!(breaks, count_breaks_impl::<u32>(bytes));
+            assert_eq!(first_break(text), first_break_impl::<u16>(text));
+            assert_eq!(first_break(text), first_break_impl::<u32>(text));
+            for i in 0..(breaks + 2) {
+                assert_eq!(to_byte_idx(text, i), to_byte_idx_impl::<u16>(bytes, i));
+                assert_eq!(to_byte_idx(text, i), to_byte_idx_impl::<u32>(bytes, i));
+            }
+        });
+    }
 }
@@ -0,0 +1,715 @@
+//! Index by lines (a custom set of line breaks).
+//!
+//! This module recognizes any subset of the line breaks defined in
+//! [Unicode Annex #14](https://www.unicode.org/reports/tr14/), as selected
+//! by a [`BreakSet`]:
+//!
+//! - `U+000A`          &mdash; LF (Line Feed)
+//! - `U+000B`          &mdash; VT (Vertical Tab)
+//! - `U+000C`          &mdash; FF (Form Feed)
+//! - `U+000D`          &mdash; CR (Carriage Return)
+//! - `U+0085`          &mdash; NEL (Next Line)
+//! - `U+2028`          &mdash; Line Separator
+//! - `U+2029`          &mdash; Paragraph Separator
+//!
+//! If both LF and CR are in the set, a CRLF is a single line break, as in
+//! the [`lines`](crate::lines) module.  If only LF is, a CRLF is still a
+//! single line break, by coincidence due to ignoring CR, as in the
+//! [`lines_lf`](crate::lines_lf) module.  So e.g. `BreakSet::ALL` gives
+//! the same results as [`lines`](crate::lines), `BreakSet::LF` as
+//! [`lines_lf`](crate::lines_lf), and `BreakSet::LF.union(BreakSet::CR)`
+//! as [`lines_crlf`](crate::lines_crlf).
+
+use crate::byte_chunk::ByteChunk;
+
+/// A set of line breaks to recognize.
+///
+/// Sets are built from the associated constants with [`union()`](Self::union),
+/// e.g. `BreakSet::LF.union(BreakSet::NEL)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BreakSet(u8);
+
+impl BreakSet {
+    /// No line breaks.
+    pub const NONE: BreakSet = BreakSet(0);
+    /// `U+000A`, LF (Line Feed).
+    pub const LF: BreakSet = BreakSet(1 << 0);
+    /// `U+000B`, VT (Vertical Tab).
+    pub const VT: BreakSet = BreakSet(1 << 1);
+    /// `U+000C`, FF (Form Feed).
+    pub const FF: BreakSet = BreakSet(1 << 2);
+    /// `U+000D`, CR (Carriage Return).
+    pub const CR: BreakSet = BreakSet(1 << 3);
+    /// `U+0085`, NEL (Next Line).
+    pub const NEL: BreakSet = BreakSet(1 << 4);
+    /// `U+2028`, Line Separator.
+    pub const LS: BreakSet = BreakSet(1 << 5);
+    /// `U+2029`, Paragraph Separator.
+    pub const PS: BreakSet = BreakSet(1 << 6);
+    /// All of the line breaks above.
+    pub const ALL: BreakSet = BreakSet((1 << 7) - 1);
+
+    /// Returns the line breaks that are in either set.
+    #[inline]
+    pub const fn union(self, other: BreakSet) -> BreakSet {
+        BreakSet(self.0 | other.0)
+    }
+
+    /// Returns the line breaks that are in this set but not `other`.
+    #[inline]
+    pub const fn difference(self, other: BreakSet) -> BreakSet {
+        BreakSet(self.0 & !other.0)
+    }
+
+    /// Returns whether all of the line breaks in `other` are in this set.
+    #[inline]
+    pub const fn contains(self, other: BreakSet) -> bool {
+        (self.0 & other.0) == other.0
+    }
+
+    /// Whether a CR followed by an LF is a single line break starting at
+    /// the CR.
+    #[inline(always)]
+    fn has_crlf(self) -> bool {
+        self.contains(BreakSet::LF.union(BreakSet::CR))
+    }
+}
+
+/// Counts the line breaks in a string slice.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn count_breaks(text: &str, breaks: BreakSet) -> usize {
+    with_chunk!(count_breaks_impl(text.as_bytes(), breaks))
+}
+
+/// Counts the lines in a string slice.
+///
+/// See [`lines::count_lines()`](crate::lines::count_lines) for the meaning
+/// of `final_empty_line`.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn count_lines(text: &str, final_empty_line: bool, breaks: BreakSet) -> usize {
+    let count = count_breaks(text, breaks);
+    if final_empty_line || !(text.is_empty() || ends_with_break(text.as_bytes(), breaks)) {
+        count + 1
+    } else {
+        count
+    }
+}
+
+/// Converts from byte-index to line-index in a string slice.
+///
+/// Line break characters are considered to be a part of the line they
+/// end.  And a string that ends with a line break is considered to have
+/// a final empty line.  So this function is equivalent to counting the
+/// line breaks before the specified byte.
+///
+/// Any past-the-end index will return the last line index.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn from_byte_idx(text: &str, byte_idx: usize, breaks: BreakSet) -> usize {
+    let i = crate::chars::floor_byte_boundary(text, byte_idx);
+    let count = with_chunk!(count_breaks_impl(
+        text.as_bytes().get(..i).unwrap_or(&[]),
+        breaks
+    ));
+    if breaks.has_crlf() && !crate::is_not_crlf_middle(i, text.as_bytes()) {
+        count - 1
+    } else {
+        count
+    }
+}
+
+/// Converts from line-index to byte-index in a string slice.
+///
+/// Returns the byte index of the start of the specified line.  Line 0 is
+/// the start of the string, and subsequent lines start immediately
+/// *after* each line break character.
+///
+/// Any past-the-end index will return the one-past-the-end byte index.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn to_byte_idx(text: &str, line_idx: usize, breaks: BreakSet) -> usize {
+    with_chunk!(to_byte_idx_impl(text.as_bytes(), line_idx, breaks))
+}
+
+/// Returns whether a line break starts at the given byte index.
+///
+/// A CRLF that is a single line break starts at the CR.  Multi-byte line
+/// breaks only start at their first byte.
+///
+/// Any past-the-end index returns false.
+///
+/// Runs in O(1) time.
+#[inline]
+pub fn is_break_start(text: &str, byte_idx: usize, breaks: BreakSet) -> bool {
+    break_len_at(text.as_bytes(), byte_idx, breaks) > 0
+}
+
+/// Returns the byte index of the start of the first line break in a
+/// string slice, if any.
+///
+/// Stops scanning at the first line break.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn first_break(text: &str, breaks: BreakSet) -> Option<usize> {
+    with_chunk!(first_break_impl(text.as_bytes(), breaks))
+}
+
+//-------------------------------------------------------------
+
+/// Returns the byte length of the line break starting at `i`, or zero if
+/// none does.
+#[inline(always)]
+fn break_len_at(bytes: &[u8], i: usize, breaks: BreakSet) -> usize {
+    let byte_at = |i: Option<usize>| i.and_then(|i| bytes.get(i)).copied();
+    let has = |set| breaks.contains(set);
+    match byte_at(Some(i)) {
+        Some(0x0A) if has(BreakSet::LF) => {
+            let after_cr = byte_at(i.checked_sub(1)) == Some(0x0D);
+            (!(after_cr && breaks.has_crlf())) as usize
+        }
+        Some(0x0B) if has(BreakSet::VT) => 1,
+        Some(0x0C) if has(BreakSet::FF) => 1,
+        Some(0x0D) if has(BreakSet::CR) => {
+            if breaks.has_crlf() && byte_at(i.checked_add(1)) == Some(0x0A) {
+                2
+            } else {
+                1
+            }
+        }
+        Some(0xC2) if has(BreakSet::NEL) && byte_at(i.checked_add(1)) == Some(0x85) => 2,
+        Some(0xE2) if byte_at(i.checked_add(1)) == Some(0x80) => match byte_at(i.checked_add(2)) {
+            Some(0xA8) if has(BreakSet::LS) => 3,
+            Some(0xA9) if has(BreakSet::PS) => 3,
+            _ => 0,
+        },
+        _ => 0,
+    }
+}
+
+/// Returns whether `bytes` ends with a line break.
+#[inline(always)]
+fn ends_with_break(bytes: &[u8], breaks: BreakSet) -> bool {
+    (1..=3)
+        .filter_map(|len| bytes.len().checked_sub(len).map(|i| (i, len)))
+        .any(|(i, len)| break_len_at(bytes, i, breaks) == len)
+}
+
+/// Counts the line breaks that start in `range`, a byte at a time.
+#[inline(always)]
+fn count_breaks_in_range(bytes: &[u8], range: core::ops::Range<usize>, breaks: BreakSet) -> usize {
+    range
+        .filter(|&i| break_len_at(bytes, i, breaks) > 0)
+        .count()
+}
+
+/// Flags the starts of the line breaks in `chunk`, which ends at
+/// `chunk_end` in `bytes`.
+///
+/// An LF at the start of the chunk that is the second half of a CRLF is
+/// flagged too, so callers must correct for that with
+/// [`is_split_crlf()`].
+#[inline(always)]
+fn break_flags<T: ByteChunk>(chunk: &T, bytes: &[u8], chunk_end: usize, breaks: BreakSet) -> T {
+    let mut flags = T::zero();
+    if breaks.contains(BreakSet::LF.union(BreakSet::VT).union(BreakSet::FF)) {
+        flags = chunk.bytes_between_127(0x09, 0x0D);
+    } else {
+        if breaks.contains(BreakSet::LF) {
+            flags = flags.add(chunk.cmp_eq_byte(0x0A));
+        }
+        if breaks.contains(BreakSet::VT) {
+            flags = flags.add(chunk.cmp_eq_byte(0x0B));
+        }
+        if breaks.contains(BreakSet::FF) {
+            flags = flags.add(chunk.cmp_eq_byte(0x0C));
+        }
+    }
+    if breaks.contains(BreakSet::CR) {
+        let cr_flags = chunk.cmp_eq_byte(0x0D);
+        flags = flags.add(cr_flags);
+        if breaks.has_crlf() {
+            // Count each CRLF once, by removing the flag of its CR.  The
+            // LF's flag is shifted back onto the CR to find them.
+            let crlf_flags = cr_flags.bitand(chunk.cmp_eq_byte(0x0A).shift_back_lex(1));
+            flags = flags.sub(crlf_flags);
+        }
+    }
+
+    // The multi-byte line breaks are flagged as in `lines`, including the
+    // ones that cross the end of the chunk, which `break_len_at()` finds.
+    let crosses_end = |len: usize| {
+        chunk_end
+            .checked_sub(len)
+            .map_or(false, |i| break_len_at(bytes, i, breaks) > len)
+    };
+
+    let has_nl = breaks.contains(BreakSet::NEL);
+    let has_sp = breaks.contains(BreakSet::LS) || breaks.contains(BreakSet::PS);
+    let nl_1_flags = if has_nl {
+        chunk.cmp_eq_byte(0xC2)
+    } else {
+        T::zero()
+    };
+    let sp_1_flags = if has_sp {
+        chunk.cmp_eq_byte(0xE2)
+    } else {
+        T::zero()
+    };
+    if (!has_nl && !has_sp) || nl_1_flags.bitor(sp_1_flags).is_zero() {
+        return flags;
+    }
+
+    // Next Line: u{0085}
+    if !nl_1_flags.is_zero() {
+        let nl_2_flags = chunk.cmp_eq_byte(0x85).shift_back_lex(1);
+        flags = flags.add(nl_1_flags.bitand(nl_2_flags));
+        if crosses_end(1) && bytes.get(chunk_end - 1) == Some(&0xC2) {
+            flags = flags.inc_nth_from_end_lex_byte(0);
+        }
+    }
+
+    // Line Separator:      u{2028}
+    // Paragraph Separator: u{2029}
+    if !sp_1_flags.is_zero() {
+        let sp_2_flags = chunk.cmp_eq_byte(0x80).shift_back_lex(1).bitand(sp_1_flags);
+        if !sp_2_flags.is_zero() {
+            let sp_3_flags = match (breaks.contains(BreakSet::LS), breaks.contains(BreakSet::PS)) {
+                // Both of 0xA8 and 0xA9 shift down to 0x54.
+                (true, true) => chunk.shr(1).bitand(T::splat(!0x80)).cmp_eq_byte(0x54),
+                (true, false) => chunk.cmp_eq_byte(0xA8),
+                _ => chunk.cmp_eq_byte(0xA9),
+            };
+            flags = flags.add(sp_2_flags.bitand(sp_3_flags.shift_back_lex(2)));
+        }
+        if crosses_end(2) {
+            flags = flags.inc_nth_from_end_lex_byte(1);
+        } else if crosses_end(1) && bytes.get(chunk_end - 1) == Some(&0xE2) {
+            flags = flags.inc_nth_from_end_lex_byte(0);
+        }
+    }
+    flags
+}
+
+/// Whether the byte at `i` is the LF of a CRLF that is a single line
+/// break.
+#[inline(always)]
+fn is_split_crlf(bytes: &[u8], i: usize, breaks: BreakSet) -> bool {
+    breaks.has_crlf() && !crate::is_not_crlf_middle(i, bytes)
+}
+
+/// Counts the line breaks of each of `sets` that start in `chunks` (at
+/// most `T::MAX_ACC` of them), whose first byte is at `byte_idx` in
+/// `bytes`.
+///
+/// Counting several sets at once shares the loads and the CRLF
+/// corrections between them.
+#[inline(always)]
+fn count_breaks_in_chunks<T: ByteChunk, const N: usize>(
+    chunks: &[T],
+    bytes: &[u8],
+    byte_idx: usize,
+    sets: [BreakSet; N],
+) -> [usize; N] {
+    let mut accs = [T::zero(); N];
+    let mut split_crlfs = 0;
+    for (i, chunk) in chunks.iter().enumerate() {
+        let chunk_start = byte_idx + (i * T::SIZE);
+        for (acc, breaks) in accs.iter_mut().zip(sets) {
+            *acc = acc.add(break_flags(chunk, bytes, chunk_start + T::SIZE, breaks));
+        }
+        split_crlfs += !crate::is_not_crlf_middle(chunk_start, bytes) as usize;
+    }
+    let mut counts = [0; N];
+    for ((count, acc), breaks) in counts.iter_mut().zip(accs).zip(sets) {
+        *count = acc.sum_bytes() - if breaks.has_crlf() { split_crlfs } else { 0 };
+    }
+    counts
+}
+
+#[inline(always)]
+fn count_breaks_impl<T: ByteChunk>(bytes: &[u8], breaks: BreakSet) -> usize {
+    // Get `middle` so we can do more efficient chunk-based counting.
+    let (start, middle, _) = unsafe { bytes.align_to::<T>() };
+
+    // Take care of any unaligned bytes at the beginning.
+    let mut count = count_breaks_in_range(bytes, 0..start.len(), breaks);
+    let mut byte_idx = start.len();
+
+    // Count chunks `T::UNROLL` at a time.
+    for chunks in middle.chunks(T::UNROLL) {
+        count += count_breaks_in_chunks(chunks, bytes, byte_idx, [breaks])[0];
+        byte_idx += T::SIZE * chunks.len();
+    }
+
+    // Take care of any unaligned bytes at the end.
+    count + count_breaks_in_range(bytes, byte_idx..bytes.len(), breaks)
+}
+
+#[inline(always)]
+fn to_byte_idx_impl<T: ByteChunk>(bytes: &[u8], line_idx: usize, breaks: BreakSet) -> usize {
+    if line_idx == 0 {
+        return 0;
+    }
+
+    // Finds the end of the `line_idx`th line break in `range`, given the
+    // number of line breaks before it.
+    let find_in_range = |range: core::ops::Range<usize>, count: &mut usize| {
+        for i in range {
+            let len = break_len_at(bytes, i, breaks);
+            if len > 0 {
+                *count += 1;
+                if *count == line_idx {
+                    return Some(i + len);
+                }
+            }
+        }
+        None
+    };
+
+    // Get `middle` so we can do more efficient chunk-based counting.
+    let (start, middle, _) = unsafe { bytes.align_to::<T>() };
+    let mut count = 0;
+
+    // Take care of any unaligned bytes at the beginning.
+    if let Some(i) = find_in_range(0..start.len(), &mut count) {
+        return i;
+    }
+    let mut byte_idx = start.len();
+
+    // Skip chunks that end before the line.
+    for chunk in middle.iter() {
+        let chunk_count = break_flags(chunk, bytes, byte_idx + T::SIZE, breaks).sum_bytes()
+            - is_split_crlf(bytes, byte_idx, breaks) as usize;
+        if count + chunk_count >= line_idx {
+            break;
+        }
+        count += chunk_count;
+        byte_idx += T::SIZE;
+    }
+
+    // Find the line in the rest of the bytes.
+    find_in_range(byte_idx..bytes.len(), &mut count).unwrap_or(bytes.len())
+}
+
+/// Converts from a line-index in the `from` set of line breaks to a
+/// line-index in the `to` set, for the same text.
+///
+/// Equivalent to `from_byte_idx(text, to_byte_idx(text, line_idx, from), to)`,
+/// but counts the line breaks of both sets in the same scan, which stops
+/// at the start of the line.  Used by the `from_lines*_idx()` functions of
+/// the lines modules, with constant sets so that the set checks fold away.
+#[inline(always)]
+pub(crate) fn convert_line_idx_impl<T: ByteChunk>(
+    bytes: &[u8],
+    line_idx: usize,
+    from: BreakSet,
+    to: BreakSet,
+) -> usize {
+    if line_idx == 0 {
+        return 0;
+    }
+
+    // Finds the end of the `line_idx`th line break of `from` in `range`,
+    // given the number of line breaks of both sets before it, and counts
+    // the line breaks of `to` on the way.  Returns whether it was found.
+    let find_in_range =
+        |range: core::ops::Range<usize>, from_count: &mut usize, to_count: &mut usize| {
+            for i in range {
+                let len = break_len_at(bytes, i, from);
+                if len > 0 && *from_count + 1 == line_idx {
+                    // Any line breaks of `to` that start within the line
+                    // break of `from` are before the line too, except for
+                    // a CRLF that the line starts in the middle of.
+                    *to_count += count_breaks_in_range(bytes, i..(i + len), to);
+                    *to_count -= is_split_crlf(bytes, i + len, to) as usize;
+                    return true;
+                }
+                *from_count += (len > 0) as usize;
+                *to_count += (break_len_at(bytes, i, to) > 0) as usize;
+            }
+            false
+        };
+
+    // Get `middle` so we can do more efficient chunk-based counting.
+    let (start, middle, _) = unsafe { bytes.align_to::<T>() };
+    let mut from_count = 0;
+    let mut to_count = 0;
+
+    // Take care of any unaligned bytes at the beginning.
+    if find_in_range(0..start.len(), &mut from_count, &mut to_count) {
+        return to_count;
+    }
+    let mut byte_idx = start.len();
+
+    // Skip chunks that end before the line, counting both sets' line
+    // breaks in them, `T::UNROLL` chunks at a time and then one at a time.
+    let groups_len = middle.len() - middle.len() % T::UNROLL;
+    let groups = middle
+        .get(..groups_len)
+        .unwrap_or(&[])
+        .chunks_exact(T::UNROLL);
+    let mut chunk_count = 0;
+    for chunks in groups.chain(middle.get(groups_len..).unwrap_or(&[]).chunks(1)) {
+        let [from_chunks_count, to_chunks_count] =
+            count_breaks_in_chunks(chunks, bytes, byte_idx, [from, to]);
+        if from_count + from_chunks_count >= line_idx {
+            break;
+        }
+        from_count += from_chunks_count;
+        to_count += to_chunks_count;
+        byte_idx += T::SIZE * chunks.len();
+        chunk_count += chunks.len();
+    }
+
+    // Before falling back to a byte at a time, skip single chunks of the
+    // group the line is in.
+    for chunk in middle.get(chunk_count..).unwrap_or(&[]).iter() {
+        let chunks = core::slice::from_ref(chunk);
+        let [from_chunk_count, to_chunk_count] =
+            count_breaks_in_chunks(chunks, bytes, byte_idx, [from, to]);
+        if from_count + from_chunk_count >= line_idx {
+            break;
+        }
+        from_count += from_chunk_count;
+        to_count += to_chunk_count;
+        byte_idx += T::SIZE;
+    }
+
+    // Find the line in the rest of the bytes.
+    find_in_range(byte_idx..bytes.len(), &mut from_count, &mut to_count);
+    to_count
+}
+
+#[inline(always)]
+fn first_break_impl<T: ByteChunk>(bytes: &[u8], breaks: BreakSet) -> Option<usize> {
+    let (start, middle, _) = unsafe { bytes.align_to::<T>() };
+    let is_start = |&i: &usize| break_len_at(bytes, i, breaks) > 0;
+
+    // Take care of any unaligned bytes at the beginning.
+    if let Some(i) = (0..start.len()).find(is_start) {
+        return Some(i);
+    }
+
+    // Skip chunks without any bytes that can start a line break, and
+    // check the rest a byte at a time.
+    let mut byte_idx = start.len();
+    for chunk in middle.iter() {
+        if !break_flags(chunk, bytes, byte_idx + T::SIZE, breaks).is_zero() {
+            if let Some(i) = (byte_idx..(byte_idx + T::SIZE)).find(is_start) {
+                return Some(i);
+            }
+        }
+        byte_idx += T::SIZE;
+    }
+
+    // Take care of any unaligned bytes at the end.
+    (byte_idx..bytes.len()).find(is_start)
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::byte_chunk::Chunk;
+
+    // 14 line breaks in `BreakSet::ALL`, including every kind.
+    const TEXT: &str = "a\nb\u{000B}c\u{000C}d\re\r\nf\u{0085}g\u{2028}h\u{2029}\
+                        Hello world!\n\rこんにちは\u{000B}\u{000C}🐸\r\n\n";
+
+    const BREAK_SETS: [BreakSet; 6] = [
+        BreakSet::NONE,
+        BreakSet::LF,
+        BreakSet::CR,
+        BreakSet::LF.union(BreakSet::CR),
+        BreakSet::LF.union(BreakSet::NEL).union(BreakSet::PS),
+        BreakSet::ALL,
+    ];
+
+    /// The byte indices of the line starts, the slow way.
+    fn line_starts(text: &str, breaks: BreakSet, out: &mut [usize; 64]) -> usize {
+        let mut n = 1;
+        out[0] = 0;
+        for i in 0..text.len() {
+            let len = break_len_at(text.as_bytes(), i, breaks);
+            if len > 0 {
+                out[n] = i + len;
+                n += 1;
+            }
+        }
+        n
+    }
+
+    #[test]
+    fn break_set_01() {
+        let set = BreakSet::LF.union(BreakSet::NEL);
+        assert!(set.contains(BreakSet::LF));
+        assert!(set.contains(BreakSet::NONE));
+        assert!(!set.contains(BreakSet::LF.union(BreakSet::CR)));
+        assert!(BreakSet::ALL.contains(set));
+        assert_eq!(BreakSet::LF, set.difference(BreakSet::NEL));
+        assert_eq!(BreakSet::NONE, BreakSet::NONE.union(BreakSet::NONE));
+    }
+
+    #[test]
+    fn count_breaks_01() {
+        assert_eq!(0, count_breaks(TEXT, BreakSet::NONE));
+        assert_eq!(5, count_breaks(TEXT, BreakSet::LF));
+        assert_eq!(4, count_breaks(TEXT, BreakSet::CR));
+        assert_eq!(7, count_breaks(TEXT, BreakSet::LF.union(BreakSet::CR)));
+        assert_eq!(2, count_breaks(TEXT, BreakSet::VT));
+        assert_eq!(14, count_breaks(TEXT, BreakSet::ALL));
+        assert_eq!(0, count_breaks("", BreakSet::ALL));
+    }
+
+    #[test]
+    fn count_lines_01() {
+        let breaks = BreakSet::LF.union(BreakSet::NEL);
+        assert_eq!(1, count_lines("", true, breaks));
+        assert_eq!(0, count_lines("", false, breaks));
+        assert_eq!(1, count_lines("a\u{000B}", false, breaks));
+        assert_eq!(1, count_lines("a\u{0085}", false, breaks));
+        assert_eq!(2, count_lines("a\u{0085}", true, breaks));
+        assert_eq!(1, count_lines("a\r\n", false, breaks));
+        assert_eq!(1, count_lines("a\r", false, BreakSet::CR));
+    }
+
+    #[test]
+    fn matches_other_modules() {
+        let text = TEXT.repeat(10);
+        let modules = [
+            (
+                BreakSet::ALL,
+                crate::lines::to_byte_idx as fn(&str, usize) -> usize,
+            ),
+            (BreakSet::LF, crate::lines_lf::to_byte_idx),
+            (
+                BreakSet::LF.union(BreakSet::CR),
+                crate::lines_crlf::to_byte_idx,
+            ),
+        ];
+        for (breaks, other_to_byte_idx) in modules {
+            for i in 0..(text.len() + 2) {
+                assert_eq!(other_to_byte_idx(&text, i), to_byte_idx(&text, i, breaks));
+            }
+        }
+        assert_eq!(
+            crate::lines::count_breaks(&text),
+            count_breaks(&text, BreakSet::ALL)
+        );
+        assert_eq!(
+            crate::lines_crlf::from_byte_idx(&text, 17),
+            from_byte_idx(&text, 17, BreakSet::LF.union(BreakSet::CR))
+        );
+    }
+
+    #[test]
+    fn from_to_byte_idx_01() {
+        let mut starts = [0; 64];
+        for breaks in BREAK_SETS {
+            let n = line_starts(TEXT, breaks, &mut starts);
+            let starts = &starts[..n];
+            assert_eq!(n - 1, count_breaks(TEXT, breaks));
+            for (line_idx, &byte_idx) in starts.iter().enumerate() {
+                assert_eq!(byte_idx, to_byte_idx(TEXT, line_idx, breaks));
+                assert_eq!(line_idx, from_byte_idx(TEXT, byte_idx, breaks));
+            }
+            assert_eq!(TEXT.len(), to_byte_idx(TEXT, n, breaks));
+            assert_eq!(n - 1, from_byte_idx(TEXT, TEXT.len() + 1, breaks));
+            for i in 0..TEXT.len() {
+                let line_idx = starts.iter().rposition(|&s| s <= i).unwrap();
+                let crlf_middle =
+                    breaks.has_crlf() && !crate::is_not_crlf_middle(i, TEXT.as_bytes());
+                if TEXT.is_char_boundary(i) && !crlf_middle {
+                    assert_eq!(line_idx, from_byte_idx(TEXT, i, breaks));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn first_break_01() {
+        assert_eq!(None, first_break(TEXT, BreakSet::NONE));
+        assert_eq!(Some(1), first_break(TEXT, BreakSet::ALL));
+        assert_eq!(Some(7), first_break(TEXT, BreakSet::CR));
+        assert_eq!(Some(12), first_break(TEXT, BreakSet::NEL));
+        assert_eq!(Some(19), first_break(TEXT, BreakSet::PS));
+        assert_eq!(None, first_break("", BreakSet::ALL));
+
+        let text = "a".repeat(100) + "\u{2029}";
+        assert_eq!(Some(100), first_break(&text, BreakSet::PS));
+        assert_eq!(None, first_break(&text, BreakSet::LS));
+        assert!(is_break_start(&text, 100, BreakSet::PS));
+        assert!(!is_break_start(&text, 101, BreakSet::PS));
+    }
+
+    #[test]
+    fn multi_byte_breaks_01() {
+        // Multi-byte line breaks crossing chunk boundaries at every
+        // alignment, for each set of them.
+        let sets = [
+            BreakSet::NEL,
+            BreakSet::LS,
+            BreakSet::PS,
+            BreakSet::LS.union(BreakSet::PS),
+            BreakSet::CR.union(BreakSet::LS),
+            BreakSet::ALL,
+        ];
+        for offset in 0..32 {
+            let text = "a".repeat(offset) + &"\u{0085}b\u{2028}\u{2029}\r\né\u{2020}".repeat(16);
+            let bytes = text.as_bytes();
+            for breaks in sets {
+                let ends = || {
+                    (0..bytes.len()).filter_map(|i| match break_len_at(bytes, i, breaks) {
+                        0 => None,
+                        len => Some(i + len),
+                    })
+                };
+                let count = ends().count();
+                assert_eq!(count, count_breaks_impl::<usize>(bytes, breaks));
+                assert_eq!(count, count_breaks_impl::<Chunk>(bytes, breaks));
+                for line_idx in 1..(count + 2) {
+                    let expected = ends().nth(line_idx - 1).unwrap_or(bytes.len());
+                    assert_eq!(expected, to_byte_idx_impl::<usize>(bytes, line_idx, breaks));
+                    assert_eq!(expected, to_byte_idx_impl::<Chunk>(bytes, line_idx, breaks));
+                }
+                let first = (0..bytes.len()).find(|&i| break_len_at(bytes, i, breaks) > 0);
+                assert_eq!(first, first_break_impl::<usize>(bytes, breaks));
+                assert_eq!(first, first_break_impl::<Chunk>(bytes, breaks));
+            }
+        }
+    }
+
+    #[test]
+    fn convert_line_idx_01() {
+        // Long enough for whole groups of chunks, and offset so that the
+        // line breaks (including CRLFs) cross chunk boundaries.
+        for offset in 0..8 {
+            let text = "a".repeat(offset) + &TEXT.repeat(20);
+            for from in BREAK_SETS {
+                for to in BREAK_SETS {
+                    for line_idx in 0..(count_breaks(&text, from) + 3) {
+                        let byte_idx = to_byte_idx(&text, line_idx, from);
+                        let line_idx_to = from_byte_idx(&text, byte_idx, to);
+                        let bytes = text.as_bytes();
+                        assert_eq!(
+                            line_idx_to,
+                            convert_line_idx_impl::<usize>(bytes, line_idx, from, to)
+                        );
+                        assert_eq!(
+                            line_idx_to,
+                            convert_line_idx_impl::<Chunk>(bytes, line_idx, from, to)
+                        );
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,348 @@
+//! Conversions to/from [`lsp_types`] positions and ranges.
+//!
+//! Only available with the `lsp-types` feature.
+//!
+//! The Language Server Protocol identifies positions in a document by
+//! line and column.  Lines are separated by LF, CR, or CRLF (see
+//! [`lines_crlf`]), and columns are measured in the position encoding
+//! negotiated between client and server: utf8 code units (bytes), utf16
+//! code units, or utf32 code units (chars).  Encodings other than those
+//! three are treated as utf16, which is the protocol's default.
+//!
+//! Following the protocol, columns past the end of a line refer to the
+//! end of that line (not including its line break), and lines past the
+//! end of the document refer to the end of the document.  Byte indices
+//! in the middle of a char refer to the start of that char, consistent
+//! with the rest of this crate.
+//!
+//! Line and column values that don't fit in a `u32` saturate to
+//! `u32::MAX`.
+
+use core::ops::Range;
+
+use lsp_types::{Position, PositionEncodingKind};
+
+use crate::{chars, lines_crlf, utf16};
+
+/// Converts from byte-index to an LSP position in a string slice.
+///
+/// Any past-the-end index will return the position of the end of the
+/// text.
+///
+/// Runs in O(N) time.
+pub fn position_from_byte_idx(
+    text: &str,
+    byte_idx: usize,
+    encoding: &PositionEncodingKind,
+) -> Position {
+    let mut byte_idx = byte_idx.min(text.len());
+    while !text.is_char_boundary(byte_idx) {
+        byte_idx -= 1;
+    }
+    if !crate::is_not_crlf_middle(byte_idx, text.as_bytes()) {
+        // Positions can't point between the CR and LF, so use the CR.
+        byte_idx -= 1;
+    }
+    let line_idx = lines_crlf::from_byte_idx(text, byte_idx);
+    let line_start = lines_crlf::to_byte_idx(text, line_idx);
+    let before = &text[line_start..byte_idx];
+    let column = match Encoding::from_kind(encoding) {
+        Encoding::Utf8 => before.len(),
+        Encoding::Utf16 => utf16::count(before),
+        Encoding::Utf32 => chars::count(before),
+    };
+
+    Position::new(saturate(line_idx), saturate(column))
+}
+
+/// Converts from an LSP position to byte-index in a string slice.
+///
+/// Runs in O(N) time.
+pub fn position_to_byte_idx(
+    text: &str,
+    position: Position,
+    encoding: &PositionEncodingKind,
+) -> usize {
+    position_to_byte_idx_from(text, (0, 0), position, encoding).1
+}
+
+/// Converts from a byte range to an LSP range in a string slice.
+///
+/// Equivalent to calling [`position_from_byte_idx()`] on both ends of the
+/// range.
+///
+/// Runs in O(N) time.
+pub fn range_from_byte_range(
+    text: &str,
+    byte_range: Range<usize>,
+    encoding: &PositionEncodingKind,
+) -> lsp_types::Range {
+    lsp_types::Range::new(
+        position_from_byte_idx(text, byte_range.start, encoding),
+        position_from_byte_idx(text, byte_range.end, encoding),
+    )
+}
+
+/// Converts from an LSP range to a byte range in a string slice.
+///
+/// Equivalent to calling [`position_to_byte_idx()`] on both ends of the
+/// range, but when the end isn't on an earlier line than the start, only
+/// scans the text once.
+///
+/// Runs in O(N) time.
+pub fn range_to_byte_range(
+    text: &str,
+    range: lsp_types::Range,
+    encoding: &PositionEncodingKind,
+) -> Range<usize> {
+    let (line_start, start) = position_to_byte_idx_from(text, (0, 0), range.start, encoding);
+    let from = if range.end.line >= range.start.line {
+        (range.start.line as usize, line_start)
+    } else {
+        (0, 0)
+    };
+    let (_, end) = position_to_byte_idx_from(text, from, range.end, encoding);
+    start..end
+}
+
+/// Returns the byte range of the text between two LSP positions.
+///
+/// Like [`range_to_byte_range()`], except that if `end` resolves to
+/// before `start`, returns an empty range at `start`.  The result is
+/// thus always valid to slice `text` with.
+///
+/// Runs in O(N) time.
+pub fn slice_byte_range(
+    text: &str,
+    start: Position,
+    end: Position,
+    encoding: &PositionEncodingKind,
+) -> Range<usize> {
+    let range = range_to_byte_range(text, lsp_types::Range::new(start, end), encoding);
+    range.start..range.end.max(range.start)
+}
+
+/// Returns the text between two LSP positions, e.g. the text of an LSP
+/// range.
+///
+/// Equivalent to slicing `text` with [`slice_byte_range()`].
+///
+/// Runs in O(N) time.
+pub fn slice<'a>(
+    text: &'a str,
+    start: Position,
+    end: Position,
+    encoding: &PositionEncodingKind,
+) -> &'a str {
+    text.get(slice_byte_range(text, start, end, encoding))
+        .unwrap_or("")
+}
+
+//-------------------------------------------------------------
+
+enum Encoding {
+    Utf8,
+    Utf16,
+    Utf32,
+}
+
+impl Encoding {
+    #[inline(always)]
+    fn from_kind(kind: &PositionEncodingKind) -> Encoding {
+        match kind.as_str() {
+            "utf-8" => Encoding::Utf8,
+            "utf-32" => Encoding::Utf32,
+            _ => Encoding::Utf16,
+        }
+    }
+}
+
+/// Converts from an LSP position to byte-index, scanning from a known line
+/// start `from` (as `(line_idx, byte_idx)`) at or before the position's
+/// line.
+///
+/// Returns the byte index of the start of the position's line, and of the
+/// position itself.
+#[inline(always)]
+fn position_to_byte_idx_from(
+    text: &str,
+    from: (usize, usize),
+    position: Position,
+    encoding: &PositionEncodingKind,
+) -> (usize, usize) {
+    let (from_line, from_byte) = from;
+    let rest = crate::split_str_at(text, from_byte).1;
+    let line_offset = (position.line as usize).saturating_sub(from_line);
+    let line_start = from_byte + lines_crlf::to_byte_idx(rest, line_offset);
+    let line = crate::split_str_at(text, line_start).1;
+    let line = trim_line_break(crate::split_str_at(line, lines_crlf::to_byte_idx(line, 1)).0);
+
+    let column = position.character as usize;
+    let offset = match Encoding::from_kind(encoding) {
+        Encoding::Utf8 => {
+            let mut i = column.min(line.len());
+            while !line.is_char_boundary(i) {
+                i -= 1;
+            }
+            i
+        }
+        Encoding::Utf16 => utf16::to_byte_idx(line, column),
+        Encoding::Utf32 => chars::to_byte_idx(line, column),
+    };
+
+    (line_start, line_start + offset)
+}
+
+#[inline(always)]
+fn saturate(n: usize) -> u32 {
+    u32::try_from(n).unwrap_or(u32::MAX)
+}
+
+/// Removes a single trailing LF, CR, or CRLF, if any.
+#[inline(always)]
+fn trim_line_break(line: &str) -> &str {
+    let line = line.strip_suffix('\n').unwrap_or(line);
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Line 1 has a surrogate pair at utf16 column 1, and a 3-byte char at
+    // byte column 5.
+    const TEXT: &str = "Hello\r\nH🐸せ!\rworld\n";
+
+    const UTF8: PositionEncodingKind = PositionEncodingKind::UTF8;
+    const UTF16: PositionEncodingKind = PositionEncodingKind::UTF16;
+    const UTF32: PositionEncodingKind = PositionEncodingKind::UTF32;
+
+    #[test]
+    fn position_from_byte_idx_01() {
+        assert_eq!(Position::new(0, 0), position_from_byte_idx(TEXT, 0, &UTF16));
+        assert_eq!(Position::new(0, 5), position_from_byte_idx(TEXT, 5, &UTF16));
+        // Middle of the CRLF.
+        assert_eq!(Position::new(0, 5), position_from_byte_idx(TEXT, 6, &UTF16));
+        assert_eq!(Position::new(1, 0), position_from_byte_idx(TEXT, 7, &UTF16));
+
+        // Before and after the frog.
+        assert_eq!(Position::new(1, 1), position_from_byte_idx(TEXT, 8, &UTF8));
+        assert_eq!(Position::new(1, 1), position_from_byte_idx(TEXT, 8, &UTF16));
+        assert_eq!(Position::new(1, 1), position_from_byte_idx(TEXT, 8, &UTF32));
+        assert_eq!(Position::new(1, 5), position_from_byte_idx(TEXT, 12, &UTF8));
+        assert_eq!(
+            Position::new(1, 3),
+            position_from_byte_idx(TEXT, 12, &UTF16)
+        );
+        assert_eq!(
+            Position::new(1, 2),
+            position_from_byte_idx(TEXT, 12, &UTF32)
+        );
+
+        // Middle of the frog.
+        assert_eq!(Position::new(1, 1), position_from_byte_idx(TEXT, 10, &UTF8));
+        assert_eq!(
+            Position::new(1, 1),
+            position_from_byte_idx(TEXT, 10, &UTF16)
+        );
+
+        assert_eq!(
+            Position::new(2, 0),
+            position_from_byte_idx(TEXT, 17, &UTF16)
+        );
+        assert_eq!(
+            Position::new(3, 0),
+            position_from_byte_idx(TEXT, 23, &UTF16)
+        );
+
+        // Past the end.
+        assert_eq!(
+            Position::new(3, 0),
+            position_from_byte_idx(TEXT, 100, &UTF16)
+        );
+    }
+
+    #[test]
+    fn position_to_byte_idx_01() {
+        assert_eq!(0, position_to_byte_idx(TEXT, Position::new(0, 0), &UTF16));
+        assert_eq!(5, position_to_byte_idx(TEXT, Position::new(0, 5), &UTF16));
+        assert_eq!(7, position_to_byte_idx(TEXT, Position::new(1, 0), &UTF16));
+
+        assert_eq!(12, position_to_byte_idx(TEXT, Position::new(1, 5), &UTF8));
+        assert_eq!(12, position_to_byte_idx(TEXT, Position::new(1, 3), &UTF16));
+        assert_eq!(12, position_to_byte_idx(TEXT, Position::new(1, 2), &UTF32));
+
+        // Middle of the frog.
+        assert_eq!(8, position_to_byte_idx(TEXT, Position::new(1, 3), &UTF8));
+        assert_eq!(8, position_to_byte_idx(TEXT, Position::new(1, 2), &UTF16));
+
+        // Past the end of a line.
+        assert_eq!(5, position_to_byte_idx(TEXT, Position::new(0, 9), &UTF8));
+        assert_eq!(16, position_to_byte_idx(TEXT, Position::new(1, 99), &UTF16));
+        assert_eq!(22, position_to_byte_idx(TEXT, Position::new(2, 99), &UTF32));
+
+        // Past the end of the text.
+        assert_eq!(23, position_to_byte_idx(TEXT, Position::new(3, 5), &UTF16));
+        assert_eq!(23, position_to_byte_idx(TEXT, Position::new(9, 5), &UTF16));
+    }
+
+    #[test]
+    fn unknown_encoding_is_utf16() {
+        let kind = PositionEncodingKind::new("utf-7");
+        assert_eq!(Position::new(1, 3), position_from_byte_idx(TEXT, 12, &kind));
+        assert_eq!(12, position_to_byte_idx(TEXT, Position::new(1, 3), &kind));
+    }
+
+    #[test]
+    fn range_to_byte_range_01() {
+        // Each end resolved independently, in either order.
+        for encoding in [UTF8, UTF16, UTF32] {
+            for start_line in 0..5 {
+                for end_line in 0..5 {
+                    for (start_col, end_col) in [(0, 0), (1, 3), (3, 1), (2, 99)] {
+                        let start = Position::new(start_line, start_col);
+                        let end = Position::new(end_line, end_col);
+                        let expected = position_to_byte_idx(TEXT, start, &encoding)
+                            ..position_to_byte_idx(TEXT, end, &encoding);
+                        let range = lsp_types::Range::new(start, end);
+                        assert_eq!(expected, range_to_byte_range(TEXT, range, &encoding));
+
+                        let expected = expected.start..expected.end.max(expected.start);
+                        assert_eq!(expected, slice_byte_range(TEXT, start, end, &encoding));
+                        assert_eq!(&TEXT[expected], slice(TEXT, start, end, &encoding));
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn slice_01() {
+        let (start, end) = (Position::new(0, 3), Position::new(1, 2));
+        assert_eq!("lo\r\nH🐸", slice(TEXT, start, end, &UTF32));
+        assert_eq!("lo\r\nH🐸", slice(TEXT, start, Position::new(1, 3), &UTF16));
+        assert_eq!("", slice(TEXT, end, start, &UTF16));
+        assert_eq!(
+            "world\n",
+            slice(TEXT, Position::new(2, 0), Position::new(9, 0), &UTF8)
+        );
+    }
+
+    #[test]
+    fn range_round_trip() {
+        for encoding in [UTF8, UTF16, UTF32] {
+            for (start, _) in TEXT.char_indices() {
+                let range = range_from_byte_range(TEXT, start..TEXT.len(), &encoding);
+                let byte_range = range_to_byte_range(TEXT, range, &encoding);
+                if TEXT[..start].ends_with('\r') && TEXT[start..].starts_with('\n') {
+                    // The middle of a CRLF maps to the end of its line.
+                    assert_eq!(start - 1..TEXT.len(), byte_range);
+                } else {
+                    assert_eq!(start..TEXT.len(), byte_range);
+                }
+            }
+        }
+    }
+}
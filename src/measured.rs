@@ -0,0 +1,289 @@
+//! A string slice with its lengths measured up front.
+//!
+//! The free functions of this crate never store anything, so asking for
+//! e.g. the char length of a text twice scans it twice.  A [`MeasuredStr`]
+//! instead measures its text once on creation, after which its lengths are
+//! O(1) to get.  It also uses them to convert indices near the end of the
+//! text, in either direction, by scanning from the end rather than the
+//! start.
+//!
+//! Lines are as defined by the [`lines`](crate::lines) module (all Unicode
+//! line breaks).
+
+use crate::{chars, lines, utf16};
+
+/// A string slice along with its length in chars, utf16 code units, and
+/// lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MeasuredStr<'a> {
+    text: &'a str,
+    char_len: usize,
+    utf16_len: usize,
+    line_breaks: usize,
+}
+
+impl<'a> MeasuredStr<'a> {
+    /// Measures a string slice.
+    ///
+    /// Runs in O(N) time.
+    pub fn new(text: &'a str) -> MeasuredStr<'a> {
+        let char_len = chars::count(text);
+        MeasuredStr {
+            text,
+            char_len,
+            utf16_len: char_len + utf16::count_surrogates(text),
+            line_breaks: lines::count_breaks(text),
+        }
+    }
+
+    /// Returns the text.
+    #[inline]
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Returns the length of the text in chars.
+    ///
+    /// Runs in O(1) time.
+    #[inline]
+    pub fn char_len(&self) -> usize {
+        self.char_len
+    }
+
+    /// Returns the length of the text in utf16 code units.
+    ///
+    /// Runs in O(1) time.
+    #[inline]
+    pub fn utf16_len(&self) -> usize {
+        self.utf16_len
+    }
+
+    /// Returns the number of lines in the text.
+    ///
+    /// This is one more than the number of line breaks, as in the rest of
+    /// the crate: a text ending in a line break ends with an empty line.
+    ///
+    /// Runs in O(1) time.
+    #[inline]
+    pub fn line_count(&self) -> usize {
+        self.line_breaks + 1
+    }
+
+    /// Converts from byte-index to char-index.
+    ///
+    /// See [`chars::from_byte_idx()`].
+    ///
+    /// Runs in O(N) time, where N is the distance from `byte_idx` to the
+    /// nearest end of the text.
+    #[inline]
+    pub fn char_from_byte_idx(&self, byte_idx: usize) -> usize {
+        chars::from_byte_idx_with_count(self.text, byte_idx, self.char_len)
+    }
+
+    /// Converts from char-index to byte-index.
+    ///
+    /// See [`chars::to_byte_idx()`].
+    ///
+    /// Runs in O(N) time, where N is the distance from `char_idx` to the
+    /// nearest end of the text, or O(1) if it's past the end.
+    #[inline]
+    pub fn char_to_byte_idx(&self, char_idx: usize) -> usize {
+        if char_idx >= self.char_len {
+            self.text.len()
+        } else if char_idx >= self.char_len / 2 {
+            self.count_back(self.char_len - char_idx, chars::count)
+                .map_or(0, |(block_start, block, idx)| {
+                    block_start + chars::to_byte_idx(block, idx)
+                })
+        } else {
+            chars::to_byte_idx(self.text, char_idx)
+        }
+    }
+
+    /// Converts from byte-index to utf16-code-unit-index.
+    ///
+    /// See [`utf16::from_byte_idx()`].
+    ///
+    /// Runs in O(N) time, where N is the distance from `byte_idx` to the
+    /// nearest end of the text.
+    #[inline]
+    pub fn utf16_from_byte_idx(&self, byte_idx: usize) -> usize {
+        utf16::from_byte_idx_with_count(self.text, byte_idx, self.utf16_len)
+    }
+
+    /// Converts from utf16-code-unit-index to byte-index.
+    ///
+    /// See [`utf16::to_byte_idx()`].
+    ///
+    /// Runs in O(N) time, where N is the distance from `utf16_idx` to the
+    /// nearest end of the text, or O(1) if it's past the end.
+    #[inline]
+    pub fn utf16_to_byte_idx(&self, utf16_idx: usize) -> usize {
+        if utf16_idx >= self.utf16_len {
+            self.text.len()
+        } else if utf16_idx >= self.utf16_len / 2 {
+            self.count_back(self.utf16_len - utf16_idx, utf16::count)
+                .map_or(0, |(block_start, block, idx)| {
+                    block_start + utf16::to_byte_idx(block, idx)
+                })
+        } else {
+            utf16::to_byte_idx(self.text, utf16_idx)
+        }
+    }
+
+    /// Converts from byte-index to line-index.
+    ///
+    /// See [`lines::from_byte_idx()`].
+    ///
+    /// Runs in O(N) time, where N is the distance from `byte_idx` to the
+    /// nearest end of the text.
+    #[inline]
+    pub fn lines_from_byte_idx(&self, byte_idx: usize) -> usize {
+        // A CRLF is only counted as a break if the tail includes its CR.
+        let byte_idx = match byte_idx {
+            i if crate::is_not_crlf_middle(i, self.text.as_bytes()) => i,
+            i => i - 1,
+        };
+        match self.tail_from(byte_idx) {
+            Some(tail) => self.line_breaks - lines::count_breaks(tail),
+            None => lines::from_byte_idx(self.text, byte_idx),
+        }
+    }
+
+    /// Converts from line-index to byte-index.
+    ///
+    /// See [`lines::to_byte_idx()`].
+    ///
+    /// Runs in O(N) time, where N is the distance from the start of the
+    /// line to the nearest end of the text, or O(1) if `line_idx` is past
+    /// the last line of the text.
+    #[inline]
+    pub fn lines_to_byte_idx(&self, line_idx: usize) -> usize {
+        if line_idx > self.line_breaks {
+            self.text.len()
+        } else if line_idx >= self.line_count() / 2 {
+            // The line starts just after the line break before it, which is
+            // the last line break that isn't in the line's tail.
+            self.count_back(self.line_breaks - line_idx + 1, lines::count_breaks)
+                .map_or(0, |(block_start, block, idx)| {
+                    block_start + lines::to_byte_idx(block, idx + 1)
+                })
+        } else {
+            lines::to_byte_idx(self.text, line_idx)
+        }
+    }
+
+    //-----------------------------------------------------
+
+    /// Scans backward from the end of the text a block at a time, with
+    /// `count` counting the units (chars, etc.) of each block, until
+    /// `tail_len` units have been counted.
+    ///
+    /// Returns the start of the block that the last of those units is in,
+    /// the block, and the index of that unit within the block.  Returns
+    /// `None` if the text has fewer than `tail_len` units.
+    #[inline(always)]
+    fn count_back<F>(&self, tail_len: usize, count: F) -> Option<(usize, &'a str, usize)>
+    where
+        F: Fn(&str) -> usize,
+    {
+        // Big enough to amortize the setup costs of the chunked counting.
+        const BLOCK_LEN: usize = 1 << 12;
+
+        let mut end = self.text.len();
+        let mut counted = 0;
+        while end > 0 {
+            // Blocks never split a char or a CRLF.
+            let mut start = chars::floor_byte_boundary(self.text, end.saturating_sub(BLOCK_LEN));
+            if !crate::is_not_crlf_middle(start, self.text.as_bytes()) {
+                start -= 1;
+            }
+            let block = self.text.get(start..end).unwrap_or("");
+            let block_count = count(block);
+            if counted + block_count >= tail_len {
+                return Some((start, block, counted + block_count - tail_len));
+            }
+            counted += block_count;
+            end = start;
+        }
+        None
+    }
+
+    /// Returns the text from the start of the char containing `byte_idx`
+    /// to the end, if `byte_idx` is in the second half of the text and so
+    /// nearer its end than its start.
+    #[inline(always)]
+    fn tail_from(&self, byte_idx: usize) -> Option<&'a str> {
+        if byte_idx < self.text.len() / 2 {
+            return None;
+        }
+        let byte_idx = chars::floor_byte_boundary(self.text, byte_idx);
+        Some(crate::split_str_at(self.text, byte_idx).1)
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r\n🐸🐸🐸";
+
+    #[test]
+    fn lengths_01() {
+        let text = MeasuredStr::new(TEXT);
+        assert_eq!(TEXT, text.text());
+        assert_eq!(chars::count(TEXT), text.char_len());
+        assert_eq!(utf16::count(TEXT), text.utf16_len());
+        assert_eq!(lines::count_breaks(TEXT) + 1, text.line_count());
+
+        let text = MeasuredStr::new("");
+        assert_eq!(
+            (0, 0, 1),
+            (text.char_len(), text.utf16_len(), text.line_count())
+        );
+    }
+
+    #[test]
+    fn matches_free_functions() {
+        for end in (0..=TEXT.len()).filter(|&i| TEXT.is_char_boundary(i)) {
+            let text = &TEXT[..end];
+            let measured = MeasuredStr::new(text);
+            for i in 0..(text.len() + 2) {
+                assert_eq!(
+                    chars::from_byte_idx(text, i),
+                    measured.char_from_byte_idx(i)
+                );
+                assert_eq!(chars::to_byte_idx(text, i), measured.char_to_byte_idx(i));
+                assert_eq!(
+                    utf16::from_byte_idx(text, i),
+                    measured.utf16_from_byte_idx(i)
+                );
+                assert_eq!(utf16::to_byte_idx(text, i), measured.utf16_to_byte_idx(i));
+                assert_eq!(
+                    lines::from_byte_idx(text, i),
+                    measured.lines_from_byte_idx(i)
+                );
+                assert_eq!(lines::to_byte_idx(text, i), measured.lines_to_byte_idx(i));
+            }
+        }
+    }
+
+    #[test]
+    fn matches_free_functions_long() {
+        // Long enough to be scanned from the end in several blocks.
+        for prefix in ["", "a", "\r"] {
+            let text = [prefix, &TEXT.repeat(300)].concat();
+            let text = text.as_str();
+            let measured = MeasuredStr::new(text);
+            for i in (0..(text.len() + 2)).step_by(13) {
+                assert_eq!(chars::to_byte_idx(text, i), measured.char_to_byte_idx(i));
+                assert_eq!(utf16::to_byte_idx(text, i), measured.utf16_to_byte_idx(i));
+            }
+            for i in 0..(measured.line_count() + 1) {
+                assert_eq!(lines::to_byte_idx(text, i), measured.lines_to_byte_idx(i));
+            }
+        }
+    }
+}
@@ -0,0 +1,857 @@
+//! Naive reference implementations of the indexing functions.
+//!
+//! This module has straightforward, obviously-correct implementations of
+//! the core counting and conversion functions of the
+//! [`chars`](crate::chars), [`utf16`](crate::utf16), and lines modules
+//! (`count()`, `from_byte_idx()`, `to_byte_idx()`, `split_at()`, and so
+//! on), with the same signatures and behavior.  Not every function has a
+//! naive counterpart: e.g. the iterators and line-access functions, and
+//! the `lines_custom`, `wtf8`, and `cesu8` modules, aren't covered.  They
+//! process text a byte or char at a time, and are far slower than their
+//! counterparts.
+//!
+//! They're the oracles that this crate's own tests check against, and are
+//! exposed for differential testing of code built on this crate, for
+//! auditing, and as a simple fallback on exotic targets.
+
+/// Naive implementations of the [`chars`](crate::chars) functions.
+pub mod chars {
+    use core::ops::Range;
+
+    /// Naive implementation of [`crate::chars::count()`].
+    pub fn count(text: &str) -> usize {
+        from_byte_idx(text, text.len())
+    }
+
+    /// Naive implementation of [`crate::chars::from_byte_idx()`].
+    pub fn from_byte_idx(text: &str, byte_idx: usize) -> usize {
+        let mut byte_i = 0;
+        let mut char_i = 0;
+
+        while byte_i < byte_idx && byte_i < text.len() {
+            byte_i += 1;
+            if text.is_char_boundary(byte_i) {
+                char_i += 1;
+            }
+        }
+
+        char_i
+    }
+
+    /// Naive implementation of [`crate::chars::from_byte_idx_snapped()`].
+    pub fn from_byte_idx_snapped(text: &str, byte_idx: usize) -> (usize, usize) {
+        let boundary = floor_byte_boundary(text, byte_idx);
+        (from_byte_idx(text, boundary), byte_idx - boundary)
+    }
+
+    /// Naive implementation of [`crate::chars::to_byte_idx()`].
+    pub fn to_byte_idx(text: &str, char_idx: usize) -> usize {
+        let mut byte_i = 0;
+        let mut char_i = 0;
+
+        while char_i < char_idx && byte_i < text.len() {
+            byte_i += 1;
+            if text.is_char_boundary(byte_i) {
+                char_i += 1;
+            }
+        }
+
+        byte_i
+    }
+
+    /// Naive implementation of [`crate::chars::split_at()`].
+    pub fn split_at(text: &str, char_idx: usize) -> (&str, &str) {
+        text.split_at(to_byte_idx(text, char_idx))
+    }
+
+    /// Naive implementation of [`crate::chars::floor_byte_boundary()`].
+    pub fn floor_byte_boundary(text: &str, byte_idx: usize) -> usize {
+        let mut i = byte_idx.min(text.len());
+        while !text.is_char_boundary(i) {
+            i -= 1;
+        }
+        i
+    }
+
+    /// Naive implementation of [`crate::chars::shrink_range()`].
+    pub fn shrink_range(text: &str, byte_range: Range<usize>) -> Range<usize> {
+        let start = ceil_byte_boundary(text, byte_range.start);
+        let end = floor_byte_boundary(text, byte_range.end);
+        start..end.max(start)
+    }
+
+    /// Naive implementation of [`crate::chars::expand_range()`].
+    pub fn expand_range(text: &str, byte_range: Range<usize>) -> Range<usize> {
+        let start = floor_byte_boundary(text, byte_range.start);
+        let end = ceil_byte_boundary(text, byte_range.end.max(byte_range.start));
+        start..end
+    }
+
+    /// Naive implementation of [`crate::chars::common_prefix()`].
+    pub fn common_prefix(a: &str, b: &str) -> usize {
+        a.chars()
+            .zip(b.chars())
+            .take_while(|(ca, cb)| ca == cb)
+            .map(|(c, _)| c.len_utf8())
+            .sum()
+    }
+
+    /// Naive implementation of [`crate::chars::common_suffix()`].
+    pub fn common_suffix(a: &str, b: &str) -> usize {
+        a.chars()
+            .rev()
+            .zip(b.chars().rev())
+            .take_while(|(ca, cb)| ca == cb)
+            .map(|(c, _)| c.len_utf8())
+            .sum()
+    }
+
+    fn ceil_byte_boundary(text: &str, byte_idx: usize) -> usize {
+        let mut i = byte_idx.min(text.len());
+        while !text.is_char_boundary(i) {
+            i += 1;
+        }
+        i
+    }
+}
+
+/// Naive implementations of the [`utf16`](crate::utf16) functions.
+pub mod utf16 {
+    /// Naive implementation of [`crate::utf16::count()`].
+    pub fn count(text: &str) -> usize {
+        from_byte_idx(text, text.len())
+    }
+
+    /// Naive implementation of [`crate::utf16::count_surrogates()`].
+    pub fn count_surrogates(text: &str) -> usize {
+        text.chars().map(|c| c.len_utf16() - 1).sum()
+    }
+
+    /// Naive implementation of [`crate::utf16::from_byte_idx()`].
+    pub fn from_byte_idx(text: &str, byte_idx: usize) -> usize {
+        let mut byte_i = 0;
+        let mut utf16_i = 0;
+
+        for c in text.chars() {
+            byte_i += c.len_utf8();
+            if byte_i > byte_idx {
+                break;
+            }
+            utf16_i += c.len_utf16();
+            if byte_i == byte_idx {
+                break;
+            }
+        }
+
+        utf16_i
+    }
+
+    /// Naive implementation of [`crate::utf16::from_byte_idx_snapped()`].
+    pub fn from_byte_idx_snapped(text: &str, byte_idx: usize) -> (usize, usize) {
+        let boundary = super::chars::floor_byte_boundary(text, byte_idx);
+        (from_byte_idx(text, boundary), byte_idx - boundary)
+    }
+
+    /// Naive implementation of [`crate::utf16::to_byte_idx()`].
+    pub fn to_byte_idx(text: &str, utf16_idx: usize) -> usize {
+        let mut byte_i = 0;
+        let mut utf16_i = 0;
+
+        for c in text.chars() {
+            utf16_i += c.len_utf16();
+            if utf16_i > utf16_idx {
+                break;
+            }
+            byte_i += c.len_utf8();
+            if utf16_i == utf16_idx {
+                break;
+            }
+        }
+
+        byte_i
+    }
+
+    /// Naive implementation of [`crate::utf16::split_at()`].
+    pub fn split_at(text: &str, utf16_idx: usize) -> (&str, &str) {
+        text.split_at(to_byte_idx(text, utf16_idx))
+    }
+}
+
+/// Naive implementations of the [`lines`](crate::lines) functions.
+pub mod lines {
+    #![allow(clippy::collapsible_match, clippy::manual_range_patterns)]
+
+    /// Naive implementation of [`crate::lines::count_breaks()`].
+    pub fn count_breaks(text: &str) -> usize {
+        from_byte_idx(text, text.len())
+    }
+
+    /// Naive implementation of [`crate::lines::count_lines()`].
+    pub fn count_lines(text: &str, final_empty_line: bool) -> usize {
+        let breaks = count_breaks(text);
+        // `str::lines()` omits the final line exactly when it's empty.
+        let last_line_is_empty = to_byte_idx(text, breaks) == text.len();
+        if final_empty_line || !last_line_is_empty {
+            breaks + 1
+        } else {
+            breaks
+        }
+    }
+
+    /// Naive implementation of [`crate::lines::from_byte_idx()`].
+    pub fn from_byte_idx(text: &str, byte_idx: usize) -> usize {
+        let mut byte_itr = text.bytes();
+        let mut i = 0;
+        let mut line_count = 0;
+
+        while let Some(byte) = byte_itr.next() {
+            if i >= byte_idx {
+                break;
+            }
+
+            match byte {
+                0x0A | 0x0B | 0x0C => {
+                    line_count += 1;
+                }
+                0x0D => {
+                    // Check for a following LF.  By cloning the itr, we're
+                    // peeking without actually stepping the original itr.
+                    if let Some(0x0A) = byte_itr.clone().next() {
+                        // Do nothing.  The CRLF will be properly counted
+                        // on the next iteration if the LF is behind
+                        // byte_idx.
+                    } else {
+                        // There's no following LF, so the stand-alone CR is a
+                        // line ending itself.
+                        line_count += 1;
+                    }
+                }
+                0xC2 => {
+                    if (i + 1) < byte_idx {
+                        i += 1;
+                        if let Some(0x85) = byte_itr.next() {
+                            line_count += 1;
+                        }
+                    }
+                }
+                0xE2 => {
+                    if (i + 2) < byte_idx {
+                        i += 2;
+                        let byte2 = byte_itr.next();
+                        let byte3 = byte_itr.next().map(|b| b >> 1);
+                        if byte2 == Some(0x80) && byte3 == Some(0x54) {
+                            line_count += 1;
+                        }
+                    }
+                }
+                _ => {}
+            }
+
+            i += 1;
+        }
+
+        line_count
+    }
+
+    /// Naive implementation of [`crate::lines::from_byte_idx_snapped()`].
+    pub fn from_byte_idx_snapped(text: &str, byte_idx: usize) -> (usize, usize) {
+        let line_idx = from_byte_idx(text, byte_idx);
+        (line_idx, byte_idx - to_byte_idx(text, line_idx))
+    }
+
+    /// Naive implementation of [`crate::lines::to_byte_idx()`].
+    pub fn to_byte_idx(text: &str, line_idx: usize) -> usize {
+        let mut byte_itr = text.bytes();
+        let mut i = 0;
+        let mut line_count = 0;
+
+        while let Some(byte) = byte_itr.next() {
+            if line_count == line_idx {
+                break;
+            }
+
+            match byte {
+                0x0A | 0x0B | 0x0C => {
+                    line_count += 1;
+                }
+                0x0D => {
+                    // Check for a following LF.  By cloning the itr, we're
+                    // peeking without actually stepping the original itr.
+                    if let Some(0x0A) = byte_itr.clone().next() {
+                        // Skip the LF, since it's part of the CRLF
+                        // pair.
+                        i += 1;
+                        byte_itr.next();
+                    }
+                    line_count += 1;
+                }
+                0xC2 => {
+                    i += 1;
+                    if let Some(0x85) = byte_itr.next() {
+                        line_count += 1;
+                    }
+                }
+                0xE2 => {
+                    i += 2;
+                    let byte2 = byte_itr.next();
+                    let byte3 = byte_itr.next().map(|b| b >> 1);
+                    if byte2 == Some(0x80) && byte3 == Some(0x54) {
+                        line_count += 1;
+                    }
+                }
+                _ => {}
+            }
+
+            i += 1;
+        }
+
+        i
+    }
+
+    /// Naive implementation of [`crate::lines::split_at()`].
+    pub fn split_at(text: &str, line_idx: usize) -> (&str, &str) {
+        text.split_at(to_byte_idx(text, line_idx))
+    }
+
+    /// Naive implementation of [`crate::lines::is_break_start()`].
+    pub fn is_break_start(text: &str, byte_idx: usize) -> bool {
+        if byte_idx >= text.len() || !text.is_char_boundary(byte_idx) {
+            return false;
+        }
+        match text[byte_idx..].chars().next() {
+            Some('\u{000A}') => !text[..byte_idx].ends_with('\u{000D}'),
+            Some('\u{000B}' | '\u{000C}' | '\u{000D}' | '\u{0085}' | '\u{2028}' | '\u{2029}') => {
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Naive implementation of [`crate::lines::from_lines_crlf_idx()`].
+    pub fn from_lines_crlf_idx(text: &str, line_idx: usize) -> usize {
+        from_byte_idx(text, super::lines_crlf::to_byte_idx(text, line_idx))
+    }
+
+    /// Naive implementation of [`crate::lines::from_lines_lf_idx()`].
+    pub fn from_lines_lf_idx(text: &str, line_idx: usize) -> usize {
+        from_byte_idx(text, super::lines_lf::to_byte_idx(text, line_idx))
+    }
+
+    /// Naive implementation of [`crate::lines::common_prefix()`].
+    pub fn common_prefix(a: &str, b: &str) -> usize {
+        (0..=a.len().min(b.len()))
+            .rev()
+            .find(|&i| {
+                a.as_bytes()[..i] == b.as_bytes()[..i] && is_line_start(a, i) && is_line_start(b, i)
+            })
+            .unwrap_or(0)
+    }
+
+    /// Naive implementation of [`crate::lines::common_suffix()`].
+    pub fn common_suffix(a: &str, b: &str) -> usize {
+        (0..=a.len().min(b.len()))
+            .rev()
+            .find(|&i| {
+                let (start_a, start_b) = (a.len() - i, b.len() - i);
+                a.as_bytes()[start_a..] == b.as_bytes()[start_b..]
+                    && is_line_start(a, start_a)
+                    && is_line_start(b, start_b)
+            })
+            .unwrap_or(0)
+    }
+
+    fn is_line_start(text: &str, byte_idx: usize) -> bool {
+        text.is_char_boundary(byte_idx)
+            && to_byte_idx(text, from_byte_idx(text, byte_idx)) == byte_idx
+    }
+
+    /// Naive implementation of [`crate::lines::has_break()`].
+    pub fn has_break(text: &str) -> bool {
+        first_break(text).is_some()
+    }
+
+    /// Naive implementation of [`crate::lines::first_break()`].
+    pub fn first_break(text: &str) -> Option<usize> {
+        (0..text.len()).find(|&i| is_break_start(text, i))
+    }
+}
+
+/// Naive implementations of the [`lines_crlf`](crate::lines_crlf) functions.
+pub mod lines_crlf {
+    /// Naive implementation of [`crate::lines_crlf::count_breaks()`].
+    pub fn count_breaks(text: &str) -> usize {
+        from_byte_idx(text, text.len())
+    }
+
+    /// Naive implementation of [`crate::lines_crlf::count_lines()`].
+    pub fn count_lines(text: &str, final_empty_line: bool) -> usize {
+        let breaks = count_breaks(text);
+        // `str::lines()` omits the final line exactly when it's empty.
+        let last_line_is_empty = to_byte_idx(text, breaks) == text.len();
+        if final_empty_line || !last_line_is_empty {
+            breaks + 1
+        } else {
+            breaks
+        }
+    }
+
+    /// Naive implementation of [`crate::lines_crlf::from_byte_idx()`].
+    pub fn from_byte_idx(text: &str, byte_idx: usize) -> usize {
+        let mut byte_itr = text.bytes();
+        let mut i = 0;
+        let mut line_count = 0;
+
+        while let Some(byte) = byte_itr.next() {
+            if i >= byte_idx {
+                break;
+            }
+
+            match byte {
+                0x0A => {
+                    line_count += 1;
+                }
+                0x0D => {
+                    // Check for a following LF.  By cloning the itr, we're
+                    // peeking without actually stepping the original itr.
+                    if let Some(0x0A) = byte_itr.clone().next() {
+                        // Do nothing.  The CRLF will be properly counted
+                        // on the next iteration if the LF is behind
+                        // byte_idx.
+                    } else {
+                        // There's no following LF, so the stand-alone CR is a
+                        // line ending itself.
+                        line_count += 1;
+                    }
+                }
+                _ => {}
+            }
+
+            i += 1;
+        }
+
+        line_count
+    }
+
+    /// Naive implementation of [`crate::lines_crlf::from_byte_idx_snapped()`].
+    pub fn from_byte_idx_snapped(text: &str, byte_idx: usize) -> (usize, usize) {
+        let line_idx = from_byte_idx(text, byte_idx);
+        (line_idx, byte_idx - to_byte_idx(text, line_idx))
+    }
+
+    /// Naive implementation of [`crate::lines_crlf::to_byte_idx()`].
+    pub fn to_byte_idx(text: &str, line_idx: usize) -> usize {
+        let mut byte_itr = text.bytes();
+        let mut i = 0;
+        let mut line_count = 0;
+
+        while let Some(byte) = byte_itr.next() {
+            if line_count == line_idx {
+                break;
+            }
+
+            match byte {
+                0x0A => {
+                    line_count += 1;
+                }
+                0x0D => {
+                    // Check for a following LF.  By cloning the itr, we're
+                    // peeking without actually stepping the original itr.
+                    if let Some(0x0A) = byte_itr.clone().next() {
+                        // Skip the LF, since it's part of the CRLF
+                        // pair.
+                        i += 1;
+                        byte_itr.next();
+                    }
+                    line_count += 1;
+                }
+                _ => {}
+            }
+
+            i += 1;
+        }
+
+        i
+    }
+
+    /// Naive implementation of [`crate::lines_crlf::split_at()`].
+    pub fn split_at(text: &str, line_idx: usize) -> (&str, &str) {
+        text.split_at(to_byte_idx(text, line_idx))
+    }
+
+    /// Naive implementation of [`crate::lines_crlf::is_break_start()`].
+    pub fn is_break_start(text: &str, byte_idx: usize) -> bool {
+        if byte_idx >= text.len() || !text.is_char_boundary(byte_idx) {
+            return false;
+        }
+        match text[byte_idx..].chars().next() {
+            Some('\u{000A}') => !text[..byte_idx].ends_with('\u{000D}'),
+            Some('\u{000D}') => true,
+            _ => false,
+        }
+    }
+
+    /// Naive implementation of [`crate::lines_crlf::from_lines_idx()`].
+    pub fn from_lines_idx(text: &str, line_idx: usize) -> usize {
+        from_byte_idx(text, super::lines::to_byte_idx(text, line_idx))
+    }
+
+    /// Naive implementation of [`crate::lines_crlf::from_lines_lf_idx()`].
+    pub fn from_lines_lf_idx(text: &str, line_idx: usize) -> usize {
+        from_byte_idx(text, super::lines_lf::to_byte_idx(text, line_idx))
+    }
+
+    /// Naive implementation of [`crate::lines_crlf::common_prefix()`].
+    pub fn common_prefix(a: &str, b: &str) -> usize {
+        (0..=a.len().min(b.len()))
+            .rev()
+            .find(|&i| {
+                a.as_bytes()[..i] == b.as_bytes()[..i] && is_line_start(a, i) && is_line_start(b, i)
+            })
+            .unwrap_or(0)
+    }
+
+    /// Naive implementation of [`crate::lines_crlf::common_suffix()`].
+    pub fn common_suffix(a: &str, b: &str) -> usize {
+        (0..=a.len().min(b.len()))
+            .rev()
+            .find(|&i| {
+                let (start_a, start_b) = (a.len() - i, b.len() - i);
+                a.as_bytes()[start_a..] == b.as_bytes()[start_b..]
+                    && is_line_start(a, start_a)
+                    && is_line_start(b, start_b)
+            })
+            .unwrap_or(0)
+    }
+
+    fn is_line_start(text: &str, byte_idx: usize) -> bool {
+        text.is_char_boundary(byte_idx)
+            && to_byte_idx(text, from_byte_idx(text, byte_idx)) == byte_idx
+    }
+
+    /// Naive implementation of [`crate::lines_crlf::has_break()`].
+    pub fn has_break(text: &str) -> bool {
+        first_break(text).is_some()
+    }
+
+    /// Naive implementation of [`crate::lines_crlf::first_break()`].
+    pub fn first_break(text: &str) -> Option<usize> {
+        (0..text.len()).find(|&i| is_break_start(text, i))
+    }
+}
+
+/// Naive implementations of the [`lines_lf`](crate::lines_lf) functions.
+pub mod lines_lf {
+    /// Naive implementation of [`crate::lines_lf::count_breaks()`].
+    pub fn count_breaks(text: &str) -> usize {
+        from_byte_idx(text, text.len())
+    }
+
+    /// Naive implementation of [`crate::lines_lf::count_lines()`].
+    pub fn count_lines(text: &str, final_empty_line: bool) -> usize {
+        let breaks = count_breaks(text);
+        // `str::lines()` omits the final line exactly when it's empty.
+        let last_line_is_empty = to_byte_idx(text, breaks) == text.len();
+        if final_empty_line || !last_line_is_empty {
+            breaks + 1
+        } else {
+            breaks
+        }
+    }
+
+    /// Naive implementation of [`crate::lines_lf::from_byte_idx()`].
+    pub fn from_byte_idx(text: &str, byte_idx: usize) -> usize {
+        let mut line_count = 0;
+
+        for (i, byte) in text.bytes().enumerate() {
+            if i >= byte_idx {
+                return line_count;
+            }
+            if byte == 0x0A {
+                line_count += 1;
+            }
+        }
+
+        line_count
+    }
+
+    /// Naive implementation of [`crate::lines_lf::from_byte_idx_snapped()`].
+    pub fn from_byte_idx_snapped(text: &str, byte_idx: usize) -> (usize, usize) {
+        let line_idx = from_byte_idx(text, byte_idx);
+        (line_idx, byte_idx - to_byte_idx(text, line_idx))
+    }
+
+    /// Naive implementation of [`crate::lines_lf::to_byte_idx()`].
+    pub fn to_byte_idx(text: &str, line_idx: usize) -> usize {
+        let mut line_count = 0;
+
+        for (i, byte) in text.bytes().enumerate() {
+            if line_count == line_idx {
+                return i;
+            }
+            if byte == 0x0A {
+                line_count += 1;
+            }
+        }
+
+        text.len()
+    }
+
+    /// Naive implementation of [`crate::lines_lf::split_at()`].
+    pub fn split_at(text: &str, line_idx: usize) -> (&str, &str) {
+        text.split_at(to_byte_idx(text, line_idx))
+    }
+
+    /// Naive implementation of [`crate::lines_lf::is_break_start()`].
+    pub fn is_break_start(text: &str, byte_idx: usize) -> bool {
+        if byte_idx >= text.len() || !text.is_char_boundary(byte_idx) {
+            return false;
+        }
+        match text[byte_idx..].chars().next() {
+            Some('\u{000A}') => !text[..byte_idx].ends_with('\u{000D}'),
+            Some('\u{000D}') => text[byte_idx..].starts_with("\r\n"),
+            _ => false,
+        }
+    }
+
+    /// Naive implementation of [`crate::lines_lf::from_lines_idx()`].
+    pub fn from_lines_idx(text: &str, line_idx: usize) -> usize {
+        from_byte_idx(text, super::lines::to_byte_idx(text, line_idx))
+    }
+
+    /// Naive implementation of [`crate::lines_lf::from_lines_crlf_idx()`].
+    pub fn from_lines_crlf_idx(text: &str, line_idx: usize) -> usize {
+        from_byte_idx(text, super::lines_crlf::to_byte_idx(text, line_idx))
+    }
+
+    /// Naive implementation of [`crate::lines_lf::common_prefix()`].
+    pub fn common_prefix(a: &str, b: &str) -> usize {
+        (0..=a.len().min(b.len()))
+            .rev()
+            .find(|&i| {
+                a.as_bytes()[..i] == b.as_bytes()[..i] && is_line_start(a, i) && is_line_start(b, i)
+            })
+            .unwrap_or(0)
+    }
+
+    /// Naive implementation of [`crate::lines_lf::common_suffix()`].
+    pub fn common_suffix(a: &str, b: &str) -> usize {
+        (0..=a.len().min(b.len()))
+            .rev()
+            .find(|&i| {
+                let (start_a, start_b) = (a.len() - i, b.len() - i);
+                a.as_bytes()[start_a..] == b.as_bytes()[start_b..]
+                    && is_line_start(a, start_a)
+                    && is_line_start(b, start_b)
+            })
+            .unwrap_or(0)
+    }
+
+    fn is_line_start(text: &str, byte_idx: usize) -> bool {
+        text.is_char_boundary(byte_idx)
+            && to_byte_idx(text, from_byte_idx(text, byte_idx)) == byte_idx
+    }
+
+    /// Naive implementation of [`crate::lines_lf::has_break()`].
+    pub fn has_break(text: &str) -> bool {
+        first_break(text).is_some()
+    }
+
+    /// Naive implementation of [`crate::lines_lf::first_break()`].
+    pub fn first_break(text: &str) -> Option<usize> {
+        (0..text.len()).find(|&i| is_break_start(text, i))
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r";
+
+    #[test]
+    fn matches_library() {
+        for i in 0..(TEXT.len() + 2) {
+            assert_eq!(
+                crate::chars::from_byte_idx(TEXT, i),
+                chars::from_byte_idx(TEXT, i)
+            );
+            assert_eq!(
+                crate::chars::from_byte_idx_snapped(TEXT, i),
+                chars::from_byte_idx_snapped(TEXT, i)
+            );
+            assert_eq!(
+                crate::chars::to_byte_idx(TEXT, i),
+                chars::to_byte_idx(TEXT, i)
+            );
+            assert_eq!(
+                crate::chars::floor_byte_boundary(TEXT, i),
+                chars::floor_byte_boundary(TEXT, i)
+            );
+            assert_eq!(
+                crate::utf16::from_byte_idx(TEXT, i),
+                utf16::from_byte_idx(TEXT, i)
+            );
+            assert_eq!(
+                crate::utf16::from_byte_idx_snapped(TEXT, i),
+                utf16::from_byte_idx_snapped(TEXT, i)
+            );
+            assert_eq!(
+                crate::utf16::to_byte_idx(TEXT, i),
+                utf16::to_byte_idx(TEXT, i)
+            );
+            assert_eq!(
+                crate::lines::from_byte_idx_snapped(TEXT, i),
+                lines::from_byte_idx_snapped(TEXT, i)
+            );
+            assert_eq!(
+                crate::lines::to_byte_idx(TEXT, i),
+                lines::to_byte_idx(TEXT, i)
+            );
+            assert_eq!(
+                crate::lines::is_break_start(TEXT, i),
+                lines::is_break_start(TEXT, i)
+            );
+            assert_eq!(
+                crate::lines_crlf::from_byte_idx_snapped(TEXT, i),
+                lines_crlf::from_byte_idx_snapped(TEXT, i)
+            );
+            assert_eq!(
+                crate::lines_crlf::to_byte_idx(TEXT, i),
+                lines_crlf::to_byte_idx(TEXT, i)
+            );
+            assert_eq!(
+                crate::lines_crlf::is_break_start(TEXT, i),
+                lines_crlf::is_break_start(TEXT, i)
+            );
+            assert_eq!(
+                crate::lines_lf::from_byte_idx_snapped(TEXT, i),
+                lines_lf::from_byte_idx_snapped(TEXT, i)
+            );
+            assert_eq!(
+                crate::lines_lf::to_byte_idx(TEXT, i),
+                lines_lf::to_byte_idx(TEXT, i)
+            );
+            assert_eq!(
+                crate::lines_lf::is_break_start(TEXT, i),
+                lines_lf::is_break_start(TEXT, i)
+            );
+            for j in 0..(TEXT.len() + 2) {
+                assert_eq!(
+                    crate::chars::shrink_range(TEXT, i..j),
+                    chars::shrink_range(TEXT, i..j)
+                );
+                assert_eq!(
+                    crate::chars::expand_range(TEXT, i..j),
+                    chars::expand_range(TEXT, i..j)
+                );
+            }
+        }
+        assert_eq!(
+            crate::utf16::count_surrogates(TEXT),
+            utf16::count_surrogates(TEXT)
+        );
+        for i in 0..12 {
+            assert_eq!(
+                crate::lines::from_lines_crlf_idx(TEXT, i),
+                lines::from_lines_crlf_idx(TEXT, i)
+            );
+            assert_eq!(
+                crate::lines::from_lines_lf_idx(TEXT, i),
+                lines::from_lines_lf_idx(TEXT, i)
+            );
+            assert_eq!(
+                crate::lines_crlf::from_lines_idx(TEXT, i),
+                lines_crlf::from_lines_idx(TEXT, i)
+            );
+            assert_eq!(
+                crate::lines_crlf::from_lines_lf_idx(TEXT, i),
+                lines_crlf::from_lines_lf_idx(TEXT, i)
+            );
+            assert_eq!(
+                crate::lines_lf::from_lines_idx(TEXT, i),
+                lines_lf::from_lines_idx(TEXT, i)
+            );
+            assert_eq!(
+                crate::lines_lf::from_lines_crlf_idx(TEXT, i),
+                lines_lf::from_lines_crlf_idx(TEXT, i)
+            );
+        }
+        let texts = [
+            "",
+            TEXT,
+            "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\r",
+            "Hel🐸lo\r\nworld\u{2028}こん\nにち\u{0085}は!\n\r",
+            "Hel🐸lo\r\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r",
+            "Hel🐸lo\nworld\u{2029}こん\rにち\u{0085}は!\n\r",
+            "Hel🐸lo\r\nworld\u{2028}\u{2028}こん\rにち\u{0085}は!\n\r",
+            "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は?\n\r",
+            "Hel🐸lo\r\nworld\u{2028}こん\rに\u{0085}は!\n\r",
+        ];
+        for a in texts {
+            for b in texts {
+                assert_eq!(
+                    crate::chars::common_prefix(a, b),
+                    chars::common_prefix(a, b)
+                );
+                assert_eq!(
+                    crate::chars::common_suffix(a, b),
+                    chars::common_suffix(a, b)
+                );
+                assert_eq!(
+                    crate::lines::common_prefix(a, b),
+                    lines::common_prefix(a, b)
+                );
+                assert_eq!(
+                    crate::lines::common_suffix(a, b),
+                    lines::common_suffix(a, b)
+                );
+                assert_eq!(
+                    crate::lines_crlf::common_prefix(a, b),
+                    lines_crlf::common_prefix(a, b)
+                );
+                assert_eq!(
+                    crate::lines_crlf::common_suffix(a, b),
+                    lines_crlf::common_suffix(a, b)
+                );
+                assert_eq!(
+                    crate::lines_lf::common_prefix(a, b),
+                    lines_lf::common_prefix(a, b)
+                );
+                assert_eq!(
+                    crate::lines_lf::common_suffix(a, b),
+                    lines_lf::common_suffix(a, b)
+                );
+            }
+        }
+        for start in (0..TEXT.len()).filter(|&i| TEXT.is_char_boundary(i)) {
+            let text = &TEXT[start..];
+            assert_eq!(crate::lines::first_break(text), lines::first_break(text));
+            assert_eq!(
+                crate::lines_crlf::first_break(text),
+                lines_crlf::first_break(text)
+            );
+            assert_eq!(
+                crate::lines_lf::first_break(text),
+                lines_lf::first_break(text)
+            );
+        }
+        for final_empty_line in [true, false] {
+            for text in ["", "a", TEXT, &TEXT[..(TEXT.len() - 1)]] {
+                assert_eq!(
+                    crate::lines::count_lines(text, final_empty_line),
+                    lines::count_lines(text, final_empty_line)
+                );
+                assert_eq!(
+                    crate::lines_crlf::count_lines(text, final_empty_line),
+                    lines_crlf::count_lines(text, final_empty_line)
+                );
+                assert_eq!(
+                    crate::lines_lf::count_lines(text, final_empty_line),
+                    lines_lf::count_lines(text, final_empty_line)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,536 @@
+//! Index text that is stored as a sequence of string segments.
+//!
+//! Gap buffers, ropes, piece tables, etc. store their text in multiple
+//! non-contiguous pieces.  By implementing [`TextSource`] for such a type,
+//! the functions in this module's submodules can count and convert
+//! indices over its text directly, without first copying it into a
+//! contiguous string.
+//!
+//! The submodules mirror the top-level modules of this crate, with the
+//! same functions and the same behavior: calling one of them on a text
+//! source is equivalent to calling the top-level function of the same
+//! name on the concatenation of its segments.
+//!
+//! ```
+//! use str_indices::segmented::{chars, lines};
+//!
+//! // E.g. the two halves of a gap buffer.
+//! let text = ["Hello\r", "\nworld!"];
+//!
+//! assert_eq!(13, chars::count(&text));
+//! assert_eq!(1, lines::count_breaks(&text));
+//! assert_eq!(7, lines::to_byte_idx(&text, 1));
+//! ```
+
+/// A piece of text stored as a sequence of string segments.
+///
+/// Segments may be empty.  Since segments are `&str`s, chars are never
+/// split across segment boundaries, but CRLF pairs may be.
+pub trait TextSource {
+    /// Iterator over the segments of the text, in order.
+    type Segments<'a>: Iterator<Item = &'a str>
+    where
+        Self: 'a;
+
+    /// Returns an iterator over the segments of the text, in order.
+    fn segments(&self) -> Self::Segments<'_>;
+}
+
+impl TextSource for str {
+    type Segments<'a> = core::iter::Once<&'a str>;
+
+    #[inline]
+    fn segments(&self) -> Self::Segments<'_> {
+        core::iter::once(self)
+    }
+}
+
+impl<S: AsRef<str>> TextSource for [S] {
+    type Segments<'a>
+        = core::iter::Map<core::slice::Iter<'a, S>, fn(&'a S) -> &'a str>
+    where
+        S: 'a;
+
+    #[inline]
+    fn segments(&self) -> Self::Segments<'_> {
+        self.iter().map(AsRef::as_ref)
+    }
+}
+
+impl<S: AsRef<str>, const N: usize> TextSource for [S; N] {
+    type Segments<'a>
+        = core::iter::Map<core::slice::Iter<'a, S>, fn(&'a S) -> &'a str>
+    where
+        S: 'a;
+
+    #[inline]
+    fn segments(&self) -> Self::Segments<'_> {
+        self.iter().map(AsRef::as_ref)
+    }
+}
+
+//-------------------------------------------------------------
+
+/// Index by chars.  See [`crate::chars`].
+pub mod chars {
+    use super::TextSource;
+
+    /// Counts the chars in a text source.
+    ///
+    /// Runs in O(N) time.
+    pub fn count<S: TextSource + ?Sized>(text: &S) -> usize {
+        text.segments().map(crate::chars::count).sum()
+    }
+
+    /// Converts from byte-index to char-index in a text source.
+    ///
+    /// See [`crate::chars::from_byte_idx()`].
+    ///
+    /// Runs in O(N) time.
+    pub fn from_byte_idx<S: TextSource + ?Sized>(text: &S, byte_idx: usize) -> usize {
+        super::from_byte_idx_summed(
+            text,
+            byte_idx,
+            crate::chars::count,
+            crate::chars::from_byte_idx,
+        )
+    }
+
+    /// Converts from char-index to byte-index in a text source.
+    ///
+    /// See [`crate::chars::to_byte_idx()`].
+    ///
+    /// Runs in O(N) time.
+    pub fn to_byte_idx<S: TextSource + ?Sized>(text: &S, char_idx: usize) -> usize {
+        super::to_byte_idx_summed(
+            text,
+            char_idx,
+            crate::chars::count,
+            crate::chars::to_byte_idx,
+        )
+    }
+}
+
+/// Index by utf16 code units.  See [`crate::utf16`].
+pub mod utf16 {
+    use super::TextSource;
+
+    /// Counts the utf16 code units that would be in a text source if it
+    /// were encoded as utf16.
+    ///
+    /// Runs in O(N) time.
+    pub fn count<S: TextSource + ?Sized>(text: &S) -> usize {
+        text.segments().map(crate::utf16::count).sum()
+    }
+
+    /// Counts the utf16 surrogate pairs that would be in a text source if
+    /// it were encoded as utf16.
+    ///
+    /// Runs in O(N) time.
+    pub fn count_surrogates<S: TextSource + ?Sized>(text: &S) -> usize {
+        text.segments().map(crate::utf16::count_surrogates).sum()
+    }
+
+    /// Converts from byte-index to utf16-code-unit-index in a text source.
+    ///
+    /// See [`crate::utf16::from_byte_idx()`].
+    ///
+    /// Runs in O(N) time.
+    pub fn from_byte_idx<S: TextSource + ?Sized>(text: &S, byte_idx: usize) -> usize {
+        super::from_byte_idx_summed(
+            text,
+            byte_idx,
+            crate::utf16::count,
+            crate::utf16::from_byte_idx,
+        )
+    }
+
+    /// Converts from utf16-code-unit-index to byte-index in a text source.
+    ///
+    /// See [`crate::utf16::to_byte_idx()`].
+    ///
+    /// Runs in O(N) time.
+    pub fn to_byte_idx<S: TextSource + ?Sized>(text: &S, utf16_idx: usize) -> usize {
+        super::to_byte_idx_summed(
+            text,
+            utf16_idx,
+            crate::utf16::count,
+            crate::utf16::to_byte_idx,
+        )
+    }
+}
+
+/// Index by lines (all Unicode line breaks).  See [`crate::lines`].
+pub mod lines {
+    use super::TextSource;
+
+    /// Counts the line breaks in a text source.
+    ///
+    /// Runs in O(N) time.
+    pub fn count_breaks<S: TextSource + ?Sized>(text: &S) -> usize {
+        super::count_breaks_crlf(text, crate::lines::count_breaks)
+    }
+
+    /// Converts from byte-index to line-index in a text source.
+    ///
+    /// See [`crate::lines::from_byte_idx()`].
+    ///
+    /// Runs in O(N) time.
+    pub fn from_byte_idx<S: TextSource + ?Sized>(text: &S, byte_idx: usize) -> usize {
+        super::from_byte_idx_crlf(
+            text,
+            byte_idx,
+            crate::lines::count_breaks,
+            crate::lines::from_byte_idx,
+        )
+    }
+
+    /// Converts from line-index to byte-index in a text source.
+    ///
+    /// See [`crate::lines::to_byte_idx()`].
+    ///
+    /// Runs in O(N) time.
+    pub fn to_byte_idx<S: TextSource + ?Sized>(text: &S, line_idx: usize) -> usize {
+        super::to_byte_idx_crlf(
+            text,
+            line_idx,
+            crate::lines::count_breaks,
+            crate::lines::to_byte_idx,
+        )
+    }
+}
+
+/// Index by lines (carriage return and line feed).  See
+/// [`crate::lines_crlf`].
+pub mod lines_crlf {
+    use super::TextSource;
+
+    /// Counts the line breaks in a text source.
+    ///
+    /// Runs in O(N) time.
+    pub fn count_breaks<S: TextSource + ?Sized>(text: &S) -> usize {
+        super::count_breaks_crlf(text, crate::lines_crlf::count_breaks)
+    }
+
+    /// Converts from byte-index to line-index in a text source.
+    ///
+    /// See [`crate::lines_crlf::from_byte_idx()`].
+    ///
+    /// Runs in O(N) time.
+    pub fn from_byte_idx<S: TextSource + ?Sized>(text: &S, byte_idx: usize) -> usize {
+        super::from_byte_idx_crlf(
+            text,
+            byte_idx,
+            crate::lines_crlf::count_breaks,
+            crate::lines_crlf::from_byte_idx,
+        )
+    }
+
+    /// Converts from line-index to byte-index in a text source.
+    ///
+    /// See [`crate::lines_crlf::to_byte_idx()`].
+    ///
+    /// Runs in O(N) time.
+    pub fn to_byte_idx<S: TextSource + ?Sized>(text: &S, line_idx: usize) -> usize {
+        super::to_byte_idx_crlf(
+            text,
+            line_idx,
+            crate::lines_crlf::count_breaks,
+            crate::lines_crlf::to_byte_idx,
+        )
+    }
+}
+
+/// Index by lines (line feed only).  See [`crate::lines_lf`].
+pub mod lines_lf {
+    use super::TextSource;
+
+    /// Counts the line breaks in a text source.
+    ///
+    /// Runs in O(N) time.
+    pub fn count_breaks<S: TextSource + ?Sized>(text: &S) -> usize {
+        text.segments().map(crate::lines_lf::count_breaks).sum()
+    }
+
+    /// Converts from byte-index to line-index in a text source.
+    ///
+    /// See [`crate::lines_lf::from_byte_idx()`].
+    ///
+    /// Runs in O(N) time.
+    pub fn from_byte_idx<S: TextSource + ?Sized>(text: &S, byte_idx: usize) -> usize {
+        super::from_byte_idx_summed(
+            text,
+            byte_idx,
+            crate::lines_lf::count_breaks,
+            crate::lines_lf::from_byte_idx,
+        )
+    }
+
+    /// Converts from line-index to byte-index in a text source.
+    ///
+    /// See [`crate::lines_lf::to_byte_idx()`].
+    ///
+    /// Runs in O(N) time.
+    pub fn to_byte_idx<S: TextSource + ?Sized>(text: &S, line_idx: usize) -> usize {
+        super::to_byte_idx_summed(
+            text,
+            line_idx,
+            crate::lines_lf::count_breaks,
+            crate::lines_lf::to_byte_idx,
+        )
+    }
+}
+
+//-------------------------------------------------------------
+
+/// `from_byte_idx()` for metrics that are a plain sum over segments.
+#[inline(always)]
+fn from_byte_idx_summed<S: TextSource + ?Sized>(
+    text: &S,
+    byte_idx: usize,
+    count: fn(&str) -> usize,
+    from_byte_idx: fn(&str, usize) -> usize,
+) -> usize {
+    let mut byte_count = 0;
+    let mut acc = 0;
+    for seg in text.segments() {
+        if byte_idx < byte_count + seg.len() {
+            return acc + from_byte_idx(seg, byte_idx - byte_count);
+        }
+        byte_count += seg.len();
+        acc += count(seg);
+    }
+    acc
+}
+
+/// `to_byte_idx()` for metrics that are a plain sum over segments.
+#[inline(always)]
+fn to_byte_idx_summed<S: TextSource + ?Sized>(
+    text: &S,
+    idx: usize,
+    count: fn(&str) -> usize,
+    to_byte_idx: fn(&str, usize) -> usize,
+) -> usize {
+    let mut byte_count = 0;
+    let mut acc = 0;
+    for seg in text.segments() {
+        let seg_count = count(seg);
+        if idx <= acc + seg_count {
+            return byte_count + to_byte_idx(seg, idx - acc);
+        }
+        byte_count += seg.len();
+        acc += seg_count;
+    }
+    byte_count
+}
+
+// The line functions for schemes that recognize CRLF have to account for
+// CRLF pairs that are split across segments.  They do so by treating the
+// LF of a split pair as belonging to the previous segment: its break was
+// already counted there as a lone CR, so it's simply skipped over.
+
+/// Returns the given segment with the LF of a split CRLF pair trimmed
+/// off, if any.
+#[inline(always)]
+fn trim_split_lf(seg: &str, prev_was_cr: bool) -> &str {
+    if prev_was_cr && seg.as_bytes().first() == Some(&0x0A) {
+        &seg[1..]
+    } else {
+        seg
+    }
+}
+
+/// Returns whether the text ends in a CR, given the segment that was just
+/// processed and whether the text up to that segment ended in a CR.
+#[inline(always)]
+fn ends_with_cr(seg: &str, prev_was_cr: bool) -> bool {
+    match seg.as_bytes().last() {
+        Some(byte) => *byte == 0x0D,
+        None => prev_was_cr,
+    }
+}
+
+/// `count_breaks()` for line schemes that recognize CRLF.
+#[inline(always)]
+fn count_breaks_crlf<S: TextSource + ?Sized>(text: &S, count_breaks: fn(&str) -> usize) -> usize {
+    let mut prev_was_cr = false;
+    let mut acc = 0;
+    for seg in text.segments() {
+        acc += count_breaks(trim_split_lf(seg, prev_was_cr));
+        prev_was_cr = ends_with_cr(seg, prev_was_cr);
+    }
+    acc
+}
+
+/// `from_byte_idx()` for line schemes that recognize CRLF.
+#[inline(always)]
+fn from_byte_idx_crlf<S: TextSource + ?Sized>(
+    text: &S,
+    byte_idx: usize,
+    count_breaks: fn(&str) -> usize,
+    from_byte_idx: fn(&str, usize) -> usize,
+) -> usize {
+    let mut prev_was_cr = false;
+    let mut byte_count = 0;
+    let mut acc = 0;
+    for seg in text.segments() {
+        let trimmed = trim_split_lf(seg, prev_was_cr);
+        if trimmed.len() != seg.len() {
+            if byte_idx == byte_count {
+                // In the middle of a split CRLF pair.
+                return acc - 1;
+            }
+            byte_count += 1;
+        }
+        if byte_idx < byte_count + trimmed.len() {
+            return acc + from_byte_idx(trimmed, byte_idx - byte_count);
+        }
+        byte_count += trimmed.len();
+        acc += count_breaks(trimmed);
+        prev_was_cr = ends_with_cr(seg, prev_was_cr);
+    }
+    acc
+}
+
+/// `to_byte_idx()` for line schemes that recognize CRLF.
+#[inline(always)]
+fn to_byte_idx_crlf<S: TextSource + ?Sized>(
+    text: &S,
+    line_idx: usize,
+    count_breaks: fn(&str) -> usize,
+    to_byte_idx: fn(&str, usize) -> usize,
+) -> usize {
+    let mut prev_was_cr = false;
+    let mut byte_count = 0;
+    let mut acc = 0;
+    for seg in text.segments() {
+        let trimmed = trim_split_lf(seg, prev_was_cr);
+        byte_count += seg.len() - trimmed.len();
+        prev_was_cr = ends_with_cr(seg, prev_was_cr);
+
+        // If the segment ends in a CR, the line after it may start after
+        // an LF in a later segment, so only stop here if the line starts
+        // strictly before the end of the segment.
+        let seg_count = count_breaks(trimmed);
+        if line_idx < acc + seg_count || (line_idx == acc + seg_count && !prev_was_cr) {
+            return byte_count + to_byte_idx(trimmed, line_idx - acc);
+        }
+        byte_count += trimmed.len();
+        acc += seg_count;
+    }
+    byte_count
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 47 bytes, 26 chars, 28 utf16 code units.
+    const TEXT: &str = "Hi🐸!\r\nこん\rにち\n\nは\r\r\n\u{2028}world\u{0085}🐸\r";
+
+    /// Calls `f` with `TEXT` split into three segments at every pair of
+    /// char boundaries, including empty segments.
+    fn for_each_split(mut f: impl FnMut(&[&str; 3])) {
+        for (i, _) in TEXT.char_indices().chain([(TEXT.len(), ' ')]) {
+            for (j, _) in TEXT[i..].char_indices().chain([(TEXT.len() - i, ' ')]) {
+                f(&[&TEXT[..i], &TEXT[i..(i + j)], &TEXT[(i + j)..]]);
+            }
+        }
+    }
+
+    #[test]
+    fn counts() {
+        for_each_split(|text| {
+            assert_eq!(crate::chars::count(TEXT), chars::count(text));
+            assert_eq!(crate::utf16::count(TEXT), utf16::count(text));
+            assert_eq!(
+                crate::utf16::count_surrogates(TEXT),
+                utf16::count_surrogates(text)
+            );
+            assert_eq!(crate::lines::count_breaks(TEXT), lines::count_breaks(text));
+            assert_eq!(
+                crate::lines_crlf::count_breaks(TEXT),
+                lines_crlf::count_breaks(text)
+            );
+            assert_eq!(
+                crate::lines_lf::count_breaks(TEXT),
+                lines_lf::count_breaks(text)
+            );
+        });
+    }
+
+    #[test]
+    fn from_byte_idx() {
+        for_each_split(|text| {
+            for i in 0..(TEXT.len() + 2) {
+                assert_eq!(
+                    crate::chars::from_byte_idx(TEXT, i),
+                    chars::from_byte_idx(text, i)
+                );
+                assert_eq!(
+                    crate::utf16::from_byte_idx(TEXT, i),
+                    utf16::from_byte_idx(text, i)
+                );
+                assert_eq!(
+                    crate::lines::from_byte_idx(TEXT, i),
+                    lines::from_byte_idx(text, i)
+                );
+                assert_eq!(
+                    crate::lines_crlf::from_byte_idx(TEXT, i),
+                    lines_crlf::from_byte_idx(text, i)
+                );
+                assert_eq!(
+                    crate::lines_lf::from_byte_idx(TEXT, i),
+                    lines_lf::from_byte_idx(text, i)
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn to_byte_idx() {
+        for_each_split(|text| {
+            for i in 0..(TEXT.len() + 2) {
+                assert_eq!(
+                    crate::chars::to_byte_idx(TEXT, i),
+                    chars::to_byte_idx(text, i)
+                );
+                assert_eq!(
+                    crate::utf16::to_byte_idx(TEXT, i),
+                    utf16::to_byte_idx(text, i)
+                );
+                assert_eq!(
+                    crate::lines::to_byte_idx(TEXT, i),
+                    lines::to_byte_idx(text, i)
+                );
+                assert_eq!(
+                    crate::lines_crlf::to_byte_idx(TEXT, i),
+                    lines_crlf::to_byte_idx(text, i)
+                );
+                assert_eq!(
+                    crate::lines_lf::to_byte_idx(TEXT, i),
+                    lines_lf::to_byte_idx(text, i)
+                );
+            }
+        });
+    }
+
+    #[test]
+    fn str_source() {
+        assert_eq!(crate::chars::count(TEXT), chars::count(TEXT));
+        assert_eq!(
+            crate::lines::to_byte_idx(TEXT, 3),
+            lines::to_byte_idx(TEXT, 3)
+        );
+    }
+
+    #[test]
+    fn empty_source() {
+        let text: [&str; 0] = [];
+        assert_eq!(0, chars::count(&text));
+        assert_eq!(0, lines::from_byte_idx(&text, 5));
+        assert_eq!(0, lines_crlf::to_byte_idx(&text, 5));
+    }
+}
@@ -0,0 +1,283 @@
+//! Index a window of a larger text with document-absolute indices.
+//!
+//! When working on part of a large text (a visible region, a diff hunk,
+//! etc.), it's often convenient to work on just that subslice, but the
+//! indices of interest are still the ones in the whole document.  A
+//! [`SubsliceIndexer`] holds the subslice together with the document
+//! indices of its start, so that its conversions take and return
+//! document-absolute indices while only scanning the subslice.
+
+use core::ops::Range;
+
+use crate::{chars, lines, lines_crlf, lines_lf, utf16};
+
+/// A subslice of a text, along with the indices of its start in the whole
+/// text.
+///
+/// All indices taken and returned by its methods are absolute indices in
+/// the whole text.  Indices outside of the subslice are clamped to its
+/// start or end.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubsliceIndexer<'a> {
+    text: &'a str,
+    byte_base: usize,
+    char_base: usize,
+    utf16_base: usize,
+    lines_base: usize,
+    lines_crlf_base: usize,
+    lines_lf_base: usize,
+}
+
+impl<'a> SubsliceIndexer<'a> {
+    /// Creates an indexer for the given byte range of a text.
+    ///
+    /// The ends of the range are snapped back to char boundaries, and off
+    /// of the LF of a CRLF so that the subslice never splits a CRLF.  Any
+    /// past-the-end indices are clamped to the end of the text.
+    ///
+    /// Runs in O(N) time, where N is the end of the range.
+    pub fn new(text: &'a str, byte_range: Range<usize>) -> SubsliceIndexer<'a> {
+        let snap = |i| {
+            let i = chars::floor_byte_boundary(text, i);
+            if crate::is_not_crlf_middle(i, text.as_bytes()) {
+                i
+            } else {
+                i - 1
+            }
+        };
+        let end = snap(byte_range.end);
+        let start = snap(byte_range.start).min(end);
+        let before = crate::split_str_at(text, start).0;
+
+        SubsliceIndexer {
+            text: text.get(start..end).unwrap_or(""),
+            byte_base: start,
+            char_base: chars::count(before),
+            utf16_base: utf16::count(before),
+            lines_base: lines::count_breaks(before),
+            lines_crlf_base: lines_crlf::count_breaks(before),
+            lines_lf_base: lines_lf::count_breaks(before),
+        }
+    }
+
+    /// Returns the subslice.
+    #[inline]
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Returns the byte range of the subslice in the whole text.
+    #[inline]
+    pub fn byte_range(&self) -> Range<usize> {
+        self.byte_base..(self.byte_base + self.text.len())
+    }
+
+    /// Converts from byte-index to char-index.
+    ///
+    /// See [`chars::from_byte_idx()`].
+    ///
+    /// Runs in O(N) time, where N is the length of the subslice.
+    #[inline]
+    pub fn char_from_byte_idx(&self, byte_idx: usize) -> usize {
+        self.char_base + chars::from_byte_idx(self.text, self.local_byte_idx(byte_idx))
+    }
+
+    /// Converts from char-index to byte-index.
+    ///
+    /// See [`chars::to_byte_idx()`].
+    ///
+    /// Runs in O(N) time, where N is the length of the subslice.
+    #[inline]
+    pub fn char_to_byte_idx(&self, char_idx: usize) -> usize {
+        let local_idx = char_idx.saturating_sub(self.char_base);
+        self.byte_base + chars::to_byte_idx(self.text, local_idx)
+    }
+
+    /// Converts from byte-index to utf16-code-unit-index.
+    ///
+    /// See [`utf16::from_byte_idx()`].
+    ///
+    /// Runs in O(N) time, where N is the length of the subslice.
+    #[inline]
+    pub fn utf16_from_byte_idx(&self, byte_idx: usize) -> usize {
+        self.utf16_base + utf16::from_byte_idx(self.text, self.local_byte_idx(byte_idx))
+    }
+
+    /// Converts from utf16-code-unit-index to byte-index.
+    ///
+    /// See [`utf16::to_byte_idx()`].
+    ///
+    /// Runs in O(N) time, where N is the length of the subslice.
+    #[inline]
+    pub fn utf16_to_byte_idx(&self, utf16_idx: usize) -> usize {
+        let local_idx = utf16_idx.saturating_sub(self.utf16_base);
+        self.byte_base + utf16::to_byte_idx(self.text, local_idx)
+    }
+
+    /// Converts from byte-index to line-index, with lines as in the
+    /// [`lines`](crate::lines) module.
+    ///
+    /// See [`lines::from_byte_idx()`].
+    ///
+    /// Runs in O(N) time, where N is the length of the subslice.
+    #[inline]
+    pub fn lines_from_byte_idx(&self, byte_idx: usize) -> usize {
+        self.lines_base + lines::from_byte_idx(self.text, self.local_byte_idx(byte_idx))
+    }
+
+    /// Converts from line-index to byte-index, with lines as in the
+    /// [`lines`](crate::lines) module.
+    ///
+    /// See [`lines::to_byte_idx()`].
+    ///
+    /// Runs in O(N) time, where N is the length of the subslice.
+    #[inline]
+    pub fn lines_to_byte_idx(&self, line_idx: usize) -> usize {
+        self.byte_base + self.local_line_to_byte_idx(line_idx, self.lines_base, lines::to_byte_idx)
+    }
+
+    /// Converts from byte-index to line-index, with lines as in the
+    /// [`lines_crlf`](crate::lines_crlf) module.
+    ///
+    /// See [`lines_crlf::from_byte_idx()`].
+    ///
+    /// Runs in O(N) time, where N is the length of the subslice.
+    #[inline]
+    pub fn lines_crlf_from_byte_idx(&self, byte_idx: usize) -> usize {
+        self.lines_crlf_base + lines_crlf::from_byte_idx(self.text, self.local_byte_idx(byte_idx))
+    }
+
+    /// Converts from line-index to byte-index, with lines as in the
+    /// [`lines_crlf`](crate::lines_crlf) module.
+    ///
+    /// See [`lines_crlf::to_byte_idx()`].
+    ///
+    /// Runs in O(N) time, where N is the length of the subslice.
+    #[inline]
+    pub fn lines_crlf_to_byte_idx(&self, line_idx: usize) -> usize {
+        self.byte_base
+            + self.local_line_to_byte_idx(line_idx, self.lines_crlf_base, lines_crlf::to_byte_idx)
+    }
+
+    /// Converts from byte-index to line-index, with lines as in the
+    /// [`lines_lf`](crate::lines_lf) module.
+    ///
+    /// See [`lines_lf::from_byte_idx()`].
+    ///
+    /// Runs in O(N) time, where N is the length of the subslice.
+    #[inline]
+    pub fn lines_lf_from_byte_idx(&self, byte_idx: usize) -> usize {
+        self.lines_lf_base + lines_lf::from_byte_idx(self.text, self.local_byte_idx(byte_idx))
+    }
+
+    /// Converts from line-index to byte-index, with lines as in the
+    /// [`lines_lf`](crate::lines_lf) module.
+    ///
+    /// See [`lines_lf::to_byte_idx()`].
+    ///
+    /// Runs in O(N) time, where N is the length of the subslice.
+    #[inline]
+    pub fn lines_lf_to_byte_idx(&self, line_idx: usize) -> usize {
+        self.byte_base
+            + self.local_line_to_byte_idx(line_idx, self.lines_lf_base, lines_lf::to_byte_idx)
+    }
+
+    //-----------------------------------------------------
+
+    #[inline(always)]
+    fn local_byte_idx(&self, byte_idx: usize) -> usize {
+        byte_idx.saturating_sub(self.byte_base)
+    }
+
+    /// The subslice generally starts in the middle of a line, so its local
+    /// line 0 is the line containing its start rather than a line start.
+    #[inline(always)]
+    fn local_line_to_byte_idx(
+        &self,
+        line_idx: usize,
+        base: usize,
+        to_byte_idx: fn(&str, usize) -> usize,
+    ) -> usize {
+        if line_idx <= base {
+            0
+        } else {
+            to_byte_idx(self.text, line_idx - base)
+        }
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r\n";
+
+    #[test]
+    fn new_01() {
+        let sub = SubsliceIndexer::new(TEXT, 5..10);
+        assert_eq!(3..9, sub.byte_range());
+        assert_eq!("🐸lo", sub.text());
+
+        let sub = SubsliceIndexer::new(TEXT, 10..100);
+        assert_eq!(9..TEXT.len(), sub.byte_range());
+
+        let sub = SubsliceIndexer::new(TEXT, Range { start: 12, end: 4 });
+        assert_eq!(3..3, sub.byte_range());
+    }
+
+    #[test]
+    fn matches_whole_text() {
+        let boundaries = || (0..=TEXT.len()).filter(|&i| TEXT.is_char_boundary(i));
+        for start in boundaries() {
+            for end in boundaries().filter(|&end| end >= start) {
+                let sub = SubsliceIndexer::new(TEXT, start..end);
+                let range = sub.byte_range();
+                for i in range.clone() {
+                    assert_eq!(chars::from_byte_idx(TEXT, i), sub.char_from_byte_idx(i));
+                    assert_eq!(utf16::from_byte_idx(TEXT, i), sub.utf16_from_byte_idx(i));
+                    assert_eq!(lines::from_byte_idx(TEXT, i), sub.lines_from_byte_idx(i));
+                    assert_eq!(
+                        lines_crlf::from_byte_idx(TEXT, i),
+                        sub.lines_crlf_from_byte_idx(i)
+                    );
+                    assert_eq!(
+                        lines_lf::from_byte_idx(TEXT, i),
+                        sub.lines_lf_from_byte_idx(i)
+                    );
+                }
+
+                // Round trips within the subslice.
+                let clamp = |i: usize| i.clamp(range.start, range.end);
+                for i in 0..30 {
+                    let idx = sub.char_to_byte_idx(i);
+                    assert_eq!(clamp(chars::to_byte_idx(TEXT, i)), idx);
+                    let idx = sub.utf16_to_byte_idx(i);
+                    assert_eq!(clamp(utf16::to_byte_idx(TEXT, i)), idx);
+                }
+                for i in 0..10 {
+                    let expected = |line_start: usize| {
+                        if line_start < range.start {
+                            range.start
+                        } else {
+                            line_start.min(range.end)
+                        }
+                    };
+                    assert_eq!(
+                        expected(lines::to_byte_idx(TEXT, i)),
+                        sub.lines_to_byte_idx(i)
+                    );
+                    assert_eq!(
+                        expected(lines_crlf::to_byte_idx(TEXT, i)),
+                        sub.lines_crlf_to_byte_idx(i)
+                    );
+                    assert_eq!(
+                        expected(lines_lf::to_byte_idx(TEXT, i)),
+                        sub.lines_lf_to_byte_idx(i)
+                    );
+                }
+            }
+        }
+    }
+}
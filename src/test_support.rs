@@ -0,0 +1,59 @@
+//! Reference implementations and proptest strategies for testing.
+//!
+//! Only available with the `test-support` feature.
+//!
+//! These are the naive reference implementations (re-exported from
+//! [`naive`](crate::naive)) and the input strategies that this crate's own
+//! property tests are built on.  Crates that wrap str_indices (ropes,
+//! editors, etc.) can use them to property test their wrappers against the
+//! same oracles.
+//!
+//! ```
+//! use proptest::test_runner::TestRunner;
+//! use str_indices::test_support::{chars, strategies};
+//!
+//! let mut runner = TestRunner::default();
+//! runner
+//!     .run(&(strategies::text(), 0usize..300), |(text, idx)| {
+//!         assert_eq!(
+//!             chars::to_byte_idx(&text, idx),
+//!             str_indices::chars::to_byte_idx(&text, idx),
+//!         );
+//!         Ok(())
+//!     })
+//!     .unwrap();
+//! ```
+
+pub use crate::naive::{chars, lines, lines_crlf, lines_lf, utf16};
+
+/// Proptest strategies for generating input text.
+///
+/// The line strategies generate text that is dense in the line breaks of
+/// the respective module, mixed with chars of every utf8 length.
+pub mod strategies {
+    extern crate alloc;
+
+    use alloc::string::String;
+
+    use proptest::strategy::Strategy;
+
+    /// Arbitrary text of up to 200 chars.
+    pub fn text() -> impl Strategy<Value = String> {
+        "\\PC{0, 200}"
+    }
+
+    /// Text for testing the [`lines`](crate::lines) functions.
+    pub fn lines_text() -> impl Strategy<Value = String> {
+        "[aあ🐸\\u{000A}\\u{000B}\\u{000C}\\u{000D}\\u{0085}\\u{2028}\\u{2029}]{0, 200}"
+    }
+
+    /// Text for testing the [`lines_crlf`](crate::lines_crlf) functions.
+    pub fn lines_crlf_text() -> impl Strategy<Value = String> {
+        "[aあ🐸\\u{000A}\\u{000D}]{0, 200}"
+    }
+
+    /// Text for testing the [`lines_lf`](crate::lines_lf) functions.
+    pub fn lines_lf_text() -> impl Strategy<Value = String> {
+        "[aあ🐸\\u{000A}]{0, 200}"
+    }
+}
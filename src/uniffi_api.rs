@@ -0,0 +1,202 @@
+//! UniFFI bindings, for use from Swift, Kotlin, Python, etc.
+//!
+//! Only available with the `uniffi` feature.  Every function here is a thin
+//! wrapper around the function of the same name in the corresponding
+//! module, e.g. `chars_count()` wraps [`chars::count()`].  See those for the
+//! details of each function's behavior.
+//!
+//! UniFFI has no `usize`, so counts and indices are passed as `u64`.
+//! Indices too large for a `usize` are treated as past-the-end.
+//!
+//! The crate itself can't be built directly as a `cdylib`.  To generate
+//! bindings, depend on it with the `uniffi` feature from a small `cdylib`
+//! crate of your own that calls
+//! `str_indices::uniffi_reexport_scaffolding!()`, and run `uniffi-bindgen`
+//! on that library.
+
+use crate::{chars, lines, lines_crlf, lines_lf, utf16};
+
+#[inline(always)]
+fn to_usize(n: u64) -> usize {
+    usize::try_from(n).unwrap_or(usize::MAX)
+}
+
+//-------------------------------------------------------------
+
+/// Counts the chars in the text.
+///
+/// See [`chars::count()`].
+#[uniffi::export]
+pub fn chars_count(text: &str) -> u64 {
+    chars::count(text) as u64
+}
+
+/// Converts from byte-index to char-index in the text.
+///
+/// See [`chars::from_byte_idx()`].
+#[uniffi::export]
+pub fn chars_from_byte_idx(text: &str, byte_idx: u64) -> u64 {
+    chars::from_byte_idx(text, to_usize(byte_idx)) as u64
+}
+
+/// Converts from char-index to byte-index in the text.
+///
+/// See [`chars::to_byte_idx()`].
+#[uniffi::export]
+pub fn chars_to_byte_idx(text: &str, char_idx: u64) -> u64 {
+    chars::to_byte_idx(text, to_usize(char_idx)) as u64
+}
+
+//-------------------------------------------------------------
+
+/// Counts the utf16 code units in the text.
+///
+/// See [`utf16::count()`].
+#[uniffi::export]
+pub fn utf16_count(text: &str) -> u64 {
+    utf16::count(text) as u64
+}
+
+/// Counts the utf16 surrogate pairs in the text.
+///
+/// See [`utf16::count_surrogates()`].
+#[uniffi::export]
+pub fn utf16_count_surrogates(text: &str) -> u64 {
+    utf16::count_surrogates(text) as u64
+}
+
+/// Converts from byte-index to utf16-code-unit-index in the text.
+///
+/// See [`utf16::from_byte_idx()`].
+#[uniffi::export]
+pub fn utf16_from_byte_idx(text: &str, byte_idx: u64) -> u64 {
+    utf16::from_byte_idx(text, to_usize(byte_idx)) as u64
+}
+
+/// Converts from utf16-code-unit-index to byte-index in the text.
+///
+/// See [`utf16::to_byte_idx()`].
+#[uniffi::export]
+pub fn utf16_to_byte_idx(text: &str, utf16_idx: u64) -> u64 {
+    utf16::to_byte_idx(text, to_usize(utf16_idx)) as u64
+}
+
+//-------------------------------------------------------------
+
+/// Counts the line breaks (all Unicode line breaks) in the text.
+///
+/// See [`lines::count_breaks()`].
+#[uniffi::export]
+pub fn lines_count_breaks(text: &str) -> u64 {
+    lines::count_breaks(text) as u64
+}
+
+/// Converts from byte-index to line-index (all Unicode line breaks) in the
+/// text.
+///
+/// See [`lines::from_byte_idx()`].
+#[uniffi::export]
+pub fn lines_from_byte_idx(text: &str, byte_idx: u64) -> u64 {
+    lines::from_byte_idx(text, to_usize(byte_idx)) as u64
+}
+
+/// Converts from line-index (all Unicode line breaks) to byte-index in the
+/// text.
+///
+/// See [`lines::to_byte_idx()`].
+#[uniffi::export]
+pub fn lines_to_byte_idx(text: &str, line_idx: u64) -> u64 {
+    lines::to_byte_idx(text, to_usize(line_idx)) as u64
+}
+
+//-------------------------------------------------------------
+
+/// Counts the line breaks (LF and CR) in the text.
+///
+/// See [`lines_crlf::count_breaks()`].
+#[uniffi::export]
+pub fn lines_crlf_count_breaks(text: &str) -> u64 {
+    lines_crlf::count_breaks(text) as u64
+}
+
+/// Converts from byte-index to line-index (LF and CR) in the text.
+///
+/// See [`lines_crlf::from_byte_idx()`].
+#[uniffi::export]
+pub fn lines_crlf_from_byte_idx(text: &str, byte_idx: u64) -> u64 {
+    lines_crlf::from_byte_idx(text, to_usize(byte_idx)) as u64
+}
+
+/// Converts from line-index (LF and CR) to byte-index in the text.
+///
+/// See [`lines_crlf::to_byte_idx()`].
+#[uniffi::export]
+pub fn lines_crlf_to_byte_idx(text: &str, line_idx: u64) -> u64 {
+    lines_crlf::to_byte_idx(text, to_usize(line_idx)) as u64
+}
+
+//-------------------------------------------------------------
+
+/// Counts the line breaks (LF only) in the text.
+///
+/// See [`lines_lf::count_breaks()`].
+#[uniffi::export]
+pub fn lines_lf_count_breaks(text: &str) -> u64 {
+    lines_lf::count_breaks(text) as u64
+}
+
+/// Converts from byte-index to line-index (LF only) in the text.
+///
+/// See [`lines_lf::from_byte_idx()`].
+#[uniffi::export]
+pub fn lines_lf_from_byte_idx(text: &str, byte_idx: u64) -> u64 {
+    lines_lf::from_byte_idx(text, to_usize(byte_idx)) as u64
+}
+
+/// Converts from line-index (LF only) to byte-index in the text.
+///
+/// See [`lines_lf::to_byte_idx()`].
+#[uniffi::export]
+pub fn lines_lf_to_byte_idx(text: &str, line_idx: u64) -> u64 {
+    lines_lf::to_byte_idx(text, to_usize(line_idx)) as u64
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 33 bytes, 18 chars, 19 utf16 code units, 4 lines.
+    const TEXT: &str = "Hi🐸!\nこんにちは\r\nworld\u{2028}";
+
+    #[test]
+    fn counts() {
+        assert_eq!(18, chars_count(TEXT));
+        assert_eq!(19, utf16_count(TEXT));
+        assert_eq!(1, utf16_count_surrogates(TEXT));
+        assert_eq!(3, lines_count_breaks(TEXT));
+        assert_eq!(2, lines_crlf_count_breaks(TEXT));
+        assert_eq!(2, lines_lf_count_breaks(TEXT));
+    }
+
+    #[test]
+    fn past_the_end() {
+        assert_eq!(18, chars_from_byte_idx(TEXT, u64::MAX));
+        assert_eq!(33, chars_to_byte_idx(TEXT, u64::MAX));
+        assert_eq!(33, utf16_to_byte_idx(TEXT, u64::MAX));
+        assert_eq!(33, lines_to_byte_idx(TEXT, u64::MAX));
+        assert_eq!(2, lines_lf_from_byte_idx(TEXT, u64::MAX));
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(3, chars_from_byte_idx(TEXT, 6));
+        assert_eq!(2, chars_to_byte_idx(TEXT, 2));
+        assert_eq!(4, utf16_from_byte_idx(TEXT, 6));
+        assert_eq!(6, utf16_to_byte_idx(TEXT, 4));
+        assert_eq!(1, lines_from_byte_idx(TEXT, 8));
+        assert_eq!(25, lines_crlf_to_byte_idx(TEXT, 2));
+        assert_eq!(25, lines_lf_to_byte_idx(TEXT, 2));
+    }
+}
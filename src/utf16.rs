@@ -1,24 +1,41 @@
 //! Index by utf16 code units.
 
-use crate::byte_chunk::{ByteChunk, Chunk};
+use crate::byte_chunk::ByteChunk;
 
 /// Counts the utf16 code units that would be in a string slice if it
 /// were encoded as utf16.
 ///
 /// Runs in O(N) time.
-#[inline]
+#[inline(never)]
 pub fn count(text: &str) -> usize {
-    crate::chars::count_impl::<Chunk>(text.as_bytes())
-        + count_surrogates_impl::<Chunk>(text.as_bytes())
+    with_chunk!(count_impl(text.as_bytes()))
 }
 
 /// Counts the utf16 surrogate pairs that would be in a string slice if
 /// it were encoded as utf16.
 ///
 /// Runs in O(N) time.
-#[inline]
+#[inline(never)]
 pub fn count_surrogates(text: &str) -> usize {
-    count_surrogates_impl::<Chunk>(text.as_bytes())
+    with_chunk!(count_surrogates_impl(text.as_bytes()))
+}
+
+/// Returns an iterator over the `(utf16_idx, byte_idx)` of each char in a
+/// string slice that is a surrogate pair in utf16.
+///
+/// These are the chars outside the Basic Multilingual Plane, i.e. the
+/// 4-byte chars in utf8.  Between them, utf16 and byte indices differ by a
+/// fixed amount per char length, so their positions are enough to build a
+/// table for fast conversions in mostly-BMP text.
+///
+/// Runs in O(N) time over the whole iteration.
+#[inline]
+pub fn surrogate_indices(text: &str) -> SurrogateIndices<'_> {
+    SurrogateIndices {
+        text,
+        byte_idx: 0,
+        utf16_idx: 0,
+    }
 }
 
 /// Converts from byte-index to utf16-code-unit-index in a string slice.
@@ -29,14 +46,81 @@ pub fn count_surrogates(text: &str) -> usize {
 /// Any past-the-end index will return the one-past-the-end utf16 index.
 ///
 /// Runs in O(N) time.
-#[inline]
+#[inline(never)]
 pub fn from_byte_idx(text: &str, byte_idx: usize) -> usize {
+    with_chunk!(from_byte_idx_impl(text, byte_idx))
+}
+
+/// Converts from byte-index to utf16-code-unit-index in a string slice,
+/// given the total utf16 length of the slice.
+///
+/// Returns the same as [`from_byte_idx()`], but if `byte_idx` is in the
+/// second half of the text, counts the code units from it to the end and
+/// subtracts them from `utf16_count` instead of counting from the start.
+/// This makes conversions near the end of large texts cheap when their
+/// utf16 length is already known, e.g. cached in a rope node.
+///
+/// `utf16_count` must be the utf16 length of `text`, as returned by
+/// [`count()`].  If it isn't, the result is unspecified (but it won't
+/// panic).
+///
+/// Runs in O(N) time, where N is the distance from `byte_idx` to the
+/// nearest end of the text.
+#[inline(never)]
+pub fn from_byte_idx_with_count(text: &str, byte_idx: usize, utf16_count: usize) -> usize {
+    if byte_idx < text.len() / 2 {
+        return with_chunk!(from_byte_idx_impl(text, byte_idx));
+    }
+    let byte_idx = crate::chars::floor_byte_boundary(text, byte_idx);
+    let tail = crate::split_str_at(text, byte_idx).1;
+    utf16_count.saturating_sub(with_chunk!(count_impl(tail.as_bytes())))
+}
+
+/// Converts many byte indices to utf16 indices in a string slice.
+///
+/// Writes the utf16 index of each of `byte_idxs` to the corresponding
+/// element of `out`, as [`from_byte_idx()`] would return it.  If `out` is
+/// shorter than `byte_idxs`, only the indices that fit are converted.
+///
+/// When `byte_idxs` is sorted in ascending order, the conversions share a
+/// single pass over the text instead of each scanning from its start.
+/// Unsorted indices are still converted correctly, but each decrease
+/// restarts the scan from the start of the text.
+///
+/// Runs in O(N + M) time for sorted indices, where M is the number of
+/// indices.
+#[inline(never)]
+pub fn from_byte_idxs(text: &str, byte_idxs: &[usize], out: &mut [usize]) {
+    let bytes = text.as_bytes();
+    let (mut byte_pos, mut utf16_pos) = (0, 0);
+    for (&byte_idx, out) in byte_idxs.iter().zip(out.iter_mut()) {
+        let byte_idx = crate::chars::floor_byte_boundary(text, byte_idx);
+        if byte_idx < byte_pos {
+            (byte_pos, utf16_pos) = (0, 0);
+        }
+        utf16_pos += with_chunk!(count_impl(bytes.get(byte_pos..byte_idx).unwrap_or(&[])));
+        byte_pos = byte_idx;
+        *out = utf16_pos;
+    }
+}
+
+/// Converts from byte-index to utf16-code-unit-index in a string slice,
+/// also reporting whether the byte index was on a char boundary.
+///
+/// Returns the same utf16 index as [`from_byte_idx()`], along with how
+/// many bytes `byte_idx` was snapped back by to reach a char boundary.
+/// The latter is zero exactly when `byte_idx` was already a char boundary
+/// (including the one-past-the-end index).
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn from_byte_idx_snapped(text: &str, byte_idx: usize) -> (usize, usize) {
     let mut i = byte_idx.min(text.len());
     while !text.is_char_boundary(i) {
         i -= 1;
     }
-    let slice = &text.as_bytes()[..i];
-    crate::chars::count_impl::<Chunk>(slice) + count_surrogates_impl::<Chunk>(slice)
+    let slice = text.as_bytes().get(..i).unwrap_or(&[]);
+    (with_chunk!(count_impl(slice)), byte_idx - i)
 }
 
 /// Converts from utf16-code-unit-index to byte-index in a string slice.
@@ -46,91 +130,481 @@ pub fn from_byte_idx(text: &str, byte_idx: usize) -> usize {
 ///
 /// Any past-the-end index will return the one-past-the-end byte index.
 ///
+/// This is also the end of the longest prefix of `text` that is at most
+/// `utf16_idx` code units long in utf16, without splitting a surrogate pair,
+/// which makes it suitable for truncating text to a utf16 budget.
+///
 /// Runs in O(N) time.
-#[inline]
+#[inline(never)]
 pub fn to_byte_idx(text: &str, utf16_idx: usize) -> usize {
-    to_byte_idx_impl::<Chunk>(text, utf16_idx)
+    with_chunk!(to_byte_idx_impl(text, utf16_idx))
+}
+
+/// Moves a byte index forward or backward by a number of utf16 code units.
+///
+/// Returns the byte index `delta` code units after `byte_idx`, or before it
+/// if `delta` is negative, stopping at the start or end of the text.  This
+/// is the same as converting `byte_idx` to a utf16 index, adding `delta`,
+/// and converting back, but only scans the text between the two
+/// positions.  So a move that lands in the middle of a surrogate pair
+/// snaps back to the start of its char.
+///
+/// If `byte_idx` is in the middle of a char, it's snapped back to the start
+/// of that char first.  Any past-the-end index is treated as the
+/// one-past-the-end index.
+///
+/// Runs in O(N) time, where N is the distance moved.
+#[inline]
+pub fn offset_by(text: &str, byte_idx: usize, delta: isize) -> usize {
+    let byte_idx = crate::chars::floor_byte_boundary(text, byte_idx);
+    let (before, after) = crate::split_str_at(text, byte_idx);
+    if delta >= 0 {
+        return byte_idx + to_byte_idx(after, delta as usize);
+    }
+
+    // Moving back into the middle of a surrogate pair overshoots by one
+    // code unit, which snaps back to the start of the pair's char as
+    // intended.
+    let mut units = delta.unsigned_abs();
+    for (i, c) in before.char_indices().rev() {
+        units = units.saturating_sub(c.len_utf16());
+        if units == 0 {
+            return i;
+        }
+    }
+    0
+}
+
+/// Counts the utf16 code units in a string slice, stopping after
+/// `max_units`.
+///
+/// Returns `(utf16_count, byte_idx)`, where `byte_idx` is the same as
+/// returned by [`to_byte_idx()`] for `max_units`, and `utf16_count` is the
+/// number of code units before it.  The latter is at most `max_units`, and
+/// is one less if stopping before a surrogate pair that would straddle the
+/// limit.  This does the work of both [`to_byte_idx()`] and [`count()`] on
+/// the prefix in a single scan.
+///
+/// Runs in O(N) time, where N is the length of the prefix.
+#[inline(never)]
+pub fn count_up_to(text: &str, max_units: usize) -> (usize, usize) {
+    let (byte_idx, _, utf16_count) = with_chunk!(scan_to_impl(text.as_bytes(), max_units, true));
+    (utf16_count, byte_idx)
+}
+
+/// Converts many utf16 indices to byte indices in a string slice.
+///
+/// Writes the byte index of each of `utf16_idxs` to the corresponding
+/// element of `out`, as [`to_byte_idx()`] would return it.  If `out` is
+/// shorter than `utf16_idxs`, only the indices that fit are converted.
+///
+/// As with [`from_byte_idxs()`], sorted indices are converted in a single
+/// pass over the text, and each decrease restarts the scan.
+///
+/// Runs in O(N + M) time for sorted indices, where M is the number of
+/// indices.
+#[inline(never)]
+pub fn to_byte_idxs(text: &str, utf16_idxs: &[usize], out: &mut [usize]) {
+    let bytes = text.as_bytes();
+    let (mut byte_pos, mut utf16_pos) = (0, 0);
+    for (&utf16_idx, out) in utf16_idxs.iter().zip(out.iter_mut()) {
+        if utf16_idx < utf16_pos {
+            (byte_pos, utf16_pos) = (0, 0);
+        }
+        let rest = bytes.get(byte_pos..).unwrap_or(&[]);
+        let (byte_idx, _, utf16_count) =
+            with_chunk!(scan_to_impl(rest, utf16_idx - utf16_pos, true));
+        byte_pos += byte_idx;
+        utf16_pos += utf16_count;
+        *out = byte_pos;
+    }
+}
+
+/// Converts from char-index to utf16-code-unit-index in a string slice.
+///
+/// Same as `from_byte_idx(text, chars::to_byte_idx(text, char_idx))`, but
+/// in a single pass over the text.
+///
+/// Any past-the-end index will return the one-past-the-end utf16 index.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn from_char_idx(text: &str, char_idx: usize) -> usize {
+    with_chunk!(scan_to_impl(text.as_bytes(), char_idx, false)).2
+}
+
+/// Converts from utf16-code-unit-index to char-index in a string slice.
+///
+/// Same as `chars::from_byte_idx(text, to_byte_idx(text, utf16_idx))`, but
+/// in a single pass over the text.  So if the utf16 index is in the middle
+/// of a surrogate pair, returns the index of the char that it belongs to.
+///
+/// Any past-the-end index will return the one-past-the-end char index.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn to_char_idx(text: &str, utf16_idx: usize) -> usize {
+    with_chunk!(scan_to_impl(text.as_bytes(), utf16_idx, true)).1
+}
+
+/// What to do with a utf16 index in the middle of a surrogate pair, i.e.
+/// that points at the second code unit of a char outside the Basic
+/// Multilingual Plane.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SurrogatePolicy {
+    /// Snap back to the start of the char, as [`to_byte_idx()`] does.
+    Floor,
+    /// Snap forward to the end of the char.
+    Ceil,
+    /// Treat the index as invalid.
+    Error,
+}
+
+/// Converts from utf16-code-unit-index to byte-index in a string slice,
+/// with an explicit policy for indices in the middle of a surrogate pair.
+///
+/// Returns the same as [`to_byte_idx()`], except for an index in the middle
+/// of a surrogate pair: it's snapped back or forward to a char boundary, or
+/// `None` is returned, according to `policy`.  `None` is only ever returned
+/// with [`SurrogatePolicy::Error`].  This lets e.g. language servers reject
+/// malformed client positions rather than silently mapping them.
+///
+/// Any past-the-end index will return the one-past-the-end byte index.
+///
+/// Runs in O(N) time.
+#[inline(never)]
+pub fn to_byte_idx_with_policy(
+    text: &str,
+    utf16_idx: usize,
+    policy: SurrogatePolicy,
+) -> Option<usize> {
+    let (byte_idx, _, utf16_count) = with_chunk!(scan_to_impl(text.as_bytes(), utf16_idx, true));
+    if utf16_count == utf16_idx || byte_idx == text.len() {
+        return Some(byte_idx);
+    }
+    match policy {
+        SurrogatePolicy::Floor => Some(byte_idx),
+        SurrogatePolicy::Ceil => Some(byte_idx + 4),
+        SurrogatePolicy::Error => None,
+    }
+}
+
+/// Returns the longest prefix of a string slice that is at most
+/// `max_units` code units long in utf16.
+///
+/// A surrogate pair is never split: if it would straddle the limit, the
+/// prefix ends before it.  Equivalent to
+/// `&text[..to_byte_idx(text, max_units)]`.
+///
+/// Runs in O(N) time, where N is the length of the prefix.
+#[inline]
+pub fn prefix(text: &str, max_units: usize) -> &str {
+    split_at(text, max_units).0
+}
+
+/// Splits a string slice at a utf16-code-unit index.
+///
+/// Equivalent to `text.split_at(to_byte_idx(text, utf16_idx))`, so an index
+/// in the middle of a char splits before that char, and any past-the-end
+/// index splits at the end of the text.
+///
+/// Runs in O(N) time.
+#[inline]
+pub fn split_at(text: &str, utf16_idx: usize) -> (&str, &str) {
+    crate::split_str_at(text, to_byte_idx(text, utf16_idx))
+}
+
+/// Iterator over the utf16 and byte indices of the chars in a string slice
+/// that are surrogate pairs in utf16, created by [`surrogate_indices()`].
+#[derive(Debug, Clone)]
+pub struct SurrogateIndices<'a> {
+    text: &'a str,
+    /// Where to continue searching from.
+    byte_idx: usize,
+    utf16_idx: usize,
 }
 
+impl Iterator for SurrogateIndices<'_> {
+    type Item = (usize, usize);
+
+    #[inline]
+    fn next(&mut self) -> Option<(usize, usize)> {
+        let rest = crate::split_str_at(self.text, self.byte_idx).1;
+        match with_chunk!(find_surrogate_impl(rest.as_bytes())) {
+            Some((byte_offset, utf16_offset)) => {
+                let item = (self.utf16_idx + utf16_offset, self.byte_idx + byte_offset);
+                self.utf16_idx = item.0 + 2;
+                self.byte_idx = item.1 + 4;
+                Some(item)
+            }
+            None => {
+                self.byte_idx = self.text.len();
+                None
+            }
+        }
+    }
+
+    #[inline]
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.text.len().saturating_sub(self.byte_idx);
+        (0, Some(len / 4))
+    }
+
+    #[inline]
+    fn count(self) -> usize {
+        count_surrogates(crate::split_str_at(self.text, self.byte_idx).1)
+    }
+}
+
+impl core::iter::FusedIterator for SurrogateIndices<'_> {}
+
 //-------------------------------------------------------------
 
 #[inline(always)]
-fn to_byte_idx_impl<T: ByteChunk>(text: &str, utf16_idx: usize) -> usize {
-    // Get `middle` so we can do more efficient chunk-based counting.
-    // We can't use this to get `end`, however, because the start index of
-    // `end` actually depends on the accumulating char counts during the
-    // counting process.
-    let (start, middle, _) = unsafe { text.as_bytes().align_to::<T>() };
+pub(crate) fn count_impl<T: ByteChunk>(text: &[u8]) -> usize {
+    // Each char is one code unit, plus one more for each 4-byte char.  In
+    // valid utf8 those are counted by the leading bytes and the 0xF0-0xF7
+    // bytes, respectively, so both can be counted in the same pass.
+    let count_bytes = |bytes: &[u8]| {
+        bytes
+            .iter()
+            .map(|byte| ((byte & 0xC0) != 0x80) as usize + ((byte & 0xF0) == 0xF0) as usize)
+            .sum::<usize>()
+    };
+    if text.len() < T::SIZE {
+        return count_bytes(text);
+    }
+
+    // Get `middle` for more efficient chunk-based counting.
+    let (start, middle, end) = unsafe { text.align_to::<T>() };
+
+    // Take care of unaligned bytes at the beginning and end.
+    let mut utf16_count = count_bytes(start) + count_bytes(end);
+
+    // Take care of the middle bytes in big chunks. Loop unrolled.
+    let groups_len = middle.len() - middle.len() % T::UNROLL;
+    let groups = middle.get(..groups_len).unwrap_or(&[]);
+    let (trailing, surrogates) = count_non_ascii_groups(groups);
+    utf16_count += (T::SIZE * groups_len) - trailing + surrogates;
+
+    // Chunk remainder.
+    let mut acc_inv_chars = T::zero();
+    let mut acc_surrogates = T::zero();
+    for chunk in middle.get(groups_len..).unwrap_or(&[]).iter() {
+        acc_inv_chars = acc_inv_chars.add(chunk.bitand(T::splat(0xc0)).cmp_eq_byte(0x80));
+        acc_surrogates = acc_surrogates.add(chunk.bitand(T::splat(0xf0)).cmp_eq_byte(0xf0));
+    }
+    utf16_count += (T::SIZE * (middle.len() - groups_len)) - acc_inv_chars.sum_bytes()
+        + acc_surrogates.sum_bytes();
+
+    utf16_count
+}
+
+#[inline(always)]
+pub(crate) fn from_byte_idx_impl<T: ByteChunk>(text: &str, byte_idx: usize) -> usize {
+    let mut i = byte_idx.min(text.len());
+    while !text.is_char_boundary(i) {
+        i -= 1;
+    }
+    count_impl::<T>(&text.as_bytes()[..i])
+}
+
+#[inline(always)]
+pub(crate) fn to_byte_idx_impl<T: ByteChunk>(text: &str, utf16_idx: usize) -> usize {
+    scan_to_impl::<T>(text.as_bytes(), utf16_idx, true).0
+}
 
-    let mut byte_count = 0;
+/// Counts chars from the start of the text until reaching char index
+/// `idx`, or utf16 index `idx` if `idx_is_utf16`.  Returns `(byte_idx,
+/// char_count, utf16_count)` of the char it stopped at, or of the end of
+/// the text.  A utf16 index in the middle of a surrogate pair stops before
+/// the pair's char.
+#[inline(always)]
+pub(crate) fn scan_to_impl<T: ByteChunk>(
+    text: &[u8],
+    idx: usize,
+    idx_is_utf16: bool,
+) -> (usize, usize, usize) {
+    let mut char_count = 0;
     let mut utf16_count = 0;
 
+    // Counts the chars in `bytes`, returning where it stopped if it
+    // reached `idx`.
+    let scan_bytes = |bytes: &[u8], char_count: &mut usize, utf16_count: &mut usize| {
+        for (i, byte) in bytes.iter().enumerate() {
+            if (byte & 0xC0) == 0x80 {
+                continue;
+            }
+            let units = 1 + ((byte & 0xf0) == 0xf0) as usize;
+            let next = if idx_is_utf16 {
+                *utf16_count + units
+            } else {
+                *char_count + 1
+            };
+            if next > idx {
+                return Some(i);
+            }
+            *char_count += 1;
+            *utf16_count += units;
+        }
+        None
+    };
+
+    // Get `middle` so we can do more efficient chunk-based counting.
+    let (start, middle, _) = unsafe { text.align_to::<T>() };
+
     // Take care of any unaligned bytes at the beginning.
-    for byte in start.iter() {
-        utf16_count += ((*byte & 0xC0) != 0x80) as usize + ((byte & 0xf0) == 0xf0) as usize;
-        if utf16_count > utf16_idx {
+    if let Some(i) = scan_bytes(start, &mut char_count, &mut utf16_count) {
+        return (i, char_count, utf16_count);
+    }
+    let mut byte_count = start.len();
+
+    // Process chunks in rounds that can't go past `idx`.  A chunk adds at
+    // most `T::SIZE` chars, and at most `T::SIZE + 1` utf16 code units
+    // (when it ends with the leading byte of a 4-byte char).  Rounds are
+    // whole groups of chunks for as long as possible, for the ascii fast
+    // path.
+    let mut chunks = middle;
+    loop {
+        let count = if idx_is_utf16 {
+            utf16_count
+        } else {
+            char_count
+        };
+        let round_len = (idx.saturating_sub(count).saturating_sub(1) / T::SIZE).min(chunks.len());
+        let round_len = round_len - round_len % T::UNROLL;
+        if round_len == 0 {
             break;
         }
-        byte_count += 1;
+        let round = chunks.get(..round_len).unwrap_or(&[]);
+        chunks = chunks.get(round_len..).unwrap_or(&[]);
+
+        let (trailing, surrogates) = count_non_ascii_groups(round);
+        let round_chars = (T::SIZE * round_len) - trailing;
+        char_count += round_chars;
+        utf16_count += round_chars + surrogates;
+        byte_count += T::SIZE * round_len;
     }
+    loop {
+        let count = if idx_is_utf16 {
+            utf16_count
+        } else {
+            char_count
+        };
+        let round_len = (idx.saturating_sub(count).saturating_sub(1) / T::SIZE)
+            .min(T::MAX_ACC)
+            .min(chunks.len());
+        if round_len == 0 {
+            break;
+        }
+        let (round, rest) = chunks.split_at(round_len);
+        chunks = rest;
 
-    // Process chunks in the fast path.
-    let mut chunks = middle;
-    let mut max_round_len = utf16_idx.saturating_sub(utf16_count) / T::MAX_ACC;
-    while max_round_len > 0 && !chunks.is_empty() {
-        // Choose the largest number of chunks we can do this round
-        // that will neither overflow `max_acc` nor blast past the
-        // utf16 code unit we're looking for.
-        let round_len = T::MAX_ACC.min(max_round_len).min(chunks.len());
-        max_round_len -= round_len;
-        let round = &chunks[..round_len];
-        chunks = &chunks[round_len..];
-
-        // Process the chunks in this round.
         let mut acc_inv_chars = T::zero();
         let mut acc_surrogates = T::zero();
         for chunk in round.iter() {
             acc_inv_chars = acc_inv_chars.add(chunk.bitand(T::splat(0xc0)).cmp_eq_byte(0x80));
             acc_surrogates = acc_surrogates.add(chunk.bitand(T::splat(0xf0)).cmp_eq_byte(0xf0));
         }
-        utf16_count +=
-            ((T::SIZE * round_len) - acc_inv_chars.sum_bytes()) + acc_surrogates.sum_bytes();
+        let round_chars = (T::SIZE * round_len) - acc_inv_chars.sum_bytes();
+        char_count += round_chars;
+        utf16_count += round_chars + acc_surrogates.sum_bytes();
         byte_count += T::SIZE * round_len;
     }
 
-    // Process chunks in the slow path.
-    for chunk in chunks.iter() {
-        let inv_chars = chunk.bitand(T::splat(0xc0)).cmp_eq_byte(0x80).sum_bytes();
-        let surrogates = chunk.bitand(T::splat(0xf0)).cmp_eq_byte(0xf0).sum_bytes();
-        let new_utf16_count = utf16_count + (T::SIZE - inv_chars) + surrogates;
-        if new_utf16_count >= utf16_idx {
-            break;
+    // Take care of the rest of the bytes.
+    let rest = text.get(byte_count..).unwrap_or(&[]);
+    let byte_idx = match scan_bytes(rest, &mut char_count, &mut utf16_count) {
+        Some(i) => byte_count + i,
+        None => text.len(),
+    };
+
+    (byte_idx, char_count, utf16_count)
+}
+
+/// Counts the trailing bytes and the leading bytes of 4-byte chars in
+/// chunks processed in groups of `T::UNROLL`, skipping ascii groups.
+///
+/// `chunks.len()` must be a multiple of `T::UNROLL`.
+#[inline(always)]
+fn count_non_ascii_groups<T: ByteChunk>(chunks: &[T]) -> (usize, usize) {
+    let mut surrogates = 0;
+    let trailing = crate::chars::sum_non_ascii_groups(chunks, |group| {
+        let mut acc_inv_chars = T::zero();
+        let mut acc_surrogates = T::zero();
+        for chunk in group.iter() {
+            acc_inv_chars = acc_inv_chars.add(chunk.bitand(T::splat(0xc0)).cmp_eq_byte(0x80));
+            acc_surrogates = acc_surrogates.add(chunk.bitand(T::splat(0xf0)).cmp_eq_byte(0xf0));
+        }
+        surrogates += acc_surrogates.sum_bytes();
+        acc_inv_chars.sum_bytes()
+    });
+    (trailing, surrogates)
+}
+
+/// Finds the first leading byte of a 4-byte char, returning its byte index
+/// and utf16 index.
+#[inline(always)]
+fn find_surrogate_impl<T: ByteChunk>(text: &[u8]) -> Option<(usize, usize)> {
+    // Finds the leading byte in `bytes`, counting the code units before
+    // it.
+    let scan_bytes = |bytes: &[u8], utf16_count: &mut usize| {
+        for (i, byte) in bytes.iter().enumerate() {
+            if (byte & 0xf0) == 0xf0 {
+                return Some(i);
+            }
+            *utf16_count += ((byte & 0xC0) != 0x80) as usize;
         }
-        utf16_count = new_utf16_count;
-        byte_count += T::SIZE;
+        None
+    };
+
+    // Get `middle` so we can do more efficient chunk-based searching.
+    let (start, middle, _) = unsafe { text.align_to::<T>() };
+    let mut utf16_count = 0;
+
+    // Take care of any unaligned bytes at the beginning.
+    if let Some(i) = scan_bytes(start, &mut utf16_count) {
+        return Some((i, utf16_count));
     }
+    let mut byte_count = start.len();
 
-    // Take care of any unaligned bytes at the end.
-    let end = &text.as_bytes()[byte_count..];
-    for byte in end.iter() {
-        utf16_count += ((*byte & 0xC0) != 0x80) as usize + ((byte & 0xf0) == 0xf0) as usize;
-        if utf16_count > utf16_idx {
+    // Skip groups of chunks without any 4-byte chars, where the code units
+    // are just the chars.
+    for group in middle.chunks(T::UNROLL) {
+        let mut acc_inv_chars = T::zero();
+        let mut acc_surrogates = T::zero();
+        for chunk in group.iter() {
+            acc_inv_chars = acc_inv_chars.add(chunk.bitand(T::splat(0xc0)).cmp_eq_byte(0x80));
+            acc_surrogates = acc_surrogates.bitor(chunk.bitand(T::splat(0xf0)).cmp_eq_byte(0xf0));
+        }
+        if !acc_surrogates.is_zero() {
             break;
         }
-        byte_count += 1;
+        utf16_count += (T::SIZE * group.len()) - acc_inv_chars.sum_bytes();
+        byte_count += T::SIZE * group.len();
     }
 
-    byte_count
+    // Find it in the rest of the bytes.
+    let rest = text.get(byte_count..).unwrap_or(&[]);
+    scan_bytes(rest, &mut utf16_count).map(|i| (byte_count + i, utf16_count))
 }
 
 #[inline(always)]
-fn count_surrogates_impl<T: ByteChunk>(text: &[u8]) -> usize {
+pub(crate) fn count_surrogates_impl<T: ByteChunk>(text: &[u8]) -> usize {
     // We chop off the last three bytes, because all surrogate pairs are
     // four bytes in utf8, and so it prevents counting partial
     // characters.
     if text.len() <= 3 {
         return 0;
     }
+    if text.len() < T::UTF16_SURROGATES_THRESHOLD {
+        // Bypass the more complex routine for short strings, where the
+        // complexity hurts performance.
+        return text[..(text.len() - 3)]
+            .iter()
+            .filter(|byte| (*byte & 0xf0) == 0xf0)
+            .count();
+    }
     let text = &text[..(text.len() - 3)];
 
     // Get `middle` for more efficient chunk-based counting.
@@ -172,6 +646,241 @@ mod tests {
     #[test]
     fn count_01() {
         assert_eq!(27, count(TEXT));
+        assert_eq!(0, count(""));
+        assert_eq!(27 * 40, count(&TEXT.repeat(40)));
+    }
+
+    #[test]
+    fn from_byte_idx_with_count_01() {
+        let text = TEXT.repeat(4);
+        for end in (0..=text.len()).filter(|&i| text.is_char_boundary(i)) {
+            let text = &text[..end];
+            let utf16_count = count(text);
+            for i in 0..(text.len() + 2) {
+                assert_eq!(
+                    from_byte_idx(text, i),
+                    from_byte_idx_with_count(text, i, utf16_count)
+                );
+            }
+        }
+
+        // Wrong counts don't panic.
+        assert_eq!(0, from_byte_idx_with_count("abc", 2, 0));
+    }
+
+    #[test]
+    fn from_byte_idxs_01() {
+        let text = TEXT.repeat(4);
+        let text = text.as_str();
+        let byte_idxs: [usize; 12] = [0, 0, 3, 4, 5, 30, 31, 60, 100, 170, 170, 999];
+        let mut out = [usize::MAX; 12];
+        from_byte_idxs(text, &byte_idxs, &mut out);
+        for (&i, &utf16_idx) in byte_idxs.iter().zip(out.iter()) {
+            assert_eq!(from_byte_idx(text, i), utf16_idx);
+        }
+
+        // Unsorted.
+        let byte_idxs = [100, 3, 999, 31, 0, 60];
+        from_byte_idxs(text, &byte_idxs, &mut out);
+        for (&i, &utf16_idx) in byte_idxs.iter().zip(out.iter()) {
+            assert_eq!(from_byte_idx(text, i), utf16_idx);
+        }
+
+        // Short output.
+        let mut out = [usize::MAX; 2];
+        from_byte_idxs(text, &byte_idxs, &mut out);
+        assert_eq!([from_byte_idx(text, 100), from_byte_idx(text, 3)], out);
+    }
+
+    #[test]
+    fn to_byte_idxs_01() {
+        let text = TEXT.repeat(4);
+        let text = text.as_str();
+        // Includes indices in the middle of surrogate pairs (e.g. 4).
+        let utf16_idxs: [usize; 12] = [0, 0, 3, 4, 4, 5, 30, 31, 60, 100, 100, 999];
+        let mut out = [usize::MAX; 12];
+        to_byte_idxs(text, &utf16_idxs, &mut out);
+        for (&i, &byte_idx) in utf16_idxs.iter().zip(out.iter()) {
+            assert_eq!(to_byte_idx(text, i), byte_idx);
+        }
+
+        // Unsorted.
+        let utf16_idxs = [100, 3, 999, 4, 31, 0, 60];
+        to_byte_idxs(text, &utf16_idxs, &mut out);
+        for (&i, &byte_idx) in utf16_idxs.iter().zip(out.iter()) {
+            assert_eq!(to_byte_idx(text, i), byte_idx);
+        }
+
+        // Every index, in order.
+        let mut utf16_idxs = [0; 120];
+        for (i, idx) in utf16_idxs.iter_mut().enumerate() {
+            *idx = i;
+        }
+        let mut out = [usize::MAX; 120];
+        to_byte_idxs(text, &utf16_idxs, &mut out);
+        for (&i, &byte_idx) in utf16_idxs.iter().zip(out.iter()) {
+            assert_eq!(to_byte_idx(text, i), byte_idx);
+        }
+    }
+
+    /// The `(utf16_idx, byte_idx)` of each 4-byte char, the slow way.
+    fn surrogate_indices_naive(text: &str) -> impl Iterator<Item = (usize, usize)> + '_ {
+        text.char_indices()
+            .scan(0, |utf16_idx, (byte_idx, c)| {
+                *utf16_idx += c.len_utf16();
+                Some((*utf16_idx - c.len_utf16(), byte_idx, c.len_utf8()))
+            })
+            .filter(|&(_, _, len)| len == 4)
+            .map(|(utf16_idx, byte_idx, _)| (utf16_idx, byte_idx))
+    }
+
+    #[test]
+    fn surrogate_indices_01() {
+        assert_eq!(None, surrogate_indices("").next());
+        assert_eq!(None, surrogate_indices("Hello こんにちは").next());
+        assert!(surrogate_indices("a🐸é🐸🐸").eq([(1, 1), (4, 7), (6, 11)]));
+        assert!(surrogate_indices(TEXT).eq(surrogate_indices_naive(TEXT)));
+        assert_eq!(4, surrogate_indices(TEXT).count());
+
+        let text = "a".repeat(300) + &TEXT.repeat(10) + &"こ".repeat(300) + "🐸";
+        assert!(surrogate_indices(&text).eq(surrogate_indices_naive(&text)));
+        let mut iter = surrogate_indices(&text);
+        iter.next();
+        assert_eq!(40, iter.count());
+    }
+
+    #[test]
+    fn offset_by_01() {
+        let text = "ab🐸éc\n";
+        assert_eq!(6, offset_by(text, 2, 2));
+        assert_eq!(2, offset_by(text, 2, 1));
+        assert_eq!(8, offset_by(text, 6, 1));
+        assert_eq!(10, offset_by(text, 2, 100));
+        assert_eq!(2, offset_by(text, 6, -2));
+        assert_eq!(2, offset_by(text, 6, -1));
+        assert_eq!(1, offset_by(text, 6, -3));
+        assert_eq!(0, offset_by(text, 6, -100));
+        assert_eq!(2, offset_by(text, 4, 0));
+        assert_eq!(9, offset_by(text, 100, -1));
+        assert_eq!(0, offset_by("", 0, isize::MIN));
+        assert_eq!(10, offset_by(text, 0, isize::MAX));
+    }
+
+    #[test]
+    fn offset_by_02() {
+        let text = TEXT.repeat(3);
+        let utf16_len = count(&text) as isize;
+        for i in 0..(text.len() + 2) {
+            let utf16_idx = from_byte_idx(&text, i) as isize;
+            for delta in -(utf16_len + 2)..(utf16_len + 2) {
+                let expected = to_byte_idx(&text, (utf16_idx + delta).max(0) as usize);
+                assert_eq!(expected, offset_by(&text, i, delta));
+            }
+        }
+    }
+
+    #[test]
+    fn count_up_to_01() {
+        let text = "ab🐸éc\n";
+        assert_eq!((0, 0), count_up_to(text, 0));
+        assert_eq!((2, 2), count_up_to(text, 3));
+        assert_eq!((4, 6), count_up_to(text, 4));
+        assert_eq!((7, 10), count_up_to(text, 100));
+        assert_eq!((0, 0), count_up_to("", 3));
+        let text = TEXT.repeat(20);
+        for i in 0..(count(&text) + 2) {
+            let byte_idx = to_byte_idx(&text, i);
+            assert_eq!((count(&text[..byte_idx]), byte_idx), count_up_to(&text, i));
+        }
+    }
+
+    #[test]
+    fn prefix_01() {
+        let text = "ab🐸éc\n";
+        assert_eq!("", prefix(text, 0));
+        assert_eq!("ab", prefix(text, 2));
+        assert_eq!("ab", prefix(text, 3));
+        assert_eq!("ab🐸", prefix(text, 4));
+        assert_eq!(text, prefix(text, 7));
+        assert_eq!(text, prefix(text, 100));
+        assert_eq!("", prefix("", 3));
+        for i in 0..(count(TEXT) + 2) {
+            assert!(count(prefix(TEXT, i)) <= i);
+            assert!(prefix(TEXT, i).len() == TEXT.len() || count(prefix(TEXT, i + 2)) > i);
+        }
+    }
+
+    #[test]
+    fn from_char_idx_01() {
+        // "He🐸llo" has the frog at char 2, utf16 2..4.
+        let text = "He🐸llo";
+        assert_eq!(0, from_char_idx(text, 0));
+        assert_eq!(2, from_char_idx(text, 2));
+        assert_eq!(4, from_char_idx(text, 3));
+        assert_eq!(7, from_char_idx(text, 6));
+        assert_eq!(7, from_char_idx(text, 100));
+        assert_eq!(0, from_char_idx("", 10));
+    }
+
+    #[test]
+    fn to_char_idx_01() {
+        let text = "He🐸llo";
+        assert_eq!(0, to_char_idx(text, 0));
+        assert_eq!(2, to_char_idx(text, 2));
+        assert_eq!(2, to_char_idx(text, 3));
+        assert_eq!(3, to_char_idx(text, 4));
+        assert_eq!(6, to_char_idx(text, 7));
+        assert_eq!(6, to_char_idx(text, 100));
+        assert_eq!(0, to_char_idx("", 10));
+    }
+
+    #[test]
+    fn char_idx_02() {
+        let text = TEXT.repeat(20);
+        for i in 0..(count(&text) + 2) {
+            assert_eq!(
+                from_byte_idx(&text, crate::chars::to_byte_idx(&text, i)),
+                from_char_idx(&text, i)
+            );
+            assert_eq!(
+                crate::chars::from_byte_idx(&text, to_byte_idx(&text, i)),
+                to_char_idx(&text, i)
+            );
+        }
+    }
+
+    #[test]
+    fn to_byte_idx_with_policy_01() {
+        use SurrogatePolicy::*;
+        let text = "He🐸llo";
+        for policy in [Floor, Ceil, Error] {
+            assert_eq!(Some(0), to_byte_idx_with_policy(text, 0, policy));
+            assert_eq!(Some(2), to_byte_idx_with_policy(text, 2, policy));
+            assert_eq!(Some(6), to_byte_idx_with_policy(text, 4, policy));
+            assert_eq!(Some(9), to_byte_idx_with_policy(text, 7, policy));
+            assert_eq!(Some(9), to_byte_idx_with_policy(text, 100, policy));
+            assert_eq!(Some(0), to_byte_idx_with_policy("", 1, policy));
+        }
+        assert_eq!(Some(2), to_byte_idx_with_policy(text, 3, Floor));
+        assert_eq!(Some(6), to_byte_idx_with_policy(text, 3, Ceil));
+        assert_eq!(None, to_byte_idx_with_policy(text, 3, Error));
+    }
+
+    #[test]
+    fn to_byte_idx_with_policy_02() {
+        let text = TEXT.repeat(20);
+        for i in 0..(count(&text) + 2) {
+            let floor = to_byte_idx(&text, i);
+            let on_boundary = i >= count(&text) || from_byte_idx(&text, floor) == i;
+            let ceil = if on_boundary { floor } else { floor + 4 };
+            let policy_idx = |policy| to_byte_idx_with_policy(&text, i, policy);
+            assert_eq!(Some(floor), policy_idx(SurrogatePolicy::Floor));
+            assert_eq!(Some(ceil), policy_idx(SurrogatePolicy::Ceil));
+            assert_eq!(
+                on_boundary.then_some(floor),
+                policy_idx(SurrogatePolicy::Error)
+            );
+        }
     }
 
     #[test]
@@ -211,6 +920,17 @@ mod tests {
         assert_eq!(27, from_byte_idx(TEXT, 46)); // Index 1 past the end.
     }
 
+    #[test]
+    fn from_byte_idx_snapped_01() {
+        let text = "H🐸せ!";
+        assert_eq!((1, 0), from_byte_idx_snapped(text, 1));
+        assert_eq!((1, 3), from_byte_idx_snapped(text, 4));
+        assert_eq!((3, 0), from_byte_idx_snapped(text, 5));
+        assert_eq!((3, 2), from_byte_idx_snapped(text, 7));
+        assert_eq!((5, 0), from_byte_idx_snapped(text, 9));
+        assert_eq!((5, 1), from_byte_idx_snapped(text, 10));
+    }
+
     #[test]
     fn to_byte_idx_01() {
         assert_eq!(to_byte_idx(TEXT, 0), 0);
@@ -234,4 +954,105 @@ mod tests {
         assert_eq!(45, to_byte_idx(TEXT, 27));
         assert_eq!(45, to_byte_idx(TEXT, 27)); // Index 1 past the end.
     }
+
+    #[test]
+    fn split_at_01() {
+        let text = "H🐸せ!";
+        assert_eq!(("H", "🐸せ!"), split_at(text, 1));
+        // Middle of the frog.
+        assert_eq!(("H", "🐸せ!"), split_at(text, 2));
+        assert_eq!(("H🐸", "せ!"), split_at(text, 3));
+        assert_eq!(("H🐸せ!", ""), split_at(text, 5));
+        assert_eq!(("H🐸せ!", ""), split_at(text, 6));
+    }
+
+    #[test]
+    fn to_byte_idx_budget() {
+        // The longest prefix with at most N utf16 code units never splits
+        // the surrogate pair.
+        let text = "H🐸せ!";
+        let prefix_len = |n| count(&text[..to_byte_idx(text, n)]);
+        assert_eq!(1, prefix_len(1));
+        assert_eq!(1, prefix_len(2));
+        assert_eq!(3, prefix_len(3));
+        assert_eq!(5, prefix_len(9));
+    }
+
+    #[test]
+    fn long_runs_01() {
+        // Long ascii and non-ascii runs, to exercise the ascii fast path and
+        // accumulate as much as possible per chunk.
+        let texts = [
+            "🐸".repeat(300),
+            "a".repeat(1000) + &"🐸".repeat(300) + &"a".repeat(1000),
+            "こ🐸".repeat(200) + &"a".repeat(700),
+        ];
+        for text in texts.iter() {
+            let bytes = text.as_bytes();
+            let utf16_len = text.encode_utf16().count();
+            assert_eq!(utf16_len, count_impl::<u16>(bytes));
+            assert_eq!(utf16_len, count_impl::<u32>(bytes));
+            assert_eq!(utf16_len, count_impl::<usize>(bytes));
+            assert_eq!(utf16_len, count(text));
+            for i in (0..(utf16_len + 2)).step_by(7) {
+                let expected = text
+                    .char_indices()
+                    .scan(0, |units, (byte_idx, c)| {
+                        *units += c.len_utf16();
+                        Some((*units, byte_idx))
+                    })
+                    .find(|&(units, _)| units > i)
+                    .map_or(text.len(), |(_, byte_idx)| byte_idx);
+                assert_eq!(expected, to_byte_idx(text, i));
+                assert_eq!(expected, to_byte_idx_impl::<u16>(text, i));
+                assert_eq!(expected, to_byte_idx_impl::<u32>(text, i));
+                assert_eq!(expected, to_byte_idx_impl::<usize>(text, i));
+            }
+        }
+    }
+
+    // Exercises the chunk-based code with 16- and 32-bit words, as used on
+    // 16-bit (AVR, MSP430) and 32-bit targets, regardless of the host.
+    #[test]
+    fn narrow_chunks() {
+        crate::byte_chunk::for_each_narrow_chunk_text(|text| {
+            let bytes = text.as_bytes();
+            let surrogates = count_surrogates(text);
+            assert_eq!(surrogates, count_surrogates_impl::<u16>(bytes));
+            assert_eq!(surrogates, count_surrogates_impl::<u32>(bytes));
+            let utf16_len = text.encode_utf16().count();
+            assert_eq!(utf16_len, count(text));
+            let mut expected = surrogate_indices_naive(text);
+            let mut byte_idx = 0;
+            let mut utf16_idx = 0;
+            for find in [find_surrogate_impl::<u16>, find_surrogate_impl::<u32>] {
+                loop {
+                    let found =
+                        find(&bytes[byte_idx..]).map(|(b, u)| (utf16_idx + u, byte_idx + b));
+                    assert_eq!(expected.next(), found);
+                    match found {
+                        Some((u, b)) => (utf16_idx, byte_idx) = (u + 2, b + 4),
+                        None => break,
+                    }
+                }
+                expected = surrogate_indices_naive(text);
+                (byte_idx, utf16_idx) = (0, 0);
+            }
+            assert_eq!(utf16_len, count_impl::<u16>(bytes));
+            assert_eq!(utf16_len, count_impl::<u32>(bytes));
+            for i in 0..(count(text) + 2) {
+                assert_eq!(to_byte_idx(text, i), to_byte_idx_impl::<u16>(text, i));
+                assert_eq!(to_byte_idx(text, i), to_byte_idx_impl::<u32>(text, i));
+                for scan in [
+                    scan_to_impl::<u16>,
+                    scan_to_impl::<u32>,
+                    scan_to_impl::<usize>,
+                ] {
+                    assert_eq!(from_char_idx(text, i), scan(bytes, i, false).2);
+                    assert_eq!(to_char_idx(text, i), scan(bytes, i, true).1);
+                    assert_eq!(to_byte_idx(text, i), scan(bytes, i, true).0);
+                }
+            }
+        });
+    }
 }
@@ -0,0 +1,146 @@
+//! Precomputed utf8 <-> utf16 index conversion.
+//!
+//! The [`utf16`](crate::utf16) functions scan the text from its start on
+//! every call.  When converting many indices in the same text, e.g. to
+//! stay in sync with a JavaScript or DOM host that addresses text by utf16
+//! offsets, a [`Utf16Table`] instead records the utf16 offset of regularly
+//! spaced checkpoints once, and then converts in either direction with a
+//! binary search plus a scan of at most one checkpoint interval.
+//!
+//! Requires the `alloc` feature.
+
+use alloc::vec::Vec;
+
+use crate::{chars, utf16};
+
+/// A table of utf16 offsets at checkpoints in a string slice, for fast
+/// conversion between byte and utf16 indices.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct Utf16Table<'a> {
+    text: &'a str,
+    /// `(byte_idx, utf16_idx)` of each checkpoint, starting with `(0, 0)`.
+    /// Both are strictly increasing.
+    checkpoints: Vec<(usize, usize)>,
+    utf16_len: usize,
+}
+
+impl<'a> Utf16Table<'a> {
+    /// The checkpoint interval used by [`new()`](Self::new), in bytes.
+    pub const DEFAULT_INTERVAL: usize = 1024;
+
+    /// Builds a table for a string slice, with the default checkpoint
+    /// interval.
+    ///
+    /// Runs in O(N) time.
+    pub fn new(text: &'a str) -> Utf16Table<'a> {
+        Self::with_interval(text, Self::DEFAULT_INTERVAL)
+    }
+
+    /// Builds a table for a string slice, with a checkpoint about every
+    /// `interval` bytes.
+    ///
+    /// Smaller intervals make conversions faster, at the cost of a larger
+    /// table: each checkpoint takes two `usize`s.  Intervals less than 4
+    /// are treated as 4.
+    ///
+    /// Runs in O(N) time.
+    pub fn with_interval(text: &'a str, interval: usize) -> Utf16Table<'a> {
+        let interval = interval.max(4);
+        let mut checkpoints = Vec::with_capacity(text.len() / interval + 1);
+        let (mut byte_idx, mut utf16_idx) = (0, 0);
+        checkpoints.push((0, 0));
+        while text.len() - byte_idx > interval {
+            // A char is at most 4 bytes, so this always makes progress.
+            let next = chars::floor_byte_boundary(text, byte_idx + interval);
+            utf16_idx += utf16::count(text.get(byte_idx..next).unwrap_or(""));
+            byte_idx = next;
+            checkpoints.push((byte_idx, utf16_idx));
+        }
+        utf16_idx += utf16::count(crate::split_str_at(text, byte_idx).1);
+
+        Utf16Table {
+            text,
+            checkpoints,
+            utf16_len: utf16_idx,
+        }
+    }
+
+    /// Returns the text the table is for.
+    #[inline]
+    pub fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// Returns the length of the text in utf16 code units.
+    ///
+    /// Runs in O(1) time.
+    #[inline]
+    pub fn utf16_len(&self) -> usize {
+        self.utf16_len
+    }
+
+    /// Converts from byte-index to utf16-code-unit-index.
+    ///
+    /// Same as [`utf16::from_byte_idx()`] on the table's text.
+    ///
+    /// Runs in O(log N) time.
+    pub fn from_byte_idx(&self, byte_idx: usize) -> usize {
+        let i = self.checkpoints.partition_point(|&(b, _)| b <= byte_idx);
+        let (b, u) = self
+            .checkpoints
+            .get(i.wrapping_sub(1))
+            .copied()
+            .unwrap_or((0, 0));
+        u + utf16::from_byte_idx(crate::split_str_at(self.text, b).1, byte_idx - b)
+    }
+
+    /// Converts from utf16-code-unit-index to byte-index.
+    ///
+    /// Same as [`utf16::to_byte_idx()`] on the table's text.
+    ///
+    /// Runs in O(log N) time.
+    pub fn to_byte_idx(&self, utf16_idx: usize) -> usize {
+        let i = self.checkpoints.partition_point(|&(_, u)| u <= utf16_idx);
+        let (b, u) = self
+            .checkpoints
+            .get(i.wrapping_sub(1))
+            .copied()
+            .unwrap_or((0, 0));
+        b + utf16::to_byte_idx(crate::split_str_at(self.text, b).1, utf16_idx - u)
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r\n🐸🐸🐸";
+
+    #[test]
+    fn matches_utf16() {
+        for interval in [0, 4, 5, 7, 16, 1024] {
+            for end in (0..=TEXT.len()).filter(|&i| TEXT.is_char_boundary(i)) {
+                let text = &TEXT[..end];
+                let table = Utf16Table::with_interval(text, interval);
+                assert_eq!(utf16::count(text), table.utf16_len());
+                for i in 0..(text.len() + 2) {
+                    assert_eq!(utf16::from_byte_idx(text, i), table.from_byte_idx(i));
+                    assert_eq!(utf16::to_byte_idx(text, i), table.to_byte_idx(i));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn checkpoints_01() {
+        let table = Utf16Table::with_interval(TEXT, 4);
+        for pair in table.checkpoints.windows(2) {
+            assert!(pair[0].0 < pair[1].0 && pair[0].1 < pair[1].1);
+            assert!(pair[1].0 - pair[0].0 <= 4);
+            assert!(TEXT.is_char_boundary(pair[1].0));
+        }
+        assert_eq!(1, Utf16Table::new("").checkpoints.len());
+    }
+}
@@ -0,0 +1,159 @@
+//! Find word-wrap points.
+//!
+//! These functions find where to end a row of wrapped text given a width
+//! budget: at the last break opportunity that fits, or failing that at the
+//! last char that fits.  Break opportunities are simply the positions after
+//! whitespace chars (other than the no-break spaces), which is enough for
+//! typical terminal and plain-text rendering but is not the full Unicode
+//! line breaking algorithm.
+//!
+//! Line breaks in the text aren't treated specially, so split the text
+//! into lines (e.g. with one of the lines modules) before wrapping it.
+
+use crate::chars;
+
+/// Returns the byte index to end a wrapped row at, with the row's width
+/// measured in chars.
+///
+/// The row starts at `row_start` and may be at most `max_chars` chars
+/// long.  If the rest of the text fits, returns the end of the text.
+/// Otherwise returns the position after the last whitespace char that
+/// fits, or if there is none, the position after the last char that fits.
+/// At least one char is always included in a row (unless the row starts at
+/// the end of the text), so that wrapping always makes progress.
+///
+/// If `row_start` is in the middle of a char, it's snapped back to the
+/// start of that char.
+///
+/// Runs in O(N) time, where N is the length of the row.
+#[inline(never)]
+pub fn wrap_point(text: &str, row_start: usize, max_chars: usize) -> usize {
+    let row_start = chars::floor_byte_boundary(text, row_start);
+    let rest = crate::split_str_at(text, row_start).1;
+    let row_end = row_start + chars::to_byte_idx(rest, max_chars);
+    if row_end == text.len() {
+        return row_end;
+    }
+    last_break_opportunity(text, row_start, row_end)
+}
+
+/// Like [`wrap_point()`], but with the row's width measured by the given
+/// function.
+///
+/// `char_width` gives the width of each char, e.g. in terminal cells via
+/// the `unicode-width` crate.  The widths of the chars in the row sum to at
+/// most `max_width`.
+///
+/// Runs in O(N) time, where N is the length of the row.
+pub fn wrap_point_with<F>(text: &str, row_start: usize, max_width: usize, char_width: F) -> usize
+where
+    F: Fn(char) -> usize,
+{
+    let row_start = chars::floor_byte_boundary(text, row_start);
+    let mut width = 0;
+    let mut row_end = text.len();
+    for (i, c) in crate::split_str_at(text, row_start).1.char_indices() {
+        width += char_width(c);
+        if width > max_width {
+            row_end = row_start + i;
+            break;
+        }
+    }
+    if row_end == text.len() {
+        return row_end;
+    }
+    last_break_opportunity(text, row_start, row_end)
+}
+
+//-------------------------------------------------------------
+
+/// Returns the position after the last breaking whitespace char in
+/// `text[row_start..row_end]`, falling back to `row_end`, or to after the
+/// first char if the row would be empty.
+#[inline(always)]
+fn last_break_opportunity(text: &str, row_start: usize, row_end: usize) -> usize {
+    let row = text.get(row_start..row_end).unwrap_or("");
+    match row
+        .char_indices()
+        .rev()
+        .find(|&(_, c)| is_breaking_space(c))
+    {
+        Some((i, c)) => row_start + i + c.len_utf8(),
+        None if row.is_empty() => {
+            row_start + chars::to_byte_idx(crate::split_str_at(text, row_start).1, 1)
+        }
+        None => row_end,
+    }
+}
+
+#[inline(always)]
+fn is_breaking_space(c: char) -> bool {
+    c.is_whitespace() && !matches!(c, '\u{00A0}' | '\u{2007}' | '\u{202F}')
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn wrap_point_01() {
+        let text = "The quick brown fox";
+        assert_eq!(4, wrap_point(text, 0, 4));
+        assert_eq!(10, wrap_point(text, 0, 12));
+        assert_eq!(16, wrap_point(text, 10, 8));
+        assert_eq!(19, wrap_point(text, 16, 8));
+        assert_eq!(19, wrap_point(text, 0, 19));
+        assert_eq!(19, wrap_point(text, 0, 100));
+
+        // No break opportunity: break mid-word.
+        assert_eq!(3, wrap_point(text, 0, 3));
+        assert_eq!(7, wrap_point(text, 4, 3));
+    }
+
+    #[test]
+    fn wrap_point_02() {
+        // Multi-byte chars, and a no-break space.
+        let text = "こんにちは みんな\u{00A0}さん";
+        assert_eq!(16, wrap_point(text, 0, 7));
+        assert_eq!(16, wrap_point(text, 0, 6));
+        assert_eq!(15, wrap_point(text, 0, 5));
+        assert_eq!(27, wrap_point(text, 16, 4));
+        assert_eq!(text.len(), wrap_point(text, 16, 6));
+
+        // Row starting in the middle of a char.
+        assert_eq!(16, wrap_point(text, 1, 7));
+    }
+
+    #[test]
+    fn wrap_point_progress() {
+        assert_eq!(4, wrap_point("🐸🐸", 0, 0));
+        assert_eq!(8, wrap_point("🐸🐸", 4, 0));
+        assert_eq!(8, wrap_point("🐸🐸", 8, 0));
+        assert_eq!(0, wrap_point("", 0, 0));
+    }
+
+    #[test]
+    fn wrap_point_with_01() {
+        // Double-width CJK.
+        let width = |c: char| if c.is_ascii() { 1 } else { 2 };
+        let text = "ab こんにちは";
+        assert_eq!(3, wrap_point_with(text, 0, 6, width));
+        assert_eq!(3, wrap_point_with(text, 0, 8, width));
+        assert_eq!(text.len(), wrap_point_with(text, 0, 13, width));
+        assert_eq!(9, wrap_point_with(text, 3, 5, width));
+        assert_eq!(6, wrap_point_with(text, 3, 0, width));
+
+        // Matches `wrap_point()` with a width of 1 per char.
+        let text = "The quick brown fox こんにちは みんな\u{00A0}さん";
+        for start in (0..text.len()).filter(|&i| text.is_char_boundary(i)) {
+            for max in 0..12 {
+                assert_eq!(
+                    wrap_point(text, start, max),
+                    wrap_point_with(text, start, max, |_| 1)
+                );
+            }
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! Index by code points and utf16 code units in WTF-8 text.
+//!
+//! [WTF-8](https://simonsapin.github.io/wtf-8/) extends utf8 to encode
+//! lone (unpaired) surrogate code points, as 3-byte sequences starting
+//! with 0xED.  It's how e.g. `OsStr` holds arbitrary utf16 file names on
+//! Windows, and how strings from JavaScript, which may contain lone
+//! surrogates, can be stored losslessly.
+//!
+//! A lone surrogate is a single code point, and a single utf16 code unit,
+//! just like any other 3-byte sequence, so the functions here give the
+//! same results as their [`chars`](crate::chars) and
+//! [`utf16`](crate::utf16) counterparts would if the text were a `&str`.
+//! They just take `&[u8]` instead.
+//!
+//! The text should be well-formed WTF-8: valid utf8 plus lone surrogates,
+//! with no surrogate pairs encoded as two 3-byte sequences.  For other
+//! bytes the results are unspecified, but nothing panics.
+
+/// Returns the start of the code point that `byte_idx` is in, or the end
+/// of the text if past the end.
+#[inline(always)]
+fn floor_boundary(text: &[u8], byte_idx: usize) -> usize {
+    if byte_idx >= text.len() {
+        return text.len();
+    }
+    // Well-formed WTF-8 has at most three trailing bytes in a row.
+    let mut i = byte_idx;
+    while i > 0 && matches!(text.get(i), Some(byte) if (byte & 0xC0) == 0x80) {
+        i -= 1;
+    }
+    i
+}
+
+/// Index by code points.
+///
+/// Lone surrogates count as one code point each.
+pub mod chars {
+    /// Counts the code points in WTF-8 text.
+    ///
+    /// See [`crate::chars::count()`].
+    ///
+    /// Runs in O(N) time.
+    #[inline(never)]
+    pub fn count(text: &[u8]) -> usize {
+        with_chunk!(crate::chars::count_impl(text))
+    }
+
+    /// Converts from byte-index to code-point-index in WTF-8 text.
+    ///
+    /// See [`crate::chars::from_byte_idx()`].
+    ///
+    /// Runs in O(N) time.
+    #[inline(never)]
+    pub fn from_byte_idx(text: &[u8], byte_idx: usize) -> usize {
+        let byte_idx = super::floor_boundary(text, byte_idx);
+        count(text.get(..byte_idx).unwrap_or(&[]))
+    }
+
+    /// Converts from code-point-index to byte-index in WTF-8 text.
+    ///
+    /// See [`crate::chars::to_byte_idx()`].
+    ///
+    /// Runs in O(N) time.
+    #[inline(never)]
+    pub fn to_byte_idx(text: &[u8], char_idx: usize) -> usize {
+        with_chunk!(crate::chars::to_byte_idx_impl(text, char_idx))
+    }
+}
+
+/// Index by utf16 code units.
+///
+/// Lone surrogates are one utf16 code unit each, and only chars outside
+/// the Basic Multilingual Plane (4-byte sequences) are surrogate pairs.
+pub mod utf16 {
+    /// Counts the utf16 code units in WTF-8 text.
+    ///
+    /// This is the length of the potentially ill-formed utf16 (e.g. a
+    /// JavaScript string) that the text encodes.
+    ///
+    /// See [`crate::utf16::count()`].
+    ///
+    /// Runs in O(N) time.
+    #[inline(never)]
+    pub fn count(text: &[u8]) -> usize {
+        with_chunk!(crate::utf16::count_impl(text))
+    }
+
+    /// Counts the utf16 surrogate pairs in WTF-8 text.
+    ///
+    /// Lone surrogates aren't counted, as they're not pairs.
+    ///
+    /// See [`crate::utf16::count_surrogates()`].
+    ///
+    /// Runs in O(N) time.
+    #[inline(never)]
+    pub fn count_surrogates(text: &[u8]) -> usize {
+        with_chunk!(crate::utf16::count_surrogates_impl(text))
+    }
+
+    /// Converts from byte-index to utf16-code-unit-index in WTF-8 text.
+    ///
+    /// See [`crate::utf16::from_byte_idx()`].
+    ///
+    /// Runs in O(N) time.
+    #[inline(never)]
+    pub fn from_byte_idx(text: &[u8], byte_idx: usize) -> usize {
+        let byte_idx = super::floor_boundary(text, byte_idx);
+        count(text.get(..byte_idx).unwrap_or(&[]))
+    }
+
+    /// Converts from utf16-code-unit-index to byte-index in WTF-8 text.
+    ///
+    /// See [`crate::utf16::to_byte_idx()`].
+    ///
+    /// Runs in O(N) time.
+    #[inline(never)]
+    pub fn to_byte_idx(text: &[u8], utf16_idx: usize) -> usize {
+        with_chunk!(crate::utf16::scan_to_impl(text, utf16_idx, true)).0
+    }
+}
+
+//=============================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // "a", lone high surrogate U+D800, "é", U+1F438 (frog), lone low
+    // surrogate U+DC00, "b".
+    const TEXT: &[u8] = b"a\xED\xA0\x80\xC3\xA9\xF0\x9F\x90\xB8\xED\xB0\x80b";
+
+    #[test]
+    fn chars_01() {
+        assert_eq!(6, chars::count(TEXT));
+        assert_eq!(0, chars::count(b""));
+        let boundaries = [0, 1, 4, 6, 10, 13, 14];
+        for (char_idx, &byte_idx) in boundaries.iter().enumerate() {
+            assert_eq!(char_idx, chars::from_byte_idx(TEXT, byte_idx));
+            assert_eq!(byte_idx, chars::to_byte_idx(TEXT, char_idx));
+        }
+        assert_eq!(1, chars::from_byte_idx(TEXT, 3));
+        assert_eq!(6, chars::from_byte_idx(TEXT, 100));
+        assert_eq!(14, chars::to_byte_idx(TEXT, 100));
+    }
+
+    #[test]
+    fn utf16_01() {
+        assert_eq!(7, utf16::count(TEXT));
+        assert_eq!(1, utf16::count_surrogates(TEXT));
+        let boundaries = [(0, 0), (1, 1), (4, 2), (6, 3), (10, 5), (13, 6), (14, 7)];
+        for &(byte_idx, utf16_idx) in boundaries.iter() {
+            assert_eq!(utf16_idx, utf16::from_byte_idx(TEXT, byte_idx));
+            assert_eq!(byte_idx, utf16::to_byte_idx(TEXT, utf16_idx));
+        }
+        assert_eq!(3, utf16::from_byte_idx(TEXT, 8));
+        assert_eq!(6, utf16::to_byte_idx(TEXT, 4));
+        assert_eq!(7, utf16::from_byte_idx(TEXT, 100));
+        assert_eq!(14, utf16::to_byte_idx(TEXT, 100));
+    }
+
+    #[test]
+    fn matches_str_functions() {
+        // On valid utf8, everything should match the `&str` functions.
+        let text = "Hel🐸lo\r\nworld\u{2028}こん\rにち\u{0085}は!\n\r\n".repeat(8);
+        let bytes = text.as_bytes();
+        assert_eq!(crate::chars::count(&text), chars::count(bytes));
+        assert_eq!(crate::utf16::count(&text), utf16::count(bytes));
+        for i in 0..(text.len() + 2) {
+            assert_eq!(
+                crate::chars::from_byte_idx(&text, i),
+                chars::from_byte_idx(bytes, i)
+            );
+            assert_eq!(
+                crate::chars::to_byte_idx(&text, i),
+                chars::to_byte_idx(bytes, i)
+            );
+            assert_eq!(
+                crate::utf16::from_byte_idx(&text, i),
+                utf16::from_byte_idx(bytes, i)
+            );
+            assert_eq!(
+                crate::utf16::to_byte_idx(&text, i),
+                utf16::to_byte_idx(bytes, i)
+            );
+        }
+    }
+}
@@ -0,0 +1,44 @@
+//! Cross-checks every module against the standard library on a corpus of
+//! tricky texts.
+
+use str_indices::debug_check_consistency;
+
+const CORPUS: &[(&str, &str)] = &[
+    ("empty", ""),
+    ("en_10", include_str!("../benches/text/en_10.txt")),
+    ("en_100", include_str!("../benches/text/en_100.txt")),
+    ("en_1000", include_str!("../benches/text/en_1000.txt")),
+    ("jp_102", include_str!("../benches/text/jp_102.txt")),
+    ("jp_1001", include_str!("../benches/text/jp_1001.txt")),
+    ("lines", include_str!("../benches/text/lines.txt")),
+    ("line_breaks", include_str!("text/line_breaks.txt")),
+    ("mixed_scripts", include_str!("text/mixed_scripts.txt")),
+];
+
+#[test]
+fn corpus() {
+    for (name, text) in CORPUS {
+        assert_eq!(Ok(()), debug_check_consistency(text), "{}", name);
+    }
+}
+
+#[test]
+fn corpus_slices() {
+    // Slices starting and ending at every char boundary near the ends, so
+    // that every chunk alignment and partial line break is hit.
+    for (name, text) in CORPUS {
+        let boundaries = || (0..=text.len()).filter(|&i| text.is_char_boundary(i));
+        for start in boundaries().take(8) {
+            for end in boundaries().filter(|&end| end >= start && end + 16 >= text.len()) {
+                assert_eq!(
+                    Ok(()),
+                    debug_check_consistency(&text[start..end]),
+                    "{} [{}..{}]",
+                    name,
+                    start,
+                    end,
+                );
+            }
+        }
+    }
+}
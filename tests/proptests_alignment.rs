@@ -0,0 +1,70 @@
+#[macro_use]
+extern crate proptest;
+
+use proptest::test_runner::Config;
+use str_indices::{chars, lines, lines_crlf, lines_lf, naive, utf16};
+
+/// The largest chunk size of any backend is 16 bytes, so starting at each
+/// of 32 offsets into a buffer covers every alignment relative to the
+/// chunks, twice, no matter how the buffer itself is aligned.
+const OFFSETS: usize = 32;
+
+/// Calls `f` with copies of `text` starting at every offset into a buffer.
+fn at_all_offsets(text: &str, f: impl Fn(&str)) {
+    let mut buf = String::with_capacity(OFFSETS + text.len());
+    for offset in 0..OFFSETS {
+        buf.clear();
+        buf.push_str(&"x".repeat(offset));
+        buf.push_str(text);
+        f(&buf[offset..]);
+    }
+}
+
+//===========================================================================
+
+#[cfg(miri)]
+const ROUNDS: u32 = 4;
+#[cfg(not(miri))]
+const ROUNDS: u32 = 256;
+
+// Text dense in multi-byte chars and in line breaks that straddle chunk
+// boundaries (CRLF, NEL, and the 3-byte separators).
+const TEXT: &str = "[aあ🐸\\u{000A}\\u{000B}\\u{000D}\\u{0085}\\u{2028}\\u{2029}]{0, 100}";
+
+proptest! {
+    #![proptest_config(Config::with_cases(ROUNDS))]
+
+    #[test]
+    fn pt_counts(ref text in TEXT) {
+        at_all_offsets(text, |text| {
+            assert_eq!(naive::chars::count(text), chars::count(text));
+            assert_eq!(naive::utf16::count(text), utf16::count(text));
+            assert_eq!(naive::utf16::count_surrogates(text), utf16::count_surrogates(text));
+            assert_eq!(naive::lines::count_breaks(text), lines::count_breaks(text));
+            assert_eq!(naive::lines_crlf::count_breaks(text), lines_crlf::count_breaks(text));
+            assert_eq!(naive::lines_lf::count_breaks(text), lines_lf::count_breaks(text));
+        });
+    }
+
+    #[test]
+    fn pt_from_byte_idx(ref text in TEXT, idx in 0usize..400) {
+        at_all_offsets(text, |text| {
+            assert_eq!(naive::chars::from_byte_idx(text, idx), chars::from_byte_idx(text, idx));
+            assert_eq!(naive::utf16::from_byte_idx(text, idx), utf16::from_byte_idx(text, idx));
+            assert_eq!(naive::lines::from_byte_idx(text, idx), lines::from_byte_idx(text, idx));
+            assert_eq!(naive::lines_crlf::from_byte_idx(text, idx), lines_crlf::from_byte_idx(text, idx));
+            assert_eq!(naive::lines_lf::from_byte_idx(text, idx), lines_lf::from_byte_idx(text, idx));
+        });
+    }
+
+    #[test]
+    fn pt_to_byte_idx(ref text in TEXT, idx in 0usize..200) {
+        at_all_offsets(text, |text| {
+            assert_eq!(naive::chars::to_byte_idx(text, idx), chars::to_byte_idx(text, idx));
+            assert_eq!(naive::utf16::to_byte_idx(text, idx), utf16::to_byte_idx(text, idx));
+            assert_eq!(naive::lines::to_byte_idx(text, idx), lines::to_byte_idx(text, idx));
+            assert_eq!(naive::lines_crlf::to_byte_idx(text, idx), lines_crlf::to_byte_idx(text, idx));
+            assert_eq!(naive::lines_lf::to_byte_idx(text, idx), lines_lf::to_byte_idx(text, idx));
+        });
+    }
+}
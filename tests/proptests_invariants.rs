@@ -0,0 +1,142 @@
+//! Invariants that hold across and between the indexing modules.
+
+#[macro_use]
+extern crate proptest;
+
+use proptest::test_runner::Config;
+use str_indices::{chars, lines, lines_crlf, lines_lf, utf16};
+
+/// The `count`, `from_byte_idx`, and `to_byte_idx` functions of a module.
+struct Scheme {
+    name: &'static str,
+    count: fn(&str) -> usize,
+    from_byte_idx: fn(&str, usize) -> usize,
+    to_byte_idx: fn(&str, usize) -> usize,
+}
+
+const SCHEMES: [Scheme; 5] = [
+    Scheme {
+        name: "chars",
+        count: chars::count,
+        from_byte_idx: chars::from_byte_idx,
+        to_byte_idx: chars::to_byte_idx,
+    },
+    Scheme {
+        name: "utf16",
+        count: utf16::count,
+        from_byte_idx: utf16::from_byte_idx,
+        to_byte_idx: utf16::to_byte_idx,
+    },
+    Scheme {
+        name: "lines",
+        count: lines::count_breaks,
+        from_byte_idx: lines::from_byte_idx,
+        to_byte_idx: lines::to_byte_idx,
+    },
+    Scheme {
+        name: "lines_crlf",
+        count: lines_crlf::count_breaks,
+        from_byte_idx: lines_crlf::from_byte_idx,
+        to_byte_idx: lines_crlf::to_byte_idx,
+    },
+    Scheme {
+        name: "lines_lf",
+        count: lines_lf::count_breaks,
+        from_byte_idx: lines_lf::from_byte_idx,
+        to_byte_idx: lines_lf::to_byte_idx,
+    },
+];
+
+//===========================================================================
+
+#[cfg(miri)]
+const ROUNDS: u32 = 4;
+#[cfg(not(miri))]
+const ROUNDS: u32 = 256;
+
+const TEXT: &str = "[aあ🐸\\u{000A}\\u{000B}\\u{000C}\\u{000D}\\u{0085}\\u{2028}\\u{2029}]{0, 200}";
+
+proptest! {
+    #![proptest_config(Config::with_cases(ROUNDS))]
+
+    #[test]
+    fn pt_count_is_from_byte_idx_of_end(ref text in TEXT) {
+        for s in SCHEMES.iter() {
+            assert_eq!((s.count)(text), (s.from_byte_idx)(text, text.len()), "{}", s.name);
+            assert_eq!((s.count)(text), (s.from_byte_idx)(text, usize::MAX), "{}", s.name);
+        }
+    }
+
+    #[test]
+    fn pt_utf16_count(ref text in TEXT) {
+        assert_eq!(utf16::count(text), chars::count(text) + utf16::count_surrogates(text));
+    }
+
+    #[test]
+    fn pt_monotonic(ref text in TEXT) {
+        for s in SCHEMES.iter() {
+            let mut prev = 0;
+            for byte_idx in 0..(text.len() + 2) {
+                let idx = (s.from_byte_idx)(text, byte_idx);
+                assert!(idx >= prev, "{} from_byte_idx({})", s.name, byte_idx);
+                prev = idx;
+            }
+
+            let mut prev = 0;
+            for idx in 0..((s.count)(text) + 3) {
+                let byte_idx = (s.to_byte_idx)(text, idx);
+                assert!(byte_idx >= prev, "{} to_byte_idx({})", s.name, idx);
+                assert!(byte_idx <= text.len());
+                assert!(text.is_char_boundary(byte_idx));
+                prev = byte_idx;
+            }
+        }
+    }
+
+    #[test]
+    fn pt_round_trip(ref text in TEXT) {
+        for s in SCHEMES.iter() {
+            // from ∘ to ∘ from = from
+            for byte_idx in 0..(text.len() + 2) {
+                let idx = (s.from_byte_idx)(text, byte_idx);
+                assert_eq!(
+                    idx,
+                    (s.from_byte_idx)(text, (s.to_byte_idx)(text, idx)),
+                    "{} byte_idx {}", s.name, byte_idx,
+                );
+            }
+
+            // to ∘ from ∘ to = to, for indices that aren't past the end.
+            // (Past-the-end line indices map to the end of the text, which
+            // is in the last line rather than past it.)
+            for idx in 0..((s.count)(text) + 1) {
+                let byte_idx = (s.to_byte_idx)(text, idx);
+                assert_eq!(
+                    byte_idx,
+                    (s.to_byte_idx)(text, (s.from_byte_idx)(text, byte_idx)),
+                    "{} idx {}", s.name, idx,
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn pt_composed(ref text in TEXT, idx in 0usize..300) {
+        // Char index to line index and back, via byte indices, lands on
+        // the start of the char's line.
+        let byte_idx = chars::to_byte_idx(text, idx);
+        let line_idx = lines::from_byte_idx(text, byte_idx);
+        let line_start = lines::to_byte_idx(text, line_idx);
+        assert!(line_start <= byte_idx);
+        assert_eq!(line_idx, lines::from_byte_idx(text, line_start));
+        assert_eq!(
+            chars::count(&text[..line_start]),
+            chars::from_byte_idx(text, line_start),
+        );
+
+        // The utf16 index of a char index matches transcoding the prefix.
+        let prefix = &text[..byte_idx];
+        assert_eq!(prefix.encode_utf16().count(), utf16::from_byte_idx(text, byte_idx));
+        assert_eq!(byte_idx, utf16::to_byte_idx(text, utf16::count(prefix)));
+    }
+}
@@ -0,0 +1,85 @@
+#[macro_use]
+extern crate proptest;
+
+use proptest::test_runner::Config;
+use str_indices::lines_custom::{self, BreakSet};
+use str_indices::{lines, lines_crlf, lines_lf};
+
+//===========================================================================
+
+#[cfg(miri)]
+const ROUNDS: u32 = 4;
+#[cfg(not(miri))]
+const ROUNDS: u32 = 512;
+
+const TEXT: &str = "[aあ🐸\\u{000A}\\u{000B}\\u{000C}\\u{000D}\\u{0085}\\u{2028}\\u{2029}]{0, 200}";
+const CRLF: BreakSet = BreakSet::LF.union(BreakSet::CR);
+
+proptest! {
+    #![proptest_config(Config::with_cases(ROUNDS))]
+
+    #[test]
+    fn pt_count_breaks(ref text in TEXT) {
+        assert_eq!(lines::count_breaks(text), lines_custom::count_breaks(text, BreakSet::ALL));
+        assert_eq!(lines_lf::count_breaks(text), lines_custom::count_breaks(text, BreakSet::LF));
+        assert_eq!(lines_crlf::count_breaks(text), lines_custom::count_breaks(text, CRLF));
+    }
+
+    #[test]
+    fn pt_count_lines(ref text in TEXT, final_empty_line: bool) {
+        assert_eq!(
+            lines::count_lines(text, final_empty_line),
+            lines_custom::count_lines(text, final_empty_line, BreakSet::ALL),
+        );
+        assert_eq!(
+            lines_lf::count_lines(text, final_empty_line),
+            lines_custom::count_lines(text, final_empty_line, BreakSet::LF),
+        );
+    }
+
+    #[test]
+    fn pt_from_byte_idx(ref text in TEXT, idx in 0usize..800) {
+        assert_eq!(
+            lines::from_byte_idx(text, idx),
+            lines_custom::from_byte_idx(text, idx, BreakSet::ALL),
+        );
+        assert_eq!(
+            lines_lf::from_byte_idx(text, idx),
+            lines_custom::from_byte_idx(text, idx, BreakSet::LF),
+        );
+        assert_eq!(
+            lines_crlf::from_byte_idx(text, idx),
+            lines_custom::from_byte_idx(text, idx, CRLF),
+        );
+    }
+
+    #[test]
+    fn pt_to_byte_idx(ref text in TEXT, idx in 0usize..300) {
+        assert_eq!(
+            lines::to_byte_idx(text, idx),
+            lines_custom::to_byte_idx(text, idx, BreakSet::ALL),
+        );
+        assert_eq!(
+            lines_lf::to_byte_idx(text, idx),
+            lines_custom::to_byte_idx(text, idx, BreakSet::LF),
+        );
+        assert_eq!(
+            lines_crlf::to_byte_idx(text, idx),
+            lines_custom::to_byte_idx(text, idx, CRLF),
+        );
+    }
+
+    #[test]
+    fn pt_first_break(ref text in TEXT) {
+        assert_eq!(lines::first_break(text), lines_custom::first_break(text, BreakSet::ALL));
+        assert_eq!(lines_crlf::first_break(text), lines_custom::first_break(text, CRLF));
+    }
+
+    #[test]
+    fn pt_subset(ref text in TEXT, idx in 0usize..300) {
+        // Dropping VT and FF can only join lines.
+        let breaks = BreakSet::ALL.difference(BreakSet::VT.union(BreakSet::FF));
+        let byte_idx = lines_custom::to_byte_idx(text, idx, breaks);
+        assert!(lines::from_byte_idx(text, byte_idx) >= lines_custom::from_byte_idx(text, byte_idx, breaks));
+    }
+}